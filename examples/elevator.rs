@@ -0,0 +1,31 @@
+//! Elevator: door and motion machines combined with a request queue
+//! Requests arrive at scripted ticks; every event is printed
+
+use fsmall::presets::elevator::{Elevator, Timing};
+
+const FLOORS: usize = 6;
+
+// Door stays open 3 ticks, the car takes 2 ticks per floor
+static TIMING: Timing = Timing::new(3, 2);
+
+// (tick, floor) requests as passengers press buttons
+static REQUESTS: [(u32, u8); 5] = [(0, 4), (1, 2), (5, 0), (12, 5), (13, 1)];
+
+fn main() {
+    let mut car: Elevator<'_, FLOORS> = Elevator::new(&TIMING);
+
+    println!("=== Elevator ({FLOORS} floors) ===");
+    for tick in 0..60 {
+        for (_at, floor) in REQUESTS.iter().filter(|(at, _floor)| *at == tick) {
+            println!("[{tick:2}] request floor {floor}");
+            car.request(*floor);
+        }
+        if let Some(event) = car.tick() {
+            println!(
+                "[{tick:2}] {event:?} (floor {}, {:?})",
+                car.floor(),
+                car.motion()
+            );
+        }
+    }
+}
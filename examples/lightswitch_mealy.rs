@@ -44,23 +44,15 @@ static OUTPUTS: [(u8, Input, Brightness); 8] = [
     (LIGHT_OFF, Input::OffPress, Brightness::Off),
 ];
 
-fn state_name(state: u8) -> &'static str {
-    match state {
-        LIGHT_OFF => "off",
-        LIGHT_ON_DIMMED => "dimmed",
-        LIGHT_ON_MEDIUM => "medium",
-        LIGHT_ON_BRIGHT => "bright",
-        _ => "unknown",
-    }
-}
+static STATE_NAMES: [&str; 4] = ["off", "dimmed", "medium", "bright"];
 
 fn main() {
     use std::io::{self, Write};
 
-    let mut fsm = Mealy::new(LIGHT_OFF, &TRANSITIONS, &OUTPUTS);
+    let mut fsm = Mealy::new(LIGHT_OFF, &TRANSITIONS, &OUTPUTS).with_state_names(&STATE_NAMES);
 
     println!("=== Mealy Lightswitch ===");
-    println!("Initial state: {}", state_name(fsm.current_state()));
+    println!("Initial state: {fsm}");
     println!("Commands: on, off, q (quit)");
 
     let stdin = io::stdin();
@@ -85,7 +77,7 @@ fn main() {
         match fsm.step(input) {
             Ok(brightness) => {
                 println!("Output: {:?}", brightness);
-                println!("State: {}", state_name(fsm.current_state()));
+                println!("State: {fsm}");
             }
             Err(e) => println!("Error: {:?}", e),
         }
@@ -41,23 +41,15 @@ static OUTPUTS: [Brightness; 4] = [
     Brightness::High,   // LIGHT_ON_BRIGHT
 ];
 
-fn state_name(state: u8) -> &'static str {
-    match state {
-        LIGHT_OFF => "off",
-        LIGHT_ON_DIMMED => "dimmed",
-        LIGHT_ON_MEDIUM => "medium",
-        LIGHT_ON_BRIGHT => "bright",
-        _ => "unknown",
-    }
-}
+static STATE_NAMES: [&str; 4] = ["off", "dimmed", "medium", "bright"];
 
 fn main() {
     use std::io::{self, Write};
 
-    let mut fsm = Moore::new(LIGHT_OFF, &TRANSITIONS, &OUTPUTS);
+    let mut fsm = Moore::new(LIGHT_OFF, &TRANSITIONS, &OUTPUTS).with_state_names(&STATE_NAMES);
 
     println!("=== Moore Lightswitch ===");
-    println!("Initial state: {}", state_name(fsm.current_state()));
+    println!("Initial state: {fsm}");
     println!("Initial output: {:?}", fsm.current_output().unwrap());
     println!("Commands: on, off, q (quit)");
 
@@ -83,7 +75,7 @@ fn main() {
         match fsm.step(input) {
             Ok(brightness) => {
                 println!("New state output: {:?}", brightness);
-                println!("State: {}", state_name(fsm.current_state()));
+                println!("State: {fsm}");
             }
             Err(e) => println!("Error: {:?}", e),
         }
@@ -0,0 +1,353 @@
+//! Adapters feeding machines from raw events and sampled levels
+//!
+//! Each adapter owns the inner machine and forwards translated inputs to
+//! it, so tables can stay in terms of clean input enums.
+
+use core::marker::PhantomData;
+
+use crate::Machine;
+
+/// Machine driven by inputs of another type, converted by `F`
+///
+/// Raw events the conversion maps to `None` are ignored without touching
+/// the inner machine.
+///
+/// # Example
+/// ```
+/// use fsmall::adapt::MapInput;
+/// use fsmall::Mealy;
+///
+/// #[derive(Copy, Clone, Eq, PartialEq)]
+/// enum Key { Up, Down }
+///
+/// static TRANSITIONS: [(u8, Key, u8); 2] = [(0, Key::Up, 1), (1, Key::Down, 0)];
+/// static OUTPUTS: [(u8, Key, i8); 2] = [(0, Key::Up, 1), (1, Key::Down, -1)];
+///
+/// let keys = |code: u8| match code {
+///     0x48 => Some(Key::Up),
+///     0x50 => Some(Key::Down),
+///     _ => None,
+/// };
+/// let mut fsm = MapInput::new(Mealy::new(0, &TRANSITIONS, &OUTPUTS), keys);
+/// assert_eq!(fsm.step(0x48), Ok(Some(1)));
+/// assert_eq!(fsm.step(0x20), Ok(None)); // Ignored
+/// assert_eq!(fsm.step(0x50), Ok(Some(-1)));
+/// ```
+pub struct MapInput<M, F, J> {
+    machine: M,
+    map: F,
+    _input: PhantomData<fn(J)>,
+}
+
+impl<M, F, J> MapInput<M, F, J>
+where
+    M: Machine,
+    F: Fn(J) -> Option<M::Input>,
+{
+    /// Wrap `machine`, converting raw inputs with `map`
+    pub fn new(machine: M, map: F) -> Self {
+        MapInput {
+            machine,
+            map,
+            _input: PhantomData,
+        }
+    }
+
+    /// Convert and process raw input
+    ///
+    /// Returns `Ok(None)` if the input was ignored.
+    ///
+    /// # Errors
+    /// Whatever the inner machine's `step` returns.
+    pub fn step(&mut self, raw: J) -> Result<Option<M::Output>, M::Error> {
+        match (self.map)(raw) {
+            Some(input) => self.machine.step(input).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Get wrapped machine
+    pub fn machine(&self) -> &M {
+        &self.machine
+    }
+
+    /// Consume adapter, returning the machine
+    pub fn into_inner(self) -> M {
+        self.machine
+    }
+}
+
+impl<M, F, J> Machine for MapInput<M, F, J>
+where
+    M: Machine,
+    F: Fn(J) -> Option<M::Input>,
+{
+    type Input = J;
+    type Output = Option<M::Output>;
+    type Error = M::Error;
+
+    fn step(&mut self, raw: J) -> Result<Option<M::Output>, M::Error> {
+        MapInput::step(self, raw)
+    }
+
+    fn current_state(&self) -> u8 {
+        self.machine.current_state()
+    }
+
+    fn reset(&mut self, state: u8) {
+        self.machine.reset(state)
+    }
+}
+
+/// Machine whose inputs must be sampled `N` times in a row to count
+///
+/// Feed every raw sample (e.g. a button level read each millisecond) to
+/// `sample`. An input is forwarded once it has been seen `N` consecutive
+/// times and differs from the last forwarded input, so contact bounce
+/// neither gets through nor repeats an input.
+///
+/// # Example
+/// ```
+/// use fsmall::adapt::Debounce;
+/// use fsmall::Moore;
+///
+/// static TRANSITIONS: [(u8, bool, u8); 2] = [(0, true, 1), (1, false, 0)];
+/// static OUTPUTS: [&str; 2] = ["released", "pressed"];
+///
+/// let mut button: Debounce<_, 3> = Debounce::new(Moore::new(0, &TRANSITIONS, &OUTPUTS));
+/// for level in [true, false, true, true] {
+///     assert_eq!(button.sample(level), Ok(None)); // Bouncing
+/// }
+/// assert_eq!(button.sample(true), Ok(Some("pressed")));
+/// assert_eq!(button.sample(true), Ok(None)); // Held
+/// ```
+pub struct Debounce<M: Machine, const N: usize> {
+    machine: M,
+    // Input of the current run of equal samples and its length
+    candidate: Option<M::Input>,
+    count: usize,
+    forwarded: Option<M::Input>,
+}
+
+impl<M, const N: usize> Debounce<M, N>
+where
+    M: Machine,
+    M::Input: Copy + Eq,
+{
+    /// Wrap `machine`, requiring `N` equal samples per input
+    pub fn new(machine: M) -> Self {
+        Debounce {
+            machine,
+            candidate: None,
+            count: 0,
+            forwarded: None,
+        }
+    }
+
+    /// Record a raw sample, forwarding it once it is stable
+    ///
+    /// Returns `Ok(None)` if nothing was forwarded.
+    ///
+    /// # Errors
+    /// Whatever the inner machine's `step` returns.
+    pub fn sample(&mut self, input: M::Input) -> Result<Option<M::Output>, M::Error> {
+        if self.candidate == Some(input) {
+            self.count = self.count.saturating_add(1);
+        } else {
+            self.candidate = Some(input);
+            self.count = 1;
+        }
+        if self.count < N || self.forwarded == Some(input) {
+            return Ok(None);
+        }
+        self.forwarded = Some(input);
+        self.machine.step(input).map(Some)
+    }
+
+    /// Get wrapped machine
+    pub fn machine(&self) -> &M {
+        &self.machine
+    }
+
+    /// Consume adapter, returning the machine
+    pub fn into_inner(self) -> M {
+        self.machine
+    }
+}
+
+impl<M, const N: usize> Machine for Debounce<M, N>
+where
+    M: Machine,
+    M::Input: Copy + Eq,
+{
+    type Input = M::Input;
+    type Output = Option<M::Output>;
+    type Error = M::Error;
+
+    fn step(&mut self, input: M::Input) -> Result<Option<M::Output>, M::Error> {
+        self.sample(input)
+    }
+
+    fn current_state(&self) -> u8 {
+        self.machine.current_state()
+    }
+
+    /// Reset the machine and forget all samples
+    fn reset(&mut self, state: u8) {
+        self.machine.reset(state);
+        self.candidate = None;
+        self.count = 0;
+        self.forwarded = None;
+    }
+}
+
+/// Machine driven by a sampled level, seeing only its edges
+///
+/// Feed every sampled level to `sample`; a low-to-high change forwards
+/// `rising`, a high-to-low change forwards `falling`. Put a `Debounce` in
+/// front for mechanical contacts.
+///
+/// # Example
+/// ```
+/// use fsmall::adapt::Edges;
+/// use fsmall::Mealy;
+///
+/// #[derive(Copy, Clone, Eq, PartialEq)]
+/// enum Input { Rise, Fall }
+///
+/// static TRANSITIONS: [(u8, Input, u8); 2] = [(0, Input::Rise, 1), (1, Input::Fall, 0)];
+/// static OUTPUTS: [(u8, Input, &str); 2] = [(0, Input::Rise, "up"), (1, Input::Fall, "down")];
+///
+/// let mut pin = Edges::new(Mealy::new(0, &TRANSITIONS, &OUTPUTS), false, Input::Rise, Input::Fall);
+/// assert_eq!(pin.sample(false), Ok(None));
+/// assert_eq!(pin.sample(true), Ok(Some("up")));
+/// assert_eq!(pin.sample(true), Ok(None));
+/// assert_eq!(pin.sample(false), Ok(Some("down")));
+/// ```
+pub struct Edges<M: Machine> {
+    machine: M,
+    level: bool,
+    rising: M::Input,
+    falling: M::Input,
+}
+
+impl<M> Edges<M>
+where
+    M: Machine,
+    M::Input: Copy,
+{
+    /// Wrap `machine`, starting from `initial_level`
+    ///
+    /// # Arguments
+    /// * `initial_level` - Level assumed before the first sample
+    /// * `rising` - Input forwarded on a low-to-high change
+    /// * `falling` - Input forwarded on a high-to-low change
+    pub fn new(machine: M, initial_level: bool, rising: M::Input, falling: M::Input) -> Self {
+        Edges {
+            machine,
+            level: initial_level,
+            rising,
+            falling,
+        }
+    }
+
+    /// Record a sampled level, forwarding an edge if it changed
+    ///
+    /// Returns `Ok(None)` if the level did not change.
+    ///
+    /// # Errors
+    /// Whatever the inner machine's `step` returns.
+    pub fn sample(&mut self, level: bool) -> Result<Option<M::Output>, M::Error> {
+        if level == self.level {
+            return Ok(None);
+        }
+        self.level = level;
+        let edge = if level { self.rising } else { self.falling };
+        self.machine.step(edge).map(Some)
+    }
+
+    /// Last sampled level
+    pub fn level(&self) -> bool {
+        self.level
+    }
+
+    /// Get wrapped machine
+    pub fn machine(&self) -> &M {
+        &self.machine
+    }
+
+    /// Consume adapter, returning the machine
+    pub fn into_inner(self) -> M {
+        self.machine
+    }
+}
+
+impl<M> Machine for Edges<M>
+where
+    M: Machine,
+    M::Input: Copy,
+{
+    type Input = bool;
+    type Output = Option<M::Output>;
+    type Error = M::Error;
+
+    fn step(&mut self, level: bool) -> Result<Option<M::Output>, M::Error> {
+        self.sample(level)
+    }
+
+    fn current_state(&self) -> u8 {
+        self.machine.current_state()
+    }
+
+    fn reset(&mut self, state: u8) {
+        self.machine.reset(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Moore, StepError};
+
+    static TRANS: [(u8, bool, u8); 2] = [(0, true, 1), (1, false, 0)];
+    static OUTS: [u8; 2] = [10, 20];
+
+    #[test]
+    fn map_input_converts_and_ignores() {
+        let mut fsm = MapInput::new(Moore::new(0, &TRANS, &OUTS), |b: u8| match b {
+            b'1' => Some(true),
+            b'0' => Some(false),
+            _ => None,
+        });
+        assert_eq!(fsm.step(b'x'), Ok(None));
+        assert_eq!(fsm.step(b'0'), Err(StepError::NoTransition));
+        assert_eq!(fsm.step(b'1'), Ok(Some(20)));
+        assert_eq!(Machine::current_state(&fsm), 1);
+        assert_eq!(fsm.into_inner().current_state(), 1);
+    }
+
+    #[test]
+    fn debounce_ignores_glitches() {
+        let mut fsm: Debounce<_, 2> = Debounce::new(Moore::new(0, &TRANS, &OUTS));
+        assert_eq!(fsm.sample(true), Ok(None));
+        assert_eq!(fsm.sample(true), Ok(Some(20)));
+        // Single-sample glitch, then the same level again
+        assert_eq!(fsm.sample(false), Ok(None));
+        assert_eq!(fsm.sample(true), Ok(None));
+        assert_eq!(fsm.sample(true), Ok(None));
+        assert_eq!(fsm.sample(false), Ok(None));
+        assert_eq!(fsm.sample(false), Ok(Some(10)));
+        assert_eq!(fsm.machine().current_state(), 0);
+    }
+
+    #[test]
+    fn edges_respect_initial_level() {
+        // Starting high, the first low sample is a falling edge
+        let mut fsm = Edges::new(Moore::new(1, &TRANS, &OUTS), true, true, false);
+        assert_eq!(fsm.sample(true), Ok(None));
+        assert_eq!(fsm.sample(false), Ok(Some(10)));
+        assert!(!fsm.level());
+        assert_eq!(fsm.sample(true), Ok(Some(20)));
+        assert_eq!(fsm.into_inner().current_state(), 1);
+    }
+}
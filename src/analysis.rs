@@ -0,0 +1,851 @@
+//! Static analysis of transition tables
+//!
+//! Functions here take a transition table, (from_state, input, to_state),
+//! and work without allocating, except `sccs` and `equivalent` which
+//! require the `alloc` feature.
+
+#[cfg(feature = "alloc")]
+use alloc::collections::{BTreeMap, VecDeque};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use crate::InputIndex;
+#[cfg(feature = "alloc")]
+use crate::Machine;
+
+/// Iterate states, in ascending order, that appear in `transitions` but have
+/// no outgoing row
+///
+/// # Example
+/// ```
+/// use fsmall::analysis::terminal_states;
+///
+/// // Downloading (0) ends in Done (1) or Failed (2)
+/// static TRANSITIONS: [(u8, char, u8); 3] = [(0, 'c', 0), (0, 'e', 1), (0, 'x', 2)];
+/// assert!(terminal_states(&TRANSITIONS).eq([1, 2]));
+/// ```
+pub fn terminal_states<I>(transitions: &[(u8, I, u8)]) -> impl Iterator<Item = u8> + '_ {
+    (0..=u8::MAX).filter(move |state| {
+        let mut mentioned = false;
+        for (from, _input, to) in transitions {
+            if from == state {
+                return false;
+            }
+            mentioned |= to == state;
+        }
+        mentioned
+    })
+}
+
+/// Check whether `state` has no outgoing row in `transitions`
+pub fn is_terminal<I>(transitions: &[(u8, I, u8)], state: u8) -> bool {
+    !transitions.iter().any(|(from, _input, _to)| *from == state)
+}
+
+/// Set of states, one bit per possible `u8` state
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct StateSet {
+    bits: [u64; 4],
+}
+
+impl StateSet {
+    /// Create empty set
+    pub const fn new() -> Self {
+        StateSet { bits: [0; 4] }
+    }
+
+    /// Add `state`, returning whether it was newly added
+    pub fn insert(&mut self, state: u8) -> bool {
+        let mask = 1 << (state & 63);
+        match self.bits.get_mut(usize::from(state >> 6)) {
+            Some(word) if *word & mask == 0 => {
+                *word |= mask;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Check whether `state` is in the set
+    pub fn contains(&self, state: u8) -> bool {
+        let word = self.bits.get(usize::from(state >> 6)).copied().unwrap_or(0);
+        word & (1 << (state & 63)) != 0
+    }
+
+    /// Number of states in the set
+    pub fn len(&self) -> usize {
+        self.bits.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// Check whether the set is empty
+    pub fn is_empty(&self) -> bool {
+        self.bits == [0; 4]
+    }
+
+    /// Iterate states in ascending order
+    pub fn iter(&self) -> impl Iterator<Item = u8> + '_ {
+        (0..=u8::MAX).filter(|state| self.contains(*state))
+    }
+
+    /// States in `self` but not in `other`
+    pub fn difference(&self, other: &StateSet) -> StateSet {
+        let mut bits = self.bits;
+        for (word, other) in bits.iter_mut().zip(other.bits) {
+            *word &= !other;
+        }
+        StateSet { bits }
+    }
+}
+
+impl FromIterator<u8> for StateSet {
+    fn from_iter<T: IntoIterator<Item = u8>>(iter: T) -> Self {
+        let mut set = StateSet::new();
+        for state in iter {
+            set.insert(state);
+        }
+        set
+    }
+}
+
+/// States mentioned in `transitions`, as source or target
+pub fn states<I>(transitions: &[(u8, I, u8)]) -> StateSet {
+    transitions
+        .iter()
+        .flat_map(|(from, _input, to)| [*from, *to])
+        .collect()
+}
+
+/// States reachable from `initial`, including itself
+pub fn reachable<I>(transitions: &[(u8, I, u8)], initial: u8) -> StateSet {
+    let mut set = StateSet::new();
+    set.insert(initial);
+    grow(&mut set, transitions, |(from, _input, to)| (*from, *to));
+    set
+}
+
+/// States from which at least one of `targets` is reachable, including the
+/// targets themselves
+pub fn can_reach<I>(transitions: &[(u8, I, u8)], targets: &[u8]) -> StateSet {
+    let mut set: StateSet = targets.iter().copied().collect();
+    grow(&mut set, transitions, |(from, _input, to)| (*to, *from));
+    set
+}
+
+/// States in `transitions` that cannot be reached from `initial`
+pub fn unreachable_states<I>(transitions: &[(u8, I, u8)], initial: u8) -> StateSet {
+    states(transitions).difference(&reachable(transitions, initial))
+}
+
+/// States in `transitions` from which none of the `good` states (e.g.
+/// accepting or idle states) can be reached
+///
+/// # Example
+/// ```
+/// use fsmall::analysis::trap_states;
+///
+/// // Typo: state 2 should lead back to idle (0) but loops on itself
+/// static TRANSITIONS: [(u8, char, u8); 4] = [(0, 'p', 1), (1, 'o', 0), (1, 'x', 2), (2, 'r', 2)];
+/// assert!(trap_states(&TRANSITIONS, &[0]).iter().eq([2]));
+/// ```
+pub fn trap_states<I>(transitions: &[(u8, I, u8)], good: &[u8]) -> StateSet {
+    states(transitions).difference(&can_reach(transitions, good))
+}
+
+/// Iterate every input of an `InputIndex` alphabet, in index order
+pub fn alphabet<I: InputIndex>() -> impl Iterator<Item = I> {
+    (0..I::COUNT).filter_map(I::from_index)
+}
+
+/// Iterate (state, input) pairs without a row, for every state with an
+/// outgoing row in `transitions`
+///
+/// Terminal states are skipped, see `terminal_states`.
+///
+/// # Example
+/// ```
+/// use fsmall::analysis::missing_transitions;
+/// use fsmall::input_index;
+///
+/// input_index! {
+///     #[derive(Copy, Clone, Eq, PartialEq, Debug)]
+///     enum Input { Coin, Push }
+/// }
+///
+/// // Forgot what pushing a locked turnstile does
+/// static TRANSITIONS: [(u8, Input, u8); 3] =
+///     [(0, Input::Coin, 1), (1, Input::Coin, 1), (1, Input::Push, 0)];
+/// assert!(missing_transitions(&TRANSITIONS).eq([(0, Input::Push)]));
+/// ```
+pub fn missing_transitions<I: InputIndex>(
+    transitions: &[(u8, I, u8)],
+) -> impl Iterator<Item = (u8, I)> + '_ {
+    let sources: StateSet = transitions
+        .iter()
+        .map(|(from, _input, _to)| *from)
+        .collect();
+    (0..=u8::MAX)
+        .filter(move |state| sources.contains(*state))
+        .flat_map(move |state| {
+            alphabet::<I>()
+                .filter(move |input| {
+                    !transitions
+                        .iter()
+                        .any(|(from, i, _to)| *from == state && i.index() == input.index())
+                })
+                .map(move |input| (state, input))
+        })
+}
+
+/// Check whether every state with an outgoing row has a row for every input
+pub fn is_complete<I: InputIndex>(transitions: &[(u8, I, u8)]) -> bool {
+    missing_transitions(transitions).next().is_none()
+}
+
+/// Build the per-state (offset, len) index of a transition table whose
+/// rows are grouped by state, for `Mealy::with_state_index` and
+/// `Moore::with_state_index`
+///
+/// States without rows get (0, 0).
+///
+/// # Panics
+/// If a state's rows are not contiguous, a state is not below `S`, or the
+/// table has more than `u16::MAX` rows; at compile time when evaluated in
+/// a `const` or `static`.
+///
+/// # Example
+/// ```
+/// use fsmall::analysis::state_index;
+/// use fsmall::Mealy;
+///
+/// static TRANSITIONS: [(u8, char, u8); 3] = [(0, 'a', 1), (0, 'b', 0), (1, 'a', 0)];
+/// static OUTPUTS: [(u8, char, u8); 3] = [(0, 'a', 1), (0, 'b', 2), (1, 'a', 3)];
+/// static INDEX: [(u16, u16); 2] = state_index(&TRANSITIONS);
+/// assert_eq!(INDEX, [(0, 2), (2, 1)]);
+///
+/// let mut fsm = Mealy::new(0, &TRANSITIONS, &OUTPUTS).with_state_index(&INDEX);
+/// assert_eq!(fsm.step('a'), Ok(1));
+/// assert_eq!(fsm.step('a'), Ok(3)); // Scans only state 1's row
+/// ```
+pub const fn state_index<I, const S: usize>(transitions: &[(u8, I, u8)]) -> [(u16, u16); S] {
+    assert!(
+        transitions.len() <= u16::MAX as usize,
+        "too many rows for a u16 index"
+    );
+    let mut index = [(0, 0); S];
+    let mut row = 0;
+    while row < transitions.len() {
+        let state = transitions[row].0 as usize;
+        assert!(state < S, "state out of range of the index");
+        assert!(index[state].1 == 0, "rows of a state must be contiguous");
+        let start = row;
+        while row < transitions.len() && transitions[row].0 as usize == state {
+            row += 1;
+        }
+        index[state] = (start as u16, (row - start) as u16);
+    }
+    index
+}
+
+/// Scratch space for `shortest_path`, reusable across calls
+#[derive(Debug, Clone)]
+pub struct PathScratch {
+    // parent[state] = previous state on a shortest path to it
+    parent: [u8; 256],
+    // States of the found path, target first
+    path: [u8; 256],
+}
+
+impl PathScratch {
+    /// Create scratch space, usable in a `static`
+    pub const fn new() -> Self {
+        PathScratch {
+            parent: [0; 256],
+            path: [0; 256],
+        }
+    }
+}
+
+impl Default for PathScratch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Find a shortest input sequence leading from state `from` to state `to`
+///
+/// Breadth-first search over `transitions`, without allocating. Only rows
+/// `step` would take are followed: when several rows share (from_state,
+/// input), the first one wins. Returns `None` if `to` is unreachable and an
+/// empty sequence if `from == to`.
+///
+/// # Example
+/// ```
+/// use fsmall::analysis::{shortest_path, PathScratch};
+///
+/// static TRANSITIONS: [(u8, char, u8); 4] = [(0, 'a', 1), (1, 'b', 2), (2, 'c', 3), (0, 'x', 2)];
+/// let mut scratch = PathScratch::new();
+/// let path = shortest_path(&TRANSITIONS, 0, 3, &mut scratch).unwrap();
+/// assert!(path.eq(['x', 'c']));
+/// ```
+pub fn shortest_path<'a, I: Copy + Eq>(
+    transitions: &'a [(u8, I, u8)],
+    from: u8,
+    to: u8,
+    scratch: &'a mut PathScratch,
+) -> Option<impl Iterator<Item = I> + 'a> {
+    let mut visited = StateSet::new();
+    visited.insert(from);
+    let mut frontier = visited;
+    while !visited.contains(to) {
+        let mut next = StateSet::new();
+        for (row, (source, _input, target)) in transitions.iter().enumerate() {
+            // Skip rows shadowed by an earlier one for (source, input)
+            if frontier.contains(*source)
+                && takes_row(transitions, *source, row)
+                && visited.insert(*target)
+            {
+                next.insert(*target);
+                if let Some(parent) = scratch.parent.get_mut(usize::from(*target)) {
+                    *parent = *source;
+                }
+            }
+        }
+        if next.is_empty() {
+            return None;
+        }
+        frontier = next;
+    }
+
+    // Walk parents back from the target, each state is visited once so the
+    // path holds at most 256 states
+    let mut len = 0;
+    let mut state = to;
+    for slot in scratch.path.iter_mut() {
+        *slot = state;
+        len += 1;
+        if state == from {
+            break;
+        }
+        state = scratch
+            .parent
+            .get(usize::from(state))
+            .copied()
+            .unwrap_or(from);
+    }
+
+    let path = scratch.path.get(..len).unwrap_or(&[]);
+    Some(path.windows(2).rev().filter_map(move |pair| {
+        let (prev, next) = (pair.get(1)?, pair.first()?);
+        transitions
+            .iter()
+            .enumerate()
+            .find(|(row, (source, _input, target))| {
+                source == prev && target == next && takes_row(transitions, *prev, *row)
+            })
+            .map(|(_row, (_source, input, _target))| *input)
+    }))
+}
+
+/// Visit every input sequence of 1 to `D` steps starting in state `from`
+///
+/// `visit` receives each sequence with the state it ends in, in depth-first
+/// order. Only rows `step` would take are followed: when several rows share
+/// (from_state, input), the first one wins. The search uses a fixed-size
+/// stack of depth `D` and does not allocate; the number of sequences grows
+/// exponentially with `D`.
+///
+/// # Example
+/// ```
+/// use fsmall::analysis::paths;
+///
+/// static TRANSITIONS: [(u8, char, u8); 3] = [(0, 'a', 1), (0, 'b', 0), (1, 'c', 0)];
+/// let mut count = 0;
+/// paths::<_, 2>(&TRANSITIONS, 0, |inputs, _state| {
+///     assert!(inputs.len() <= 2);
+///     count += 1;
+/// });
+/// assert_eq!(count, 5); // a, ac, b, ba, bb
+/// ```
+pub fn paths<I: Copy + Eq, const D: usize>(
+    transitions: &[(u8, I, u8)],
+    from: u8,
+    mut visit: impl FnMut(&[I], u8),
+) {
+    let Some((_from, fill, _to)) = transitions.first() else {
+        return;
+    };
+    let mut inputs = [*fill; D];
+    // states[d] = state after d inputs, next[d] = next row to try there
+    let mut states = [from; D];
+    let mut next = [0; D];
+    let mut depth = 0;
+
+    loop {
+        let state = states.get(depth).copied();
+        let start = next.get(depth).copied().unwrap_or(0);
+        let found = state.and_then(|state| {
+            (start..transitions.len()).find(|row| takes_row(transitions, state, *row))
+        });
+        match found.and_then(|row| Some((row, transitions.get(row)?))) {
+            Some((row, (_from, input, to))) => {
+                if let (Some(n), Some(slot)) = (next.get_mut(depth), inputs.get_mut(depth)) {
+                    *n = row + 1;
+                    *slot = *input;
+                }
+                depth += 1;
+                visit(inputs.get(..depth).unwrap_or(&[]), *to);
+                if let (Some(s), Some(n)) = (states.get_mut(depth), next.get_mut(depth)) {
+                    *s = *to;
+                    *n = 0;
+                }
+            }
+            None if depth == 0 => return,
+            None => depth -= 1,
+        }
+    }
+}
+
+// Whether `step` in `state` would take `row`, i.e. no earlier row matches
+pub(crate) fn takes_row<I: Eq>(transitions: &[(u8, I, u8)], state: u8, row: usize) -> bool {
+    let Some((from, input, _to)) = transitions.get(row) else {
+        return false;
+    };
+    *from == state
+        && !transitions
+            .iter()
+            .take(row)
+            .any(|(f, i, _t)| *f == state && i == input)
+}
+
+/// Condensation of the transition graph: its strongly connected components
+/// and the edges between them
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Condensation {
+    components: Vec<StateSet>,
+    edges: Vec<(usize, usize)>,
+}
+
+#[cfg(feature = "alloc")]
+impl Condensation {
+    /// Components in topological order, no edge leads to an earlier one
+    pub fn components(&self) -> &[StateSet] {
+        &self.components
+    }
+
+    /// Index of the component containing `state`
+    pub fn component_of(&self, state: u8) -> Option<usize> {
+        self.components.iter().position(|c| c.contains(state))
+    }
+
+    /// Edges between distinct components, (from, to) indices, sorted and
+    /// without duplicates
+    pub fn edges(&self) -> &[(usize, usize)] {
+        &self.edges
+    }
+
+    /// Components with no edge out; every run eventually settles in one
+    pub fn sinks(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.components.len()).filter(|c| !self.edges.iter().any(|(from, _to)| from == c))
+    }
+
+    /// Check whether every state can reach every other state
+    pub fn is_strongly_connected(&self) -> bool {
+        self.components.len() <= 1
+    }
+}
+
+/// Compute strongly connected components of `transitions`
+///
+/// Each component is a set of states that can all reach each other, e.g. an
+/// operating mode. A machine can always return to idle if idle's component
+/// is the only sink.
+///
+/// # Example
+/// ```
+/// use fsmall::analysis::sccs;
+///
+/// // Boot (0) enters the idle/busy mode (1, 2), a fault (3) latches
+/// static TRANSITIONS: [(u8, char, u8); 5] = [(0, 'b', 1), (1, 'g', 2), (2, 'd', 1), (2, 'f', 3), (3, 'f', 3)];
+/// let graph = sccs(&TRANSITIONS);
+/// assert_eq!(graph.components().len(), 3);
+/// assert_eq!(graph.component_of(1), graph.component_of(2));
+/// assert!(graph.sinks().eq([graph.component_of(3).unwrap()]));
+/// ```
+#[cfg(feature = "alloc")]
+pub fn sccs<I>(transitions: &[(u8, I, u8)]) -> Condensation {
+    let mut tarjan = Tarjan {
+        transitions,
+        index: [0; 256],
+        low: [0; 256],
+        on_stack: [false; 256],
+        stack: Vec::new(),
+        next: 0,
+        components: Vec::new(),
+    };
+    for state in states(transitions).iter() {
+        if tarjan.index[usize::from(state)] == 0 {
+            tarjan.visit(state);
+        }
+    }
+
+    // Tarjan finds sinks first
+    let mut components = tarjan.components;
+    components.reverse();
+    let mut graph = Condensation {
+        components,
+        edges: Vec::new(),
+    };
+    for (from, _input, to) in transitions {
+        if let (Some(from), Some(to)) = (graph.component_of(*from), graph.component_of(*to)) {
+            if from != to {
+                graph.edges.push((from, to));
+            }
+        }
+    }
+    graph.edges.sort_unstable();
+    graph.edges.dedup();
+    graph
+}
+
+// Tarjan's algorithm, indices start at 1 so 0 marks unvisited states
+#[cfg(feature = "alloc")]
+struct Tarjan<'a, I> {
+    transitions: &'a [(u8, I, u8)],
+    index: [u16; 256],
+    low: [u16; 256],
+    on_stack: [bool; 256],
+    stack: Vec<u8>,
+    next: u16,
+    components: Vec<StateSet>,
+}
+
+#[cfg(feature = "alloc")]
+impl<I> Tarjan<'_, I> {
+    fn visit(&mut self, state: u8) {
+        let v = usize::from(state);
+        self.next += 1;
+        self.index[v] = self.next;
+        self.low[v] = self.next;
+        self.stack.push(state);
+        self.on_stack[v] = true;
+
+        let transitions = self.transitions;
+        for (_from, _input, to) in transitions.iter().filter(|(from, _, _)| *from == state) {
+            let w = usize::from(*to);
+            if self.index[w] == 0 {
+                self.visit(*to);
+                self.low[v] = self.low[v].min(self.low[w]);
+            } else if self.on_stack[w] {
+                self.low[v] = self.low[v].min(self.index[w]);
+            }
+        }
+
+        if self.low[v] == self.index[v] {
+            let mut component = StateSet::new();
+            while let Some(member) = self.stack.pop() {
+                self.on_stack[usize::from(member)] = false;
+                component.insert(member);
+                if member == state {
+                    break;
+                }
+            }
+            self.components.push(component);
+        }
+    }
+}
+
+/// Input sequence on which two machines behave differently
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Counterexample<I> {
+    /// Inputs from the starting states; only the last one is answered
+    /// differently
+    pub inputs: Vec<I>,
+}
+
+/// Check that `a` and `b` answer every input sequence over `alphabet` the
+/// same way, starting from their current states
+///
+/// Answers match if both steps fail or both return equal outputs. States
+/// may be numbered differently. Explores pairs of states breadth-first by
+/// cloning the machines and resetting the clones, so the counterexample is
+/// a shortest one.
+///
+/// # Errors
+/// Returns a `Counterexample` if some input sequence tells them apart.
+///
+/// # Example
+/// ```
+/// use fsmall::analysis::equivalent;
+/// use fsmall::Mealy;
+///
+/// // Same toggle, states renumbered
+/// static T1: [(u8, char, u8); 2] = [(0, 't', 1), (1, 't', 0)];
+/// static O1: [(u8, char, bool); 2] = [(0, 't', true), (1, 't', false)];
+/// static T2: [(u8, char, u8); 2] = [(5, 't', 9), (9, 't', 5)];
+/// static O2: [(u8, char, bool); 2] = [(5, 't', true), (9, 't', false)];
+///
+/// let a = Mealy::new(0, &T1, &O1);
+/// let b = Mealy::new(5, &T2, &O2);
+/// assert!(equivalent(&a, &b, &['t']).is_ok());
+/// ```
+#[cfg(feature = "alloc")]
+pub fn equivalent<A, B>(a: &A, b: &B, alphabet: &[A::Input]) -> Result<(), Counterexample<A::Input>>
+where
+    A: Machine + Clone,
+    B: Machine<Input = A::Input, Output = A::Output> + Clone,
+    A::Input: Copy,
+    A::Output: PartialEq,
+{
+    let start = (a.current_state(), b.current_state());
+    // parent[pair] = (previous pair, input) of a shortest route to it
+    let mut parent = BTreeMap::new();
+    let mut queue = VecDeque::new();
+    parent.insert(start, None);
+    queue.push_back(start);
+
+    while let Some(pair) = queue.pop_front() {
+        for input in alphabet {
+            let (mut x, mut y) = (a.clone(), b.clone());
+            x.reset(pair.0);
+            y.reset(pair.1);
+            let (out_x, out_y) = (x.step(*input).ok(), y.step(*input).ok());
+
+            if out_x != out_y {
+                let mut inputs = Vec::from([*input]);
+                let mut at = pair;
+                while let Some(Some((prev, input))) = parent.get(&at) {
+                    inputs.push(*input);
+                    at = *prev;
+                }
+                inputs.reverse();
+                return Err(Counterexample { inputs });
+            }
+
+            let next = (x.current_state(), y.current_state());
+            if out_x.is_some() && !parent.contains_key(&next) {
+                parent.insert(next, Some((pair, *input)));
+                queue.push_back(next);
+            }
+        }
+    }
+    Ok(())
+}
+
+// Add `to` for every edge (from, to) = `edge(row)` with `from` in the set,
+// until nothing changes
+fn grow<I>(
+    set: &mut StateSet,
+    transitions: &[(u8, I, u8)],
+    edge: impl Fn(&(u8, I, u8)) -> (u8, u8),
+) {
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for row in transitions {
+            let (from, to) = edge(row);
+            if set.contains(from) {
+                changed |= set.insert(to);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static TRANS: [(u8, char, u8); 4] = [(0, 'a', 1), (1, 'b', 0), (1, 'c', 5), (0, 'd', 200)];
+
+    #[test]
+    fn terminal_states_are_targets_without_rows() {
+        assert!(terminal_states(&TRANS).eq([5, 200]));
+        assert_eq!(terminal_states::<char>(&[]).count(), 0);
+    }
+
+    #[test]
+    fn is_terminal_checks_outgoing_rows() {
+        assert!(!is_terminal(&TRANS, 0));
+        assert!(is_terminal(&TRANS, 5));
+        // Unknown states have no rows either
+        assert!(is_terminal(&TRANS, 9));
+    }
+
+    #[test]
+    fn state_set_basics() {
+        let mut set = StateSet::new();
+        assert!(set.insert(0) && set.insert(200) && !set.insert(0));
+        assert!(set.contains(200) && !set.contains(199));
+        assert_eq!(set.len(), 2);
+        assert!(set.iter().eq([0, 200]));
+        assert!(set.difference(&[0].into_iter().collect()).iter().eq([200]));
+    }
+
+    #[test]
+    fn traps_differ_from_unreachable() {
+        // 3 is unreachable from 0 but can reach 0; 5 and 200 are traps
+        static GRAPH: [(u8, char, u8); 5] = [
+            (0, 'a', 1),
+            (1, 'b', 0),
+            (1, 'c', 5),
+            (0, 'd', 200),
+            (3, 'e', 0),
+        ];
+        assert!(unreachable_states(&GRAPH, 0).iter().eq([3]));
+        assert!(trap_states(&GRAPH, &[0]).iter().eq([5, 200]));
+        assert!(reachable(&GRAPH, 1).iter().eq([0, 1, 5, 200]));
+    }
+
+    #[test]
+    fn state_index_keeps_row_numbers() {
+        use crate::{Mealy, Moore, StepError};
+
+        static GROUPED: [(u8, char, u8); 5] = [
+            (0, 'a', 1),
+            (0, 'a', 2),
+            (1, 'b', 0),
+            (2, 'c', 2),
+            (2, 'b', 0),
+        ];
+        static OUTS: [(u8, char, u8); 4] = [(0, 'a', 1), (1, 'b', 2), (2, 'c', 3), (2, 'b', 4)];
+        static LABELS: [Option<&str>; 5] = [None, None, Some("back"), None, Some("reset")];
+        const INDEX: [(u16, u16); 3] = state_index(&GROUPED);
+        assert_eq!(INDEX, [(0, 2), (2, 1), (3, 2)]);
+
+        let mut indexed = Mealy::new(0, &GROUPED, &OUTS)
+            .with_labels(&LABELS)
+            .with_state_index(&INDEX);
+        assert_eq!(indexed.step('a'), Ok(1));
+        assert_eq!(indexed.step('b'), Ok(2));
+        assert_eq!(indexed.last_label(), Some("back"));
+        assert_eq!(indexed.step('c'), Err(StepError::NoTransition));
+
+        // States past the end of the index have no rows
+        let mut moore = Moore::new(2, &GROUPED, &[0, 1, 2]).with_state_index(&INDEX[..2]);
+        assert_eq!(moore.step('b'), Err(StepError::NoTransition));
+    }
+
+    #[test]
+    fn missing_transitions_cover_alphabet() {
+        static BITS: [(u8, bool, u8); 3] = [(0, false, 0), (0, true, 1), (2, true, 0)];
+        assert!(missing_transitions(&BITS).eq([(2, false)]));
+        assert!(!is_complete(&BITS));
+        assert!(is_complete(&BITS[..2]));
+        assert_eq!(alphabet::<u8>().count(), 256);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn sccs_in_topological_order() {
+        static GRAPH: [(u8, char, u8); 6] = [
+            (3, 'a', 0),
+            (0, 'b', 1),
+            (1, 'c', 0),
+            (1, 'd', 2),
+            (0, 'e', 2),
+            (2, 'f', 2),
+        ];
+        let graph = sccs(&GRAPH);
+        let components: Vec<Vec<u8>> = graph
+            .components()
+            .iter()
+            .map(|c| c.iter().collect())
+            .collect();
+        assert_eq!(components, [&[3][..], &[0, 1], &[2]]);
+        assert_eq!(graph.edges(), [(0, 1), (1, 2)]);
+        assert!(!graph.is_strongly_connected());
+        assert!(graph.sinks().eq([2]));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn sccs_ring_is_strongly_connected() {
+        static RING: [(u8, char, u8); 3] = [(0, 'n', 1), (1, 'n', 2), (2, 'n', 0)];
+        let graph = sccs(&RING);
+        assert!(graph.is_strongly_connected());
+        assert_eq!(graph.component_of(2), Some(0));
+        assert_eq!(graph.component_of(9), None);
+    }
+
+    #[test]
+    fn shortest_path_prefers_fewest_steps() {
+        static GRAPH: [(u8, char, u8); 5] = [
+            (0, 'a', 1),
+            (1, 'b', 2),
+            (2, 'c', 3),
+            (1, 'd', 3),
+            (4, 'e', 0),
+        ];
+        let mut scratch = PathScratch::new();
+        assert!(shortest_path(&GRAPH, 0, 3, &mut scratch)
+            .unwrap()
+            .eq(['a', 'd']));
+        assert!(shortest_path(&GRAPH, 2, 2, &mut scratch).unwrap().eq([]));
+        assert!(shortest_path(&GRAPH, 0, 4, &mut scratch).is_none());
+    }
+
+    #[test]
+    fn shortest_path_skips_shadowed_rows() {
+        let mut scratch = PathScratch::new();
+        let shadowed = [(0, 'a', 2), (0, 'a', 1), (0, 'b', 1)];
+        assert!(shortest_path(&shadowed, 0, 1, &mut scratch)
+            .unwrap()
+            .eq(['b']));
+        let unreachable = [(0, 'a', 0), (0, 'a', 1)];
+        assert!(shortest_path(&unreachable, 0, 1, &mut scratch).is_none());
+    }
+
+    #[test]
+    fn paths_enumerates_up_to_depth() {
+        // Row 2 is shadowed by row 0
+        static GRAPH: [(u8, char, u8); 4] = [(0, 'a', 1), (1, 'b', 0), (0, 'a', 2), (1, 'c', 3)];
+        let mut seen = [[' '; 3]; 8];
+        let mut ends = [0; 8];
+        let mut count = 0;
+        paths::<_, 3>(&GRAPH, 0, |inputs, state| {
+            seen[count][..inputs.len()].copy_from_slice(inputs);
+            ends[count] = state;
+            count += 1;
+        });
+        assert_eq!(count, 4);
+        assert_eq!(
+            &seen[..4],
+            [
+                ['a', ' ', ' '],
+                ['a', 'b', ' '],
+                ['a', 'b', 'a'],
+                ['a', 'c', ' ']
+            ]
+        );
+        assert_eq!(&ends[..4], [1, 0, 1, 3]);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn equivalent_finds_shortest_counterexample() {
+        use crate::{Mealy, MealyConst};
+
+        static TRANS: [(u8, char, u8); 4] = [(0, 'a', 1), (1, 'a', 2), (2, 'a', 0), (2, 'b', 0)];
+        static OUTS: [(u8, char, u8); 4] = [(0, 'a', 0), (1, 'a', 0), (2, 'a', 1), (2, 'b', 7)];
+        let a = Mealy::new(0, &TRANS, &OUTS);
+
+        // Same table, but 'b' in state 2 outputs 8
+        let b = MealyConst::new(
+            0,
+            [(0, 'a', 1), (1, 'a', 2), (2, 'a', 0), (2, 'b', 0)],
+            [(0, 'a', 0), (1, 'a', 0), (2, 'a', 1), (2, 'b', 8)],
+        );
+        let err = equivalent(&a, &b, &['a', 'b']).unwrap_err();
+        assert_eq!(err.inputs, ['a', 'a', 'b']);
+
+        // Missing row shows up as a failed step on one side only
+        static SHORT: [(u8, char, u8); 3] = [(0, 'a', 1), (1, 'a', 2), (2, 'a', 0)];
+        let c = Mealy::new(0, &SHORT, &OUTS);
+        assert_eq!(
+            equivalent(&a, &c, &['a', 'b']).unwrap_err().inputs,
+            ['a', 'a', 'b']
+        );
+        assert!(equivalent(&a, &a.clone(), &['a', 'b']).is_ok());
+    }
+}
@@ -0,0 +1,202 @@
+//! Per-frame stimulus arbitration for game loops
+//!
+//! A game steps many small machines (NPC brains) once per frame. During
+//! the frame each one is offered stimuli with a priority, e.g. "enemy in
+//! sight" over "heard a noise"; at the end of the frame `resolve` steps it
+//! with the strongest stimulus it accepts and forgets the rest.
+
+use crate::Machine;
+
+/// Machine wrapper keeping the `N` strongest stimuli offered this frame
+///
+/// Stimuli are tried from strongest to weakest, equal priorities in offer
+/// order, until the machine accepts one. Fallback assumes a rejected input
+/// leaves the machine unchanged, as under the default `ErrorPolicy::Reject`.
+///
+/// # Example
+/// ```
+/// use fsmall::arbiter::Arbiter;
+/// use fsmall::Mealy;
+///
+/// #[derive(Copy, Clone, Eq, PartialEq, Debug)]
+/// enum Stimulus { Noise, Enemy, Food }
+///
+/// // 0: wander, 1: investigate, 2: flee; fleeing ignores noise
+/// static TRANSITIONS: [(u8, Stimulus, u8); 3] = [
+///     (0, Stimulus::Noise, 1),
+///     (0, Stimulus::Enemy, 2),
+///     (1, Stimulus::Enemy, 2),
+/// ];
+///
+/// let mut brain: Arbiter<_, 2> = Arbiter::new(Mealy::from_output_fn(0, &TRANSITIONS, |_, i| i));
+/// brain.offer(Stimulus::Noise, 1);
+/// brain.offer(Stimulus::Enemy, 9);
+/// brain.offer(Stimulus::Food, 0); // Weaker than both, dropped
+/// assert_eq!(brain.resolve(), Some(Ok(Stimulus::Enemy)));
+/// assert_eq!(brain.resolve(), None); // Stimuli last one frame
+/// ```
+#[derive(Debug, Clone)]
+pub struct Arbiter<M: Machine, const N: usize> {
+    machine: M,
+    // (priority, stimulus) by descending priority, [0..len) occupied
+    stimuli: [Option<(u8, M::Input)>; N],
+    len: usize,
+}
+
+impl<M: Machine, const N: usize> Arbiter<M, N> {
+    /// Wrap `machine` with no stimuli pending
+    pub fn new(machine: M) -> Self {
+        Arbiter {
+            machine,
+            stimuli: core::array::from_fn(|_| None),
+            len: 0,
+        }
+    }
+
+    /// Offer a stimulus for this frame
+    ///
+    /// Once `N` stimuli are held, the weakest one is dropped to make room.
+    ///
+    /// # Errors
+    /// Returns the stimulus back if it is not stronger than any held one.
+    pub fn offer(&mut self, input: M::Input, priority: u8) -> Result<(), M::Input> {
+        let held = self.stimuli.get(..self.len).unwrap_or(&[]);
+        let at = held
+            .iter()
+            .position(|entry| entry.as_ref().is_some_and(|(p, _)| *p < priority))
+            .unwrap_or(self.len);
+        let Some(tail) = self.stimuli.get_mut(at..).filter(|tail| !tail.is_empty()) else {
+            return Err(input);
+        };
+        // Tail shifts down by one, the last entry falls off
+        tail.rotate_right(1);
+        if let Some(slot) = tail.first_mut() {
+            *slot = Some((priority, input));
+        }
+        self.len = (self.len + 1).min(N);
+        Ok(())
+    }
+
+    /// Step with the strongest accepted stimulus and clear the frame
+    ///
+    /// Returns `None` if nothing was offered, otherwise the first accepted
+    /// step result, or the error of the weakest stimulus if all were
+    /// rejected.
+    pub fn resolve(&mut self) -> Option<Result<M::Output, M::Error>> {
+        let len = core::mem::take(&mut self.len);
+        let mut last = None;
+        for entry in self.stimuli.iter_mut().take(len) {
+            let Some((_, input)) = entry.take() else {
+                continue;
+            };
+            match self.machine.step(input) {
+                Ok(output) => {
+                    self.clear();
+                    return Some(Ok(output));
+                }
+                Err(e) => last = Some(Err(e)),
+            }
+        }
+        last
+    }
+
+    /// Drop all stimuli offered this frame
+    pub fn clear(&mut self) {
+        self.stimuli.iter_mut().for_each(|entry| *entry = None);
+        self.len = 0;
+    }
+
+    /// Number of stimuli held
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check whether no stimuli are held
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get wrapped machine
+    pub fn machine(&self) -> &M {
+        &self.machine
+    }
+
+    /// Get wrapped machine mutably
+    pub fn machine_mut(&mut self) -> &mut M {
+        &mut self.machine
+    }
+
+    /// Consume arbiter, dropping held stimuli
+    pub fn into_inner(self) -> M {
+        self.machine
+    }
+}
+
+/// Resolve the frame of every arbiter in `brains`
+///
+/// Returns the number of machines that accepted a stimulus.
+pub fn resolve_all<M: Machine, const N: usize>(brains: &mut [Arbiter<M, N>]) -> usize {
+    brains
+        .iter_mut()
+        .map(|brain| brain.resolve())
+        .filter(|result| matches!(result, Some(Ok(_))))
+        .count()
+}
+
+impl<M: Machine, const N: usize> Machine for Arbiter<M, N> {
+    type Input = M::Input;
+    type Output = M::Output;
+    type Error = M::Error;
+
+    /// Step immediately, bypassing arbitration
+    fn step(&mut self, input: M::Input) -> Result<M::Output, M::Error> {
+        self.machine.step(input)
+    }
+
+    fn current_state(&self) -> u8 {
+        self.machine.current_state()
+    }
+
+    /// Reset the machine and drop held stimuli
+    fn reset(&mut self, state: u8) {
+        self.clear();
+        self.machine.reset(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Mealy, StepError};
+
+    static TRANS: [(u8, char, u8); 3] = [(0, 'a', 1), (0, 'b', 2), (1, 'c', 0)];
+
+    fn brain() -> Arbiter<Mealy<'static, char, char>, 2> {
+        Arbiter::new(Mealy::from_output_fn(0, &TRANS, |_, i| i))
+    }
+
+    #[test]
+    fn arbiter_falls_back_to_accepted_stimulus() {
+        let mut brain = brain();
+        assert_eq!(brain.offer('c', 5), Ok(()));
+        assert_eq!(brain.offer('b', 1), Ok(()));
+        assert_eq!(brain.offer('a', 1), Err('a')); // Ties keep offer order
+        assert_eq!(brain.offer('a', 3), Ok(()));
+        assert_eq!(brain.len(), 2);
+        assert_eq!(brain.resolve(), Some(Ok('a')));
+        assert!(brain.is_empty());
+
+        brain.offer('a', 0).unwrap();
+        brain.offer('b', 0).unwrap();
+        assert_eq!(brain.resolve(), Some(Err(StepError::NoTransition)));
+    }
+
+    #[test]
+    fn arbiter_resolves_crowd() {
+        let mut crowd = [brain(), brain(), brain()];
+        crowd[0].offer('a', 0).unwrap();
+        crowd[1].offer('c', 0).unwrap();
+        assert_eq!(resolve_all(&mut crowd), 1);
+        assert_eq!(crowd[0].machine().current_state(), 1);
+    }
+}
@@ -0,0 +1,252 @@
+//! Machines whose guards and actions may await
+//!
+//! Executor-agnostic: `AsyncMealy::step` is a plain future, so it runs
+//! under embassy or any other executor.
+
+use core::future::Future;
+
+use crate::StepError;
+
+/// Async guard and action hooks consulted by `AsyncMealy::step`
+///
+/// Both methods default to no-ops, implement only the ones you need.
+pub trait AsyncHooks<I> {
+    /// Decide whether the transition `from --input--> to` may commit
+    fn guard(&mut self, from: u8, input: I, to: u8) -> impl Future<Output = bool> {
+        let _ = (from, input, to);
+        async { true }
+    }
+
+    /// Run after the transition `from --input--> to` committed
+    fn action(&mut self, from: u8, input: I, to: u8) -> impl Future<Output = ()> {
+        let _ = (from, input, to);
+        async {}
+    }
+}
+
+impl<I> AsyncHooks<I> for () {}
+
+/// Mealy machine with awaitable guards and actions
+///
+/// # Example
+/// ```
+/// use fsmall::asynch::{AsyncHooks, AsyncMealy};
+///
+/// #[derive(Copy, Clone, Eq, PartialEq)]
+/// enum Input { Open }
+///
+/// struct Door;
+///
+/// impl AsyncHooks<Input> for Door {
+///     async fn guard(&mut self, _from: u8, _input: Input, _to: u8) -> bool {
+///         // e.g. await the motor controller's acknowledgment
+///         true
+///     }
+/// }
+///
+/// static TRANSITIONS: [(u8, Input, u8); 1] = [(0, Input::Open, 1)];
+/// static OUTPUTS: [(u8, Input, bool); 1] = [(0, Input::Open, true)];
+///
+/// async fn open() -> Result<bool, fsmall::StepError> {
+///     let mut fsm = AsyncMealy::new(0, &TRANSITIONS, &OUTPUTS, Door);
+///     fsm.step(Input::Open).await
+/// }
+/// ```
+pub struct AsyncMealy<'t, I, O, H> {
+    state: u8,
+    // Table: (from_state, input, to_state)
+    transitions: &'t [(u8, I, u8)],
+    // Table: (state, input, output)
+    outputs: &'t [(u8, I, O)],
+    hooks: H,
+}
+
+impl<'t, I: Copy + Eq, O: Copy, H: AsyncHooks<I>> AsyncMealy<'t, I, O, H> {
+    /// Create new async Mealy machine
+    ///
+    /// # Arguments
+    /// * `initial_state` - Starting state (0-255)
+    /// * `transitions` - Transition table: (from_state, input, to_state)
+    /// * `outputs` - Output table: (state, input, output)
+    /// * `hooks` - Guards and actions
+    pub fn new(
+        initial_state: u8,
+        transitions: &'t [(u8, I, u8)],
+        outputs: &'t [(u8, I, O)],
+        hooks: H,
+    ) -> Self {
+        AsyncMealy {
+            state: initial_state,
+            transitions,
+            outputs,
+            hooks,
+        }
+    }
+
+    /// Process input, awaiting guards before and the action after commit
+    ///
+    /// Several rows may share a (state, input) pair, the first row whose
+    /// guard passes wins. Dropping the future before a guard resolves
+    /// leaves the state unchanged.
+    ///
+    /// # Errors
+    /// * `StepError::NoTransition` - No rule for (state, input) whose guard passes
+    /// * `StepError::NoOutput` - No output for (state, input)
+    pub async fn step(&mut self, input: I) -> Result<O, StepError> {
+        let from = self.state;
+
+        let mut next = None;
+        for (_from, _input, to) in self
+            .transitions
+            .iter()
+            .filter(|(f, i, _to)| *f == from && *i == input)
+        {
+            if self.hooks.guard(from, input, *to).await {
+                next = Some(*to);
+                break;
+            }
+        }
+        let next = next.ok_or(StepError::NoTransition)?;
+
+        let output = self
+            .outputs
+            .iter()
+            .find(|(s, i, _o)| *s == from && *i == input)
+            .map(|(_s, _i, o)| *o)
+            .ok_or(StepError::NoOutput)?;
+
+        // Commit state transition
+        self.state = next;
+        self.hooks.action(from, input, next).await;
+
+        Ok(output)
+    }
+
+    /// Get current state
+    pub fn current_state(&self) -> u8 {
+        self.state
+    }
+
+    /// Reset to specific state
+    pub fn reset(&mut self, state: u8) {
+        self.state = state;
+    }
+
+    /// Borrow the hooks
+    pub fn hooks(&self) -> &H {
+        &self.hooks
+    }
+
+    /// Mutably borrow the hooks
+    pub fn hooks_mut(&mut self) -> &mut H {
+        &mut self.hooks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::pin::pin;
+    use core::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+    // Waker doing nothing, `Waker::noop` needs Rust 1.85
+    const NOOP: RawWakerVTable = RawWakerVTable::new(|_| RAW, |_| {}, |_| {}, |_| {});
+    const RAW: RawWaker = RawWaker::new(core::ptr::null(), &NOOP);
+
+    fn block_on<F: Future>(fut: F) -> F::Output {
+        let mut fut = pin!(fut);
+        // SAFETY: every vtable function ignores the null data pointer
+        let waker = unsafe { Waker::from_raw(RAW) };
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            if let Poll::Ready(out) = fut.as_mut().poll(&mut cx) {
+                return out;
+            }
+        }
+    }
+
+    // Yields once before resolving, like a peripheral not ready yet
+    struct YieldOnce(bool);
+
+    impl Future for YieldOnce {
+        type Output = ();
+        fn poll(mut self: core::pin::Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<()> {
+            if self.0 {
+                Poll::Ready(())
+            } else {
+                self.0 = true;
+                Poll::Pending
+            }
+        }
+    }
+
+    #[derive(Copy, Clone, Eq, PartialEq, Debug)]
+    enum TestInput {
+        Go,
+    }
+
+    // Two rows for (0, Go): to 1 if acknowledged, else to 2
+    static TRANS: [(u8, TestInput, u8); 2] = [(0, TestInput::Go, 1), (0, TestInput::Go, 2)];
+    static OUTS: [(u8, TestInput, u8); 1] = [(0, TestInput::Go, 7)];
+
+    struct Hw {
+        ack: bool,
+        actions: u8,
+    }
+
+    impl AsyncHooks<TestInput> for Hw {
+        async fn guard(&mut self, _from: u8, _input: TestInput, to: u8) -> bool {
+            YieldOnce(false).await;
+            to != 1 || self.ack
+        }
+
+        async fn action(&mut self, _from: u8, _input: TestInput, _to: u8) {
+            YieldOnce(false).await;
+            self.actions += 1;
+        }
+    }
+
+    #[test]
+    fn async_guard_selects_row() {
+        let mut fsm = AsyncMealy::new(
+            0,
+            &TRANS,
+            &OUTS,
+            Hw {
+                ack: true,
+                actions: 0,
+            },
+        );
+        assert_eq!(block_on(fsm.step(TestInput::Go)), Ok(7));
+        assert_eq!(fsm.current_state(), 1);
+        assert_eq!(fsm.hooks().actions, 1);
+
+        fsm.reset(0);
+        fsm.hooks_mut().ack = false;
+        assert_eq!(block_on(fsm.step(TestInput::Go)), Ok(7));
+        assert_eq!(fsm.current_state(), 2);
+    }
+
+    #[test]
+    fn async_no_transition_runs_no_action() {
+        let mut fsm = AsyncMealy::new(
+            1,
+            &TRANS,
+            &OUTS,
+            Hw {
+                ack: true,
+                actions: 0,
+            },
+        );
+        assert_eq!(
+            block_on(fsm.step(TestInput::Go)),
+            Err(StepError::NoTransition)
+        );
+        assert_eq!(fsm.current_state(), 1);
+        assert_eq!(fsm.hooks().actions, 0);
+
+        let mut plain = AsyncMealy::new(0, &TRANS, &OUTS, ());
+        assert_eq!(block_on(plain.step(TestInput::Go)), Ok(7));
+        assert_eq!(plain.current_state(), 1);
+    }
+}
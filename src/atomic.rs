@@ -0,0 +1,249 @@
+//! Machines whose state lives in an `AtomicU8`
+//!
+//! `step` takes `&self` and commits with compare-and-swap, so a machine in a
+//! `static` can be stepped from thread context while `current_state()` is
+//! read from an interrupt handler without a critical section. If another
+//! context commits first, the step is retried from the new state.
+
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use crate::{Machine, StepError};
+
+/// Mealy machine with atomic state
+///
+/// # Example
+/// ```
+/// use fsmall::atomic::AtomicMealy;
+///
+/// #[derive(Copy, Clone, Eq, PartialEq)]
+/// enum Input { Arm, Fire }
+///
+/// static TRANSITIONS: [(u8, Input, u8); 2] = [(0, Input::Arm, 1), (1, Input::Fire, 0)];
+/// static OUTPUTS: [(u8, Input, bool); 2] = [(0, Input::Arm, false), (1, Input::Fire, true)];
+///
+/// static FSM: AtomicMealy<Input, bool> = AtomicMealy::new(0, &TRANSITIONS, &OUTPUTS);
+///
+/// assert_eq!(FSM.step(Input::Arm), Ok(false));
+/// assert_eq!(FSM.current_state(), 1); // Safe to read from an ISR
+/// ```
+pub struct AtomicMealy<'t, I, O> {
+    state: AtomicU8,
+    // Table: (from_state, input, to_state)
+    transitions: &'t [(u8, I, u8)],
+    // Table: (state, input, output)
+    outputs: &'t [(u8, I, O)],
+}
+
+impl<'t, I: Copy + Eq, O: Copy> AtomicMealy<'t, I, O> {
+    /// Create new atomic Mealy machine, usable in a `static`
+    ///
+    /// # Arguments
+    /// * `initial_state` - Starting state (0-255)
+    /// * `transitions` - Transition table: (from_state, input, to_state)
+    /// * `outputs` - Output table: (state, input, output)
+    pub const fn new(
+        initial_state: u8,
+        transitions: &'t [(u8, I, u8)],
+        outputs: &'t [(u8, I, O)],
+    ) -> Self {
+        AtomicMealy {
+            state: AtomicU8::new(initial_state),
+            transitions,
+            outputs,
+        }
+    }
+
+    /// Process input, transition to next state, return output
+    ///
+    /// # Errors
+    /// * `StepError::NoTransition` - No rule for (state, input)
+    /// * `StepError::NoOutput` - No output for (state, input)
+    pub fn step(&self, input: I) -> Result<O, StepError> {
+        let mut state = self.state.load(Ordering::Acquire);
+        loop {
+            // Find next state in transition table
+            let next = self
+                .transitions
+                .iter()
+                .find(|(from, inp, _to)| *from == state && *inp == input)
+                .map(|(_from, _inp, to)| *to)
+                .ok_or(StepError::NoTransition)?;
+
+            // Find output in output table
+            let output = self
+                .outputs
+                .iter()
+                .find(|(s, i, _o)| *s == state && *i == input)
+                .map(|(_s, _i, o)| *o)
+                .ok_or(StepError::NoOutput)?;
+
+            // Commit unless another context moved the machine meanwhile
+            match self
+                .state
+                .compare_exchange(state, next, Ordering::AcqRel, Ordering::Acquire)
+            {
+                Ok(_) => return Ok(output),
+                Err(actual) => state = actual,
+            }
+        }
+    }
+
+    /// Get current state
+    pub fn current_state(&self) -> u8 {
+        self.state.load(Ordering::Acquire)
+    }
+
+    /// Reset to specific state
+    pub fn reset(&self, state: u8) {
+        self.state.store(state, Ordering::Release);
+    }
+}
+
+impl<I: Copy + Eq, O: Copy> Machine for AtomicMealy<'_, I, O> {
+    type Input = I;
+    type Output = O;
+    type Error = StepError;
+
+    fn step(&mut self, input: I) -> Result<O, StepError> {
+        AtomicMealy::step(self, input)
+    }
+
+    fn current_state(&self) -> u8 {
+        AtomicMealy::current_state(self)
+    }
+
+    fn reset(&mut self, state: u8) {
+        AtomicMealy::reset(self, state)
+    }
+}
+
+/// Moore machine with atomic state
+pub struct AtomicMoore<'t, I, O> {
+    state: AtomicU8,
+    // Table: (from_state, input, to_state)
+    transitions: &'t [(u8, I, u8)],
+    // Array: outputs[state] = output
+    outputs: &'t [O],
+}
+
+impl<'t, I: Copy + Eq, O: Copy> AtomicMoore<'t, I, O> {
+    /// Create new atomic Moore machine, usable in a `static`
+    ///
+    /// # Arguments
+    /// * `initial_state` - Starting state (0-255)
+    /// * `transitions` - Transition table: (from_state, input, to_state)
+    /// * `outputs` - Output array: index=state, value=output
+    pub const fn new(initial_state: u8, transitions: &'t [(u8, I, u8)], outputs: &'t [O]) -> Self {
+        AtomicMoore {
+            state: AtomicU8::new(initial_state),
+            transitions,
+            outputs,
+        }
+    }
+
+    /// Process input, transition to next state, return new state's output
+    ///
+    /// # Errors
+    /// * `StepError::NoTransition` - No rule for (state, input)
+    /// * `StepError::InvalidState` - Next state index out of bounds
+    pub fn step(&self, input: I) -> Result<O, StepError> {
+        let mut state = self.state.load(Ordering::Acquire);
+        let next = loop {
+            // Find next state in transition table
+            let next = self
+                .transitions
+                .iter()
+                .find(|(from, inp, _to)| *from == state && *inp == input)
+                .map(|(_from, _inp, to)| *to)
+                .ok_or(StepError::NoTransition)?;
+
+            // Commit unless another context moved the machine meanwhile
+            match self
+                .state
+                .compare_exchange(state, next, Ordering::AcqRel, Ordering::Acquire)
+            {
+                Ok(_) => break next,
+                Err(actual) => state = actual,
+            }
+        };
+
+        // Get output for new state
+        self.outputs
+            .get(next as usize)
+            .copied()
+            .ok_or(StepError::InvalidState)
+    }
+
+    /// Get current state
+    pub fn current_state(&self) -> u8 {
+        self.state.load(Ordering::Acquire)
+    }
+
+    /// Get current output (without transitioning)
+    pub fn current_output(&self) -> Result<O, StepError> {
+        self.outputs
+            .get(self.current_state() as usize)
+            .copied()
+            .ok_or(StepError::InvalidState)
+    }
+
+    /// Reset to specific state
+    pub fn reset(&self, state: u8) {
+        self.state.store(state, Ordering::Release);
+    }
+}
+
+impl<I: Copy + Eq, O: Copy> Machine for AtomicMoore<'_, I, O> {
+    type Input = I;
+    type Output = O;
+    type Error = StepError;
+
+    fn step(&mut self, input: I) -> Result<O, StepError> {
+        AtomicMoore::step(self, input)
+    }
+
+    fn current_state(&self) -> u8 {
+        AtomicMoore::current_state(self)
+    }
+
+    fn reset(&mut self, state: u8) {
+        AtomicMoore::reset(self, state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Copy, Clone, Eq, PartialEq, Debug)]
+    enum TestInput {
+        A,
+        B,
+    }
+
+    static TRANS: [(u8, TestInput, u8); 2] = [(0, TestInput::A, 1), (1, TestInput::B, 0)];
+
+    static MEALY_OUTS: [(u8, TestInput, char); 2] =
+        [(0, TestInput::A, 'x'), (1, TestInput::B, 'y')];
+
+    static MOORE_OUTS: [char; 2] = ['x', 'y'];
+
+    static SHARED: AtomicMoore<TestInput, char> = AtomicMoore::new(0, &TRANS, &MOORE_OUTS);
+
+    #[test]
+    fn atomic_mealy_steps_through_shared_ref() {
+        let fsm = AtomicMealy::new(0, &TRANS, &MEALY_OUTS);
+        let shared = &fsm;
+        assert_eq!(shared.step(TestInput::A), Ok('x'));
+        assert_eq!(shared.step(TestInput::A), Err(StepError::NoTransition));
+        assert_eq!(fsm.current_state(), 1);
+    }
+
+    #[test]
+    fn atomic_moore_in_static() {
+        SHARED.reset(0);
+        assert_eq!(SHARED.current_output(), Ok('x'));
+        assert_eq!(SHARED.step(TestInput::A), Ok('y'));
+        assert_eq!(SHARED.current_state(), 1);
+    }
+}
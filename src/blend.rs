@@ -0,0 +1,188 @@
+//! Transitions with a duration, for animation cross-fades
+//!
+//! The wrapped machine still changes state instantly; `Animator` adds the
+//! visible side, reporting a `Transitioning` pose while the blend from the
+//! old state to the new one runs for its duration in ticks.
+
+use crate::Machine;
+
+/// What an animation should show
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Pose {
+    /// Settled in a state
+    State(u8),
+    /// Blending between two states
+    Transitioning {
+        /// State blended from
+        from: u8,
+        /// State blended to, already the machine's current state
+        to: u8,
+        /// Fraction of the blend done, from 0.0 up to but excluding 1.0
+        progress: f32,
+    },
+}
+
+/// Machine wrapper tracking blends between states
+///
+/// A step during a blend starts a new blend from the old blend's target,
+/// so interrupted cross-fades restart rather than queue up.
+///
+/// # Example
+/// ```
+/// use fsmall::blend::{Animator, Pose};
+/// use fsmall::Mealy;
+///
+/// // 0: idle, 1: walk, 2: run
+/// static TRANSITIONS: [(u8, char, u8); 3] = [(0, 'w', 1), (1, 'r', 2), (2, 'i', 0)];
+/// static DURATIONS: [(u8, u8, u32); 2] = [(0, 1, 4), (1, 2, 2)];
+///
+/// let mut anim = Animator::new(Mealy::from_output_fn(0, &TRANSITIONS, |_, _| ()), &DURATIONS);
+/// anim.step('w').unwrap();
+/// assert_eq!(anim.tick(), Pose::Transitioning { from: 0, to: 1, progress: 0.25 });
+/// anim.tick();
+/// anim.tick();
+/// assert_eq!(anim.tick(), Pose::State(1));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Animator<'t, M> {
+    machine: M,
+    // Table: (from_state, to_state, duration)
+    durations: &'t [(u8, u8, u32)],
+    // (from, to, elapsed, duration) of the running blend
+    blend: Option<(u8, u8, u32, u32)>,
+}
+
+impl<'t, M: Machine> Animator<'t, M> {
+    /// Wrap `machine`, settled in its current state
+    ///
+    /// # Arguments
+    /// * `machine` - Machine whose states are animation clips
+    /// * `durations` - Duration table: (from_state, to_state, ticks);
+    ///   missing pairs switch instantly
+    pub fn new(machine: M, durations: &'t [(u8, u8, u32)]) -> Self {
+        Animator {
+            machine,
+            durations,
+            blend: None,
+        }
+    }
+
+    /// Step the machine, starting a blend if the transition has a duration
+    ///
+    /// # Errors
+    /// Whatever the wrapped machine returns; a rejected step leaves a
+    /// running blend untouched.
+    pub fn step(&mut self, input: M::Input) -> Result<M::Output, M::Error> {
+        let from = self.machine.current_state();
+        let output = self.machine.step(input)?;
+        let to = self.machine.current_state();
+        self.blend = self
+            .durations
+            .iter()
+            .find(|(f, t, _ticks)| *f == from && *t == to)
+            .filter(|(_f, _t, ticks)| *ticks > 0)
+            .map(|(_f, _t, ticks)| (from, to, 0, *ticks));
+        Ok(output)
+    }
+
+    /// Advance a running blend by one tick, return the pose to show
+    pub fn tick(&mut self) -> Pose {
+        if let Some((from, to, elapsed, duration)) = self.blend {
+            let elapsed = elapsed + 1;
+            self.blend = (elapsed < duration).then_some((from, to, elapsed, duration));
+        }
+        self.pose()
+    }
+
+    /// Get pose to show, without advancing time
+    pub fn pose(&self) -> Pose {
+        match self.blend {
+            Some((from, to, elapsed, duration)) => Pose::Transitioning {
+                from,
+                to,
+                progress: elapsed as f32 / duration as f32,
+            },
+            None => Pose::State(self.machine.current_state()),
+        }
+    }
+
+    /// Check whether a blend is running
+    pub fn is_blending(&self) -> bool {
+        self.blend.is_some()
+    }
+
+    /// Get current state, the target of a running blend
+    pub fn current_state(&self) -> u8 {
+        self.machine.current_state()
+    }
+
+    /// Jump to `state` without blending
+    pub fn reset(&mut self, state: u8) {
+        self.machine.reset(state);
+        self.blend = None;
+    }
+
+    /// Get wrapped machine
+    pub fn machine(&self) -> &M {
+        &self.machine
+    }
+
+    /// Consume animator, returning the wrapped machine
+    pub fn into_inner(self) -> M {
+        self.machine
+    }
+}
+
+impl<M: Machine> Machine for Animator<'_, M> {
+    type Input = M::Input;
+    type Output = M::Output;
+    type Error = M::Error;
+
+    fn step(&mut self, input: M::Input) -> Result<M::Output, M::Error> {
+        Animator::step(self, input)
+    }
+
+    fn current_state(&self) -> u8 {
+        Animator::current_state(self)
+    }
+
+    fn reset(&mut self, state: u8) {
+        Animator::reset(self, state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Mealy;
+
+    static TRANS: [(u8, char, u8); 3] = [(0, 'a', 1), (1, 'b', 2), (2, 'c', 0)];
+    static DURATIONS: [(u8, u8, u32); 2] = [(0, 1, 2), (1, 2, 4)];
+
+    #[test]
+    fn animator_blend_interrupted_and_instant() {
+        let mut anim = Animator::new(Mealy::from_output_fn(0, &TRANS, |_, _| ()), &DURATIONS);
+        anim.step('a').unwrap();
+        assert_eq!(
+            anim.pose(),
+            Pose::Transitioning {
+                from: 0,
+                to: 1,
+                progress: 0.0
+            }
+        );
+        anim.step('b').unwrap();
+        assert_eq!(
+            anim.tick(),
+            Pose::Transitioning {
+                from: 1,
+                to: 2,
+                progress: 0.25
+            }
+        );
+        assert!(anim.step('x').is_err());
+        assert!(anim.is_blending());
+        anim.step('c').unwrap();
+        assert_eq!(anim.pose(), Pose::State(0));
+    }
+}
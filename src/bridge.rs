@@ -0,0 +1,278 @@
+//! Single-producer single-consumer event queue between interrupts and the
+//! main loop
+//!
+//! Split an `EventQueue` once into a `Producer`, posted to from interrupt
+//! context, and a `Consumer` that pumps events into a machine. Neither side
+//! blocks or needs a critical section; a full queue rejects and counts the
+//! event instead.
+
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::Machine;
+
+/// Fixed-capacity lock-free ring buffer of `N` events
+///
+/// # Example
+/// ```
+/// use fsmall::bridge::EventQueue;
+/// use fsmall::Mealy;
+///
+/// static TRANSITIONS: [(u8, u8, u8); 1] = [(0, b'x', 1)];
+/// static OUTPUTS: [(u8, u8, bool); 1] = [(0, b'x', true)];
+///
+/// let mut queue: EventQueue<u8, 8> = EventQueue::new();
+/// let (mut producer, mut consumer) = queue.split();
+///
+/// // Interrupt handler
+/// producer.post(b'x').unwrap();
+///
+/// // Main loop
+/// let mut fsm = Mealy::new(0, &TRANSITIONS, &OUTPUTS);
+/// assert_eq!(consumer.pump(&mut fsm, |result| assert_eq!(result, Ok(true))), 1);
+/// assert_eq!(consumer.overflows(), 0);
+/// ```
+pub struct EventQueue<T, const N: usize> {
+    slots: [UnsafeCell<MaybeUninit<T>>; N],
+    // Events popped, modulo 2 * N, written by the consumer only
+    head: AtomicUsize,
+    // Events pushed, modulo 2 * N, written by the producer only
+    tail: AtomicUsize,
+    // Events rejected because the queue was full, written by the producer only
+    overflows: AtomicUsize,
+}
+
+// SAFETY: slots are only written by the single `Producer` and read by the
+// single `Consumer`, with ownership handed over through `head`/`tail`.
+unsafe impl<T: Send, const N: usize> Sync for EventQueue<T, N> {}
+
+impl<T, const N: usize> EventQueue<T, N> {
+    /// Create empty queue, usable in a `static`
+    pub const fn new() -> Self {
+        const { assert!(N > 0, "EventQueue needs a capacity of at least 1") };
+        const { assert!(N <= usize::MAX / 2, "EventQueue capacity too large") };
+        EventQueue {
+            slots: [const { UnsafeCell::new(MaybeUninit::uninit()) }; N],
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            overflows: AtomicUsize::new(0),
+        }
+    }
+
+    /// Split into the posting and the pumping half
+    pub fn split(&mut self) -> (Producer<'_, T, N>, Consumer<'_, T, N>) {
+        (Producer { queue: self }, Consumer { queue: self })
+    }
+
+    /// Number of queued events
+    pub fn len(&self) -> usize {
+        let head = self.head.load(Ordering::Acquire);
+        Self::distance(head, self.tail.load(Ordering::Acquire))
+    }
+
+    /// Check whether no events are queued
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of events rejected because the queue was full
+    pub fn overflows(&self) -> usize {
+        self.overflows.load(Ordering::Relaxed)
+    }
+
+    // Counters run modulo 2 * N rather than wrapping at `usize::MAX`, which
+    // would skip slots whenever N is not a power of two; equal counters mean
+    // empty, counters N apart mean full.
+    fn advance(index: usize) -> usize {
+        if index + 1 == 2 * N {
+            0
+        } else {
+            index + 1
+        }
+    }
+
+    fn distance(head: usize, tail: usize) -> usize {
+        if tail >= head {
+            tail - head
+        } else {
+            2 * N - (head - tail)
+        }
+    }
+
+    fn slot(&self, index: usize) -> Option<*mut MaybeUninit<T>> {
+        let index = if index >= N { index - N } else { index };
+        self.slots.get(index).map(UnsafeCell::get)
+    }
+}
+
+impl<T, const N: usize> Default for EventQueue<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Drop for EventQueue<T, N> {
+    fn drop(&mut self) {
+        let (_, mut consumer) = self.split();
+        while consumer.pop().is_some() {}
+    }
+}
+
+/// Posting half of an `EventQueue`, meant for interrupt context
+pub struct Producer<'q, T, const N: usize> {
+    queue: &'q EventQueue<T, N>,
+}
+
+impl<T, const N: usize> Producer<'_, T, N> {
+    /// Queue an event
+    ///
+    /// # Errors
+    /// Returns the event back, and counts an overflow, if the queue is full.
+    pub fn post(&mut self, event: T) -> Result<(), T> {
+        let tail = self.queue.tail.load(Ordering::Relaxed);
+        let head = self.queue.head.load(Ordering::Acquire);
+        let slot = match self.queue.slot(tail) {
+            Some(slot) if EventQueue::<T, N>::distance(head, tail) < N => slot,
+            _ => {
+                let overflows = self.queue.overflows.load(Ordering::Relaxed);
+                self.queue
+                    .overflows
+                    .store(overflows.saturating_add(1), Ordering::Relaxed);
+                return Err(event);
+            }
+        };
+        // SAFETY: the slot is free (not between head and tail) and only this
+        // producer writes free slots.
+        unsafe { (*slot).write(event) };
+        self.queue
+            .tail
+            .store(EventQueue::<T, N>::advance(tail), Ordering::Release);
+        Ok(())
+    }
+
+    /// Number of events rejected because the queue was full
+    pub fn overflows(&self) -> usize {
+        self.queue.overflows()
+    }
+}
+
+/// Pumping half of an `EventQueue`, meant for the main loop
+pub struct Consumer<'q, T, const N: usize> {
+    queue: &'q EventQueue<T, N>,
+}
+
+impl<T, const N: usize> Consumer<'_, T, N> {
+    /// Take the oldest event, if any
+    pub fn pop(&mut self) -> Option<T> {
+        let head = self.queue.head.load(Ordering::Relaxed);
+        if self.queue.tail.load(Ordering::Acquire) == head {
+            return None;
+        }
+        let slot = self.queue.slot(head)?;
+        // SAFETY: the slot is between head and tail, so the producer has
+        // initialized it and will not touch it until head moves past.
+        let event = unsafe { (*slot).assume_init_read() };
+        self.queue
+            .head
+            .store(EventQueue::<T, N>::advance(head), Ordering::Release);
+        Some(event)
+    }
+
+    /// Step `machine` with every queued event, handing results to `on_result`
+    ///
+    /// Returns the number of events dispatched. Events posted meanwhile are
+    /// dispatched too.
+    pub fn pump<M: Machine<Input = T>>(
+        &mut self,
+        machine: &mut M,
+        mut on_result: impl FnMut(Result<M::Output, M::Error>),
+    ) -> usize {
+        let mut count = 0;
+        while let Some(event) = self.pop() {
+            on_result(machine.step(event));
+            count += 1;
+        }
+        count
+    }
+
+    /// Number of events rejected because the queue was full
+    pub fn overflows(&self) -> usize {
+        self.queue.overflows()
+    }
+}
+
+// SAFETY: each half only touches the queue through its own side of the
+// protocol, so the halves may live in different contexts.
+unsafe impl<T: Send, const N: usize> Send for Producer<'_, T, N> {}
+unsafe impl<T: Send, const N: usize> Send for Consumer<'_, T, N> {}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::{Mealy, StepError};
+
+    static TRANS: [(u8, u8, u8); 2] = [(0, 1, 1), (1, 1, 0)];
+    static OUTS: [(u8, u8, bool); 2] = [(0, 1, true), (1, 1, false)];
+
+    #[test]
+    fn bridge_pumps_and_counts_overflows() {
+        let mut queue: EventQueue<u8, 2> = EventQueue::new();
+        let (mut producer, mut consumer) = queue.split();
+        assert_eq!(producer.post(1), Ok(()));
+        assert_eq!(producer.post(7), Ok(()));
+        assert_eq!(producer.post(1), Err(1));
+        assert_eq!(consumer.overflows(), 1);
+
+        let mut fsm = Mealy::new(0, &TRANS, &OUTS);
+        let mut results = std::vec::Vec::new();
+        assert_eq!(consumer.pump(&mut fsm, |r| results.push(r)), 2);
+        assert_eq!(results, [Ok(true), Err(StepError::NoTransition)]);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn bridge_across_threads() {
+        let mut queue: EventQueue<u8, 4> = EventQueue::new();
+        let (mut producer, mut consumer) = queue.split();
+        let mut fsm = Mealy::new(0, &TRANS, &OUTS);
+        let mut received = 0;
+        std::thread::scope(|s| {
+            s.spawn(move || {
+                let mut sent = 0;
+                while sent < 200 {
+                    if producer.post(1).is_ok() {
+                        sent += 1;
+                    }
+                }
+            });
+            while received < 200 {
+                received += consumer.pump(&mut fsm, |r| assert!(r.is_ok()));
+            }
+        });
+        assert_eq!(fsm.current_state(), 0);
+    }
+
+    #[test]
+    fn bridge_counters_wrap_with_odd_capacity() {
+        // Start one short of the counters' wrap point; with counters wrapping
+        // at `usize::MAX` instead, N = 3 would reuse a live slot here
+        let mut queue: EventQueue<std::string::String, 3> = EventQueue::new();
+        queue.head.store(5, Ordering::Relaxed);
+        queue.tail.store(5, Ordering::Relaxed);
+        let (mut producer, mut consumer) = queue.split();
+        for round in 0..10 {
+            for k in 0..3 {
+                assert!(producer.post(std::format!("{round}.{k}")).is_ok());
+            }
+            assert!(producer.post(std::string::String::new()).is_err());
+            for k in 0..3 {
+                assert_eq!(consumer.pop(), Some(std::format!("{round}.{k}")));
+            }
+            assert_eq!(consumer.pop(), None);
+        }
+        assert_eq!(queue.overflows(), 10);
+    }
+}
@@ -0,0 +1,176 @@
+//! Step budgets bounding how long a machine may run
+//!
+//! `Budget` refuses steps once a limit is used up, so a run-to-completion
+//! loop over an event queue has a hard upper bound for WCET analysis.
+
+use core::fmt;
+
+use crate::Machine;
+
+/// Error returned by `Budget::step`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BudgetError<E> {
+    /// Wrapped machine rejected the input
+    Step(E),
+    /// Step limit reached, the input was not processed
+    BudgetExceeded,
+}
+
+impl<E: fmt::Display> fmt::Display for BudgetError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BudgetError::Step(e) => e.fmt(f),
+            BudgetError::BudgetExceeded => f.write_str("step budget exceeded"),
+        }
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> core::error::Error for BudgetError<E> {}
+
+/// Machine wrapper allowing a limited number of steps
+///
+/// Every `step` call counts, failed ones included. Optionally also limits
+/// the steps taken since last being in a home state, catching machines
+/// that keep running but never settle.
+///
+/// # Example
+/// ```
+/// use fsmall::budget::{Budget, BudgetError};
+/// use fsmall::Mealy;
+///
+/// static TRANSITIONS: [(u8, char, u8); 2] = [(0, 'a', 1), (1, 'a', 0)];
+/// static OUTPUTS: [(u8, char, ()); 2] = [(0, 'a', ()), (1, 'a', ())];
+///
+/// let mut fsm = Budget::new(Mealy::new(0, &TRANSITIONS, &OUTPUTS), 2);
+/// assert!(fsm.step('a').is_ok());
+/// assert!(fsm.step('a').is_ok());
+/// assert_eq!(fsm.step('a'), Err(BudgetError::BudgetExceeded));
+/// fsm.refill();
+/// assert!(fsm.step('a').is_ok());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Budget<M> {
+    machine: M,
+    max_steps: u32,
+    used: u32,
+    // (home_state, max steps away from it)
+    home: Option<(u8, u32)>,
+    away: u32,
+}
+
+impl<M: Machine> Budget<M> {
+    /// Wrap `machine`, allowing `max_steps` steps until `refill`
+    pub fn new(machine: M, max_steps: u32) -> Self {
+        Budget {
+            machine,
+            max_steps,
+            used: 0,
+            home: None,
+            away: 0,
+        }
+    }
+
+    /// Also allow at most `max_away` steps in a row outside `home`
+    pub fn with_home(mut self, home: u8, max_away: u32) -> Self {
+        self.home = Some((home, max_away));
+        self
+    }
+
+    /// Process input if the budget allows
+    ///
+    /// # Errors
+    /// * `BudgetError::BudgetExceeded` - A limit was reached, nothing done
+    /// * `BudgetError::Step` - The wrapped machine rejected the input
+    pub fn step(&mut self, input: M::Input) -> Result<M::Output, BudgetError<M::Error>> {
+        let away_exceeded = self
+            .home
+            .is_some_and(|(_home, max_away)| self.away >= max_away);
+        if self.used >= self.max_steps || away_exceeded {
+            return Err(BudgetError::BudgetExceeded);
+        }
+        self.used += 1;
+        let result = self.machine.step(input);
+        self.away = match self.home {
+            Some((home, _max_away)) if self.machine.current_state() == home => 0,
+            _ => self.away.saturating_add(1),
+        };
+        result.map_err(BudgetError::Step)
+    }
+
+    /// Steps left until `refill`
+    pub fn remaining(&self) -> u32 {
+        self.max_steps.saturating_sub(self.used)
+    }
+
+    /// Restore the full step budget; steps away from home keep counting
+    pub fn refill(&mut self) {
+        self.used = 0;
+    }
+
+    /// Get wrapped machine
+    pub fn machine(&self) -> &M {
+        &self.machine
+    }
+
+    /// Consume wrapper, returning the machine
+    pub fn into_inner(self) -> M {
+        self.machine
+    }
+}
+
+impl<M: Machine> Machine for Budget<M> {
+    type Input = M::Input;
+    type Output = M::Output;
+    type Error = BudgetError<M::Error>;
+
+    fn step(&mut self, input: M::Input) -> Result<M::Output, Self::Error> {
+        Budget::step(self, input)
+    }
+
+    fn current_state(&self) -> u8 {
+        self.machine.current_state()
+    }
+
+    /// Reset the machine and both counters
+    fn reset(&mut self, state: u8) {
+        self.machine.reset(state);
+        self.used = 0;
+        self.away = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Moore, StepError};
+
+    static TRANS: [(u8, u8, u8); 4] = [(0, 1, 1), (1, 1, 2), (2, 1, 1), (1, 0, 0)];
+    static OUTS: [(); 3] = [(); 3];
+
+    #[test]
+    fn budget_counts_failed_steps() {
+        let mut fsm = Budget::new(Moore::new(0, &TRANS, &OUTS), 2);
+        assert_eq!(fsm.step(9), Err(BudgetError::Step(StepError::NoTransition)));
+        assert_eq!(fsm.remaining(), 1);
+        assert_eq!(fsm.step(1), Ok(()));
+        assert_eq!(fsm.step(1), Err(BudgetError::BudgetExceeded));
+        assert_eq!(fsm.current_state(), 1);
+        fsm.reset(0);
+        assert_eq!(fsm.remaining(), 2);
+    }
+
+    #[test]
+    fn budget_limits_steps_away_from_home() {
+        let mut fsm = Budget::new(Moore::new(0, &TRANS, &OUTS), 100).with_home(0, 3);
+        // Cycling between 1 and 2 never returns home
+        for _ in 0..3 {
+            assert_eq!(fsm.step(1), Ok(()));
+        }
+        assert_eq!(fsm.step(0), Err(BudgetError::BudgetExceeded));
+
+        fsm.reset(0);
+        for input in [1, 0, 1, 1, 1] {
+            assert_eq!(fsm.step(input), Ok(()));
+        }
+    }
+}
@@ -0,0 +1,137 @@
+//! Byte equivalence classes for machines over `u8`
+
+use crate::Machine;
+
+/// Wrapper mapping each input byte to its class before the table lookup
+///
+/// Bytes that every state treats alike share a class, so the inner
+/// machine's tables need one row per class instead of one per byte.
+///
+/// # Example
+/// ```
+/// use fsmall::{ByteClasses, Moore};
+///
+/// // Class 0: other, 1: digit
+/// static CLASSES: [u8; 256] = {
+///     let mut classes = [0; 256];
+///     let mut b = b'0';
+///     while b <= b'9' {
+///         classes[b as usize] = 1;
+///         b += 1;
+///     }
+///     classes
+/// };
+///
+/// static TRANSITIONS: [(u8, u8, u8); 2] = [(0, 1, 1), (1, 1, 1)];
+/// static OUTPUTS: [bool; 2] = [false, true];
+///
+/// let mut fsm = ByteClasses::new(Moore::new(0, &TRANSITIONS, &OUTPUTS), &CLASSES);
+/// assert_eq!(fsm.step(b'7'), Ok(true));
+/// assert!(fsm.step(b'x').is_err());
+/// ```
+pub struct ByteClasses<M> {
+    machine: M,
+    // classes[byte] = class index used as the inner machine's input
+    classes: &'static [u8; 256],
+}
+
+impl<M: Machine<Input = u8>> ByteClasses<M> {
+    /// Wrap `machine`, whose tables are written over class indices
+    ///
+    /// # Arguments
+    /// * `machine` - Machine over class indices
+    /// * `classes` - Class table: `classes[byte]` = class index
+    pub fn new(machine: M, classes: &'static [u8; 256]) -> Self {
+        ByteClasses { machine, classes }
+    }
+
+    /// Map byte to its class and step the inner machine
+    ///
+    /// # Errors
+    /// Whatever the inner machine returns for the class.
+    pub fn step(&mut self, byte: u8) -> Result<M::Output, M::Error> {
+        self.machine.step(self.classes[byte as usize])
+    }
+
+    /// Get class of `byte`
+    pub fn class_of(&self, byte: u8) -> u8 {
+        self.classes[byte as usize]
+    }
+
+    /// Get current state
+    pub fn current_state(&self) -> u8 {
+        self.machine.current_state()
+    }
+
+    /// Reset to specific state
+    pub fn reset(&mut self, state: u8) {
+        self.machine.reset(state);
+    }
+
+    /// Get wrapped machine
+    pub fn machine(&self) -> &M {
+        &self.machine
+    }
+
+    /// Consume wrapper, returning the inner machine
+    pub fn into_inner(self) -> M {
+        self.machine
+    }
+}
+
+impl<M: Machine<Input = u8>> Machine for ByteClasses<M> {
+    type Input = u8;
+    type Output = M::Output;
+    type Error = M::Error;
+
+    fn step(&mut self, byte: u8) -> Result<M::Output, M::Error> {
+        ByteClasses::step(self, byte)
+    }
+
+    fn current_state(&self) -> u8 {
+        self.machine.current_state()
+    }
+
+    fn reset(&mut self, state: u8) {
+        self.machine.reset(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Mealy, StepError};
+
+    // 0: other, 1: hex digit
+    static CLASSES: [u8; 256] = {
+        let mut classes = [0; 256];
+        let mut b = 0;
+        while b < 256 {
+            if (b as u8).is_ascii_hexdigit() {
+                classes[b] = 1;
+            }
+            b += 1;
+        }
+        classes
+    };
+
+    static TRANS: [(u8, u8, u8); 2] = [(0, 1, 1), (1, 1, 0)];
+
+    static OUTS: [(u8, u8, bool); 2] = [(0, 1, false), (1, 1, true)];
+
+    #[test]
+    fn classes_shrink_tables() {
+        let mut fsm = ByteClasses::new(Mealy::new(0, &TRANS, &OUTS), &CLASSES);
+        assert_eq!(fsm.step(b'f'), Ok(false));
+        assert_eq!(fsm.step(b'0'), Ok(true)); // Full byte
+        assert_eq!(fsm.step(b'g'), Err(StepError::NoTransition));
+        assert_eq!(fsm.current_state(), 0);
+    }
+
+    #[test]
+    fn classes_lookup() {
+        let fsm = ByteClasses::new(Mealy::new(0, &TRANS, &OUTS), &CLASSES);
+        assert_eq!(fsm.class_of(b'A'), 1);
+        assert_eq!(fsm.class_of(b'Z'), 0);
+    }
+}
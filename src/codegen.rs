@@ -0,0 +1,265 @@
+//! Generate static tables from machine descriptions in `build.rs`
+//!
+//! Requires the `std` feature; add fsmall to `[build-dependencies]` with it
+//! enabled. The description file stays the editable source of truth and
+//! the firmware includes the generated tables.
+//!
+//! ```no_run
+//! // build.rs, in `main`
+//! fsmall::codegen::build("machines/door.toml", "DOOR", "door.rs").unwrap();
+//! ```
+//!
+//! ```ignore
+//! // src/door.rs
+//! use crate::Input;
+//! include!(concat!(env!("OUT_DIR"), "/door.rs"));
+//!
+//! let fsm = fsmall::Mealy::new(DOOR_INITIAL, &DOOR_TRANSITIONS, &DOOR_OUTPUTS)
+//!     .with_state_names(&DOOR_STATE_NAMES);
+//! ```
+
+use std::format;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::string::String;
+use std::vec::Vec;
+
+use core::fmt::{self, Write};
+
+use crate::description::{Description, DescriptionError, Kind};
+
+/// Error returned by `to_rust`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CodegenError {
+    /// `name` does not start a Rust identifier
+    InvalidName,
+    /// Two states, or a state and a table, map to this constant
+    Collision(String),
+}
+
+impl fmt::Display for CodegenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CodegenError::InvalidName => f.write_str("name is not an identifier"),
+            CodegenError::Collision(constant) => write!(f, "constant `{constant}` generated twice"),
+        }
+    }
+}
+
+impl core::error::Error for CodegenError {}
+
+/// Emit the tables of a description as Rust source
+///
+/// Produces a `{name}_{STATE}` constant per state, `{name}_INITIAL`,
+/// `{name}_STATE_NAMES`, `{name}_TRANSITIONS`, and `{name}_OUTPUTS`, in
+/// Mealy or Moore form. State names become upper case, with anything but
+/// letters and digits replaced by `_`.
+///
+/// # Errors
+/// * `CodegenError::InvalidName` - `name` empty, starting with a digit, or
+///   holding anything but ASCII letters, digits and `_`
+/// * `CodegenError::Collision` - Distinct states, e.g. `idle` and `IDLE`,
+///   or a state and a table get the same constant
+///
+/// # Example
+/// ```
+/// use fsmall::description::Description;
+///
+/// let description = Description::from_toml(r#"
+///     input = "char"
+///     output = "bool"
+///     states = ["off", "on"]
+///     transitions = [["off", "'t'", "on", "true"]]
+/// "#).unwrap();
+/// let source = fsmall::codegen::to_rust(&description, "LAMP").unwrap();
+/// assert!(source.contains("pub const LAMP_ON: u8 = 1;"));
+/// assert!(source.contains("(LAMP_OFF, 't', LAMP_ON),"));
+/// ```
+pub fn to_rust(description: &Description, name: &str) -> Result<String, CodegenError> {
+    if name.is_empty()
+        || name.starts_with(|c: char| c.is_ascii_digit())
+        || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    {
+        return Err(CodegenError::InvalidName);
+    }
+    let constant = |state: u8| -> String {
+        let state = description
+            .states
+            .get(usize::from(state))
+            .map_or("", String::as_str);
+        let mut ident = format!("{name}_");
+        ident.extend(state.chars().map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        }));
+        ident
+    };
+    let states = description.states.len();
+    let rows = description.transitions.len();
+
+    let mut constants: Vec<String> = ["INITIAL", "STATE_NAMES", "TRANSITIONS", "OUTPUTS"]
+        .iter()
+        .map(|table| format!("{name}_{table}"))
+        .collect();
+    for state in 0..states {
+        let ident = constant(state as u8);
+        if constants.contains(&ident) {
+            return Err(CodegenError::Collision(ident));
+        }
+        constants.push(ident);
+    }
+
+    let mut out = String::from("// Generated by fsmall::codegen, do not edit\n\n");
+    for state in 0..states {
+        let _ = writeln!(out, "pub const {}: u8 = {state};", constant(state as u8));
+    }
+    let _ = writeln!(
+        out,
+        "pub const {name}_INITIAL: u8 = {};\n",
+        constant(description.initial)
+    );
+
+    let names: Vec<String> = description
+        .states
+        .iter()
+        .map(|s| format!("{s:?}"))
+        .collect();
+    let _ = writeln!(
+        out,
+        "pub static {name}_STATE_NAMES: [&str; {states}] = [{}];\n",
+        names.join(", ")
+    );
+
+    let input = &description.input;
+    let _ = writeln!(
+        out,
+        "pub static {name}_TRANSITIONS: [(u8, {input}, u8); {rows}] = ["
+    );
+    for row in &description.transitions {
+        let _ = writeln!(
+            out,
+            "    ({}, {}, {}),",
+            constant(row.from),
+            row.input,
+            constant(row.to)
+        );
+    }
+    out.push_str("];\n\n");
+
+    let output = &description.output;
+    match description.kind {
+        Kind::Mealy => {
+            let _ = writeln!(
+                out,
+                "pub static {name}_OUTPUTS: [(u8, {input}, {output}); {rows}] = ["
+            );
+            for row in &description.transitions {
+                let _ = writeln!(
+                    out,
+                    "    ({}, {}, {}),",
+                    constant(row.from),
+                    row.input,
+                    row.output.as_deref().unwrap_or_default()
+                );
+            }
+        }
+        Kind::Moore => {
+            let _ = writeln!(out, "pub static {name}_OUTPUTS: [{output}; {states}] = [");
+            for output in &description.outputs {
+                let _ = writeln!(out, "    {output},");
+            }
+        }
+    }
+    out.push_str("];\n");
+    Ok(out)
+}
+
+/// Generate `OUT_DIR/out_file` from the TOML description at `description`
+///
+/// Call from `build.rs`; tells Cargo to rerun when the description changes.
+///
+/// # Errors
+/// I/O errors, `OUT_DIR` not set (`NotFound`), or a `DescriptionError` or
+/// `CodegenError` wrapped as `InvalidData`.
+pub fn build(
+    description: impl AsRef<Path>,
+    name: &str,
+    out_file: impl AsRef<Path>,
+) -> io::Result<PathBuf> {
+    let description = description.as_ref();
+    std::println!("cargo:rerun-if-changed={}", description.display());
+
+    let src = std::fs::read_to_string(description)?;
+    let parsed = Description::from_toml(&src).map_err(|e: DescriptionError| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{}: {e}", description.display()),
+        )
+    })?;
+    let out_dir = std::env::var_os("OUT_DIR")
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "OUT_DIR not set"))?;
+    let source = to_rust(&parsed, name).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{}: {e}", description.display()),
+        )
+    })?;
+    let path = Path::new(&out_dir).join(out_file);
+    std::fs::write(&path, source)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codegen_emits_moore_tables() {
+        let description = Description::from_toml(
+            "kind = \"moore\"\n\
+             input = \"u8\"\n\
+             output = \"&str\"\n\
+             states = [\"idle\", \"run-fast\"]\n\
+             transitions = [[\"idle\", \"1\", \"run-fast\"]]\n\
+             outputs = ['\"stopped\"', '\"running\"']\n",
+        )
+        .unwrap();
+        let source = to_rust(&description, "M").unwrap();
+        assert!(source.contains("pub const M_RUN_FAST: u8 = 1;\n"));
+        assert!(source.contains("pub const M_INITIAL: u8 = M_IDLE;\n"));
+        assert!(source.contains("pub static M_STATE_NAMES: [&str; 2] = [\"idle\", \"run-fast\"];"));
+        assert!(source.contains("[(u8, u8, u8); 1] = [\n    (M_IDLE, 1, M_RUN_FAST),\n];"));
+        assert!(source.contains("pub static M_OUTPUTS: [&str; 2] = [\n    \"stopped\",\n"));
+    }
+
+    #[test]
+    fn codegen_rejects_clashing_constants() {
+        let states = |states: &str| {
+            let toml =
+                format!("input = \"u8\"\noutput = \"u8\"\nstates = [{states}]\ntransitions = []\n");
+            Description::from_toml(&toml).unwrap()
+        };
+        let collision = |constant: &str| Err(CodegenError::Collision(constant.into()));
+        assert_eq!(
+            to_rust(&states("\"run-fast\", \"run_fast\""), "M"),
+            collision("M_RUN_FAST")
+        );
+        assert_eq!(
+            to_rust(&states("\"idle\", \"IDLE\""), "M"),
+            collision("M_IDLE")
+        );
+        assert_eq!(to_rust(&states("\"outputs\""), "M"), collision("M_OUTPUTS"));
+        assert_eq!(
+            to_rust(&states("\"idle\""), "1M"),
+            Err(CodegenError::InvalidName)
+        );
+        assert_eq!(
+            to_rust(&states("\"idle\""), "M-2"),
+            Err(CodegenError::InvalidName)
+        );
+        assert!(to_rust(&states("\"1st\""), "M").unwrap().contains("M_1ST"));
+    }
+}
@@ -0,0 +1,632 @@
+//! Combinators building one machine out of several
+
+use crate::{Machine, StepError};
+
+/// Error of a `Chain` step, telling which stage rejected the input
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ChainError<E1, E2> {
+    /// First stage failed, second stage untouched
+    First(E1),
+    /// Second stage failed after the first stage advanced
+    Second(E2),
+}
+
+/// Two machines in series: the first's output is the second's input
+///
+/// As a `Machine`, `current_state` reports the first stage and `reset`
+/// resets both stages.
+///
+/// # Example
+/// ```
+/// use fsmall::compose::Chain;
+/// use fsmall::{Mealy, Moore};
+///
+/// // Stage 1 turns bytes into "is digit" flags, stage 2 counts digits mod 3
+/// static DIGITS: [(u8, u8, u8); 2] = [(0, b'1', 0), (0, b'x', 0)];
+/// static FLAGS: [(u8, u8, bool); 2] = [(0, b'1', true), (0, b'x', false)];
+/// static COUNT: [(u8, bool, u8); 6] = [
+///     (0, true, 1), (1, true, 2), (2, true, 0),
+///     (0, false, 0), (1, false, 1), (2, false, 2),
+/// ];
+/// static COUNT_OUT: [u8; 3] = [0, 1, 2];
+///
+/// let mut fsm = Chain::new(Mealy::new(0, &DIGITS, &FLAGS), Moore::new(0, &COUNT, &COUNT_OUT));
+/// assert_eq!(fsm.step(b'1'), Ok(1));
+/// assert_eq!(fsm.step(b'x'), Ok(1));
+/// assert_eq!(fsm.step(b'1'), Ok(2));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Chain<M1, M2> {
+    first: M1,
+    second: M2,
+}
+
+impl<M1, M2> Chain<M1, M2>
+where
+    M1: Machine,
+    M2: Machine,
+    M1::Output: Into<M2::Input>,
+{
+    /// Connect `first`'s outputs to `second`'s inputs
+    pub fn new(first: M1, second: M2) -> Self {
+        Chain { first, second }
+    }
+
+    /// Step the first stage, feed its output to the second, return the
+    /// second's output
+    ///
+    /// # Errors
+    /// * `ChainError::First` - First stage rejected the input
+    /// * `ChainError::Second` - Second stage rejected the first's output
+    pub fn step(
+        &mut self,
+        input: M1::Input,
+    ) -> Result<M2::Output, ChainError<M1::Error, M2::Error>> {
+        let mid = self.first.step(input).map_err(ChainError::First)?;
+        self.second.step(mid.into()).map_err(ChainError::Second)
+    }
+
+    /// Get first stage
+    pub fn first(&self) -> &M1 {
+        &self.first
+    }
+
+    /// Get second stage
+    pub fn second(&self) -> &M2 {
+        &self.second
+    }
+
+    /// Get both stages mutably, e.g. to reset them independently
+    pub fn stages_mut(&mut self) -> (&mut M1, &mut M2) {
+        (&mut self.first, &mut self.second)
+    }
+
+    /// Consume chain, returning both stages
+    pub fn into_parts(self) -> (M1, M2) {
+        (self.first, self.second)
+    }
+}
+
+impl<M1, M2> Machine for Chain<M1, M2>
+where
+    M1: Machine,
+    M2: Machine,
+    M1::Output: Into<M2::Input>,
+{
+    type Input = M1::Input;
+    type Output = M2::Output;
+    type Error = ChainError<M1::Error, M2::Error>;
+
+    fn step(&mut self, input: M1::Input) -> Result<M2::Output, Self::Error> {
+        Chain::step(self, input)
+    }
+
+    fn current_state(&self) -> u8 {
+        self.first.current_state()
+    }
+
+    fn reset(&mut self, state: u8) {
+        self.first.reset(state);
+        self.second.reset(state);
+    }
+}
+
+/// Error of a `Product` step, holding every machine's error
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ProductError<O1, O2, E1, E2> {
+    /// First machine failed, second advanced
+    First(E1, O2),
+    /// Second machine failed, first advanced
+    Second(O1, E2),
+    /// Both machines failed
+    Both(E1, E2),
+}
+
+// Result of a `Product` step
+type ProductResult<M1, M2> = Result<
+    (<M1 as Machine>::Output, <M2 as Machine>::Output),
+    ProductError<
+        <M1 as Machine>::Output,
+        <M2 as Machine>::Output,
+        <M1 as Machine>::Error,
+        <M2 as Machine>::Error,
+    >,
+>;
+
+/// Two machines stepped in lockstep on the same input
+///
+/// Both machines always see every input, so they stay in sync even when one
+/// of them rejects it; the error carries the other machine's output. As a
+/// `Machine`, `current_state` reports the first machine and `reset` resets
+/// both.
+///
+/// # Example
+/// ```
+/// use fsmall::compose::Product;
+/// use fsmall::Moore;
+///
+/// #[derive(Copy, Clone, Eq, PartialEq)]
+/// enum Input { Tick }
+///
+/// static HALF: [(u8, Input, u8); 2] = [(0, Input::Tick, 1), (1, Input::Tick, 0)];
+/// static HALF_OUT: [bool; 2] = [false, true];
+/// static THIRD: [(u8, Input, u8); 3] = [(0, Input::Tick, 1), (1, Input::Tick, 2), (2, Input::Tick, 0)];
+/// static THIRD_OUT: [bool; 3] = [false, false, true];
+///
+/// let mut fsm = Product::new(Moore::new(0, &HALF, &HALF_OUT), Moore::new(0, &THIRD, &THIRD_OUT));
+/// assert_eq!(fsm.step(Input::Tick), Ok((true, false)));
+/// assert_eq!(fsm.step(Input::Tick), Ok((false, true)));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Product<M1, M2> {
+    first: M1,
+    second: M2,
+}
+
+impl<M1, M2> Product<M1, M2>
+where
+    M1: Machine,
+    M1::Input: Copy,
+    M2: Machine<Input = M1::Input>,
+{
+    /// Run `first` and `second` side by side
+    pub fn new(first: M1, second: M2) -> Self {
+        Product { first, second }
+    }
+
+    /// Step both machines on `input`, returning both outputs
+    ///
+    /// # Errors
+    /// * `ProductError::First` - First machine rejected the input
+    /// * `ProductError::Second` - Second machine rejected the input
+    /// * `ProductError::Both` - Both machines rejected the input
+    pub fn step(&mut self, input: M1::Input) -> ProductResult<M1, M2> {
+        match (self.first.step(input), self.second.step(input)) {
+            (Ok(a), Ok(b)) => Ok((a, b)),
+            (Err(a), Ok(b)) => Err(ProductError::First(a, b)),
+            (Ok(a), Err(b)) => Err(ProductError::Second(a, b)),
+            (Err(a), Err(b)) => Err(ProductError::Both(a, b)),
+        }
+    }
+
+    /// Get both states
+    pub fn states(&self) -> (u8, u8) {
+        (self.first.current_state(), self.second.current_state())
+    }
+
+    /// Get first machine
+    pub fn first(&self) -> &M1 {
+        &self.first
+    }
+
+    /// Get second machine
+    pub fn second(&self) -> &M2 {
+        &self.second
+    }
+
+    /// Consume product, returning both machines
+    pub fn into_parts(self) -> (M1, M2) {
+        (self.first, self.second)
+    }
+}
+
+impl<M1, M2> Machine for Product<M1, M2>
+where
+    M1: Machine,
+    M1::Input: Copy,
+    M2: Machine<Input = M1::Input>,
+{
+    type Input = M1::Input;
+    type Output = (M1::Output, M2::Output);
+    type Error = ProductError<M1::Output, M2::Output, M1::Error, M2::Error>;
+
+    fn step(&mut self, input: M1::Input) -> Result<Self::Output, Self::Error> {
+        Product::step(self, input)
+    }
+
+    fn current_state(&self) -> u8 {
+        self.first.current_state()
+    }
+
+    fn reset(&mut self, state: u8) {
+        self.first.reset(state);
+        self.second.reset(state);
+    }
+}
+
+/// Error of a `CallStack` step
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CallError<E> {
+    /// The machine rejected the input
+    Step(E),
+    /// A call state was entered with the invocation stack full; the machine
+    /// stays in the call state
+    StackOverflow,
+}
+
+/// Maps a submachine output to the input resuming the caller, if it is a
+/// completion output
+pub type ReturnFn<M> = fn(&<M as Machine>::Output) -> Option<<M as Machine>::Input>;
+
+/// Machine with call states that invoke a submachine and resume on return
+///
+/// Submachine states live in the same tables as the caller's. Entering a
+/// call state pushes it on a stack of depth `D` and jumps to the
+/// submachine's start state. When the submachine produces an output that
+/// `returns` maps to an input, the caller's state is popped and stepped with
+/// that input, and its output is returned. One submachine can thus serve
+/// many call states.
+///
+/// As a `Machine`, `reset` also clears the stack.
+///
+/// # Example
+/// ```
+/// use fsmall::compose::CallStack;
+/// use fsmall::Mealy;
+///
+/// // 0 idle; 1 waits for the response parser (states 10..) to return
+/// static TRANSITIONS: [(u8, u8, u8); 4] = [
+///     (0, b'T', 1),
+///     (1, b'!', 0),
+///     (10, b'O', 11),
+///     (11, b'K', 0), // target unused: returning pops the caller's state
+/// ];
+/// static OUTPUTS: [(u8, u8, char); 4] = [(0, b'T', '>'), (1, b'!', 'y'), (10, b'O', '.'), (11, b'K', 'k')];
+/// static CALLS: [(u8, u8); 1] = [(1, 10)];
+///
+/// fn returns(output: &char) -> Option<u8> {
+///     (*output == 'k').then_some(b'!')
+/// }
+///
+/// let mut fsm: CallStack<_, 2> = CallStack::new(Mealy::new(0, &TRANSITIONS, &OUTPUTS), &CALLS, returns);
+/// assert_eq!(fsm.step(b'T'), Ok('>'));
+/// assert_eq!(fsm.current_state(), 10);
+/// assert_eq!(fsm.step(b'O'), Ok('.'));
+/// assert_eq!(fsm.step(b'K'), Ok('y'));
+/// assert_eq!(fsm.current_state(), 0);
+/// ```
+pub struct CallStack<'t, M: Machine, const D: usize> {
+    machine: M,
+    // Table: (call_state, submachine_start_state)
+    calls: &'t [(u8, u8)],
+    // Maps a submachine output to the input resuming the caller
+    returns: ReturnFn<M>,
+    stack: [u8; D],
+    depth: usize,
+}
+
+impl<'t, M: Machine, const D: usize> CallStack<'t, M, D> {
+    /// Wrap `machine`, designating call states
+    ///
+    /// # Arguments
+    /// * `machine` - Machine holding caller and submachine states
+    /// * `calls` - Call table: (call_state, submachine_start_state)
+    /// * `returns` - Completion check on submachine outputs
+    pub fn new(machine: M, calls: &'t [(u8, u8)], returns: ReturnFn<M>) -> Self {
+        CallStack {
+            machine,
+            calls,
+            returns,
+            stack: [0; D],
+            depth: 0,
+        }
+    }
+
+    /// Process input, entering or returning from submachines as needed
+    ///
+    /// # Errors
+    /// * `CallError::Step` - The machine rejected the input (or, on return,
+    ///   the caller rejected the resume input and stays in its call state)
+    /// * `CallError::StackOverflow` - Call nesting deeper than `D`
+    pub fn step(&mut self, input: M::Input) -> Result<M::Output, CallError<M::Error>> {
+        let output = self.machine.step(input).map_err(CallError::Step)?;
+
+        // Completion output inside a submachine: resume the caller
+        let resume = match self.depth.checked_sub(1) {
+            Some(top) => (self.returns)(&output).map(|input| (top, input)),
+            None => None,
+        };
+        let Some((top, input)) = resume else {
+            return self.enter(output);
+        };
+        self.depth = top;
+        let caller = self.stack.get(top).copied().unwrap_or_default();
+        self.machine.reset(caller);
+        let output = self.machine.step(input).map_err(CallError::Step)?;
+        self.enter(output)
+    }
+
+    // Follow a call if the machine just entered a call state
+    fn enter(&mut self, output: M::Output) -> Result<M::Output, CallError<M::Error>> {
+        let state = self.machine.current_state();
+        let Some((_call, start)) = self.calls.iter().find(|(call, _start)| *call == state) else {
+            return Ok(output);
+        };
+        let slot = self
+            .stack
+            .get_mut(self.depth)
+            .ok_or(CallError::StackOverflow)?;
+        *slot = state;
+        self.depth += 1;
+        self.machine.reset(*start);
+        Ok(output)
+    }
+
+    /// Number of submachine invocations in progress
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Get current state
+    pub fn current_state(&self) -> u8 {
+        self.machine.current_state()
+    }
+
+    /// Reset to specific state, abandoning all invocations
+    pub fn reset(&mut self, state: u8) {
+        self.machine.reset(state);
+        self.depth = 0;
+    }
+
+    /// Get wrapped machine
+    pub fn machine(&self) -> &M {
+        &self.machine
+    }
+
+    /// Consume wrapper, returning the machine
+    pub fn into_inner(self) -> M {
+        self.machine
+    }
+}
+
+impl<M: Machine, const D: usize> Machine for CallStack<'_, M, D> {
+    type Input = M::Input;
+    type Output = M::Output;
+    type Error = CallError<M::Error>;
+
+    fn step(&mut self, input: M::Input) -> Result<M::Output, Self::Error> {
+        CallStack::step(self, input)
+    }
+
+    fn current_state(&self) -> u8 {
+        CallStack::current_state(self)
+    }
+
+    fn reset(&mut self, state: u8) {
+        CallStack::reset(self, state)
+    }
+}
+
+/// Child machine whose unhandled inputs bubble up to a parent
+///
+/// Inputs go to the child first; if it has no transition for them they are
+/// retried against the parent, as in hierarchical state machines. Other
+/// child errors are returned as is. As a `Machine`, `current_state` reports
+/// the child and `reset` resets both.
+///
+/// # Example
+/// ```
+/// use fsmall::compose::Bubble;
+/// use fsmall::Mealy;
+///
+/// #[derive(Copy, Clone, Eq, PartialEq)]
+/// enum Input { Next, PowerOff }
+///
+/// // Child cycles menu pages, parent handles power for every page
+/// static MENU: [(u8, Input, u8); 2] = [(0, Input::Next, 1), (1, Input::Next, 0)];
+/// static MENU_OUT: [(u8, Input, &str); 2] = [(0, Input::Next, "page 1"), (1, Input::Next, "page 0")];
+/// static POWER: [(u8, Input, u8); 1] = [(0, Input::PowerOff, 1)];
+/// static POWER_OUT: [(u8, Input, &str); 1] = [(0, Input::PowerOff, "bye")];
+///
+/// let mut fsm = Bubble::new(Mealy::new(0, &MENU, &MENU_OUT), Mealy::new(0, &POWER, &POWER_OUT));
+/// assert_eq!(fsm.step(Input::Next), Ok("page 1"));
+/// assert_eq!(fsm.step(Input::PowerOff), Ok("bye"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Bubble<C, P> {
+    child: C,
+    parent: P,
+}
+
+impl<C, P> Bubble<C, P>
+where
+    C: Machine<Error = StepError>,
+    C::Input: Copy,
+    P: Machine<Input = C::Input, Output = C::Output, Error = StepError>,
+{
+    /// Nest `child` inside `parent`
+    pub fn new(child: C, parent: P) -> Self {
+        Bubble { child, parent }
+    }
+
+    /// Process input in the child, falling back to the parent
+    ///
+    /// # Errors
+    /// * `StepError::NoTransition` - Neither child nor parent handles the input
+    /// * Any other child error, or the parent's error after bubbling
+    pub fn step(&mut self, input: C::Input) -> Result<C::Output, StepError> {
+        match self.child.step(input) {
+            Err(StepError::NoTransition) => self.parent.step(input),
+            result => result,
+        }
+    }
+
+    /// Get child machine
+    pub fn child(&self) -> &C {
+        &self.child
+    }
+
+    /// Get parent machine
+    pub fn parent(&self) -> &P {
+        &self.parent
+    }
+
+    /// Consume wrapper, returning child and parent
+    pub fn into_parts(self) -> (C, P) {
+        (self.child, self.parent)
+    }
+}
+
+impl<C, P> Machine for Bubble<C, P>
+where
+    C: Machine<Error = StepError>,
+    C::Input: Copy,
+    P: Machine<Input = C::Input, Output = C::Output, Error = StepError>,
+{
+    type Input = C::Input;
+    type Output = C::Output;
+    type Error = StepError;
+
+    fn step(&mut self, input: C::Input) -> Result<C::Output, StepError> {
+        Bubble::step(self, input)
+    }
+
+    fn current_state(&self) -> u8 {
+        self.child.current_state()
+    }
+
+    fn reset(&mut self, state: u8) {
+        self.child.reset(state);
+        self.parent.reset(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Mealy, Moore};
+
+    #[derive(Copy, Clone, Eq, PartialEq, Debug)]
+    enum Bit {
+        Zero,
+        One,
+    }
+
+    impl From<bool> for Bit {
+        fn from(b: bool) -> Self {
+            if b {
+                Bit::One
+            } else {
+                Bit::Zero
+            }
+        }
+    }
+
+    // Emits true on every second One
+    static PAIRS: [(u8, Bit, u8); 3] = [(0, Bit::One, 1), (1, Bit::One, 0), (0, Bit::Zero, 0)];
+    static PAIRS_OUT: [(u8, Bit, bool); 3] = [
+        (0, Bit::One, false),
+        (1, Bit::One, true),
+        (0, Bit::Zero, false),
+    ];
+
+    // Tracks last bit seen
+    static LAST: [(u8, Bit, u8); 4] = [
+        (0, Bit::Zero, 0),
+        (0, Bit::One, 1),
+        (1, Bit::Zero, 0),
+        (1, Bit::One, 1),
+    ];
+    static LAST_OUT: [char; 2] = ['0', '1'];
+
+    #[test]
+    fn chain_feeds_second_stage() {
+        let mut fsm = Chain::new(
+            Mealy::new(0, &PAIRS, &PAIRS_OUT),
+            Moore::new(0, &LAST, &LAST_OUT),
+        );
+        assert_eq!(fsm.step(Bit::One), Ok('0'));
+        assert_eq!(fsm.step(Bit::One), Ok('1'));
+        assert_eq!(fsm.second().current_state(), 1);
+
+        // Zero has no row in state 1 of the first stage
+        fsm.step(Bit::One).unwrap();
+        assert_eq!(
+            fsm.step(Bit::Zero),
+            Err(ChainError::First(StepError::NoTransition))
+        );
+        assert_eq!(fsm.second().current_state(), 0);
+    }
+
+    #[test]
+    fn product_keeps_machines_in_lockstep() {
+        let mut fsm = Product::new(
+            Mealy::new(0, &PAIRS, &PAIRS_OUT),
+            Moore::new(0, &LAST, &LAST_OUT),
+        );
+        assert_eq!(fsm.step(Bit::One), Ok((false, '1')));
+        assert_eq!(
+            fsm.step(Bit::Zero),
+            Err(ProductError::First(StepError::NoTransition, '0'))
+        );
+        assert_eq!(fsm.states(), (1, 0));
+        fsm.reset(0);
+        assert_eq!(fsm.states(), (0, 0));
+    }
+
+    // Two command states share the response parser at 10..
+    static AT_TRANS: [(u8, u8, u8); 7] = [
+        (0, b'A', 1),
+        (0, b'B', 2),
+        (1, b'!', 0),
+        (2, b'!', 0),
+        (10, b'o', 11),
+        (10, b'A', 1),
+        (11, b'k', 0),
+    ];
+    static AT_OUTS: [(u8, u8, char); 7] = [
+        (0, b'A', '>'),
+        (0, b'B', '>'),
+        (1, b'!', 'a'),
+        (2, b'!', 'b'),
+        (10, b'o', '.'),
+        (10, b'A', '>'),
+        (11, b'k', 'D'),
+    ];
+    static AT_CALLS: [(u8, u8); 2] = [(1, 10), (2, 10)];
+
+    fn at_returns(output: &char) -> Option<u8> {
+        (*output == 'D').then_some(b'!')
+    }
+
+    #[test]
+    fn call_stack_resumes_each_caller() {
+        let mealy = Mealy::new(0, &AT_TRANS, &AT_OUTS);
+        let mut fsm: CallStack<_, 2> = CallStack::new(mealy, &AT_CALLS, at_returns);
+        for (command, resumed) in [(b'A', 'a'), (b'B', 'b')] {
+            assert_eq!(fsm.step(command), Ok('>'));
+            assert_eq!((fsm.current_state(), fsm.depth()), (10, 1));
+            assert_eq!(fsm.step(b'o'), Ok('.'));
+            assert_eq!(fsm.step(b'k'), Ok(resumed));
+            assert_eq!((fsm.current_state(), fsm.depth()), (0, 0));
+        }
+    }
+
+    #[test]
+    fn call_stack_overflow() {
+        let mealy = Mealy::new(0, &AT_TRANS, &AT_OUTS);
+        let mut fsm: CallStack<_, 1> = CallStack::new(mealy, &AT_CALLS, at_returns);
+        fsm.step(b'A').unwrap();
+        // Nested call from inside the submachine
+        assert_eq!(fsm.step(b'A'), Err(CallError::StackOverflow));
+        assert_eq!(fsm.current_state(), 1);
+        fsm.reset(0);
+        assert_eq!(fsm.depth(), 0);
+    }
+
+    #[test]
+    fn bubble_retries_rejected_input_on_parent() {
+        // Child only knows Zero in state 0; parent counts Ones
+        let child = Mealy::new(0, &PAIRS[2..], &PAIRS_OUT[2..]);
+        let parent = Mealy::new(0, &PAIRS, &PAIRS_OUT);
+        let mut fsm = Bubble::new(child, parent);
+        assert_eq!(fsm.step(Bit::Zero), Ok(false));
+        assert_eq!(fsm.step(Bit::One), Ok(false));
+        assert_eq!(fsm.parent().current_state(), 1);
+
+        // Parent rejects Zero in state 1, child still handles it
+        assert_eq!(fsm.step(Bit::Zero), Ok(false));
+        fsm.reset(1);
+        assert_eq!(fsm.step(Bit::Zero), Err(StepError::NoTransition));
+    }
+}
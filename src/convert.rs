@@ -0,0 +1,152 @@
+//! Convert tables between Mealy and Moore form
+//!
+//! Requires the `alloc` feature. Converted tables are returned as vectors;
+//! borrow them into a machine directly or print them into source.
+//!
+//! # Example
+//! ```
+//! use fsmall::convert::mealy_to_moore;
+//! use fsmall::{Mealy, Moore};
+//!
+//! static TRANSITIONS: [(u8, char, u8); 2] = [(0, 't', 1), (1, 't', 0)];
+//! static OUTPUTS: [(u8, char, bool); 2] = [(0, 't', true), (1, 't', false)];
+//!
+//! let tables = mealy_to_moore(0, &TRANSITIONS, &OUTPUTS, false).unwrap();
+//! let mut mealy = Mealy::new(0, &TRANSITIONS, &OUTPUTS);
+//! let mut moore = Moore::new(0, &tables.transitions, &tables.outputs);
+//! for _ in 0..4 {
+//!     assert_eq!(mealy.step('t'), moore.step('t'));
+//! }
+//! ```
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+/// Error returned when a conversion cannot be represented
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ConvertError {
+    /// Result needs more than 256 states
+    TooManyStates,
+}
+
+/// Tables of a Moore machine, start state 0
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MooreTables<I, O> {
+    /// Transition table: (from_state, input, to_state)
+    pub transitions: Vec<(u8, I, u8)>,
+    /// Output array: index=state, value=output
+    pub outputs: Vec<O>,
+}
+
+/// Tables of a Mealy machine, same states as the source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MealyTables<I, O> {
+    /// Transition table: (from_state, input, to_state)
+    pub transitions: Vec<(u8, I, u8)>,
+    /// Output table: (state, input, output)
+    pub outputs: Vec<(u8, I, O)>,
+}
+
+/// Build a Moore machine stepping like the Mealy machine starting in
+/// `initial` with the given tables
+///
+/// Each Moore state is a Mealy state paired with the output that led into
+/// it, so only pairs reachable from `initial` are kept. The start state 0
+/// outputs `initial_output`, which `step` never returns. Rows without an
+/// output are dropped, their `step` fails either way.
+///
+/// # Errors
+/// `ConvertError::TooManyStates` if more than 256 pairs are reachable.
+pub fn mealy_to_moore<I: Copy + Eq, O: Copy + PartialEq>(
+    initial: u8,
+    transitions: &[(u8, I, u8)],
+    outputs: &[(u8, I, O)],
+    initial_output: O,
+) -> Result<MooreTables<I, O>, ConvertError> {
+    // pairs[moore_state] = (mealy_state, output on entry)
+    let mut pairs = Vec::from([(initial, None)]);
+    let mut tables = MooreTables {
+        transitions: Vec::new(),
+        outputs: Vec::from([initial_output]),
+    };
+    let mut queue = VecDeque::from([0u8]);
+
+    while let Some(moore) = queue.pop_front() {
+        let (state, _entry) = pairs[usize::from(moore)];
+        for (row, (from, input, to)) in transitions.iter().enumerate() {
+            // Only the first row for (state, input) is ever taken
+            let shadowed = transitions[..row]
+                .iter()
+                .any(|(f, i, _t)| f == from && i == input);
+            let output = outputs
+                .iter()
+                .find(|(s, i, _o)| s == from && i == input)
+                .map(|(_s, _i, o)| *o);
+            let (true, false, Some(output)) = (*from == state, shadowed, output) else {
+                continue;
+            };
+
+            let target = match pairs.iter().position(|pair| *pair == (*to, Some(output))) {
+                Some(index) => index,
+                None => {
+                    pairs.push((*to, Some(output)));
+                    tables.outputs.push(output);
+                    let index = pairs.len() - 1;
+                    queue.push_back(u8::try_from(index).map_err(|_| ConvertError::TooManyStates)?);
+                    index
+                }
+            };
+            let target = u8::try_from(target).map_err(|_| ConvertError::TooManyStates)?;
+            tables.transitions.push((moore, *input, target));
+        }
+    }
+    Ok(tables)
+}
+
+/// Build a Mealy machine stepping like the Moore machine with the given
+/// tables, over the same states
+///
+/// Each row outputs what its target state outputs. Rows whose target has
+/// no output are kept but get no output row, their `step` fails either way.
+pub fn moore_to_mealy<I: Copy, O: Copy>(
+    transitions: &[(u8, I, u8)],
+    outputs: &[O],
+) -> MealyTables<I, O> {
+    MealyTables {
+        transitions: transitions.to_vec(),
+        outputs: transitions
+            .iter()
+            .filter_map(|(from, input, to)| Some((*from, *input, *outputs.get(usize::from(*to))?)))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::equivalent;
+    use crate::{Mealy, Moore};
+
+    // Outputs whether the last two inputs were equal
+    static TRANS: [(u8, u8, u8); 4] = [(0, 0, 0), (0, 1, 1), (1, 0, 0), (1, 1, 1)];
+    static OUTS: [(u8, u8, bool); 4] = [(0, 0, true), (0, 1, false), (1, 0, false), (1, 1, true)];
+
+    #[test]
+    fn mealy_to_moore_splits_states() {
+        let tables = mealy_to_moore(0, &TRANS, &OUTS, false).unwrap();
+        // (0, start) plus each Mealy state entered with true and false
+        assert_eq!(tables.outputs.len(), 5);
+        let mealy = Mealy::new(0, &TRANS, &OUTS);
+        let moore = Moore::new(0, &tables.transitions, &tables.outputs);
+        assert_eq!(equivalent(&mealy, &moore, &[0, 1]), Ok(()));
+    }
+
+    #[test]
+    fn round_trip_is_equivalent() {
+        let moore_tables = mealy_to_moore(1, &TRANS, &OUTS, true).unwrap();
+        let mealy_tables = moore_to_mealy(&moore_tables.transitions, &moore_tables.outputs);
+        let original = Mealy::new(1, &TRANS, &OUTS);
+        let round_trip = Mealy::new(0, &mealy_tables.transitions, &mealy_tables.outputs);
+        assert_eq!(equivalent(&original, &round_trip, &[0, 1]), Ok(()));
+    }
+}
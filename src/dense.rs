@@ -0,0 +1,450 @@
+//! Dense tables indexed by state and input
+//!
+//! One slot per (state, input) at `state * I::COUNT + input.index()`, so a
+//! step is a single indexed load whatever the table size. Memory grows as
+//! states x inputs; for sparse tables see `displacement`.
+//!
+//! # Example
+//! ```
+//! use fsmall::dense::DenseMealy;
+//! use fsmall::input_index;
+//!
+//! input_index! {
+//!     #[derive(Copy, Clone, Eq, PartialEq)]
+//!     enum Input { Coin, Push }
+//! }
+//!
+//! // Turnstile: locked (0) and unlocked (1)
+//! static TABLE: [Option<(u8, &str)>; 4] = [
+//!     Some((1, "unlock")), None,              // Locked: Coin, Push
+//!     Some((1, "refund")), Some((0, "lock")), // Unlocked: Coin, Push
+//! ];
+//!
+//! let mut fsm = DenseMealy::new(0, &TABLE);
+//! assert!(fsm.step(Input::Push).is_err());
+//! assert_eq!(fsm.step(Input::Coin), Ok("unlock"));
+//! assert_eq!(fsm.step(Input::Push), Ok("lock"));
+//! ```
+//!
+//! The lookup is also available as `const fn`s, so tables can be built
+//! with `from_rows` and simulated at compile time. Inputs are passed by
+//! index there; for `input_index!` enums that is `input as usize`.
+//!
+//! ```
+//! use fsmall::dense::{from_rows, run_const};
+//! use fsmall::input_index;
+//!
+//! input_index! {
+//!     #[derive(Copy, Clone)]
+//!     enum Input { Coin, Push }
+//! }
+//! use Input::*;
+//!
+//! const LOCKED: u8 = 0;
+//! const UNLOCKED: u8 = 1;
+//!
+//! const TABLE: [Option<(u8, &str)>; 4] = from_rows::<Input, _, 4>(&[
+//!     (LOCKED, Coin as usize, UNLOCKED, "unlock"),
+//!     (UNLOCKED, Coin as usize, UNLOCKED, "refund"),
+//!     (UNLOCKED, Push as usize, LOCKED, "lock"),
+//! ]);
+//!
+//! // Fails the build if the turnstile stops relocking
+//! const _: () = assert!(matches!(
+//!     run_const::<Input, _>(LOCKED, &[Coin as usize, Coin as usize, Push as usize], &TABLE),
+//!     Ok(LOCKED)
+//! ));
+//! ```
+
+use core::marker::PhantomData;
+
+use crate::{InputIndex, Machine, StepError};
+
+/// Mealy machine over a dense (state, input) table
+pub struct DenseMealy<'t, I, O> {
+    state: u8,
+    // table[state * I::COUNT + input.index()] = (next_state, output)
+    table: &'t [Option<(u8, O)>],
+    input: PhantomData<fn(I)>,
+}
+
+impl<'t, I: InputIndex, O: Copy> DenseMealy<'t, I, O> {
+    /// Create machine over `table`
+    ///
+    /// # Arguments
+    /// * `initial_state` - Starting state (0-255)
+    /// * `table` - Slots of every state's inputs in order, state-major:
+    ///   `Some((next_state, output))`, or `None` without a transition
+    pub const fn new(initial_state: u8, table: &'t [Option<(u8, O)>]) -> Self {
+        DenseMealy {
+            state: initial_state,
+            table,
+            input: PhantomData,
+        }
+    }
+
+    /// Process input, transition to next state, return output
+    ///
+    /// # Errors
+    /// * `StepError::NoTransition` - Empty slot for (state, input), or
+    ///   state beyond the table
+    #[inline]
+    pub fn step(&mut self, input: I) -> Result<O, StepError> {
+        let (next, output) = step_const::<I, O>(self.state, input.index(), self.table)?;
+        self.state = next;
+        Ok(output)
+    }
+
+    /// Get current state
+    pub fn current_state(&self) -> u8 {
+        self.state
+    }
+
+    /// Reset to specific state
+    pub fn reset(&mut self, state: u8) {
+        self.state = state;
+    }
+}
+
+impl<I: InputIndex, O: Copy> Machine for DenseMealy<'_, I, O> {
+    type Input = I;
+    type Output = O;
+    type Error = StepError;
+
+    fn step(&mut self, input: I) -> Result<O, StepError> {
+        DenseMealy::step(self, input)
+    }
+
+    fn current_state(&self) -> u8 {
+        DenseMealy::current_state(self)
+    }
+
+    fn reset(&mut self, state: u8) {
+        DenseMealy::reset(self, state)
+    }
+}
+
+/// Look up (state, input index) in a dense table: (next_state, output)
+///
+/// # Errors
+/// * `StepError::NoTransition` - Empty slot, index at or above
+///   `I::COUNT`, or state beyond the table
+pub const fn step_const<I: InputIndex, O: Copy>(
+    state: u8,
+    input_index: usize,
+    table: &[Option<(u8, O)>],
+) -> Result<(u8, O), StepError> {
+    if input_index >= I::COUNT {
+        return Err(StepError::NoTransition);
+    }
+    let slot = match (state as usize).checked_mul(I::COUNT) {
+        Some(row) => row.checked_add(input_index),
+        None => None,
+    };
+    let slot = match slot {
+        Some(slot) if slot < table.len() => slot,
+        _ => return Err(StepError::NoTransition),
+    };
+    match table[slot] {
+        Some(entry) => Ok(entry),
+        None => Err(StepError::NoTransition),
+    }
+}
+
+/// Feed input indices from `initial`, returning the final state
+///
+/// # Errors
+/// The first error of `step_const`
+pub const fn run_const<I: InputIndex, O: Copy>(
+    initial: u8,
+    inputs: &[usize],
+    table: &[Option<(u8, O)>],
+) -> Result<u8, StepError> {
+    let mut state = initial;
+    let mut k = 0;
+    while k < inputs.len() {
+        match step_const::<I, O>(state, inputs[k], table) {
+            Ok((next, _output)) => state = next,
+            Err(e) => return Err(e),
+        }
+        k += 1;
+    }
+    Ok(state)
+}
+
+/// Build a dense table of `N` slots from (state, input index, next_state,
+/// output) rows, in `const` context
+///
+/// The first row for a (state, input) wins, as in `Mealy::step`.
+///
+/// # Panics
+/// If a row's slot is not below `N`, or its index not below `I::COUNT`;
+/// at compile time when evaluated in a `const` or `static`.
+pub const fn from_rows<I: InputIndex, O: Copy, const N: usize>(
+    rows: &[(u8, usize, u8, O)],
+) -> [Option<(u8, O)>; N] {
+    let mut table = [None; N];
+    let mut k = 0;
+    while k < rows.len() {
+        let (from, input_index, to, output) = rows[k];
+        assert!(input_index < I::COUNT, "input index out of range");
+        let slot = from as usize * I::COUNT + input_index;
+        assert!(slot < N, "state out of range of the table");
+        if table[slot].is_none() {
+            table[slot] = Some((to, output));
+        }
+        k += 1;
+    }
+    table
+}
+
+/// Dense Mealy machine whose step takes the same time for every input
+///
+/// Slots without a transition hold a sentinel entry leading to `error`, a
+/// state whose own slots all lead back to it, so `step` always performs
+/// one clamped load and one store: no search, no `Option`, no early
+/// return, and worst-case execution time equals the best case. Check for
+/// failure with `is_error` when convenient, e.g. once per control cycle.
+/// Build tables with `from_rows` and `with_sentinels`.
+///
+/// # Example
+/// ```
+/// use fsmall::dense::{from_rows, with_sentinels, BranchlessMealy};
+///
+/// // Two-state toggle on `true`; state 2 is the error sink
+/// const TABLE: [(u8, u8); 6] =
+///     with_sentinels(from_rows::<bool, _, 6>(&[(0, 1, 1, 10), (1, 1, 0, 20)]), 2, 0);
+///
+/// let mut fsm = BranchlessMealy::new(0, 2, &TABLE);
+/// assert_eq!(fsm.step(true), 10);
+/// assert_eq!(fsm.step(true), 20);
+/// assert!(!fsm.is_error());
+/// fsm.step(false);
+/// assert!(fsm.is_error());
+/// ```
+pub struct BranchlessMealy<'t, I, O> {
+    state: u8,
+    error: u8,
+    // table[state * I::COUNT + input.index()] = (next_state, output)
+    table: &'t [(u8, O)],
+    // Copy of the table's last slot, read for slots past the end
+    last: (u8, O),
+    input: PhantomData<fn(I)>,
+}
+
+impl<'t, I: InputIndex, O: Copy> BranchlessMealy<'t, I, O> {
+    /// Create machine over `table`
+    ///
+    /// Slots past the end of the table, reached only from states beyond
+    /// it, read the last slot; make `error` the last state so they land in
+    /// its row.
+    ///
+    /// # Arguments
+    /// * `initial_state` - Starting state (0-255)
+    /// * `error` - Sink state entered for missing transitions
+    /// * `table` - Slots of every state's inputs in order, state-major
+    ///
+    /// # Panics
+    /// If `table` is empty
+    pub const fn new(initial_state: u8, error: u8, table: &'t [(u8, O)]) -> Self {
+        assert!(!table.is_empty(), "branchless table must not be empty");
+        BranchlessMealy {
+            state: initial_state,
+            error,
+            table,
+            last: table[table.len() - 1],
+            input: PhantomData,
+        }
+    }
+
+    /// Process input, transition to next state, return output
+    ///
+    /// A missing transition moves to the error state and returns the
+    /// sentinel's output.
+    #[inline]
+    pub fn step(&mut self, input: I) -> O {
+        let slot = usize::from(self.state)
+            .wrapping_mul(I::COUNT)
+            .wrapping_add(input.index());
+        // The fallback compiles to a conditional move, not a branch
+        let (next, output) = self.table.get(slot).copied().unwrap_or(self.last);
+        self.state = next;
+        output
+    }
+
+    /// Check whether a missing transition led to the error state
+    pub fn is_error(&self) -> bool {
+        self.state == self.error
+    }
+
+    /// Get current state
+    pub fn current_state(&self) -> u8 {
+        self.state
+    }
+
+    /// Reset to specific state
+    pub fn reset(&mut self, state: u8) {
+        self.state = state;
+    }
+}
+
+impl<I: InputIndex, O: Copy> Machine for BranchlessMealy<'_, I, O> {
+    type Input = I;
+    type Output = O;
+    type Error = StepError;
+
+    /// Step, reporting `StepError::NoTransition` once in the error state
+    fn step(&mut self, input: I) -> Result<O, StepError> {
+        let output = BranchlessMealy::step(self, input);
+        if self.is_error() {
+            return Err(StepError::NoTransition);
+        }
+        Ok(output)
+    }
+
+    fn current_state(&self) -> u8 {
+        BranchlessMealy::current_state(self)
+    }
+
+    fn reset(&mut self, state: u8) {
+        BranchlessMealy::reset(self, state)
+    }
+}
+
+/// Replace the empty slots of a dense table with sentinel entries for
+/// `BranchlessMealy`, in `const` context
+///
+/// # Arguments
+/// * `table` - Dense table, e.g. from `from_rows`
+/// * `error` - Error state every empty slot leads to
+/// * `invalid` - Output of the sentinel entries
+pub const fn with_sentinels<O: Copy, const N: usize>(
+    table: [Option<(u8, O)>; N],
+    error: u8,
+    invalid: O,
+) -> [(u8, O); N] {
+    let mut out = [(error, invalid); N];
+    let mut k = 0;
+    while k < N {
+        if let Some(entry) = table[k] {
+            out[k] = entry;
+        }
+        k += 1;
+    }
+    out
+}
+
+/// Build the dense table stepping like `Mealy` over the given tables
+///
+/// Requires the `alloc` feature. As in `Mealy::step`, the first rows for
+/// a (state, input) win. Rows without an output are dropped, their step
+/// fails either way.
+///
+/// # Arguments
+/// * `transitions` - Transition table: (from_state, input, to_state)
+/// * `outputs` - Output table: (state, input, output)
+#[cfg(feature = "alloc")]
+pub fn build<I: InputIndex, O: Copy>(
+    transitions: &[(u8, I, u8)],
+    outputs: &[(u8, I, O)],
+) -> alloc::vec::Vec<Option<(u8, O)>> {
+    use alloc::vec;
+
+    let states = transitions
+        .iter()
+        .map(|(from, _input, to)| usize::from(*from.max(to)) + 1)
+        .max()
+        .unwrap_or(0);
+    let mut table = vec![None; states * I::COUNT];
+    let mut seen = vec![false; states * I::COUNT];
+    for (from, input, to) in transitions {
+        let slot = usize::from(*from) * I::COUNT + input.index();
+        if seen.get(slot).copied().unwrap_or(true) {
+            continue;
+        }
+        seen[slot] = true;
+        table[slot] = outputs
+            .iter()
+            .find(|(s, i, _o)| s == from && i.index() == input.index())
+            .map(|(_s, _i, o)| (*to, *o));
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn const_lookup_simulates_at_compile_time() {
+        const TABLE: [Option<(u8, u8)>; 6] =
+            from_rows::<bool, _, 6>(&[(0, 1, 1, 10), (1, 1, 2, 20), (2, 0, 0, 30), (0, 1, 0, 99)]);
+        const END: Result<u8, StepError> = run_const::<bool, _>(0, &[1, 1, 0, 1], &TABLE);
+        const _: () = assert!(matches!(END, Ok(1)));
+
+        assert_eq!(step_const::<bool, _>(0, 1, &TABLE), Ok((1, 10)));
+        assert_eq!(
+            step_const::<bool, _>(0, 2, &TABLE),
+            Err(StepError::NoTransition)
+        );
+        assert_eq!(
+            step_const::<bool, _>(3, 0, &TABLE),
+            Err(StepError::NoTransition)
+        );
+        assert_eq!(
+            step_const::<bool, _>(1, usize::MAX, &TABLE),
+            Err(StepError::NoTransition)
+        );
+        assert_eq!(
+            run_const::<bool, _>(0, &[1, 0], &TABLE),
+            Err(StepError::NoTransition)
+        );
+    }
+
+    #[test]
+    fn branchless_matches_dense() {
+        const ROWS: [(u8, usize, u8, char); 3] = [(0, 0, 1, 'a'), (1, 1, 0, 'b'), (1, 0, 1, 'c')];
+        const DENSE: [Option<(u8, char)>; 6] = from_rows::<bool, _, 6>(&ROWS);
+        const TABLE: [(u8, char); 6] = with_sentinels(DENSE, 2, '!');
+
+        let mut branchless = BranchlessMealy::new(0, 2, &TABLE);
+        let mut dense = DenseMealy::new(0, &DENSE);
+        for input in [false, false, true, false, true, true] {
+            match dense.step(input) {
+                Ok(output) => assert_eq!(branchless.step(input), output),
+                Err(_) => assert_eq!(
+                    Machine::step(&mut branchless, input),
+                    Err(StepError::NoTransition)
+                ),
+            }
+        }
+        assert!(branchless.is_error());
+        assert_eq!(branchless.step(false), '!');
+
+        // Beyond the table: clamped to the last slot, the error row
+        branchless.reset(200);
+        assert_eq!(branchless.step(true), '!');
+        assert_eq!(branchless.current_state(), 2);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn dense_build_steps_like_mealy() {
+        use crate::Mealy;
+
+        static TRANS: [(u8, u8, u8); 5] = [(0, 1, 1), (0, 1, 2), (1, 2, 0), (1, 3, 2), (2, 200, 0)];
+        static OUTS: [(u8, u8, char); 4] = [(0, 1, 'a'), (1, 2, 'b'), (1, 2, 'x'), (2, 200, 'c')];
+        let table = build(&TRANS, &OUTS);
+        assert_eq!(table.len(), 3 * 256);
+
+        for state in 0..4 {
+            for input in 0..=255 {
+                let mut dense = DenseMealy::new(state, &table);
+                let mut mealy = Mealy::new(state, &TRANS, &OUTS);
+                let ok = mealy.step(input).ok();
+                assert_eq!(dense.step(input).ok(), ok);
+                assert_eq!(dense.current_state(), mealy.current_state());
+            }
+        }
+    }
+}
@@ -0,0 +1,616 @@
+//! Machine descriptions kept outside Rust source
+//!
+//! Requires the `alloc` feature. A description names the states and spells
+//! inputs and outputs as Rust expressions, so it can be reviewed and edited
+//! as the source of truth and turned into tables by `codegen`.
+//!
+//! The file format is a TOML subset: one `key = value` per line, values
+//! are strings (`"..."` or `'...'`) or arrays of them, arrays may span
+//! lines, and `#` starts a comment. The same keys and values can also be
+//! written as a JSON object or a RON struct, see `from_json` and `from_ron`.
+//!
+//! ```toml
+//! kind = "mealy"              # or "moore", default "mealy"
+//! input = "Input"             # Rust input type
+//! output = "&'static str"     # Rust output type
+//! initial = "closed"          # default: first state
+//! states = ["closed", "open", "locked"]
+//! transitions = [
+//!     # from, input, to, output (Mealy only)
+//!     ["closed", "Input::Open", "open", '"creak"'],
+//!     ["open", "Input::Close", "closed", '"thud"'],
+//! ]
+//! # Moore only, one output per state
+//! # outputs = ["Light::Off", "Light::On"]
+//! ```
+//!
+//! # Example
+//! ```
+//! use fsmall::description::{Description, Kind};
+//!
+//! let description = Description::from_toml(r#"
+//!     output = "bool"
+//!     input = "char"
+//!     states = ["off", "on"]
+//!     transitions = [["off", "'t'", "on", "true"], ["on", "'t'", "off", "false"]]
+//! "#).unwrap();
+//! assert_eq!(description.kind, Kind::Mealy);
+//! assert_eq!(description.transitions[1].from, 1);
+//! ```
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+/// Error returned when a description cannot be read
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DescriptionError {
+    /// Malformed line
+    Syntax {
+        /// 1-based line number
+        line: usize,
+    },
+    /// Key not part of the format, or given twice
+    UnknownKey {
+        /// 1-based line number
+        line: usize,
+    },
+    /// Required key absent
+    Missing(&'static str),
+    /// Reference to an undeclared state, or a state declared twice
+    UnknownState {
+        /// 1-based line number
+        line: usize,
+    },
+    /// Mealy row without output, or Moore outputs not one per state
+    Output {
+        /// 1-based line number
+        line: usize,
+    },
+    /// More than 256 states
+    TooManyStates,
+}
+
+impl fmt::Display for DescriptionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DescriptionError::Syntax { line } => write!(f, "line {line}: syntax error"),
+            DescriptionError::UnknownKey { line } => write!(f, "line {line}: unknown key"),
+            DescriptionError::Missing(key) => write!(f, "missing key `{key}`"),
+            DescriptionError::UnknownState { line } => write!(f, "line {line}: unknown state"),
+            DescriptionError::Output { line } => write!(f, "line {line}: bad output"),
+            DescriptionError::TooManyStates => f.write_str("more than 256 states"),
+        }
+    }
+}
+
+impl core::error::Error for DescriptionError {}
+
+/// Machine type a description generates
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Kind {
+    /// Outputs per transition
+    Mealy,
+    /// Outputs per state
+    Moore,
+}
+
+/// One transition of a description
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Row {
+    /// Source state
+    pub from: u8,
+    /// Input, as a Rust expression
+    pub input: String,
+    /// Target state
+    pub to: u8,
+    /// Output as a Rust expression, Mealy only
+    pub output: Option<String>,
+}
+
+/// Machine described by a file, states resolved to indices
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Description {
+    /// Mealy or Moore
+    pub kind: Kind,
+    /// Rust input type
+    pub input: String,
+    /// Rust output type
+    pub output: String,
+    /// State names, index=state
+    pub states: Vec<String>,
+    /// Initial state
+    pub initial: u8,
+    /// Transitions in file order
+    pub transitions: Vec<Row>,
+    /// Moore outputs as Rust expressions, index=state; empty for Mealy
+    pub outputs: Vec<String>,
+}
+
+// Parsed value and the line it started on
+#[derive(Debug)]
+enum Value {
+    Str(String, usize),
+    Array(Vec<Value>, usize),
+}
+
+impl Value {
+    fn line(&self) -> usize {
+        match self {
+            Value::Str(_, line) | Value::Array(_, line) => *line,
+        }
+    }
+
+    fn into_str(self) -> Result<(String, usize), DescriptionError> {
+        match self {
+            Value::Str(s, line) => Ok((s, line)),
+            Value::Array(_, line) => Err(DescriptionError::Syntax { line }),
+        }
+    }
+
+    fn into_array(self) -> Result<(Vec<Value>, usize), DescriptionError> {
+        match self {
+            Value::Array(items, line) => Ok((items, line)),
+            Value::Str(_, line) => Err(DescriptionError::Syntax { line }),
+        }
+    }
+}
+
+// Comment syntax of a file format
+#[derive(Copy, Clone, PartialEq)]
+enum Comments {
+    None,
+    // `#` to the end of the line
+    Hash,
+    // `//` to the end of the line, and nesting `/* */` blocks
+    Slashes,
+}
+
+struct Parser<'a> {
+    rest: core::iter::Peekable<core::str::Chars<'a>>,
+    line: usize,
+    comments: Comments,
+}
+
+impl<'a> Parser<'a> {
+    fn new(src: &'a str, comments: Comments) -> Self {
+        Parser {
+            rest: src.chars().peekable(),
+            line: 1,
+            comments,
+        }
+    }
+
+    fn syntax(&self) -> DescriptionError {
+        DescriptionError::Syntax { line: self.line }
+    }
+
+    // Skip blanks and comments, and newlines too if `newlines`
+    fn skip(&mut self, newlines: bool) {
+        while let Some(&c) = self.rest.peek() {
+            match c {
+                ' ' | '\t' | '\r' => {}
+                '\n' if newlines => self.line += 1,
+                '#' if self.comments == Comments::Hash => {
+                    while self.rest.next_if(|&c| c != '\n').is_some() {}
+                    continue;
+                }
+                '/' if self.comments == Comments::Slashes => {
+                    let mut ahead = self.rest.clone();
+                    ahead.next();
+                    match ahead.next() {
+                        Some('/') => while self.rest.next_if(|&c| c != '\n').is_some() {},
+                        Some('*') => self.block_comment(),
+                        _ => return,
+                    }
+                    continue;
+                }
+                _ => return,
+            }
+            self.rest.next();
+        }
+    }
+
+    // Skip a `/* */` comment, opening `/*` not yet consumed; an unclosed
+    // one runs to the end of the input
+    fn block_comment(&mut self) {
+        self.rest.next();
+        self.rest.next();
+        let mut depth = 1;
+        while depth > 0 {
+            match self.rest.next() {
+                Some('\n') => self.line += 1,
+                Some('*') if self.rest.next_if_eq(&'/').is_some() => depth -= 1,
+                Some('/') if self.rest.next_if_eq(&'*').is_some() => depth += 1,
+                Some(_) => {}
+                None => return,
+            }
+        }
+    }
+
+    fn key(&mut self) -> String {
+        let mut key = String::new();
+        while let Some(c) = self
+            .rest
+            .next_if(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '-')
+        {
+            key.push(c);
+        }
+        key
+    }
+
+    fn value(&mut self) -> Result<Value, DescriptionError> {
+        let line = self.line;
+        match self.rest.next() {
+            Some('"') => self.basic_string().map(|s| Value::Str(s, line)),
+            Some('\'') => {
+                let mut s = String::new();
+                loop {
+                    match self.rest.next() {
+                        Some('\'') => return Ok(Value::Str(s, line)),
+                        Some('\n') | None => return Err(self.syntax()),
+                        Some(c) => s.push(c),
+                    }
+                }
+            }
+            Some('[') => {
+                let mut items = Vec::new();
+                loop {
+                    self.skip(true);
+                    if self.rest.next_if_eq(&']').is_some() {
+                        return Ok(Value::Array(items, line));
+                    }
+                    items.push(self.value()?);
+                    self.skip(true);
+                    match self.rest.next() {
+                        Some(',') => {}
+                        Some(']') => return Ok(Value::Array(items, line)),
+                        _ => return Err(self.syntax()),
+                    }
+                }
+            }
+            _ => Err(self.syntax()),
+        }
+    }
+
+    // Rest of a "..." string, opening quote consumed
+    fn basic_string(&mut self) -> Result<String, DescriptionError> {
+        let mut s = String::new();
+        loop {
+            match self.rest.next() {
+                Some('"') => return Ok(s),
+                Some('\\') => s.push(match self.rest.next() {
+                    Some('n') => '\n',
+                    Some('t') => '\t',
+                    Some('r') => '\r',
+                    Some(c @ ('"' | '\\' | '/')) => c,
+                    Some('u') => {
+                        let code = (0..4).try_fold(0, |code, _| {
+                            let digit = self.rest.next()?.to_digit(16)?;
+                            Some(code * 16 + digit)
+                        });
+                        match code.and_then(char::from_u32) {
+                            Some(c) => c,
+                            None => return Err(self.syntax()),
+                        }
+                    }
+                    _ => return Err(self.syntax()),
+                }),
+                Some('\n') | None => return Err(self.syntax()),
+                Some(c) => s.push(c),
+            }
+        }
+    }
+
+    // `{"key": value, ...}`, or `Name(key: value, ...)` with `ron`
+    fn object(&mut self, ron: bool) -> Result<Vec<(String, Value)>, DescriptionError> {
+        self.skip(true);
+        let close = if ron {
+            // Struct name is optional and not checked
+            self.key();
+            self.skip(true);
+            ')'
+        } else {
+            '}'
+        };
+        if self.rest.next() != Some(if ron { '(' } else { '{' }) {
+            return Err(self.syntax());
+        }
+        let mut pairs = Vec::new();
+        loop {
+            self.skip(true);
+            if self.rest.next_if_eq(&close).is_some() {
+                break;
+            }
+            let key = if ron {
+                self.key()
+            } else if self.rest.next_if_eq(&'"').is_some() {
+                self.basic_string()?
+            } else {
+                return Err(self.syntax());
+            };
+            self.skip(true);
+            if key.is_empty() || self.rest.next_if_eq(&':').is_none() {
+                return Err(self.syntax());
+            }
+            self.skip(true);
+            pairs.push((key, self.value()?));
+            self.skip(true);
+            match self.rest.next() {
+                Some(',') => {}
+                Some(c) if c == close => break,
+                _ => return Err(self.syntax()),
+            }
+        }
+        self.skip(true);
+        match self.rest.peek() {
+            None => Ok(pairs),
+            Some(_) => Err(self.syntax()),
+        }
+    }
+}
+
+// Top-level (key, value) pairs in file order
+fn parse_toml(src: &str) -> Result<Vec<(String, Value)>, DescriptionError> {
+    let mut parser = Parser::new(src, Comments::Hash);
+    let mut pairs = Vec::new();
+    loop {
+        parser.skip(true);
+        if parser.rest.peek().is_none() {
+            return Ok(pairs);
+        }
+        let key = parser.key();
+        parser.skip(false);
+        if key.is_empty() || parser.rest.next_if_eq(&'=').is_none() {
+            return Err(parser.syntax());
+        }
+        parser.skip(false);
+        pairs.push((key, parser.value()?));
+        parser.skip(false);
+        if parser.rest.next_if(|&c| c == '\n').is_none() && parser.rest.peek().is_some() {
+            return Err(parser.syntax());
+        }
+        parser.line += 1;
+    }
+}
+
+impl Description {
+    /// Read a description in the TOML subset described in the module docs
+    ///
+    /// # Errors
+    /// See `DescriptionError`; every error but `Missing` and `TooManyStates`
+    /// carries the offending line.
+    pub fn from_toml(src: &str) -> Result<Self, DescriptionError> {
+        Self::from_pairs(parse_toml(src)?)
+    }
+
+    /// Read a description written as one JSON object
+    ///
+    /// Takes the keys of the TOML form, with string and array values.
+    ///
+    /// # Example
+    /// ```
+    /// use fsmall::description::Description;
+    ///
+    /// let description = Description::from_json(r#"{
+    ///     "input": "char",
+    ///     "output": "bool",
+    ///     "states": ["off", "on"],
+    ///     "transitions": [["off", "'t'", "on", "true"]]
+    /// }"#).unwrap();
+    /// assert_eq!(description.states, ["off", "on"]);
+    /// ```
+    ///
+    /// # Errors
+    /// As `from_toml`
+    pub fn from_json(src: &str) -> Result<Self, DescriptionError> {
+        Self::from_pairs(Parser::new(src, Comments::None).object(false)?)
+    }
+
+    /// Read a description written as one RON struct
+    ///
+    /// Takes the keys of the TOML form as fields, e.g.
+    /// `Machine(input: "u8", states: ["idle"], ...)`. Comments are `//` to
+    /// the end of the line and `/* */` blocks, which nest as in RON.
+    ///
+    /// # Errors
+    /// As `from_toml`
+    pub fn from_ron(src: &str) -> Result<Self, DescriptionError> {
+        Self::from_pairs(Parser::new(src, Comments::Slashes).object(true)?)
+    }
+
+    // Build from top-level pairs, shared by every file format
+    fn from_pairs(pairs: Vec<(String, Value)>) -> Result<Self, DescriptionError> {
+        let mut kind = None;
+        let mut input = None;
+        let mut output = None;
+        let mut initial = None;
+        let mut states = None;
+        let mut transitions = None;
+        let mut outputs = None;
+        for (key, value) in pairs {
+            let line = value.line();
+            let slot = match key.as_str() {
+                "kind" => &mut kind,
+                "input" => &mut input,
+                "output" => &mut output,
+                "initial" => &mut initial,
+                "states" => &mut states,
+                "transitions" => &mut transitions,
+                "outputs" => &mut outputs,
+                _ => return Err(DescriptionError::UnknownKey { line }),
+            };
+            if slot.replace(value).is_some() {
+                return Err(DescriptionError::UnknownKey { line });
+            }
+        }
+
+        let kind = match kind.map(Value::into_str).transpose()? {
+            None => Kind::Mealy,
+            Some((k, _)) if k == "mealy" => Kind::Mealy,
+            Some((k, _)) if k == "moore" => Kind::Moore,
+            Some((_, line)) => return Err(DescriptionError::Syntax { line }),
+        };
+        let (input, _) = input
+            .ok_or(DescriptionError::Missing("input"))?
+            .into_str()?;
+        let (output, _) = output
+            .ok_or(DescriptionError::Missing("output"))?
+            .into_str()?;
+
+        let mut names: Vec<String> = Vec::new();
+        for state in states
+            .ok_or(DescriptionError::Missing("states"))?
+            .into_array()?
+            .0
+        {
+            let (name, line) = state.into_str()?;
+            if names.contains(&name) {
+                return Err(DescriptionError::UnknownState { line });
+            }
+            names.push(name);
+        }
+        if names.len() > 256 {
+            return Err(DescriptionError::TooManyStates);
+        }
+        let index = |value: Value| -> Result<u8, DescriptionError> {
+            let (name, line) = value.into_str()?;
+            names
+                .iter()
+                .position(|s| *s == name)
+                .map(|state| state as u8)
+                .ok_or(DescriptionError::UnknownState { line })
+        };
+
+        let initial = match initial {
+            Some(value) => index(value)?,
+            None if names.is_empty() => return Err(DescriptionError::Missing("states")),
+            None => 0,
+        };
+
+        let mut rows = Vec::new();
+        let transitions = transitions.ok_or(DescriptionError::Missing("transitions"))?;
+        for row in transitions.into_array()?.0 {
+            let (fields, line) = row.into_array()?;
+            let mut fields = fields.into_iter();
+            let (Some(from), Some(input), Some(to)) = (fields.next(), fields.next(), fields.next())
+            else {
+                return Err(DescriptionError::Syntax { line });
+            };
+            let output = fields.next().map(Value::into_str).transpose()?;
+            if fields.next().is_some() || output.is_some() != (kind == Kind::Mealy) {
+                return Err(DescriptionError::Output { line });
+            }
+            rows.push(Row {
+                from: index(from)?,
+                input: input.into_str()?.0,
+                to: index(to)?,
+                output: output.map(|(o, _)| o),
+            });
+        }
+
+        let outputs = match (kind, outputs) {
+            (Kind::Mealy, None) => Vec::new(),
+            (Kind::Mealy, Some(value)) => {
+                return Err(DescriptionError::Output { line: value.line() })
+            }
+            (Kind::Moore, None) => return Err(DescriptionError::Missing("outputs")),
+            (Kind::Moore, Some(value)) => {
+                let (items, line) = value.into_array()?;
+                if items.len() != names.len() {
+                    return Err(DescriptionError::Output { line });
+                }
+                items
+                    .into_iter()
+                    .map(|item| item.into_str().map(|(o, _)| o))
+                    .collect::<Result<_, _>>()?
+            }
+        };
+
+        Ok(Description {
+            kind,
+            input,
+            output,
+            states: names,
+            initial,
+            transitions: rows,
+            outputs,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn description_reads_moore_toml() {
+        let description = Description::from_toml(
+            "# Blinker\n\
+             kind = 'moore'\n\
+             input = \"()\"\n\
+             output = \"bool\"\n\
+             initial = \"on\"\n\
+             states = [\"off\", \"on\"]  # two phases\n\
+             transitions = [\n\
+                 [\"off\", \"()\", \"on\"],\n\
+                 [\"on\", \"()\", \"off\"],\n\
+             ]\n\
+             outputs = [\"false\", \"true\"]\n",
+        )
+        .unwrap();
+        assert_eq!(description.kind, Kind::Moore);
+        assert_eq!(description.initial, 1);
+        assert_eq!(description.transitions.len(), 2);
+        assert_eq!(description.transitions[0].output, None);
+        assert_eq!(description.outputs, ["false", "true"]);
+    }
+
+    #[test]
+    fn description_reports_error_lines() {
+        let head = "input = \"u8\"\noutput = \"u8\"\nstates = [\"a\"]\n";
+        let parse = |rest: &str| Description::from_toml(&(String::from(head) + rest));
+        assert_eq!(
+            parse("transitions = [[\"a\", \"0\", \"b\", \"1\"]]"),
+            Err(DescriptionError::UnknownState { line: 4 })
+        );
+        assert_eq!(
+            parse("transitions = [\n[\"a\", \"0\", \"a\"]]"),
+            Err(DescriptionError::Output { line: 5 })
+        );
+        assert_eq!(
+            parse("transitions = [] extra"),
+            Err(DescriptionError::Syntax { line: 4 })
+        );
+        assert_eq!(parse(""), Err(DescriptionError::Missing("transitions")));
+    }
+
+    #[test]
+    fn description_formats_agree() {
+        let toml = Description::from_toml(
+            "input = \"u8\"\noutput = \"u8\"\nstates = [\"a\", \"b\"]\n\
+             transitions = [[\"a\", \"0\", \"b\", \"'\\\\n'\"]]\n",
+        );
+        let json = Description::from_json(
+            "{\"input\": \"u8\", \"output\": \"u8\", \"states\": [\"a\", \"\\u0062\"],\n\
+             \"transitions\": [[\"a\", \"0\", \"b\", \"'\\\\n'\"]]}",
+        );
+        let ron = Description::from_ron(
+            "// Same machine\n/* block, /* nested */\n over lines */\n\
+             Machine(\n    input: \"u8\",\n    output: \"u8\",\n    states: [\"a\", \"b\"],\n\
+             transitions: [[\"a\", \"0\", \"b\", \"'\\\\n'\"]],\n)\n",
+        );
+        assert!(toml.is_ok());
+        assert_eq!(json, toml);
+        assert_eq!(ron, toml);
+        assert_eq!(
+            Description::from_json("{\"input\": \"u8\"} trailing"),
+            Err(DescriptionError::Syntax { line: 1 })
+        );
+        // A lone `/` is no comment
+        assert_eq!(
+            Description::from_ron("/ Machine()"),
+            Err(DescriptionError::Syntax { line: 1 })
+        );
+    }
+}
@@ -0,0 +1,395 @@
+//! Row-displacement compressed tables
+//!
+//! The lex/yacc layout: every state's row of the dense (state, column)
+//! table is shifted by `base[state]` into shared `next`/`check` arrays so
+//! the rows interleave where they are empty, and `check` records which
+//! state owns each slot. A lookup is two indexed loads and a compare,
+//! while memory tracks the number of rows rather than states x columns.
+//!
+//! Columns are `u8`, typically byte classes, so a `DisplacedMealy` runs
+//! behind `ByteClasses`. `compress` (requires `alloc`) builds the arrays,
+//! e.g. in a build script printing them with `DisplacedTables::to_rust`.
+//!
+//! # Example
+//! ```
+//! use fsmall::displacement::{Displaced, DisplacedMealy};
+//!
+//! // State 0: column 0 -> 1 / 'a'; state 1: column 1 -> 0 / 'b'
+//! static BASE: [u16; 2] = [0, 0];
+//! static NEXT: [u8; 2] = [1, 0];
+//! static CHECK: [u16; 2] = [0, 1];
+//! static OUTPUTS: [char; 2] = ['a', 'b'];
+//!
+//! let mut fsm = DisplacedMealy::new(0, Displaced::new(&BASE, &NEXT, &CHECK, &OUTPUTS));
+//! assert_eq!(fsm.step(0), Ok('a'));
+//! assert!(fsm.step(0).is_err());
+//! assert_eq!(fsm.step(1), Ok('b'));
+//! ```
+
+use crate::{InputIndex, Machine, StepError};
+
+/// `check` value of a slot no state owns
+pub const EMPTY: u16 = u16::MAX;
+
+/// Row-displacement table borrowing its arrays
+#[derive(Debug)]
+pub struct Displaced<'t, O> {
+    base: &'t [u16],
+    next: &'t [u8],
+    check: &'t [u16],
+    outputs: &'t [O],
+}
+
+impl<O> Clone for Displaced<'_, O> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<O> Copy for Displaced<'_, O> {}
+
+impl<'t, O: Copy> Displaced<'t, O> {
+    /// Create table from its arrays
+    ///
+    /// # Arguments
+    /// * `base` - Row offset of each state: index=state
+    /// * `next` - Target state of each slot
+    /// * `check` - Owning state of each slot, `EMPTY` if none
+    /// * `outputs` - Output of each slot
+    pub const fn new(base: &'t [u16], next: &'t [u8], check: &'t [u16], outputs: &'t [O]) -> Self {
+        Displaced {
+            base,
+            next,
+            check,
+            outputs,
+        }
+    }
+
+    /// Look up (state, column): (next_state, output)
+    #[inline]
+    pub fn lookup(&self, state: u8, column: u8) -> Option<(u8, O)> {
+        let slot = usize::from(*self.base.get(usize::from(state))?) + usize::from(column);
+        if *self.check.get(slot)? != u16::from(state) {
+            return None;
+        }
+        Some((*self.next.get(slot)?, *self.outputs.get(slot)?))
+    }
+
+    /// Number of slots in `next`, `check` and `outputs`
+    pub fn slots(&self) -> usize {
+        self.check.len()
+    }
+}
+
+/// Mealy machine over column indices stepping through a `Displaced` table
+pub struct DisplacedMealy<'t, O> {
+    state: u8,
+    table: Displaced<'t, O>,
+}
+
+impl<'t, O: Copy> DisplacedMealy<'t, O> {
+    /// Create machine over `table`
+    pub const fn new(initial_state: u8, table: Displaced<'t, O>) -> Self {
+        DisplacedMealy {
+            state: initial_state,
+            table,
+        }
+    }
+
+    /// Process column, transition to next state, return output
+    ///
+    /// # Errors
+    /// * `StepError::NoTransition` - No slot for (state, column)
+    #[inline]
+    pub fn step(&mut self, column: u8) -> Result<O, StepError> {
+        let (next, output) = self
+            .table
+            .lookup(self.state, column)
+            .ok_or(StepError::NoTransition)?;
+        self.state = next;
+        Ok(output)
+    }
+
+    /// Step with the column of an `InputIndex` input
+    ///
+    /// # Errors
+    /// * `StepError::NoTransition` - No slot for (state, input), or index
+    ///   above 255
+    pub fn step_input<I: InputIndex>(&mut self, input: &I) -> Result<O, StepError> {
+        let column = u8::try_from(input.index()).map_err(|_| StepError::NoTransition)?;
+        self.step(column)
+    }
+
+    /// Get current state
+    pub fn current_state(&self) -> u8 {
+        self.state
+    }
+
+    /// Get table
+    pub fn table(&self) -> &Displaced<'t, O> {
+        &self.table
+    }
+
+    /// Reset to specific state
+    pub fn reset(&mut self, state: u8) {
+        self.state = state;
+    }
+}
+
+impl<O: Copy> Machine for DisplacedMealy<'_, O> {
+    type Input = u8;
+    type Output = O;
+    type Error = StepError;
+
+    fn step(&mut self, column: u8) -> Result<O, StepError> {
+        DisplacedMealy::step(self, column)
+    }
+
+    fn current_state(&self) -> u8 {
+        DisplacedMealy::current_state(self)
+    }
+
+    fn reset(&mut self, state: u8) {
+        DisplacedMealy::reset(self, state)
+    }
+}
+
+/// Error returned by `compress`
+#[cfg(feature = "alloc")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CompressError {
+    /// Transition row has no output row
+    MissingOutput {
+        /// Index into the transition table
+        row: usize,
+    },
+    /// Slots do not fit below `EMPTY`
+    TooLarge,
+}
+
+#[cfg(feature = "alloc")]
+impl core::fmt::Display for CompressError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            CompressError::MissingOutput { row } => {
+                write!(f, "transition row {row} has no output row")
+            }
+            CompressError::TooLarge => f.write_str("compressed table too large"),
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl core::error::Error for CompressError {}
+
+/// Owned row-displacement arrays built by `compress`
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DisplacedTables<O> {
+    /// Row offset of each state: index=state
+    pub base: alloc::vec::Vec<u16>,
+    /// Target state of each slot
+    pub next: alloc::vec::Vec<u8>,
+    /// Owning state of each slot, `EMPTY` if none
+    pub check: alloc::vec::Vec<u16>,
+    /// Output of each slot
+    pub outputs: alloc::vec::Vec<O>,
+}
+
+#[cfg(feature = "alloc")]
+impl<O: Copy> DisplacedTables<O> {
+    /// Borrow as a `Displaced` table
+    pub fn table(&self) -> Displaced<'_, O> {
+        Displaced::new(&self.base, &self.next, &self.check, &self.outputs)
+    }
+
+    /// Emit the arrays as Rust statics
+    ///
+    /// # Arguments
+    /// * `name` - Prefix of the statics: `{name}_BASE`, `{name}_NEXT`,
+    ///   `{name}_CHECK` and `{name}_OUTPUTS`
+    /// * `output_ty` - Output type as written in Rust
+    /// * `output` - Output as a Rust expression
+    pub fn to_rust(
+        &self,
+        name: &str,
+        output_ty: &str,
+        output: impl Fn(&O) -> alloc::string::String,
+    ) -> alloc::string::String {
+        use alloc::format;
+        use alloc::string::{String, ToString};
+        use alloc::vec::Vec;
+        use core::fmt::Write;
+
+        fn array(out: &mut String, name: &str, ty: &str, values: Vec<String>) {
+            let _ = writeln!(out, "pub static {name}: [{ty}; {}] = [", values.len());
+            for chunk in values.chunks(16) {
+                let _ = writeln!(out, "    {},", chunk.join(", "));
+            }
+            out.push_str("];\n");
+        }
+
+        let mut out = String::new();
+        let base = self.base.iter().map(ToString::to_string).collect();
+        array(&mut out, &format!("{name}_BASE"), "u16", base);
+        let next = self.next.iter().map(ToString::to_string).collect();
+        array(&mut out, &format!("{name}_NEXT"), "u8", next);
+        let check = self.check.iter().map(ToString::to_string).collect();
+        array(&mut out, &format!("{name}_CHECK"), "u16", check);
+        let outputs = self.outputs.iter().map(output).collect();
+        array(&mut out, &format!("{name}_OUTPUTS"), output_ty, outputs);
+        out
+    }
+}
+
+/// Compress Mealy tables over `u8` columns into row-displacement arrays
+///
+/// As in `Mealy::step`, the first transition and output rows for a
+/// (state, column) win. Rows are placed densest first at the lowest
+/// offset where none of their slots are taken. Empty slots repeat an
+/// arbitrary output; `check` keeps them unreachable.
+///
+/// # Arguments
+/// * `transitions` - Transition table: (from_state, column, to_state)
+/// * `outputs` - Output table: (state, column, output)
+///
+/// # Errors
+/// See `CompressError`
+#[cfg(feature = "alloc")]
+pub fn compress<O: Copy>(
+    transitions: &[(u8, u8, u8)],
+    outputs: &[(u8, u8, O)],
+) -> Result<DisplacedTables<O>, CompressError> {
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    let states = transitions
+        .iter()
+        .map(|(from, _c, _to)| usize::from(*from) + 1)
+        .max()
+        .unwrap_or(0);
+    // rows[state] = (column, next, output), first row per column
+    let mut rows: Vec<Vec<(u8, u8, O)>> = vec![Vec::new(); states];
+    for (row, (from, column, to)) in transitions.iter().enumerate() {
+        let cells = &mut rows[usize::from(*from)];
+        if cells.iter().any(|(c, _to, _o)| c == column) {
+            continue;
+        }
+        let output = outputs
+            .iter()
+            .find(|(s, c, _o)| s == from && c == column)
+            .ok_or(CompressError::MissingOutput { row })?
+            .2;
+        cells.push((*column, *to, output));
+    }
+
+    let mut order: Vec<usize> = (0..states).collect();
+    order.sort_by_key(|state| core::cmp::Reverse(rows[*state].len()));
+
+    let mut tables = DisplacedTables {
+        base: vec![0; states],
+        next: Vec::new(),
+        check: Vec::new(),
+        outputs: Vec::new(),
+    };
+    let Some(filler) = rows.iter().flatten().map(|(_c, _to, o)| *o).next() else {
+        return Ok(tables);
+    };
+    for state in order {
+        let cells = &rows[state];
+        let base = (0..)
+            .find(|base| {
+                cells.iter().all(|(c, _to, _o)| {
+                    tables
+                        .check
+                        .get(base + usize::from(*c))
+                        .is_none_or(|owner| *owner == EMPTY)
+                })
+            })
+            .unwrap_or_default();
+        for (column, to, output) in cells {
+            let slot = base + usize::from(*column);
+            if slot >= usize::from(EMPTY) {
+                return Err(CompressError::TooLarge);
+            }
+            if slot >= tables.check.len() {
+                tables.next.resize(slot + 1, 0);
+                tables.check.resize(slot + 1, EMPTY);
+                tables.outputs.resize(slot + 1, filler);
+            }
+            tables.next[slot] = *to;
+            tables.check[slot] = state as u16;
+            tables.outputs[slot] = *output;
+        }
+        tables.base[state] = base as u16;
+    }
+    Ok(tables)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn displaced_lookup_checks_owner() {
+        // State 1's row sits at offset 1, overlapping state 0's empty slot
+        static BASE: [u16; 2] = [0, 1];
+        static NEXT: [u8; 3] = [1, 0, 1];
+        static CHECK: [u16; 3] = [0, 1, 0];
+        static OUTPUTS: [u8; 3] = [10, 20, 30];
+
+        let table = Displaced::new(&BASE, &NEXT, &CHECK, &OUTPUTS);
+        assert_eq!(table.lookup(0, 0), Some((1, 10)));
+        assert_eq!(table.lookup(0, 1), None);
+        assert_eq!(table.lookup(0, 2), Some((1, 30)));
+        assert_eq!(table.lookup(1, 0), Some((0, 20)));
+        assert_eq!(table.lookup(1, 2), None);
+        assert_eq!(table.lookup(2, 0), None);
+
+        let mut fsm = DisplacedMealy::new(0, table);
+        assert_eq!(fsm.step_input(&false), Ok(10));
+        assert_eq!(fsm.step_input(&false), Ok(20));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn compress_matches_mealy() {
+        use crate::Mealy;
+        use alloc::vec::Vec;
+
+        // Sparse scanner-like rows, with a shadowed duplicate in state 3
+        let mut transitions = Vec::new();
+        let mut outputs = Vec::new();
+        for state in 0..12u8 {
+            for column in (state % 5..40).step_by(usize::from(state % 4) + 3) {
+                let to = (state + column) % 12;
+                transitions.push((state, column, to));
+                outputs.push((state, column, u16::from(state) * 100 + u16::from(column)));
+            }
+        }
+        transitions.push((3, 3, 0));
+
+        let tables = compress(&transitions, &outputs).unwrap();
+        assert!(tables.next.len() < 12 * 40);
+        let table = tables.table();
+        let mealy = Mealy::new(0, &transitions, &outputs);
+        for state in 0..13 {
+            for column in 0..=255 {
+                let mut expected = mealy;
+                expected.reset(state);
+                let expected = expected
+                    .step(column)
+                    .ok()
+                    .map(|o| (expected.current_state(), o));
+                assert_eq!(table.lookup(state, column), expected);
+            }
+        }
+
+        let rust = tables.to_rust("SCAN", "u16", |o| alloc::format!("{o}"));
+        assert!(rust.contains("pub static SCAN_BASE: [u16; 12] = ["));
+        assert_eq!(
+            compress(&[(0, 1, 0)], &[(0, 2, 'x')]),
+            Err(CompressError::MissingOutput { row: 0 })
+        );
+    }
+}
@@ -0,0 +1,298 @@
+//! Deferred event queue with prioritized, run-to-completion dispatch
+
+use crate::bridge::{Consumer, EventQueue, Producer};
+use crate::Machine;
+
+/// Owns a machine and a fixed-capacity queue of pending inputs
+///
+/// Inputs are queued with `post` or `post_with_priority` and dispatched by
+/// `run`, each one processed to completion before the next is taken.
+/// Higher priorities are dispatched first; equal priorities in FIFO order.
+///
+/// To post from an interrupt handler, `split` the executor into a `Poster`
+/// for the handler and a `Dispatcher` for the main loop. Posting is
+/// lock-free, through a `bridge::EventQueue` of `N` inputs; `run` moves
+/// them into the executor's own `N` slots before picking the next one.
+///
+/// # Example
+/// ```
+/// use fsmall::executor::Executor;
+/// use fsmall::Mealy;
+///
+/// static TRANSITIONS: [(u8, u8, u8); 2] = [(0, b'a', 1), (1, b'b', 0)];
+/// static OUTPUTS: [(u8, u8, char); 2] = [(0, b'a', 'x'), (1, b'b', 'y')];
+///
+/// let mut exec: Executor<_, 4> = Executor::new(Mealy::new(0, &TRANSITIONS, &OUTPUTS));
+/// let (mut poster, mut dispatcher) = exec.split();
+///
+/// // Interrupt handler
+/// poster.post(b'b').unwrap();
+/// poster.post_with_priority(b'a', 1).unwrap();
+///
+/// // Main loop
+/// assert_eq!(dispatcher.run_one(), Some(Ok('x')));
+/// assert_eq!(dispatcher.run_one(), Some(Ok('y')));
+/// ```
+pub struct Executor<M: Machine, const N: usize> {
+    machine: M,
+    // Posted (priority, input) entries not yet collected by a dispatcher
+    inbox: EventQueue<(u8, M::Input), N>,
+    // Collected entries in posting order, [0..len) occupied
+    queue: [Option<(u8, M::Input)>; N],
+    len: usize,
+}
+
+impl<M: Machine, const N: usize> Executor<M, N> {
+    /// Create executor around `machine` with an empty queue
+    pub fn new(machine: M) -> Self {
+        Executor {
+            machine,
+            inbox: EventQueue::new(),
+            queue: core::array::from_fn(|_| None),
+            len: 0,
+        }
+    }
+
+    /// Split into the posting half, for interrupt context, and the
+    /// dispatching half
+    pub fn split(&mut self) -> (Poster<'_, M::Input, N>, Dispatcher<'_, M, N>) {
+        let (producer, consumer) = self.inbox.split();
+        (
+            Poster { producer },
+            Dispatcher {
+                machine: &mut self.machine,
+                consumer,
+                queue: &mut self.queue,
+                len: &mut self.len,
+            },
+        )
+    }
+
+    /// Queue input for later dispatch at priority 0
+    ///
+    /// # Errors
+    /// Returns the input back if the queue is full.
+    pub fn post(&mut self, input: M::Input) -> Result<(), M::Input> {
+        self.post_with_priority(input, 0)
+    }
+
+    /// Queue input for dispatch ahead of all lower-priority inputs
+    ///
+    /// # Errors
+    /// Returns the input back if the queue is full.
+    pub fn post_with_priority(&mut self, input: M::Input, priority: u8) -> Result<(), M::Input> {
+        self.split().0.post_with_priority(input, priority)
+    }
+
+    /// Dispatch the highest-priority pending input, if any
+    pub fn run_one(&mut self) -> Option<Result<M::Output, M::Error>> {
+        self.split().1.run_one()
+    }
+
+    /// Drain the queue, handing every step result to `on_result`
+    ///
+    /// Returns the number of inputs dispatched.
+    pub fn run(&mut self, on_result: impl FnMut(Result<M::Output, M::Error>)) -> usize {
+        self.split().1.run(on_result)
+    }
+
+    /// Number of pending inputs
+    pub fn len(&self) -> usize {
+        self.len + self.inbox.len()
+    }
+
+    /// Check whether no inputs are pending
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get wrapped machine
+    pub fn machine(&self) -> &M {
+        &self.machine
+    }
+
+    /// Get wrapped machine mutably
+    pub fn machine_mut(&mut self) -> &mut M {
+        &mut self.machine
+    }
+
+    /// Consume executor, dropping pending inputs
+    pub fn into_inner(self) -> M {
+        self.machine
+    }
+}
+
+/// Posting half of an `Executor`, meant for interrupt context
+pub struct Poster<'e, I, const N: usize> {
+    producer: Producer<'e, (u8, I), N>,
+}
+
+impl<I, const N: usize> Poster<'_, I, N> {
+    /// Queue input for later dispatch at priority 0
+    ///
+    /// # Errors
+    /// Returns the input back if the queue is full.
+    pub fn post(&mut self, input: I) -> Result<(), I> {
+        self.post_with_priority(input, 0)
+    }
+
+    /// Queue input for dispatch ahead of all lower-priority inputs
+    ///
+    /// # Errors
+    /// Returns the input back if the queue is full.
+    pub fn post_with_priority(&mut self, input: I, priority: u8) -> Result<(), I> {
+        self.producer
+            .post((priority, input))
+            .map_err(|(_priority, input)| input)
+    }
+}
+
+/// Dispatching half of an `Executor`, meant for the main loop
+pub struct Dispatcher<'e, M: Machine, const N: usize> {
+    machine: &'e mut M,
+    consumer: Consumer<'e, (u8, M::Input), N>,
+    queue: &'e mut [Option<(u8, M::Input)>; N],
+    len: &'e mut usize,
+}
+
+impl<M: Machine, const N: usize> Dispatcher<'_, M, N> {
+    /// Dispatch the highest-priority pending input, if any
+    pub fn run_one(&mut self) -> Option<Result<M::Output, M::Error>> {
+        // Collect posted inputs while there is room, oldest first
+        while let Some(slot) = self.queue.get_mut(*self.len) {
+            let Some(entry) = self.consumer.pop() else {
+                break;
+            };
+            *slot = Some(entry);
+            *self.len += 1;
+        }
+        let pending = self.queue.get_mut(..*self.len)?;
+
+        // Oldest entry among those with the highest priority
+        let mut best: Option<(usize, u8)> = None;
+        for (i, entry) in pending.iter().enumerate() {
+            if let Some((prio, _)) = entry {
+                if best.is_none_or(|(_, p)| *prio > p) {
+                    best = Some((i, *prio));
+                }
+            }
+        }
+        let (index, _) = best?;
+
+        let (_, input) = pending.get_mut(index)?.take()?;
+        if let Some(tail) = pending.get_mut(index..) {
+            tail.rotate_left(1);
+        }
+        *self.len = self.len.saturating_sub(1);
+        Some(self.machine.step(input))
+    }
+
+    /// Drain the queue, handing every step result to `on_result`
+    ///
+    /// Returns the number of inputs dispatched. Inputs posted meanwhile are
+    /// dispatched too.
+    pub fn run(&mut self, mut on_result: impl FnMut(Result<M::Output, M::Error>)) -> usize {
+        let mut count = 0;
+        while let Some(result) = self.run_one() {
+            on_result(result);
+            count += 1;
+        }
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::{Mealy, StepError};
+
+    #[derive(Copy, Clone, Eq, PartialEq, Debug)]
+    enum TestInput {
+        A,
+        B,
+    }
+
+    #[derive(Copy, Clone, Eq, PartialEq, Debug)]
+    enum TestOutput {
+        X,
+        Y,
+    }
+
+    static TRANS: [(u8, TestInput, u8); 2] = [(0, TestInput::A, 1), (1, TestInput::B, 0)];
+
+    static OUTS: [(u8, TestInput, TestOutput); 2] = [
+        (0, TestInput::A, TestOutput::X),
+        (1, TestInput::B, TestOutput::Y),
+    ];
+
+    #[test]
+    fn executor_runs_in_fifo_order() {
+        let mut exec: Executor<_, 4> = Executor::new(Mealy::new(0, &TRANS, &OUTS));
+        exec.post(TestInput::A).unwrap();
+        exec.post(TestInput::B).unwrap();
+        exec.post(TestInput::B).unwrap();
+
+        let mut results = [None; 3];
+        let mut i = 0;
+        let count = exec.run(|r| {
+            results[i] = Some(r);
+            i += 1;
+        });
+
+        assert_eq!(count, 3);
+        assert_eq!(results[0], Some(Ok(TestOutput::X)));
+        assert_eq!(results[1], Some(Ok(TestOutput::Y)));
+        assert_eq!(results[2], Some(Err(StepError::NoTransition)));
+        assert!(exec.is_empty());
+    }
+
+    #[test]
+    fn executor_rejects_when_full() {
+        let mut exec: Executor<_, 2> = Executor::new(Mealy::new(0, &TRANS, &OUTS));
+        assert_eq!(exec.post(TestInput::A), Ok(()));
+        assert_eq!(exec.post(TestInput::B), Ok(()));
+        assert_eq!(exec.post(TestInput::A), Err(TestInput::A));
+        assert_eq!(exec.run_one(), Some(Ok(TestOutput::X)));
+        assert_eq!(exec.post(TestInput::A), Ok(()));
+        assert_eq!(exec.len(), 2);
+    }
+
+    #[test]
+    fn executor_dispatches_by_priority() {
+        let mut exec: Executor<_, 4> = Executor::new(Mealy::new(1, &TRANS, &OUTS));
+        exec.post(TestInput::A).unwrap();
+        exec.post_with_priority(TestInput::B, 5).unwrap();
+        exec.post(TestInput::B).unwrap();
+
+        // B (prio 5) preempts, then routine inputs in FIFO order
+        assert_eq!(exec.run_one(), Some(Ok(TestOutput::Y)));
+        assert_eq!(exec.run_one(), Some(Ok(TestOutput::X)));
+        assert_eq!(exec.run_one(), Some(Ok(TestOutput::Y)));
+        assert_eq!(exec.run_one(), None);
+    }
+
+    #[test]
+    fn executor_posts_from_another_context() {
+        let mut exec: Executor<_, 2> = Executor::new(Mealy::new(0, &TRANS, &OUTS));
+        let (mut poster, mut dispatcher) = exec.split();
+        let mut dispatched = 0;
+        std::thread::scope(|s| {
+            s.spawn(move || {
+                let mut sent = 0;
+                while sent < 100 {
+                    let input = [TestInput::A, TestInput::B][sent % 2];
+                    if poster.post(input).is_ok() {
+                        sent += 1;
+                    }
+                }
+            });
+            while dispatched < 100 {
+                dispatched += dispatcher.run(|r| assert!(r.is_ok()));
+            }
+        });
+        assert_eq!(exec.machine().current_state(), 0);
+        assert!(exec.is_empty());
+    }
+}
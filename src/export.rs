@@ -0,0 +1,269 @@
+//! Export machine tables as C headers
+//!
+//! Requires the `alloc` feature. The header holds the tables, the initial
+//! state and a reference step function with the same lookup rules as
+//! `Mealy::step` and `Moore::step` without guards or hooks, so C code can
+//! run the identical machine. Inputs and outputs are mapped to `uint32_t`
+//! codes by the caller. Empty tables emit no array, as ISO C has no
+//! zero-length ones.
+//!
+//! # Example
+//! ```
+//! #[derive(Copy, Clone, Eq, PartialEq)]
+//! enum Input { Open = 1, Close = 2 }
+//!
+//! static TRANSITIONS: [(u8, Input, u8); 2] = [(0, Input::Open, 1), (1, Input::Close, 0)];
+//! static OUTPUTS: [(u8, Input, u8); 2] = [(0, Input::Open, b'o'), (1, Input::Close, b'c')];
+//!
+//! let header = fsmall::export::mealy_to_c("door", 0, &TRANSITIONS, &OUTPUTS, |i| i as u32, u32::from);
+//! assert!(header.contains("#define DOOR_INITIAL 0u"));
+//! assert!(header.contains("    {0u, 1u, 1u, 1u, 111u},"));
+//! assert!(header.contains("static inline int door_step(uint8_t *state, uint32_t input, uint32_t *output)"));
+//! ```
+
+use alloc::format;
+use alloc::string::String;
+use core::fmt::Write;
+
+// Include guard, result codes and initial state shared by both machine kinds
+fn prologue(out: &mut String, name: &str, initial: u8) -> String {
+    let upper = name.to_ascii_uppercase();
+    let _ = write!(
+        out,
+        "/* Generated by fsmall::export, do not edit */\n\
+         #ifndef {upper}_H\n\
+         #define {upper}_H\n\
+         \n\
+         #include <stdint.h>\n\
+         \n\
+         #define {upper}_OK 0\n\
+         #define {upper}_NO_TRANSITION 1\n\
+         #define {upper}_NO_OUTPUT 2\n\
+         #define {upper}_INVALID_STATE 3\n\
+         \n\
+         #define {upper}_INITIAL {initial}u\n"
+    );
+    upper
+}
+
+// Step function of a machine without transitions, ISO C has no empty arrays
+fn empty_step(out: &mut String, name: &str, upper: &str) {
+    let _ = write!(
+        out,
+        "static inline int {name}_step(uint8_t *state, uint32_t input, uint32_t *output)\n\
+         {{\n\
+         \x20   (void)state;\n\
+         \x20   (void)input;\n\
+         \x20   (void)output;\n\
+         \x20   return {upper}_NO_TRANSITION;\n\
+         }}\n\
+         \n\
+         #endif /* {upper}_H */\n"
+    );
+}
+
+/// Emit a Mealy machine as a C header
+///
+/// Each transition row is paired with the first output row for its
+/// (state, input), as `Mealy::step` would find it. `{name}_step` returns
+/// `{NAME}_OK` and updates `*state` and `*output`, or `{NAME}_NO_TRANSITION`
+/// or `{NAME}_NO_OUTPUT` leaving both untouched.
+///
+/// # Arguments
+/// * `name` - Prefix of every C identifier
+/// * `initial` - Initial state
+/// * `transitions` - Transition table: (from_state, input, to_state)
+/// * `outputs` - Output table: (state, input, output)
+/// * `input` - Code of an input in C
+/// * `output` - Code of an output in C
+pub fn mealy_to_c<I: Copy + Eq, O: Copy>(
+    name: &str,
+    initial: u8,
+    transitions: &[(u8, I, u8)],
+    outputs: &[(u8, I, O)],
+    input: impl Fn(I) -> u32,
+    output: impl Fn(O) -> u32,
+) -> String {
+    let mut out = String::new();
+    let upper = prologue(&mut out, name, initial);
+    if transitions.is_empty() {
+        let _ = writeln!(out, "#define {upper}_ROWS 0u\n");
+        empty_step(&mut out, name, &upper);
+        return out;
+    }
+    let _ = write!(
+        out,
+        "#define {upper}_ROWS {}u\n\
+         \n\
+         /* (from_state, input, to_state, has_output, output), first match wins */\n\
+         static const struct {{\n\
+         \x20   uint8_t from;\n\
+         \x20   uint32_t input;\n\
+         \x20   uint8_t to;\n\
+         \x20   uint8_t has_output;\n\
+         \x20   uint32_t output;\n\
+         }} {name}_rows[{upper}_ROWS] = {{\n",
+        transitions.len()
+    );
+    for (from, i, to) in transitions {
+        let found = outputs
+            .iter()
+            .find(|(s, oi, _o)| s == from && oi == i)
+            .map(|(_s, _i, o)| output(*o));
+        let _ = writeln!(
+            out,
+            "    {{{from}u, {}u, {to}u, {}u, {}u}},",
+            input(*i),
+            u8::from(found.is_some()),
+            found.unwrap_or(0)
+        );
+    }
+    let _ = write!(
+        out,
+        "}};\n\
+         \n\
+         static inline int {name}_step(uint8_t *state, uint32_t input, uint32_t *output)\n\
+         {{\n\
+         \x20   for (uint32_t i = 0; i < {upper}_ROWS; i++) {{\n\
+         \x20       if ({name}_rows[i].from == *state && {name}_rows[i].input == input) {{\n\
+         \x20           if (!{name}_rows[i].has_output) {{\n\
+         \x20               return {upper}_NO_OUTPUT;\n\
+         \x20           }}\n\
+         \x20           *state = {name}_rows[i].to;\n\
+         \x20           *output = {name}_rows[i].output;\n\
+         \x20           return {upper}_OK;\n\
+         \x20       }}\n\
+         \x20   }}\n\
+         \x20   return {upper}_NO_TRANSITION;\n\
+         }}\n\
+         \n\
+         #endif /* {upper}_H */\n"
+    );
+    out
+}
+
+/// Emit a Moore machine as a C header
+///
+/// `{name}_step` returns `{NAME}_OK` and updates `*state` and `*output`,
+/// `{NAME}_NO_TRANSITION` leaving both untouched, or, like `Moore::step`,
+/// `{NAME}_INVALID_STATE` after moving to a state without an output.
+///
+/// # Arguments
+/// * `name` - Prefix of every C identifier
+/// * `initial` - Initial state
+/// * `transitions` - Transition table: (from_state, input, to_state)
+/// * `outputs` - Output array: index=state, value=output
+/// * `input` - Code of an input in C
+/// * `output` - Code of an output in C
+pub fn moore_to_c<I: Copy, O: Copy>(
+    name: &str,
+    initial: u8,
+    transitions: &[(u8, I, u8)],
+    outputs: &[O],
+    input: impl Fn(I) -> u32,
+    output: impl Fn(O) -> u32,
+) -> String {
+    let mut out = String::new();
+    let upper = prologue(&mut out, name, initial);
+    if transitions.is_empty() {
+        let _ = writeln!(
+            out,
+            "#define {upper}_ROWS 0u\n#define {upper}_STATES {}u\n",
+            outputs.len()
+        );
+        empty_step(&mut out, name, &upper);
+        return out;
+    }
+    let _ = write!(
+        out,
+        "#define {upper}_ROWS {}u\n\
+         #define {upper}_STATES {}u\n\
+         \n\
+         /* (from_state, input, to_state), first match wins */\n\
+         static const struct {{\n\
+         \x20   uint8_t from;\n\
+         \x20   uint32_t input;\n\
+         \x20   uint8_t to;\n\
+         }} {name}_transitions[{upper}_ROWS] = {{\n",
+        transitions.len(),
+        outputs.len()
+    );
+    for (from, i, to) in transitions {
+        let _ = writeln!(out, "    {{{from}u, {}u, {to}u}},", input(*i));
+    }
+    let codes: alloc::vec::Vec<String> =
+        outputs.iter().map(|o| format!("{}u", output(*o))).collect();
+    out.push_str("};\n\n");
+    // Without states, every transition ends in an invalid state
+    let (array, lookup) = if outputs.is_empty() {
+        (String::new(), String::from("(void)output;\n"))
+    } else {
+        (
+            format!(
+                "static const uint32_t {name}_outputs[{upper}_STATES] = {{{}}};\n\n",
+                codes.join(", ")
+            ),
+            format!("*output = {name}_outputs[*state];\n"),
+        )
+    };
+    let _ = write!(
+        out,
+        "{array}\
+         static inline int {name}_step(uint8_t *state, uint32_t input, uint32_t *output)\n\
+         {{\n\
+         \x20   for (uint32_t i = 0; i < {upper}_ROWS; i++) {{\n\
+         \x20       if ({name}_transitions[i].from == *state && {name}_transitions[i].input == input) {{\n\
+         \x20           *state = {name}_transitions[i].to;\n\
+         \x20           if (*state >= {upper}_STATES) {{\n\
+         \x20               return {upper}_INVALID_STATE;\n\
+         \x20           }}\n\
+         \x20           {lookup}\
+         \x20           return {upper}_OK;\n\
+         \x20       }}\n\
+         \x20   }}\n\
+         \x20   return {upper}_NO_TRANSITION;\n\
+         }}\n\
+         \n\
+         #endif /* {upper}_H */\n"
+    );
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn export_pairs_rows_with_first_output() {
+        static TRANS: [(u8, u8, u8); 3] = [(0, 7, 1), (1, 7, 0), (1, 8, 1)];
+        static OUTS: [(u8, u8, u8); 3] = [(0, 7, 10), (0, 7, 99), (1, 7, 20)];
+        let header = mealy_to_c("m", 1, &TRANS, &OUTS, u32::from, u32::from);
+        assert!(header.contains("#define M_ROWS 3u\n"));
+        assert!(header.contains("    {0u, 7u, 1u, 1u, 10u},\n"));
+        assert!(header.contains("    {1u, 8u, 1u, 0u, 0u},\n"));
+        assert!(header.ends_with("#endif /* M_H */\n"));
+
+        let header = moore_to_c(
+            "blink",
+            0,
+            &[(0, (), 1), (1, (), 0)],
+            &[false, true],
+            |()| 0,
+            u32::from,
+        );
+        assert!(header.contains("static const uint32_t blink_outputs[BLINK_STATES] = {0u, 1u};"));
+    }
+    #[test]
+    fn export_emits_no_empty_arrays() {
+        let header = mealy_to_c("m", 0, &[], &[(0, 7u8, 1u8)], u32::from, u32::from);
+        assert!(header.contains("#define M_ROWS 0u\n"));
+        assert!(!header.contains('['));
+
+        let header = moore_to_c("n", 0, &[(0, 7u8, 1)], &[] as &[u8], u32::from, u32::from);
+        assert!(header.contains("n_transitions[N_ROWS]"));
+        assert!(!header.contains("n_outputs"));
+        assert!(
+            !moore_to_c("o", 0, &[] as &[(u8, u8, u8)], &[1u8], u32::from, u32::from).contains('[')
+        );
+    }
+}
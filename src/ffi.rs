@@ -0,0 +1,297 @@
+//! C ABI for driving machines from C and C++
+//!
+//! Requires the `ffi` feature. A `FsmallMachine` is a `#[repr(C)]` handle
+//! over a table of `FsmallRow`s with `u32` input and output codes, the
+//! same lookup as `Mealy::step`: the first row for (state, input) wins.
+//! `HEADER` holds the matching C declarations.
+//!
+//! ```c
+//! static const fsmall_row_t rows[] = {{0, 1, 1, 10}, {1, 2, 0, 20}};
+//! fsmall_machine_t fsm;
+//! uint32_t output;
+//! fsmall_init(&fsm, rows, 2, 0);
+//! if (fsmall_step(&fsm, 1, &output) == FSMALL_OK) { /* output == 10 */ }
+//! ```
+
+use core::ptr;
+
+use crate::{Machine, StepError};
+
+/// Result code: step taken
+pub const FSMALL_OK: i32 = 0;
+/// Result code: no row for (state, input), state unchanged
+pub const FSMALL_NO_TRANSITION: i32 = 1;
+/// Result code: null pointer argument
+pub const FSMALL_NULL: i32 = -1;
+
+/// C declarations of this module
+pub const HEADER: &str = "\
+#ifndef FSMALL_H
+#define FSMALL_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#define FSMALL_OK 0
+#define FSMALL_NO_TRANSITION 1
+#define FSMALL_NULL (-1)
+
+typedef struct {
+    uint8_t from;
+    uint32_t input;
+    uint8_t to;
+    uint32_t output;
+} fsmall_row_t;
+
+typedef struct {
+    const fsmall_row_t *rows;
+    size_t len;
+    uint8_t state;
+} fsmall_machine_t;
+
+int32_t fsmall_init(fsmall_machine_t *machine, const fsmall_row_t *rows, size_t len, uint8_t initial);
+int32_t fsmall_step(fsmall_machine_t *machine, uint32_t input, uint32_t *output);
+uint8_t fsmall_state(const fsmall_machine_t *machine);
+int32_t fsmall_reset(fsmall_machine_t *machine, uint8_t state);
+
+#endif /* FSMALL_H */
+";
+
+/// Transition with its output: (from_state, input, to_state, output)
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FsmallRow {
+    /// Source state
+    pub from: u8,
+    /// Input code
+    pub input: u32,
+    /// Target state
+    pub to: u8,
+    /// Output code
+    pub output: u32,
+}
+
+/// Machine handle with a C layout, see `HEADER`
+///
+/// # Example
+/// ```
+/// use fsmall::ffi::{FsmallMachine, FsmallRow};
+///
+/// static ROWS: [FsmallRow; 2] = [
+///     FsmallRow { from: 0, input: 1, to: 1, output: 10 },
+///     FsmallRow { from: 1, input: 2, to: 0, output: 20 },
+/// ];
+///
+/// let mut fsm = FsmallMachine::new(&ROWS, 0);
+/// assert_eq!(fsm.step(1), Ok(10));
+/// assert_eq!(fsm.current_state(), 1);
+/// ```
+#[repr(C)]
+#[derive(Debug)]
+pub struct FsmallMachine {
+    rows: *const FsmallRow,
+    len: usize,
+    state: u8,
+}
+
+// SAFETY: the rows are only read, and `new` takes them as a shared
+// 'static slice; `fsmall_init` callers promise the same.
+unsafe impl Send for FsmallMachine {}
+
+impl FsmallMachine {
+    /// Create handle over `rows`
+    pub const fn new(rows: &'static [FsmallRow], initial_state: u8) -> Self {
+        FsmallMachine {
+            rows: rows.as_ptr(),
+            len: rows.len(),
+            state: initial_state,
+        }
+    }
+
+    fn rows(&self) -> &[FsmallRow] {
+        if self.rows.is_null() {
+            return &[];
+        }
+        // SAFETY: `new` stores a 'static slice, and `fsmall_init` callers
+        // guarantee `len` readable rows for as long as the handle is used.
+        unsafe { core::slice::from_raw_parts(self.rows, self.len) }
+    }
+
+    /// Process input code, returning the output code
+    ///
+    /// # Errors
+    /// * `StepError::NoTransition` - No row for (state, input)
+    pub fn step(&mut self, input: u32) -> Result<u32, StepError> {
+        let row = self
+            .rows()
+            .iter()
+            .find(|row| row.from == self.state && row.input == input)
+            .copied()
+            .ok_or(StepError::NoTransition)?;
+        self.state = row.to;
+        Ok(row.output)
+    }
+
+    /// Get current state
+    pub fn current_state(&self) -> u8 {
+        self.state
+    }
+
+    /// Jump to `state`
+    pub fn reset(&mut self, state: u8) {
+        self.state = state;
+    }
+}
+
+impl Machine for FsmallMachine {
+    type Input = u32;
+    type Output = u32;
+    type Error = StepError;
+
+    fn step(&mut self, input: u32) -> Result<u32, StepError> {
+        FsmallMachine::step(self, input)
+    }
+
+    fn current_state(&self) -> u8 {
+        FsmallMachine::current_state(self)
+    }
+
+    fn reset(&mut self, state: u8) {
+        FsmallMachine::reset(self, state)
+    }
+}
+
+/// Initialize `machine` over `len` rows starting in `initial`
+///
+/// Returns `FSMALL_OK`, or `FSMALL_NULL` if `machine` is null or `rows` is
+/// null with a nonzero `len`.
+///
+/// # Safety
+/// `machine` must be null or valid for writes. `rows` must point to `len`
+/// rows that stay valid and unmodified while the handle is used.
+#[no_mangle]
+pub unsafe extern "C" fn fsmall_init(
+    machine: *mut FsmallMachine,
+    rows: *const FsmallRow,
+    len: usize,
+    initial: u8,
+) -> i32 {
+    if machine.is_null() || (rows.is_null() && len != 0) {
+        return FSMALL_NULL;
+    }
+    let handle = FsmallMachine {
+        rows,
+        len,
+        state: initial,
+    };
+    // SAFETY: non-null and valid for writes per the contract above
+    unsafe { ptr::write(machine, handle) };
+    FSMALL_OK
+}
+
+/// Process `input`, storing the output code in `*output` on success
+///
+/// Returns `FSMALL_OK`, `FSMALL_NO_TRANSITION` with state and output left
+/// unchanged, or `FSMALL_NULL` if a pointer is null.
+///
+/// # Safety
+/// `machine` must be null or a handle set up by `fsmall_init` or
+/// `FsmallMachine::new`, not used concurrently; `output` must be null or
+/// valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn fsmall_step(
+    machine: *mut FsmallMachine,
+    input: u32,
+    output: *mut u32,
+) -> i32 {
+    if output.is_null() {
+        return FSMALL_NULL;
+    }
+    // SAFETY: valid and unaliased per the contract above
+    let Some(machine) = (unsafe { machine.as_mut() }) else {
+        return FSMALL_NULL;
+    };
+    match machine.step(input) {
+        Ok(out) => {
+            // SAFETY: non-null and valid for writes per the contract above
+            unsafe { ptr::write(output, out) };
+            FSMALL_OK
+        }
+        Err(_) => FSMALL_NO_TRANSITION,
+    }
+}
+
+/// Get the current state, 0 if `machine` is null
+///
+/// # Safety
+/// `machine` must be null or a handle set up by `fsmall_init` or
+/// `FsmallMachine::new`.
+#[no_mangle]
+pub unsafe extern "C" fn fsmall_state(machine: *const FsmallMachine) -> u8 {
+    // SAFETY: valid per the contract above
+    unsafe { machine.as_ref() }.map_or(0, FsmallMachine::current_state)
+}
+
+/// Jump to `state`; returns `FSMALL_OK`, or `FSMALL_NULL` if `machine` is null
+///
+/// # Safety
+/// As for `fsmall_step`
+#[no_mangle]
+pub unsafe extern "C" fn fsmall_reset(machine: *mut FsmallMachine, state: u8) -> i32 {
+    // SAFETY: valid and unaliased per the contract above
+    match unsafe { machine.as_mut() } {
+        Some(machine) => {
+            machine.reset(state);
+            FSMALL_OK
+        }
+        None => FSMALL_NULL,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static ROWS: [FsmallRow; 2] = [
+        FsmallRow {
+            from: 0,
+            input: 5,
+            to: 1,
+            output: 50,
+        },
+        FsmallRow {
+            from: 1,
+            input: 5,
+            to: 0,
+            output: 51,
+        },
+    ];
+
+    #[test]
+    fn ffi_functions_drive_handle() {
+        let mut fsm = core::mem::MaybeUninit::<FsmallMachine>::uninit();
+        let mut output = 0;
+        unsafe {
+            assert_eq!(
+                fsmall_init(fsm.as_mut_ptr(), ROWS.as_ptr(), ROWS.len(), 0),
+                FSMALL_OK
+            );
+            assert_eq!(fsmall_step(fsm.as_mut_ptr(), 5, &mut output), FSMALL_OK);
+            assert_eq!(output, 50);
+            assert_eq!(fsmall_state(fsm.as_ptr()), 1);
+            assert_eq!(
+                fsmall_step(fsm.as_mut_ptr(), 6, &mut output),
+                FSMALL_NO_TRANSITION
+            );
+            assert_eq!((fsmall_state(fsm.as_ptr()), output), (1, 50));
+            assert_eq!(fsmall_reset(fsm.as_mut_ptr(), 0), FSMALL_OK);
+            assert_eq!(fsmall_state(fsm.as_ptr()), 0);
+
+            assert_eq!(fsmall_step(ptr::null_mut(), 5, &mut output), FSMALL_NULL);
+            assert_eq!(
+                fsmall_init(fsm.as_mut_ptr(), ptr::null(), 1, 0),
+                FSMALL_NULL
+            );
+        }
+    }
+}
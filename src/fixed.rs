@@ -0,0 +1,141 @@
+//! Machines owning their tables by value
+
+use crate::{Machine, StepError};
+
+/// Mealy machine storing its tables as arrays instead of `&'static` slices
+///
+/// Buildable in `const` contexts and from data computed at runtime, and
+/// embeddable in other structs without lifetimes.
+///
+/// # Example
+/// ```
+/// use fsmall::MealyConst;
+///
+/// #[derive(Copy, Clone, Eq, PartialEq)]
+/// enum Input { Toggle }
+///
+/// const SWITCH: MealyConst<Input, bool, 2, 2> = MealyConst::new(
+///     0,
+///     [(0, Input::Toggle, 1), (1, Input::Toggle, 0)],
+///     [(0, Input::Toggle, true), (1, Input::Toggle, false)],
+/// );
+///
+/// let mut fsm = SWITCH;
+/// assert_eq!(fsm.step(Input::Toggle), Ok(true));
+/// assert_eq!(fsm.step(Input::Toggle), Ok(false));
+/// ```
+#[derive(Debug, Copy, Clone)]
+pub struct MealyConst<I, O, const T: usize, const U: usize> {
+    state: u8,
+    // Table: (from_state, input, to_state)
+    transitions: [(u8, I, u8); T],
+    // Table: (state, input, output)
+    outputs: [(u8, I, O); U],
+}
+
+impl<I: Copy + Eq, O: Copy, const T: usize, const U: usize> MealyConst<I, O, T, U> {
+    /// Create new Mealy machine owning its tables
+    ///
+    /// # Arguments
+    /// * `initial_state` - Starting state (0-255)
+    /// * `transitions` - Transition table: (from_state, input, to_state)
+    /// * `outputs` - Output table: (state, input, output)
+    pub const fn new(
+        initial_state: u8,
+        transitions: [(u8, I, u8); T],
+        outputs: [(u8, I, O); U],
+    ) -> Self {
+        MealyConst {
+            state: initial_state,
+            transitions,
+            outputs,
+        }
+    }
+
+    /// Process input, transition to next state, return output
+    ///
+    /// # Errors
+    /// * `StepError::NoTransition` - No rule for (state, input)
+    /// * `StepError::NoOutput` - No output for (state, input)
+    #[inline]
+    pub fn step(&mut self, input: I) -> Result<O, StepError> {
+        // Find next state in transition table
+        let next = self
+            .transitions
+            .iter()
+            .find(|(from, inp, _to)| *from == self.state && *inp == input)
+            .map(|(_from, _inp, to)| *to)
+            .ok_or(StepError::NoTransition)?;
+
+        // Find output in output table
+        let output = self
+            .outputs
+            .iter()
+            .find(|(s, i, _o)| *s == self.state && *i == input)
+            .map(|(_s, _i, o)| *o)
+            .ok_or(StepError::NoOutput)?;
+
+        // Commit state transition
+        self.state = next;
+
+        Ok(output)
+    }
+
+    /// Get current state
+    pub fn current_state(&self) -> u8 {
+        self.state
+    }
+
+    /// Reset to specific state
+    pub fn reset(&mut self, state: u8) {
+        self.state = state;
+    }
+}
+
+impl<I: Copy + Eq, O: Copy, const T: usize, const U: usize> Machine for MealyConst<I, O, T, U> {
+    type Input = I;
+    type Output = O;
+    type Error = StepError;
+
+    fn step(&mut self, input: I) -> Result<O, StepError> {
+        MealyConst::step(self, input)
+    }
+
+    fn current_state(&self) -> u8 {
+        self.state
+    }
+
+    fn reset(&mut self, state: u8) {
+        MealyConst::reset(self, state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_from_runtime_data() {
+        // Counter mod 4 whose tables are generated, not static
+        let transitions = core::array::from_fn(|s| (s as u8, 1u8, ((s + 1) % 4) as u8));
+        let outputs = core::array::from_fn(|s| (s as u8, 1u8, s == 3));
+        let mut fsm: MealyConst<u8, bool, 4, 4> = MealyConst::new(0, transitions, outputs);
+
+        assert_eq!(fsm.step(1), Ok(false));
+        assert_eq!(fsm.step(1), Ok(false));
+        assert_eq!(fsm.step(1), Ok(false));
+        assert_eq!(fsm.step(1), Ok(true));
+        assert_eq!(fsm.current_state(), 0);
+        assert_eq!(fsm.step(2), Err(StepError::NoTransition));
+    }
+
+    #[test]
+    fn fixed_missing_output() {
+        const FSM: MealyConst<u8, u8, 1, 0> = MealyConst::new(0, [(0, 1, 1)], []);
+        let mut fsm = FSM;
+        assert_eq!(fsm.step(1), Err(StepError::NoOutput));
+        assert_eq!(fsm.current_state(), 0);
+        fsm.reset(1);
+        assert_eq!(fsm.current_state(), 1);
+    }
+}
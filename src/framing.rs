@@ -0,0 +1,301 @@
+//! Byte-stream frame parser for serial links
+//!
+//! Frames have the layout `START LEN PAYLOAD[LEN] CHECK`. Any byte after
+//! `START` may be escaped as `ESCAPE (byte ^ 0x20)`, so `START` and
+//! `ESCAPE` can appear in the length, payload and checksum. The checksum
+//! covers the length byte and the payload, both unescaped.
+
+use core::convert::Infallible;
+
+use crate::Machine;
+
+/// Waiting for the start byte
+pub const IDLE: u8 = 0;
+/// Waiting for the length byte
+pub const LENGTH: u8 = 1;
+/// Receiving payload bytes
+pub const PAYLOAD: u8 = 2;
+/// Waiting for the checksum byte
+pub const CHECK: u8 = 3;
+
+/// Checksum algorithm over length and payload bytes
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Checksum {
+    /// XOR of all bytes
+    Xor8,
+    /// Wrapping sum of all bytes
+    Sum8,
+    /// CRC-8 with the given polynomial, zero initial value
+    Crc8(u8),
+}
+
+impl Checksum {
+    fn update(self, acc: u8, byte: u8) -> u8 {
+        match self {
+            Checksum::Xor8 => acc ^ byte,
+            Checksum::Sum8 => acc.wrapping_add(byte),
+            Checksum::Crc8(poly) => {
+                let mut crc = acc ^ byte;
+                for _ in 0..8 {
+                    crc = if crc & 0x80 != 0 {
+                        (crc << 1) ^ poly
+                    } else {
+                        crc << 1
+                    };
+                }
+                crc
+            }
+        }
+    }
+
+    /// Compute checksum of `bytes`
+    pub fn compute(self, bytes: &[u8]) -> u8 {
+        bytes.iter().fold(0, |acc, b| self.update(acc, *b))
+    }
+}
+
+/// Frame format configuration
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FrameConfig {
+    /// Start-of-frame byte
+    pub start: u8,
+    /// Escape byte, `None` disables escaping
+    pub escape: Option<u8>,
+    /// Largest accepted payload length
+    pub max_len: u8,
+    /// Checksum algorithm
+    pub checksum: Checksum,
+}
+
+/// Event reported by `FrameParser::step`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FrameEvent {
+    /// Start byte seen, a new frame begins
+    FrameStart,
+    /// Payload byte, unescaped
+    Byte(u8),
+    /// Checksum matched, frame complete
+    FrameOk,
+    /// Frame aborted: bad length, bad checksum or unexpected start byte
+    FrameError,
+}
+
+/// Frame parser state machine
+///
+/// States are `IDLE`, `LENGTH`, `PAYLOAD` and `CHECK`. An unescaped start
+/// byte inside a frame aborts it with `FrameError` and begins a new frame.
+///
+/// # Example
+/// ```
+/// use fsmall::framing::{Checksum, FrameConfig, FrameEvent, FrameParser};
+///
+/// let mut parser = FrameParser::new(FrameConfig {
+///     start: 0x7E,
+///     escape: Some(0x7D),
+///     max_len: 16,
+///     checksum: Checksum::Xor8,
+/// });
+///
+/// // Payload [0x7E, 0x01]; it and the checksum 0x7D must be escaped
+/// let mut payload = [0; 2];
+/// let mut len = 0;
+/// let mut ok = false;
+/// for byte in [0x7E, 0x02, 0x7D, 0x5E, 0x01, 0x7D, 0x5D] {
+///     match parser.step(byte) {
+///         Some(FrameEvent::Byte(b)) => {
+///             payload[len] = b;
+///             len += 1;
+///         }
+///         Some(FrameEvent::FrameOk) => ok = true,
+///         _ => {}
+///     }
+/// }
+/// assert!(ok);
+/// assert_eq!(payload, [0x7E, 0x01]);
+/// ```
+pub struct FrameParser {
+    config: FrameConfig,
+    state: u8,
+    // Previous byte was the escape byte
+    escaped: bool,
+    len: u8,
+    received: u8,
+    check: u8,
+}
+
+impl FrameParser {
+    /// Create new frame parser in `IDLE`
+    pub fn new(config: FrameConfig) -> Self {
+        FrameParser {
+            config,
+            state: IDLE,
+            escaped: false,
+            len: 0,
+            received: 0,
+            check: 0,
+        }
+    }
+
+    /// Feed one byte from the link, return event if any
+    pub fn step(&mut self, byte: u8) -> Option<FrameEvent> {
+        if self.state == IDLE {
+            if byte == self.config.start {
+                self.begin();
+                return Some(FrameEvent::FrameStart);
+            }
+            return None;
+        }
+
+        // Unescaped start byte: abort and resynchronize
+        if byte == self.config.start && !self.escaped {
+            self.begin();
+            return Some(FrameEvent::FrameError);
+        }
+
+        let value = if self.escaped {
+            self.escaped = false;
+            byte ^ 0x20
+        } else if Some(byte) == self.config.escape {
+            self.escaped = true;
+            return None;
+        } else {
+            byte
+        };
+
+        match self.state {
+            LENGTH => {
+                if value > self.config.max_len {
+                    return self.fail();
+                }
+                self.len = value;
+                self.check = self.config.checksum.update(0, value);
+                self.state = if value == 0 { CHECK } else { PAYLOAD };
+                None
+            }
+            PAYLOAD => {
+                self.check = self.config.checksum.update(self.check, value);
+                self.received += 1;
+                if self.received == self.len {
+                    self.state = CHECK;
+                }
+                Some(FrameEvent::Byte(value))
+            }
+            _ => {
+                self.state = IDLE;
+                if value == self.check {
+                    Some(FrameEvent::FrameOk)
+                } else {
+                    Some(FrameEvent::FrameError)
+                }
+            }
+        }
+    }
+
+    fn begin(&mut self) {
+        self.state = LENGTH;
+        self.escaped = false;
+        self.len = 0;
+        self.received = 0;
+        self.check = 0;
+    }
+
+    fn fail(&mut self) -> Option<FrameEvent> {
+        self.state = IDLE;
+        Some(FrameEvent::FrameError)
+    }
+
+    /// Get current state
+    pub fn current_state(&self) -> u8 {
+        self.state
+    }
+
+    /// Reset to `IDLE`, dropping any partial frame
+    pub fn reset(&mut self) {
+        self.begin();
+        self.state = IDLE;
+    }
+}
+
+impl Machine for FrameParser {
+    type Input = u8;
+    type Output = Option<FrameEvent>;
+    type Error = Infallible;
+
+    fn step(&mut self, byte: u8) -> Result<Option<FrameEvent>, Infallible> {
+        Ok(FrameParser::step(self, byte))
+    }
+
+    fn current_state(&self) -> u8 {
+        self.state
+    }
+
+    /// Any state other than `IDLE` restarts frame reception
+    fn reset(&mut self, state: u8) {
+        self.begin();
+        self.state = if state == IDLE { IDLE } else { LENGTH };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: FrameConfig = FrameConfig {
+        start: 0xAA,
+        escape: Some(0xBB),
+        max_len: 4,
+        checksum: Checksum::Crc8(0x07),
+    };
+
+    fn feed(parser: &mut FrameParser, bytes: &[u8], events: &mut [Option<FrameEvent>]) {
+        for (slot, byte) in events.iter_mut().zip(bytes) {
+            *slot = parser.step(*byte);
+        }
+    }
+
+    #[test]
+    fn framing_accepts_valid_frame() {
+        let crc = Checksum::Crc8(0x07).compute(&[2, 0x10, 0xAA]);
+        let mut parser = FrameParser::new(CONFIG);
+        let mut events = [None; 7];
+        feed(
+            &mut parser,
+            &[0x00, 0xAA, 2, 0x10, 0xBB, 0x8A, crc],
+            &mut events,
+        );
+        assert_eq!(
+            events,
+            [
+                None,
+                Some(FrameEvent::FrameStart),
+                None,
+                Some(FrameEvent::Byte(0x10)),
+                None,
+                Some(FrameEvent::Byte(0xAA)),
+                Some(FrameEvent::FrameOk),
+            ]
+        );
+        assert_eq!(parser.current_state(), IDLE);
+    }
+
+    #[test]
+    fn framing_rejects_bad_checksum_and_length() {
+        let mut parser = FrameParser::new(CONFIG);
+        let mut events = [None; 4];
+        feed(&mut parser, &[0xAA, 1, 0x42, 0x00], &mut events);
+        assert_eq!(events[3], Some(FrameEvent::FrameError));
+
+        feed(&mut parser, &[0xAA, 5], &mut events);
+        assert_eq!(events[1], Some(FrameEvent::FrameError));
+        assert_eq!(parser.current_state(), IDLE);
+    }
+
+    #[test]
+    fn framing_resyncs_on_start_byte() {
+        let mut parser = FrameParser::new(CONFIG);
+        let mut events = [None; 3];
+        feed(&mut parser, &[0xAA, 3, 0xAA], &mut events);
+        assert_eq!(events[2], Some(FrameEvent::FrameError));
+        assert_eq!(parser.current_state(), LENGTH);
+    }
+}
@@ -0,0 +1,154 @@
+//! Helpers for fuzz targets
+//!
+//! A fuzzer hands out raw bytes; `decode` maps each byte onto an input
+//! alphabet and `drive` feeds the result to a machine, panicking on the
+//! first broken invariant so the fuzzer records the crash:
+//!
+//! ```ignore
+//! #![no_main]
+//! use libfuzzer_sys::fuzz_target;
+//!
+//! fuzz_target!(|data: &[u8]| {
+//!     let mut fsm = fsmall::Mealy::new(0, &TRANSITIONS, &OUTPUTS);
+//!     fsmall::fuzz::drive(&mut fsm, &ALPHABET, data, STATE_COUNT);
+//! });
+//! ```
+
+use core::fmt;
+
+use crate::Machine;
+
+/// Invariant broken while driving a machine
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Violation {
+    /// A step left the machine in a state outside `0..states`
+    StateOutOfRange {
+        /// Index of the offending input
+        step: usize,
+        /// State after the step
+        state: u8,
+    },
+    /// A step returned an error but changed the state
+    StateChangedOnError {
+        /// Index of the offending input
+        step: usize,
+        /// State before the step
+        before: u8,
+        /// State after the step
+        after: u8,
+    },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Violation::StateOutOfRange { step, state } => {
+                write!(f, "step {step} left machine in out-of-range state {state}")
+            }
+            Violation::StateChangedOnError {
+                step,
+                before,
+                after,
+            } => write!(f, "step {step} failed but moved state {before} to {after}"),
+        }
+    }
+}
+
+impl core::error::Error for Violation {}
+
+/// Map each byte of `data` onto `alphabet`, modulo its length
+///
+/// Yields nothing if `alphabet` is empty.
+pub fn decode<'a, I: Copy>(alphabet: &'a [I], data: &'a [u8]) -> impl Iterator<Item = I> + 'a {
+    data.iter().filter_map(move |byte| {
+        alphabet
+            .get(usize::from(*byte) % alphabet.len().max(1))
+            .copied()
+    })
+}
+
+/// Step `machine` with `data` decoded over `alphabet`, checking after every
+/// step that the state is below `states` and that failed steps left the
+/// state unchanged
+///
+/// # Errors
+/// The first `Violation` found; the machine is left where it happened.
+pub fn check<M: Machine>(
+    machine: &mut M,
+    alphabet: &[M::Input],
+    data: &[u8],
+    states: usize,
+) -> Result<(), Violation>
+where
+    M::Input: Copy,
+{
+    for (step, input) in decode(alphabet, data).enumerate() {
+        let before = machine.current_state();
+        let result = machine.step(input);
+        let after = machine.current_state();
+        if usize::from(after) >= states {
+            return Err(Violation::StateOutOfRange { step, state: after });
+        }
+        if result.is_err() && after != before {
+            return Err(Violation::StateChangedOnError {
+                step,
+                before,
+                after,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Like `check`, but panics on a violation, for use in fuzz targets
+///
+/// # Panics
+/// If any invariant is broken, with the `Violation` as message.
+pub fn drive<M: Machine>(machine: &mut M, alphabet: &[M::Input], data: &[u8], states: usize)
+where
+    M::Input: Copy,
+{
+    if let Err(violation) = check(machine, alphabet, data, states) {
+        panic!("{violation}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Mealy, Moore};
+
+    static TRANS: [(u8, char, u8); 3] = [(0, 'a', 1), (1, 'b', 0), (1, 'c', 2)];
+    static MEALY_OUTS: [(u8, char, u8); 3] = [(0, 'a', 0), (1, 'b', 1), (1, 'c', 2)];
+    static ALPHABET: [char; 3] = ['a', 'b', 'c'];
+
+    #[test]
+    fn decode_wraps_bytes() {
+        assert!(decode(&ALPHABET, &[0, 4, 5, 255]).eq(['a', 'b', 'c', 'a']));
+        assert_eq!(decode::<char>(&[], &[1, 2]).count(), 0);
+    }
+
+    #[test]
+    fn check_finds_violations() {
+        let mut fsm = Mealy::new(0, &TRANS, &MEALY_OUTS);
+        assert_eq!(check(&mut fsm, &ALPHABET, &[0, 1, 2, 0, 1], 3), Ok(()));
+
+        fsm.reset(0);
+        assert_eq!(
+            check(&mut fsm, &ALPHABET, &[0, 2], 2),
+            Err(Violation::StateOutOfRange { step: 1, state: 2 })
+        );
+
+        // Moore commits before finding state 2 has no output
+        static SHORT: [u8; 2] = [0, 1];
+        let mut fsm = Moore::new(0, &TRANS, &SHORT);
+        assert_eq!(
+            check(&mut fsm, &ALPHABET, &[0, 2], 3),
+            Err(Violation::StateChangedOnError {
+                step: 1,
+                before: 1,
+                after: 2
+            })
+        );
+    }
+}
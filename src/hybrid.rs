@@ -0,0 +1,172 @@
+//! Hybrid machines with both per-state and per-transition outputs
+
+use crate::{Machine, StepError};
+
+/// Hybrid Mealy–Moore machine
+///
+/// Every state has a Moore output, and transitions may additionally carry a
+/// Mealy output. `step` returns the new state's output together with the
+/// transition's output, if the transition has one.
+///
+/// # Example
+/// ```
+/// use fsmall::Hybrid;
+///
+/// #[derive(Copy, Clone, Eq, PartialEq)]
+/// enum Input { Open, Close }
+///
+/// #[derive(Copy, Clone, Debug, PartialEq)]
+/// enum Event { Chime }
+///
+/// static TRANSITIONS: [(u8, Input, u8); 2] = [(0, Input::Open, 1), (1, Input::Close, 0)];
+/// static STATE_OUTPUTS: [bool; 2] = [false, true]; // Door light
+/// static TRANSITION_OUTPUTS: [(u8, Input, Event); 1] = [(0, Input::Open, Event::Chime)];
+///
+/// let mut fsm = Hybrid::new(0, &TRANSITIONS, &STATE_OUTPUTS, &TRANSITION_OUTPUTS);
+/// assert_eq!(fsm.step(Input::Open), Ok((true, Some(Event::Chime))));
+/// assert_eq!(fsm.step(Input::Close), Ok((false, None)));
+/// ```
+pub struct Hybrid<'t, I, S, T> {
+    state: u8,
+    // Table: (from_state, input, to_state)
+    transitions: &'t [(u8, I, u8)],
+    // Array: state_outputs[state] = output
+    state_outputs: &'t [S],
+    // Table: (state, input, output), rows are optional
+    transition_outputs: &'t [(u8, I, T)],
+}
+
+impl<'t, I: Copy + Eq, S: Copy, T: Copy> Hybrid<'t, I, S, T> {
+    /// Create new hybrid machine
+    ///
+    /// # Arguments
+    /// * `initial_state` - Starting state (0-255)
+    /// * `transitions` - Transition table: (from_state, input, to_state)
+    /// * `state_outputs` - State output array: index=state, value=output
+    /// * `transition_outputs` - Transition output table: (state, input, output)
+    pub fn new(
+        initial_state: u8,
+        transitions: &'t [(u8, I, u8)],
+        state_outputs: &'t [S],
+        transition_outputs: &'t [(u8, I, T)],
+    ) -> Self {
+        Hybrid {
+            state: initial_state,
+            transitions,
+            state_outputs,
+            transition_outputs,
+        }
+    }
+
+    /// Process input, transition to next state, return both outputs
+    ///
+    /// Returns the new state's output and the transition output, if any.
+    ///
+    /// # Errors
+    /// * `StepError::NoTransition` - No rule for (state, input)
+    /// * `StepError::InvalidState` - Next state index out of bounds
+    pub fn step(&mut self, input: I) -> Result<(S, Option<T>), StepError> {
+        // Find next state in transition table
+        let next = self
+            .transitions
+            .iter()
+            .find(|(from, inp, _to)| *from == self.state && *inp == input)
+            .map(|(_from, _inp, to)| *to)
+            .ok_or(StepError::NoTransition)?;
+
+        // Find optional transition output
+        let transition_output = self
+            .transition_outputs
+            .iter()
+            .find(|(s, i, _o)| *s == self.state && *i == input)
+            .map(|(_s, _i, o)| *o);
+
+        // Commit state transition
+        self.state = next;
+
+        // Get output for new state
+        let state_output = self.current_output()?;
+
+        Ok((state_output, transition_output))
+    }
+
+    /// Get current state
+    pub fn current_state(&self) -> u8 {
+        self.state
+    }
+
+    /// Get current state's output (without transitioning)
+    pub fn current_output(&self) -> Result<S, StepError> {
+        self.state_outputs
+            .get(self.state as usize)
+            .copied()
+            .ok_or(StepError::InvalidState)
+    }
+
+    /// Reset to specific state
+    pub fn reset(&mut self, state: u8) {
+        self.state = state;
+    }
+}
+
+impl<I: Copy + Eq, S: Copy, T: Copy> Machine for Hybrid<'_, I, S, T> {
+    type Input = I;
+    type Output = (S, Option<T>);
+    type Error = StepError;
+
+    fn step(&mut self, input: I) -> Result<(S, Option<T>), StepError> {
+        Hybrid::step(self, input)
+    }
+
+    fn current_state(&self) -> u8 {
+        self.state
+    }
+
+    fn reset(&mut self, state: u8) {
+        Hybrid::reset(self, state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Copy, Clone, Eq, PartialEq, Debug)]
+    enum TestInput {
+        A,
+        B,
+    }
+
+    static TRANS: [(u8, TestInput, u8); 3] = [
+        (0, TestInput::A, 1),
+        (1, TestInput::B, 0),
+        (1, TestInput::A, 2),
+    ];
+
+    static STATE_OUTS: [char; 2] = ['x', 'y'];
+
+    static TRANS_OUTS: [(u8, TestInput, u8); 1] = [(1, TestInput::B, 7)];
+
+    #[test]
+    fn hybrid_reports_both_outputs() {
+        let mut fsm = Hybrid::new(0, &TRANS, &STATE_OUTS, &TRANS_OUTS);
+        assert_eq!(fsm.step(TestInput::A), Ok(('y', None)));
+        assert_eq!(fsm.step(TestInput::B), Ok(('x', Some(7))));
+        assert_eq!(fsm.step(TestInput::B), Err(StepError::NoTransition));
+    }
+
+    #[test]
+    fn hybrid_missing_state_output() {
+        let mut fsm = Hybrid::new(1, &TRANS, &STATE_OUTS, &TRANS_OUTS);
+        assert_eq!(fsm.step(TestInput::A), Err(StepError::InvalidState));
+        assert_eq!(fsm.current_output(), Err(StepError::InvalidState));
+    }
+
+    #[test]
+    fn hybrid_borrows_runtime_tables() {
+        let trans = [(1, TestInput::A, 2)];
+        let state_outs = [STATE_OUTS[1], STATE_OUTS[0], 'z'];
+        let mut fsm = Hybrid::new(1, &trans, &state_outs, &TRANS_OUTS);
+        assert_eq!(fsm.step(TestInput::A), Ok(('z', None)));
+    }
+}
@@ -0,0 +1,202 @@
+//! Machines over inputs that carry payload data
+
+use crate::{Machine, StepError};
+
+/// Maps an input to the key used for table lookup
+///
+/// Lets inputs carry payloads (e.g. `SetSpeed(u16)`) while tables only
+/// mention the variant.
+pub trait Discriminant {
+    /// Comparable key stored in the tables
+    type Key: Copy + Eq;
+
+    /// Get lookup key of this input
+    fn key(&self) -> Self::Key;
+}
+
+/// Output function receiving the full input, payload included
+pub type OutputFn<I, O> = fn(&I) -> O;
+
+/// Mealy machine matching inputs by key and computing outputs from payload
+///
+/// # Example
+/// ```
+/// use fsmall::keyed::{Discriminant, KeyedMealy, OutputFn};
+///
+/// enum Input { SetSpeed(u16), Stop }
+///
+/// #[derive(Copy, Clone, Eq, PartialEq)]
+/// enum Key { SetSpeed, Stop }
+///
+/// impl Discriminant for Input {
+///     type Key = Key;
+///     fn key(&self) -> Key {
+///         match self {
+///             Input::SetSpeed(_) => Key::SetSpeed,
+///             Input::Stop => Key::Stop,
+///         }
+///     }
+/// }
+///
+/// fn speed(input: &Input) -> u16 {
+///     match input {
+///         Input::SetSpeed(v) => *v,
+///         Input::Stop => 0,
+///     }
+/// }
+///
+/// static TRANSITIONS: [(u8, Key, u8); 3] = [
+///     (0, Key::SetSpeed, 1),
+///     (1, Key::SetSpeed, 1),
+///     (1, Key::Stop, 0),
+/// ];
+///
+/// static OUTPUTS: [(u8, Key, OutputFn<Input, u16>); 3] = [
+///     (0, Key::SetSpeed, speed),
+///     (1, Key::SetSpeed, speed),
+///     (1, Key::Stop, speed),
+/// ];
+///
+/// let mut fsm = KeyedMealy::new(0, &TRANSITIONS, &OUTPUTS);
+/// assert_eq!(fsm.step(Input::SetSpeed(1200)), Ok(1200));
+/// assert_eq!(fsm.step(Input::Stop), Ok(0));
+/// ```
+pub struct KeyedMealy<'t, I: Discriminant, O> {
+    state: u8,
+    // Table: (from_state, key, to_state)
+    transitions: &'t [(u8, I::Key, u8)],
+    // Table: (state, key, output fn)
+    outputs: &'t [(u8, I::Key, OutputFn<I, O>)],
+}
+
+impl<'t, I: Discriminant, O> KeyedMealy<'t, I, O> {
+    /// Create new keyed Mealy machine
+    ///
+    /// # Arguments
+    /// * `initial_state` - Starting state (0-255)
+    /// * `transitions` - Transition table: (from_state, key, to_state)
+    /// * `outputs` - Output table: (state, key, output fn)
+    pub fn new(
+        initial_state: u8,
+        transitions: &'t [(u8, I::Key, u8)],
+        outputs: &'t [(u8, I::Key, OutputFn<I, O>)],
+    ) -> Self {
+        KeyedMealy {
+            state: initial_state,
+            transitions,
+            outputs,
+        }
+    }
+
+    /// Process input, transition to next state, return output
+    ///
+    /// # Errors
+    /// * `StepError::NoTransition` - No rule for (state, key)
+    /// * `StepError::NoOutput` - No output for (state, key)
+    pub fn step(&mut self, input: I) -> Result<O, StepError> {
+        let key = input.key();
+
+        // Find next state in transition table
+        let next = self
+            .transitions
+            .iter()
+            .find(|(from, k, _to)| *from == self.state && *k == key)
+            .map(|(_from, _k, to)| *to)
+            .ok_or(StepError::NoTransition)?;
+
+        // Find output function in output table
+        let output = self
+            .outputs
+            .iter()
+            .find(|(s, k, _f)| *s == self.state && *k == key)
+            .map(|(_s, _k, f)| f(&input))
+            .ok_or(StepError::NoOutput)?;
+
+        // Commit state transition
+        self.state = next;
+
+        Ok(output)
+    }
+
+    /// Get current state
+    pub fn current_state(&self) -> u8 {
+        self.state
+    }
+
+    /// Reset to specific state
+    pub fn reset(&mut self, state: u8) {
+        self.state = state;
+    }
+}
+
+impl<I: Discriminant, O> Machine for KeyedMealy<'_, I, O> {
+    type Input = I;
+    type Output = O;
+    type Error = StepError;
+
+    fn step(&mut self, input: I) -> Result<O, StepError> {
+        KeyedMealy::step(self, input)
+    }
+
+    fn current_state(&self) -> u8 {
+        self.state
+    }
+
+    fn reset(&mut self, state: u8) {
+        KeyedMealy::reset(self, state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Deliberately not Copy
+    #[derive(Debug, PartialEq)]
+    enum TestInput {
+        Add(u32),
+        Clear,
+    }
+
+    #[derive(Copy, Clone, Eq, PartialEq)]
+    enum TestKey {
+        Add,
+        Clear,
+    }
+
+    impl Discriminant for TestInput {
+        type Key = TestKey;
+        fn key(&self) -> TestKey {
+            match self {
+                TestInput::Add(_) => TestKey::Add,
+                TestInput::Clear => TestKey::Clear,
+            }
+        }
+    }
+
+    fn amount(input: &TestInput) -> u32 {
+        match input {
+            TestInput::Add(n) => *n,
+            TestInput::Clear => 0,
+        }
+    }
+
+    static TRANS: [(u8, TestKey, u8); 2] = [(0, TestKey::Add, 1), (1, TestKey::Clear, 0)];
+
+    static OUTS: [(u8, TestKey, OutputFn<TestInput, u32>); 1] = [(0, TestKey::Add, amount)];
+
+    #[test]
+    fn keyed_payload_reaches_output() {
+        let mut fsm = KeyedMealy::new(0, &TRANS, &OUTS);
+        assert_eq!(fsm.step(TestInput::Add(42)), Ok(42));
+        assert_eq!(fsm.current_state(), 1);
+    }
+
+    #[test]
+    fn keyed_missing_rows() {
+        let mut fsm = KeyedMealy::new(1, &TRANS, &OUTS);
+        assert_eq!(fsm.step(TestInput::Add(1)), Err(StepError::NoTransition));
+        assert_eq!(fsm.step(TestInput::Clear), Err(StepError::NoOutput));
+        assert_eq!(fsm.current_state(), 1);
+    }
+}
@@ -0,0 +1,194 @@
+//! Longest-match tokenizer over byte slices
+
+use core::ops::Range;
+
+use crate::{Machine, StepError};
+
+/// Error returned when no token matches at a position
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct LexError {
+    /// Byte offset where no token could be matched
+    pub position: usize,
+}
+
+/// Tokenizer driving a byte-level machine over a slice
+///
+/// Typically a `Moore` machine (optionally behind `ByteClasses`) whose
+/// output for each state is `Some(kind)` if the state accepts a token of
+/// that kind, `None` otherwise. Each token is the longest prefix of the
+/// remaining input ending in an accepting state; the machine is restarted
+/// from the state it was in when the lexer was created for every token.
+///
+/// On error, the offending byte is skipped so iteration can continue.
+///
+/// # Example
+/// ```
+/// use fsmall::{lexer::Lexer, Moore};
+///
+/// #[derive(Copy, Clone, Debug, PartialEq)]
+/// enum Kind { Num, Space }
+///
+/// static TRANSITIONS: [(u8, u8, u8); 6] = [
+///     (0, b'1', 1), (0, b'2', 1), (1, b'1', 1), (1, b'2', 1),
+///     (0, b' ', 2), (2, b' ', 2),
+/// ];
+/// static OUTPUTS: [Option<Kind>; 3] = [None, Some(Kind::Num), Some(Kind::Space)];
+///
+/// let mut lexer = Lexer::new(Moore::new(0, &TRANSITIONS, &OUTPUTS), b"12  21");
+/// assert_eq!(lexer.next(), Some(Ok((Kind::Num, 0..2))));
+/// assert_eq!(lexer.next(), Some(Ok((Kind::Space, 2..4))));
+/// assert_eq!(lexer.next(), Some(Ok((Kind::Num, 4..6))));
+/// assert_eq!(lexer.next(), None);
+/// ```
+pub struct Lexer<'a, M> {
+    machine: M,
+    // State every token match starts from
+    start: u8,
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl<'a, M, K> Lexer<'a, M>
+where
+    M: Machine<Input = u8, Output = Option<K>, Error = StepError>,
+{
+    /// Create lexer over `input`, starting each token in the machine's current state
+    ///
+    /// # Arguments
+    /// * `machine` - Byte machine whose output is the accepted token kind
+    /// * `input` - Bytes to tokenize
+    pub fn new(machine: M, input: &'a [u8]) -> Self {
+        Lexer {
+            start: machine.current_state(),
+            machine,
+            input,
+            pos: 0,
+        }
+    }
+
+    /// Byte offset of the next token
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Input not yet tokenized
+    pub fn remaining(&self) -> &'a [u8] {
+        self.input.get(self.pos..).unwrap_or(&[])
+    }
+
+    // Longest accepted match starting at `self.pos`: (kind, end)
+    fn longest_match(&mut self) -> Option<(K, usize)> {
+        // Empty matches are never tokens
+        self.machine.reset(self.start);
+
+        let mut last = None;
+        for (offset, byte) in self.remaining().iter().enumerate() {
+            match self.machine.step(*byte) {
+                Ok(Some(kind)) => last = Some((kind, self.pos + offset + 1)),
+                // States without an output row do not accept
+                Ok(None) | Err(StepError::NoOutput | StepError::InvalidState) => {}
+                Err(StepError::NoTransition | StepError::BufferFull) => break,
+            }
+        }
+        last
+    }
+}
+
+impl<M, K> Iterator for Lexer<'_, M>
+where
+    M: Machine<Input = u8, Output = Option<K>, Error = StepError>,
+{
+    type Item = Result<(K, Range<usize>), LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos >= self.input.len() {
+            return None;
+        }
+
+        let start = self.pos;
+        match self.longest_match() {
+            Some((kind, end)) => {
+                self.pos = end;
+                Some(Ok((kind, start..end)))
+            }
+            None => {
+                self.pos += 1;
+                Some(Err(LexError { position: start }))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ByteClasses, Moore};
+
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    enum Kind {
+        Ident,
+        Arrow,
+        Minus,
+    }
+
+    // "-" → Minus, "->" → Arrow, [a-b]+ → Ident
+    static TRANS: [(u8, u8, u8); 6] = [
+        (0, b'-', 1),
+        (1, b'>', 2),
+        (0, b'a', 3),
+        (0, b'b', 3),
+        (3, b'a', 3),
+        (3, b'b', 3),
+    ];
+
+    static OUTS: [Option<Kind>; 4] = [
+        None,
+        Some(Kind::Minus),
+        Some(Kind::Arrow),
+        Some(Kind::Ident),
+    ];
+
+    #[test]
+    fn lexer_prefers_longest_match() {
+        let fsm = Moore::new(0, &TRANS, &OUTS);
+        let mut lexer = Lexer::new(fsm, b"ab->-b");
+        assert_eq!(lexer.next(), Some(Ok((Kind::Ident, 0..2))));
+        assert_eq!(lexer.next(), Some(Ok((Kind::Arrow, 2..4))));
+        assert_eq!(lexer.next(), Some(Ok((Kind::Minus, 4..5))));
+        assert_eq!(lexer.next(), Some(Ok((Kind::Ident, 5..6))));
+        assert_eq!(lexer.next(), None);
+    }
+
+    #[test]
+    fn lexer_reports_and_skips_bad_byte() {
+        let fsm = Moore::new(0, &TRANS, &OUTS);
+        let mut lexer = Lexer::new(fsm, b"a?b");
+        assert_eq!(lexer.next(), Some(Ok((Kind::Ident, 0..1))));
+        assert_eq!(lexer.next(), Some(Err(LexError { position: 1 })));
+        assert_eq!(lexer.remaining(), b"b");
+        assert_eq!(lexer.next(), Some(Ok((Kind::Ident, 2..3))));
+    }
+
+    // 0: other, 1: 'a'..='z'
+    static ALPHA: [u8; 256] = {
+        let mut classes = [0; 256];
+        let mut b = b'a';
+        while b <= b'z' {
+            classes[b as usize] = 1;
+            b += 1;
+        }
+        classes
+    };
+
+    static CLASS_TRANS: [(u8, u8, u8); 2] = [(0, 1, 1), (1, 1, 1)];
+
+    static CLASS_OUTS: [Option<Kind>; 2] = [None, Some(Kind::Ident)];
+
+    #[test]
+    fn lexer_over_byte_classes() {
+        let fsm = ByteClasses::new(Moore::new(0, &CLASS_TRANS, &CLASS_OUTS), &ALPHA);
+        let mut lexer = Lexer::new(fsm, b"hello-");
+        assert_eq!(lexer.next(), Some(Ok((Kind::Ident, 0..5))));
+        assert_eq!(lexer.next(), Some(Err(LexError { position: 5 })));
+    }
+}
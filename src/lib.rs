@@ -1,13 +1,68 @@
 //! # fsmall
 //!
 //! Small finite state machine library with no allocations.
-//! Supports both Mealy and Moore machines.
+//! Supports both Mealy and Moore machines, and hybrids of the two.
 //!
 //! ## Features
 //! - Zero heap allocations
 //! - No standard library required (no_std compatible)
-//! - Static transition and output tables
-//! - Explicit error handling
+//! - Borrowed transition and output tables, `static` or built at runtime
+//! - Tables owned by value for `const` and runtime-built machines (`MealyConst`)
+//! - Explicit error handling, with state and input context (`step_reported`)
+//! - Silent and multi-output Mealy transitions (`step_optional`, `step_into`)
+//! - Table cross-validation at construction (`new_checked`)
+//! - Selectable recovery from missing transitions (`ErrorPolicy`), an optional sticky error state (`with_error_state`) and a central error hook (`with_on_error`)
+//! - Rules written once, expanded to both Mealy tables (`mealy_tables!`)
+//! - Typestate machines with compile-time checked transitions (`typestate!`)
+//! - Accepting states for recognizers (`is_accepting`)
+//! - Optional state names for `Display`, `Debug` and `state_name()`
+//! - Optional transition labels for logging (`last_label()`)
+//! - Context-aware guards (`step_with`)
+//! - Before/after hooks for global vetoes and instrumentation (`with_before`, `with_after`)
+//! - Extended state owned by the machine (`StatefulMealy`)
+//! - Tick-driven timed transitions and timeouts, with time spent in the current state (`ticks_in_state()`)
+//! - Periodic Moore outputs on ticks for blink codes and heartbeats (`with_periodic`)
+//! - Transition durations with a blending pseudo-state for animation cross-fades (`blend::Animator`)
+//! - Lock-free atomic-state machines for sharing with interrupts (`atomic`)
+//! - Critical-section wrapper for any machine (`shared::SharedFsm`, ready-made with `critical-section`)
+//! - Async guards and actions (`asynch::AsyncMealy`, requires `async`)
+//! - Panic-free stepping, verified at link time by `cargo test --release`
+//! - Series and lockstep composition (`compose::Chain`, `compose::Product`)
+//! - Submachine call/return with a bounded stack (`compose::CallStack`)
+//! - Hierarchical event bubbling from child to parent (`compose::Bubble`)
+//! - Hierarchical statecharts with initial substates, entry/exit actions and guards, written as nested states with `statechart!` (`statechart`)
+//! - Step budgets for bounded run-to-completion loops (`budget::Budget`)
+//! - Per-frame stimulus arbitration for game AI, strongest accepted input wins (`arbiter`)
+//! - Lock-free interrupt-to-main-loop event queue (`bridge::EventQueue`)
+//! - Fixed-capacity prioritized event queue (`Executor`) with run-to-completion dispatch, posted to lock-free from interrupts
+//! - Payload-carrying inputs matched by key (`keyed::KeyedMealy`)
+//! - Byte equivalence classes to shrink `u8` tables (`ByteClasses`)
+//! - Input remapping, debouncing and edge-detection adapters (`adapt`)
+//! - Digital pin level-change adapter (`pins::PinInputs`), over `embedded-hal` input pins and awaiting their edges with `embedded-hal-async`
+//! - Serial frame parser with escaping and checksums (`framing`)
+//! - Longest-match byte tokenizer (`lexer::Lexer`)
+//! - Presets: cyclic timed sequencers, menu navigation with back stack, input combo detectors, Modbus RTU frames, SLIP and COBS decoders, RFC 793 TCP connections, MQTT client keepalive, battery charging, stepper motor phases, elevator control, coin-credit vending, JSON tokens, UTF-8 validation, ANSI escape sequences, NMEA 0183 sentences (`presets`)
+//! - Table analysis: terminal, unreachable and trap states, shortest and all bounded input sequences, strongly connected components and machine equivalence with `alloc` (`analysis`)
+//! - Random-walk smoke testing, property-test input sequences, generated with `proptest` or `arbitrary`, and transition tours with `alloc` (`testing`)
+//! - Fuzz target helpers checking state invariants (`fuzz`)
+//! - Timestamped transition traces in a ring buffer (`trace`)
+//! - Regex-to-DFA table compiler (`regex`, requires `alloc`)
+//! - Mealy to Moore table conversion and back (`convert`, requires `alloc`)
+//! - Streaming multi-pattern matcher builder (`matcher`, requires `alloc`)
+//! - Tables generated in `build.rs` from reviewable TOML machine descriptions (`codegen`, requires `std`)
+//! - Runtime loading of TOML, JSON and RON descriptions into owned tables (`load`, requires `std`)
+//! - C header export of tables with a reference step function (`export`, requires `alloc`)
+//! - `#[repr(C)]` machine handles with `extern "C"` step and state functions for C firmware (`ffi`, requires `ffi`)
+//! - WebAssembly bindings running the same rows in browser simulators, with a transition trace (`wasm`, requires `wasm`)
+//! - Packed tables with varint offsets executed in place from a `&'static [u8]` blob in flash or loaded over the air (`packed`)
+//! - Row-displacement compressed tables (base/next/check) with a build-time compressor, near-dense lookups at sparse-table size (`displacement`)
+//! - `InputIndex` numbering input enums densely (`input_index!`), with dense (state, input) tables that also build and step in `const` context (`dense`) and missing-transition analysis
+//! - Compile-time table validation with `assert_fsm_valid!`, failing the build at the first out-of-range, duplicate or unmatched row (`validate`)
+//! - Combined (state, input, next, output) rows resolved in a single scan per step (`MealyRows`), optionally checking recently matched rows first (`rows::CachedRows`)
+//! - Constant-time dense stepping with sentinel entries for hard real-time loops (`dense::BranchlessMealy`)
+//! - Vectorized row scans shared by many simulated machines (`simd`, requires `simd`)
+//! - Per-state (offset, len) index turning lookups from O(rows) into O(fanout) (`with_state_index`, `analysis::state_index`)
+//! - Non-`Copy` inputs, e.g. events carrying payloads, stepped by reference (`step_ref`)
 //!
 //! ## Example (Mealy)
 //! ```
@@ -35,26 +90,440 @@
 
 #![no_std]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+pub mod adapt;
+pub mod analysis;
+pub mod arbiter;
+#[cfg(feature = "async")]
+pub mod asynch;
+#[cfg(target_has_atomic = "8")]
+pub mod atomic;
+pub mod blend;
+pub mod bridge;
+pub mod budget;
+pub mod classes;
+#[cfg(feature = "std")]
+pub mod codegen;
+pub mod compose;
+#[cfg(feature = "alloc")]
+pub mod convert;
+pub mod dense;
+#[cfg(feature = "alloc")]
+pub mod description;
+pub mod displacement;
+pub mod executor;
+#[cfg(feature = "alloc")]
+pub mod export;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod fixed;
+pub mod framing;
+pub mod fuzz;
+pub mod hybrid;
+pub mod keyed;
+pub mod lexer;
+#[cfg(feature = "std")]
+pub mod load;
+mod macros;
+#[cfg(feature = "alloc")]
+pub mod matcher;
+pub mod packed;
+pub mod pins;
+pub mod presets;
+#[cfg(feature = "alloc")]
+pub mod regex;
+pub mod rows;
+pub mod shared;
+#[cfg(feature = "simd")]
+pub mod simd;
+pub mod statechart;
+pub mod stateful;
+pub mod testing;
+pub mod trace;
+pub mod typestate;
+pub mod validate;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use classes::ByteClasses;
+pub use executor::Executor;
+pub use fixed::MealyConst;
+pub use hybrid::Hybrid;
+pub use rows::MealyRows;
+pub use stateful::StatefulMealy;
+
+use core::{fmt, ptr};
+
 /// Error returned when FSM step fails
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum StepError {
     /// No transition defined for (state, input) pair
     NoTransition,
-    /// No output defined for (state, input) pair in Mealy
-    /// or state index out of bounds in Moore
+    /// No output defined for (state, input) pair
     NoOutput,
+    /// State index out of bounds of a per-state output array, or a state
+    /// the tables never mention
+    InvalidState,
+    /// Output buffer passed to `step_into` has too little room left
+    BufferFull,
+}
+
+impl fmt::Display for StepError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StepError::NoTransition => f.write_str("no transition"),
+            StepError::NoOutput => f.write_str("no output"),
+            StepError::InvalidState => f.write_str("invalid state"),
+            StepError::BufferFull => f.write_str("output buffer full"),
+        }
+    }
+}
+
+impl core::error::Error for StepError {}
+
+impl StepError {
+    /// Attach the state the failure happened in
+    pub fn at<I>(self, state: u8) -> StepFailure<I> {
+        StepFailure {
+            kind: self,
+            state,
+            input: None,
+        }
+    }
+}
+
+/// `StepError` with the context needed to act on it in a log
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct StepFailure<I> {
+    /// What went wrong
+    pub kind: StepError,
+    /// State the machine was in (and still is)
+    pub state: u8,
+    /// Rejected input, if known
+    pub input: Option<I>,
 }
 
+impl<I> StepFailure<I> {
+    /// Attach the rejected input
+    pub fn with_input(mut self, input: I) -> Self {
+        self.input = Some(input);
+        self
+    }
+}
+
+impl<I: fmt::Debug> fmt::Display for StepFailure<I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} in state {}", self.kind, self.state)?;
+        if let Some(input) = &self.input {
+            write!(f, " on input {input:?}")?;
+        }
+        Ok(())
+    }
+}
+
+impl<I: fmt::Debug> core::error::Error for StepFailure<I> {}
+
+impl<I> From<StepFailure<I>> for StepError {
+    fn from(failure: StepFailure<I>) -> Self {
+        failure.kind
+    }
+}
+
+/// Inconsistency between tables, reported by `new_checked`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TableError {
+    /// Transition row has no output row for its (state, input)
+    MissingOutput {
+        /// Index into the transition table
+        row: usize,
+    },
+    /// Output row has no transition row for its (state, input)
+    UnusedOutput {
+        /// Index into the output table
+        row: usize,
+    },
+    /// Transition row or initial state refers to a state without an output
+    StateOutOfRange {
+        /// Index into the transition table, `None` for the initial state
+        row: Option<usize>,
+        /// Offending state
+        state: u8,
+    },
+}
+
+impl fmt::Display for TableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TableError::MissingOutput { row } => {
+                write!(f, "transition row {row} has no output row")
+            }
+            TableError::UnusedOutput { row } => {
+                write!(f, "output row {row} has no transition row")
+            }
+            TableError::StateOutOfRange {
+                row: Some(row),
+                state,
+            } => {
+                write!(
+                    f,
+                    "transition row {row} refers to state {state} without an output"
+                )
+            }
+            TableError::StateOutOfRange { row: None, state } => {
+                write!(f, "initial state {state} has no output")
+            }
+        }
+    }
+}
+
+impl core::error::Error for TableError {}
+
+/// Common interface of machine types, used by wrappers such as `Executor`
+pub trait Machine {
+    /// Input consumed by `step`
+    type Input;
+    /// Output produced by `step`
+    type Output;
+    /// Error returned by `step`
+    type Error;
+
+    /// Process input, transition to next state, return output
+    fn step(&mut self, input: Self::Input) -> Result<Self::Output, Self::Error>;
+
+    /// Get current state
+    fn current_state(&self) -> u8;
+
+    /// Reset to specific state
+    fn reset(&mut self, state: u8);
+}
+
+/// Input alphabet numbered densely from 0, see `input_index!`
+///
+/// Lets tables be indexed by input, as in `dense::DenseMealy`, and lets
+/// analysis enumerate every input, as in `analysis::missing_transitions`.
+pub trait InputIndex: Sized {
+    /// Number of inputs
+    const COUNT: usize;
+
+    /// Index of this input, below `COUNT`
+    fn index(&self) -> usize;
+
+    /// Input with index `index`, `None` at or above `COUNT`
+    fn from_index(index: usize) -> Option<Self>;
+}
+
+impl InputIndex for u8 {
+    const COUNT: usize = 256;
+
+    fn index(&self) -> usize {
+        usize::from(*self)
+    }
+
+    fn from_index(index: usize) -> Option<Self> {
+        u8::try_from(index).ok()
+    }
+}
+
+impl InputIndex for bool {
+    const COUNT: usize = 2;
+
+    fn index(&self) -> usize {
+        usize::from(*self)
+    }
+
+    fn from_index(index: usize) -> Option<Self> {
+        [false, true].get(index).copied()
+    }
+}
+
+impl InputIndex for () {
+    const COUNT: usize = 1;
+
+    fn index(&self) -> usize {
+        0
+    }
+
+    fn from_index(index: usize) -> Option<Self> {
+        (index == 0).then_some(())
+    }
+}
+
+/// Output sink for `Mealy::step_into`
+///
+/// Implemented for `&mut [T]`, which fills from the front and shrinks like
+/// `std::io::Write` for byte slices, with `alloc` for `Vec<T>`, and with
+/// `heapless` for `heapless::Vec<T, N>`.
+pub trait Extendable<T> {
+    /// Number of further items that fit
+    fn remaining(&self) -> usize;
+
+    /// Append `item`; only called while `remaining() > 0`
+    fn push(&mut self, item: T);
+}
+
+impl<T> Extendable<T> for &mut [T] {
+    fn remaining(&self) -> usize {
+        self.len()
+    }
+
+    fn push(&mut self, item: T) {
+        if let Some((first, rest)) = core::mem::take(self).split_first_mut() {
+            *first = item;
+            *self = rest;
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> Extendable<T> for alloc::vec::Vec<T> {
+    fn remaining(&self) -> usize {
+        usize::MAX - self.len()
+    }
+
+    fn push(&mut self, item: T) {
+        alloc::vec::Vec::push(self, item)
+    }
+}
+
+#[cfg(feature = "heapless")]
+impl<T, const N: usize> Extendable<T> for heapless::Vec<T, N> {
+    fn remaining(&self) -> usize {
+        N - self.len()
+    }
+
+    fn push(&mut self, item: T) {
+        let _ = heapless::Vec::push(self, item);
+    }
+}
+
+/// Guard predicate consulted by `step_with`
+pub type Guard<C> = fn(&C) -> bool;
+
+/// Action run on the context when a transition commits
+pub type Action<C> = fn(&mut C);
+
+/// Hook called with (state, input) when a step fails, see `with_on_error`
+pub type ErrorHook<I> = fn(u8, &I);
+
+/// Hook called with (state, input) before a step, see `with_before`
+pub type BeforeHook<I> = fn(u8, &I) -> bool;
+
+/// Hook called with (from_state, input, to_state) after a transition, see
+/// `with_after`
+pub type AfterHook<I> = fn(u8, &I, u8);
+
+/// Result of consuming a whole input slice with `run()`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RunOutcome {
+    /// State the machine ended in
+    pub state: u8,
+    /// Whether that state is accepting
+    pub accepted: bool,
+}
+
+/// Transition taken by `step_detailed`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct StepResult<O> {
+    /// State the input was consumed in
+    pub from: u8,
+    /// State entered
+    pub to: u8,
+    /// Output produced
+    pub output: O,
+    /// Index of the transition table row taken
+    pub row_index: usize,
+}
+
+/// Outcome of a `tick()` call
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Tick<O> {
+    /// Nothing was due this tick
+    Idle,
+    /// Timed transition fired, machine is now in the given state
+    Timed(u8),
+    /// Timeout input was injected, carrying the step result
+    Timeout(Result<O, StepError>),
+    /// Moore state's output re-emitted by its period, see `with_periodic`
+    Periodic(O),
+}
+
+/// What `step` does when no row matches (state, input)
+///
+/// `step` returns `StepError::NoTransition` under every policy; the policy
+/// only decides where the machine ends up.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum ErrorPolicy {
+    /// Ignore the input, leaving state and tick count untouched (default)
+    #[default]
+    Reject,
+    /// Treat the input as a self-loop: stay, but re-enter the state so its
+    /// tick count and timeouts restart
+    StayAndReport,
+    /// Move to the given state, e.g. a recovery or idle state
+    GotoState(u8),
+}
+
+// Source of Mealy outputs
+enum Outputs<'t, I, O> {
+    // Table: (state, input, output)
+    Table(&'t [(u8, I, O)]),
+    // Computed from (state, input); output fns need `I: Copy`, and the
+    // second fn copies the input for them
+    Fn(fn(u8, I) -> O, fn(&I) -> I),
+}
+
+impl<I, O> Clone for Outputs<'_, I, O> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<I, O> Copy for Outputs<'_, I, O> {}
+
 /// Mealy machine: output depends on (current_state, input)
-pub struct Mealy<I: 'static, O: 'static> {
+///
+/// Tables are borrowed for `'t`, so they may be `static`s or built at
+/// runtime, e.g. on the stack in tests or loaded into RAM.
+pub struct Mealy<'t, I, O, C = ()> {
     state: u8,
     // Table: (from_state, input, to_state)
-    transitions: &'static [(u8, I, u8)],
-    // Table: (state, input, output)
-    outputs: &'static [(u8, I, O)],
+    transitions: &'t [(u8, I, u8)],
+    // Per-state (offset, len) into `transitions`; empty to scan all rows
+    index: &'t [(u16, u16)],
+    // Guards parallel to `transitions`: guards[row] gates that row
+    guards: &'t [Option<Guard<C>>],
+    // Table: (from_state, input, action)
+    actions: &'t [(u8, I, Action<C>)],
+    outputs: Outputs<'t, I, O>,
+    // Table: (state, ticks, to_state)
+    timed: &'t [(u8, u32, u8)],
+    // Table: (state, ticks) after which `timeout_input` is injected
+    timeouts: &'t [(u8, u32)],
+    timeout_input: Option<&'t I>,
+    // Ticks elapsed since entering current state
+    ticks: u32,
+    // Accepting (final) states
+    accepting: &'t [u8],
+    // Array: names[state] = name
+    names: &'t [&'t str],
+    // Labels parallel to `transitions`: labels[row] names that row
+    labels: &'t [Option<&'t str>],
+    // Label of the last transition taken
+    label: Option<&'t str>,
+    // State entered on any step error, refusing input until `reset`
+    error_state: Option<u8>,
+    error_policy: ErrorPolicy,
+    // Called with (state, input) whenever a step fails
+    on_error: Option<ErrorHook<I>>,
+    before_hook: Option<BeforeHook<I>>,
+    after_hook: Option<AfterHook<I>>,
 }
 
-impl<I: Copy + Eq + 'static, O: Copy + 'static> Mealy<I, O> {
+impl<'t, I: Eq, O: Copy> Mealy<'t, I, O> {
     /// Create new Mealy machine
     ///
     /// # Arguments
@@ -63,42 +532,482 @@ impl<I: Copy + Eq + 'static, O: Copy + 'static> Mealy<I, O> {
     /// * `outputs` - Output table: (state, input, output)
     pub fn new(
         initial_state: u8,
-        transitions: &'static [(u8, I, u8)],
-        outputs: &'static [(u8, I, O)],
+        transitions: &'t [(u8, I, u8)],
+        outputs: &'t [(u8, I, O)],
+    ) -> Self {
+        Self::from_parts(initial_state, transitions, Outputs::Table(outputs))
+    }
+
+    /// Create new Mealy machine, verifying that the tables agree
+    ///
+    /// Every transition row needs an output row for the same (state, input)
+    /// and vice versa.
+    ///
+    /// # Errors
+    /// * `TableError::MissingOutput` - Transition row without output row
+    /// * `TableError::UnusedOutput` - Output row without transition row
+    pub fn new_checked(
+        initial_state: u8,
+        transitions: &'t [(u8, I, u8)],
+        outputs: &'t [(u8, I, O)],
+    ) -> Result<Self, TableError> {
+        for (row, (from, input, _to)) in transitions.iter().enumerate() {
+            if !outputs.iter().any(|(s, i, _o)| s == from && i == input) {
+                return Err(TableError::MissingOutput { row });
+            }
+        }
+        for (row, (state, input, _o)) in outputs.iter().enumerate() {
+            if !transitions
+                .iter()
+                .any(|(f, i, _to)| f == state && i == input)
+            {
+                return Err(TableError::UnusedOutput { row });
+            }
+        }
+        Ok(Self::new(initial_state, transitions, outputs))
+    }
+
+    fn from_parts(
+        initial_state: u8,
+        transitions: &'t [(u8, I, u8)],
+        outputs: Outputs<'t, I, O>,
     ) -> Self {
         Mealy {
             state: initial_state,
             transitions,
+            index: &[],
+            guards: &[],
+            actions: &[],
             outputs,
+            timed: &[],
+            timeouts: &[],
+            timeout_input: None,
+            ticks: 0,
+            accepting: &[],
+            names: &[],
+            labels: &[],
+            label: None,
+            error_state: None,
+            error_policy: ErrorPolicy::Reject,
+            on_error: None,
+            before_hook: None,
+            after_hook: None,
         }
     }
 
+    /// Select the context type `C` seen by guards and actions
+    ///
+    /// Call before `with_guards` and `with_actions`; the context type is
+    /// usually inferred from the tables attached next.
+    pub fn with_context<C>(self) -> Mealy<'t, I, O, C> {
+        Mealy {
+            state: self.state,
+            transitions: self.transitions,
+            index: self.index,
+            guards: &[],
+            actions: &[],
+            outputs: self.outputs,
+            timed: self.timed,
+            timeouts: self.timeouts,
+            timeout_input: self.timeout_input,
+            ticks: self.ticks,
+            accepting: self.accepting,
+            names: self.names,
+            labels: self.labels,
+            label: self.label,
+            error_state: self.error_state,
+            error_policy: self.error_policy,
+            on_error: self.on_error,
+            before_hook: self.before_hook,
+            after_hook: self.after_hook,
+        }
+    }
+}
+
+impl<'t, I: Copy + Eq, O: Copy> Mealy<'t, I, O> {
+    /// Create new Mealy machine computing outputs with a function
+    ///
+    /// Useful when outputs are cheap to compute but expensive to enumerate.
+    ///
+    /// # Arguments
+    /// * `initial_state` - Starting state (0-255)
+    /// * `transitions` - Transition table: (from_state, input, to_state)
+    /// * `output` - Output function: (state, input) -> output
+    pub fn from_output_fn(
+        initial_state: u8,
+        transitions: &'t [(u8, I, u8)],
+        output: fn(u8, I) -> O,
+    ) -> Self {
+        let copy = |input: &I| *input;
+        Self::from_parts(initial_state, transitions, Outputs::Fn(output, copy))
+    }
+}
+
+impl<'t, I: Eq, O: Copy, C> Mealy<'t, I, O, C> {
+    /// Attach a per-state index into the transition table
+    ///
+    /// `index[state]` is the (offset, len) of `state`'s rows, which must be
+    /// contiguous, so a step scans only those instead of the whole table;
+    /// build it with `analysis::state_index`. States past the end of
+    /// `index` have no rows.
+    pub fn with_state_index(mut self, index: &'t [(u16, u16)]) -> Self {
+        self.index = index;
+        self
+    }
+
+    /// Attach guards consulted by `step_with`
+    ///
+    /// `guards[row]` gates `transitions[row]`; rows past the end of `guards`
+    /// or with `None` are unguarded. Several rows may share a (state, input)
+    /// pair, the first row whose guard passes wins. Plain `step` never takes
+    /// a guarded row.
+    pub fn with_guards(mut self, guards: &'t [Option<Guard<C>>]) -> Self {
+        self.guards = guards;
+        self
+    }
+
+    /// Attach actions run on the context when a transition commits
+    ///
+    /// Actions only run from `step_with_mut`, which has mutable access to
+    /// the context.
+    ///
+    /// # Arguments
+    /// * `actions` - Action table: (from_state, input, action)
+    pub fn with_actions(mut self, actions: &'t [(u8, I, Action<C>)]) -> Self {
+        self.actions = actions;
+        self
+    }
+
+    /// Mark states as accepting (final), see `is_accepting()`
+    pub fn with_accepting(mut self, accepting: &'t [u8]) -> Self {
+        self.accepting = accepting;
+        self
+    }
+
+    /// Attach transition labels, see `last_label()`
+    ///
+    /// `labels[row]` labels `transitions[row]`; rows past the end of `labels`
+    /// or with `None` are unlabeled.
+    pub fn with_labels(mut self, labels: &'t [Option<&'t str>]) -> Self {
+        self.labels = labels;
+        self
+    }
+
+    /// Attach state names, see `state_name()`
+    ///
+    /// # Arguments
+    /// * `names` - Name array: index=state, value=name
+    pub fn with_state_names(mut self, names: &'t [&'t str]) -> Self {
+        self.names = names;
+        self
+    }
+
+    /// Attach timed transitions, fired by `tick()`
+    ///
+    /// # Arguments
+    /// * `timed` - Timed table: (state, ticks, to_state)
+    pub fn with_timed(mut self, timed: &'t [(u8, u32, u8)]) -> Self {
+        self.timed = timed;
+        self
+    }
+
+    /// Attach per-state timeouts, injecting `input` from `tick()` on expiry
+    ///
+    /// The injected input goes through the normal transition table.
+    ///
+    /// # Arguments
+    /// * `timeouts` - Timeout table: (state, ticks)
+    /// * `input` - Input injected when the state's timeout expires
+    pub fn with_timeouts(mut self, timeouts: &'t [(u8, u32)], input: &'t I) -> Self {
+        self.timeouts = timeouts;
+        self.timeout_input = Some(input);
+        self
+    }
+
+    /// Make errors sticky: any failed step moves to `state`, where every
+    /// input is refused with `StepError::NoTransition` until `reset`
+    ///
+    /// Without an error state, a failed step leaves the machine where it was
+    /// and ready for the next input.
+    pub fn with_error_state(mut self, state: u8) -> Self {
+        self.error_state = Some(state);
+        self
+    }
+
+    /// Select what happens on a missing transition, see `ErrorPolicy`
+    ///
+    /// An error state set with `with_error_state` takes precedence.
+    pub fn with_error_policy(mut self, policy: ErrorPolicy) -> Self {
+        self.error_policy = policy;
+        self
+    }
+
+    /// Call `hook` with the state and input of every failed step, e.g. to
+    /// count or log rejected inputs in one place
+    pub fn with_on_error(mut self, hook: ErrorHook<I>) -> Self {
+        self.on_error = Some(hook);
+        self
+    }
+
+    /// Call `hook` with (state, input) before every step; returning `false`
+    /// vetoes the step as if no row matched
+    ///
+    /// A lightweight global guard, consulted before the transition table.
+    pub fn with_before(mut self, hook: BeforeHook<I>) -> Self {
+        self.before_hook = Some(hook);
+        self
+    }
+
+    /// Call `hook` with (from_state, input, to_state) after every committed
+    /// transition
+    pub fn with_after(mut self, hook: AfterHook<I>) -> Self {
+        self.after_hook = Some(hook);
+        self
+    }
+
     /// Process input, transition to next state, return output
     ///
     /// # Errors
     /// * `StepError::NoTransition` - No rule for (state, input)
     /// * `StepError::NoOutput` - No output for (state, input)
+    #[inline(always)]
     pub fn step(&mut self, input: I) -> Result<O, StepError> {
-        // Find next state in transition table
-        let next = self
-            .transitions
-            .iter()
-            .find(|(from, inp, _to)| *from == self.state && *inp == input)
-            .map(|(_from, _inp, to)| *to)
-            .ok_or(StepError::NoTransition)?;
+        self.step_guarded(&input, None).map(|r| r.output)
+    }
+
+    /// Process input by reference, like `step`
+    ///
+    /// For inputs that are not `Copy`, e.g. events carrying a payload.
+    ///
+    /// # Errors
+    /// As for `step`.
+    #[inline]
+    pub fn step_ref(&mut self, input: &I) -> Result<O, StepError> {
+        self.step_guarded(input, None).map(|r| r.output)
+    }
+
+    /// Process input like `step`, reporting failures with state and input
+    ///
+    /// # Errors
+    /// `StepFailure` whose `kind` is as for `step`.
+    pub fn step_reported(&mut self, input: I) -> Result<O, StepFailure<I>> {
+        let state = self.state;
+        self.step_ref(&input)
+            .map_err(|e| e.at(state).with_input(input))
+    }
+
+    /// Process input like `step`, also reporting the transition taken
+    ///
+    /// # Errors
+    /// As for `step`.
+    #[inline]
+    pub fn step_detailed(&mut self, input: I) -> Result<StepResult<O>, StepError> {
+        self.step_guarded(&input, None)
+    }
+
+    /// Process input, choosing among guarded rows using `ctx`
+    ///
+    /// # Errors
+    /// * `StepError::NoTransition` - No rule for (state, input) whose guard passes
+    /// * `StepError::NoOutput` - No output for (state, input)
+    #[inline]
+    pub fn step_with(&mut self, input: I, ctx: &C) -> Result<O, StepError> {
+        self.step_guarded(&input, Some(ctx)).map(|r| r.output)
+    }
+
+    /// Process input like `step_with`, then run the (state, input) action
+    ///
+    /// The action runs only if the transition commits.
+    ///
+    /// # Errors
+    /// * `StepError::NoTransition` - No rule for (state, input) whose guard passes
+    /// * `StepError::NoOutput` - No output for (state, input)
+    #[inline]
+    pub fn step_with_mut(&mut self, input: I, ctx: &mut C) -> Result<O, StepError> {
+        let from = self.state;
+        let output = self.step_guarded(&input, Some(ctx))?.output;
+        if let Some(action) = action_for(self.actions, from, &input) {
+            action(ctx);
+        }
+        Ok(output)
+    }
+
+    /// Process input like `step`, treating a missing output row as a silent
+    /// transition that commits and returns `Ok(None)`
+    ///
+    /// For bookkeeping transitions without visible effect, so the output
+    /// type needs no dummy variant. Alternatively, use `Option<_>` as the
+    /// output type and write `None` into the output table.
+    ///
+    /// # Errors
+    /// * `StepError::NoTransition` - No rule for (state, input)
+    #[inline]
+    pub fn step_optional(&mut self, input: I) -> Result<Option<O>, StepError> {
+        self.step_inner(&input, None, true).map(|r| r.output)
+    }
+
+    /// Process input, appending every output row for (state, input) to `out`
+    ///
+    /// Returns the number of outputs appended: zero for a silent transition,
+    /// several if the output table has several rows for (state, input).
+    ///
+    /// # Errors
+    /// * `StepError::NoTransition` - No rule for (state, input)
+    /// * `StepError::BufferFull` - `out` cannot take all outputs, checked
+    ///   before anything changes
+    ///
+    /// # Example
+    /// ```
+    /// use fsmall::Mealy;
+    ///
+    /// static TRANSITIONS: [(u8, char, u8); 2] = [(0, 'a', 1), (1, 'b', 0)];
+    /// static OUTPUTS: [(u8, char, u8); 2] = [(0, 'a', 1), (0, 'a', 2)];
+    ///
+    /// let mut fsm = Mealy::new(0, &TRANSITIONS, &OUTPUTS);
+    /// let mut buf = [0; 4];
+    /// let mut out = &mut buf[..];
+    /// assert_eq!(fsm.step_into('a', &mut out), Ok(2));
+    /// assert_eq!(fsm.step_into('b', &mut out), Ok(0));
+    /// assert_eq!(buf[..2], [1, 2]);
+    /// ```
+    pub fn step_into(
+        &mut self,
+        input: I,
+        out: &mut impl Extendable<O>,
+    ) -> Result<usize, StepError> {
+        let from = self.state;
+        let matches = |(s, i, _o): &&(u8, I, O)| *s == from && *i == input;
+        let count = match self.outputs {
+            Outputs::Table(outputs) => outputs.iter().filter(matches).count(),
+            Outputs::Fn(..) => 1,
+        };
+        if count > out.remaining() {
+            return Err(StepError::BufferFull);
+        }
+
+        self.step_inner(&input, None, true)?;
+        match self.outputs {
+            Outputs::Table(outputs) => outputs
+                .iter()
+                .filter(matches)
+                .for_each(|(_s, _i, o)| out.push(*o)),
+            Outputs::Fn(f, copy) => out.push(f(from, copy(&input))),
+        }
+        Ok(count)
+    }
+
+    #[inline(always)]
+    fn step_guarded(&mut self, input: &I, ctx: Option<&C>) -> Result<StepResult<O>, StepError> {
+        let result = self.step_inner(input, ctx, false)?;
+        Ok(StepResult {
+            from: result.from,
+            to: result.to,
+            output: result.output.ok_or(StepError::NoOutput)?,
+            row_index: result.row_index,
+        })
+    }
+
+    // Step, with a missing output row reported as `None` if `silent`
+    #[inline(always)]
+    fn step_inner(
+        &mut self,
+        input: &I,
+        ctx: Option<&C>,
+        silent: bool,
+    ) -> Result<StepResult<Option<O>>, StepError> {
+        let from = self.state;
+        let result = if self.error_state == Some(from) {
+            Err(StepError::NoTransition)
+        } else {
+            let result = self.transition(input, ctx, silent);
+            if let (Err(_), Some(error_state)) = (&result, self.error_state) {
+                self.state = error_state;
+                self.ticks = 0;
+                self.label = None;
+            }
+            result
+        };
+        if let (Err(_), Some(on_error)) = (&result, self.on_error) {
+            on_error(from, input);
+        }
+        result
+    }
+
+    #[inline]
+    fn transition(
+        &mut self,
+        input: &I,
+        ctx: Option<&C>,
+        silent: bool,
+    ) -> Result<StepResult<Option<O>>, StepError> {
+        let from = self.state;
+
+        // Find next state in transition table, unless vetoed
+        let vetoed = self.before_hook.is_some_and(|hook| !hook(from, input));
+        let found = if vetoed {
+            None
+        } else {
+            next_state(self.transitions, self.index, self.guards, from, input, ctx)
+        };
+        let Some((row, next)) = found else {
+            match self.error_policy {
+                ErrorPolicy::Reject => {}
+                ErrorPolicy::StayAndReport => self.ticks = 0,
+                ErrorPolicy::GotoState(state) => self.reset(state),
+            }
+            return Err(StepError::NoTransition);
+        };
 
         // Find output in output table
-        let output = self
-            .outputs
-            .iter()
-            .find(|(s, i, _o)| *s == self.state && *i == input)
-            .map(|(_s, _i, o)| *o)
-            .ok_or(StepError::NoOutput)?;
+        let output = match self.outputs {
+            Outputs::Table(outputs) => outputs
+                .iter()
+                .find(|(s, i, _o)| *s == self.state && i == input)
+                .map(|(_s, _i, o)| *o),
+            Outputs::Fn(f, copy) => Some(f(self.state, copy(input))),
+        };
+        if output.is_none() && !silent {
+            return Err(StepError::NoOutput);
+        }
 
         // Commit state transition
         self.state = next;
+        self.ticks = 0;
+        self.label = self.labels.get(row).copied().flatten();
+        if let Some(hook) = self.after_hook {
+            hook(from, input, next);
+        }
 
-        Ok(output)
+        Ok(StepResult {
+            from,
+            to: next,
+            output,
+            row_index: row,
+        })
+    }
+
+    /// Advance time by one tick, firing a timed transition or timeout if due
+    ///
+    /// Timed transitions take precedence over timeouts. An expired timeout
+    /// re-arms even if the injected input is rejected.
+    #[inline]
+    pub fn tick(&mut self) -> Tick<O> {
+        self.ticks = self.ticks.saturating_add(1);
+
+        if let Some(next) = timed_next(self.timed, self.state, self.ticks) {
+            self.state = next;
+            self.ticks = 0;
+            self.label = None;
+            return Tick::Timed(next);
+        }
+
+        match self.timeout_input {
+            Some(input) if timeout_due(self.timeouts, self.state, self.ticks) => {
+                self.ticks = 0;
+                Tick::Timeout(self.step_ref(input))
+            }
+            _ => Tick::Idle,
+        }
     }
 
     /// Get current state
@@ -106,88 +1015,976 @@ impl<I: Copy + Eq + 'static, O: Copy + 'static> Mealy<I, O> {
         self.state
     }
 
+    /// Ticks counted by `tick()` since the current state was entered
+    ///
+    /// Restarts at 0 on every committed transition and on `reset`.
+    pub fn ticks_in_state(&self) -> u32 {
+        self.ticks
+    }
+
+    /// Check whether the current state is accepting
+    pub fn is_accepting(&self) -> bool {
+        self.accepting.contains(&self.state)
+    }
+
+    /// Get label of the transition taken by the last successful `step`
+    ///
+    /// `None` after timed transitions, `reset`, or an unlabeled row.
+    pub fn last_label(&self) -> Option<&'t str> {
+        self.label
+    }
+
+    /// Iterate outgoing transitions of `state` as (input, to_state), in
+    /// table order
+    ///
+    /// Guarded rows are included.
+    pub fn transitions_from(&self, state: u8) -> impl Iterator<Item = (&'t I, u8)> + 't {
+        self.transitions
+            .iter()
+            .filter(move |(from, _inp, _to)| *from == state)
+            .map(|(_from, inp, to)| (inp, *to))
+    }
+
+    /// Iterate incoming transitions of `state` as (from_state, input), in
+    /// table order
+    ///
+    /// Answers "how could we have gotten here?"; guarded rows are included.
+    /// See `analysis::can_reach` for states reaching it over several steps.
+    pub fn transitions_to(&self, state: u8) -> impl Iterator<Item = (u8, &'t I)> + 't {
+        self.transitions
+            .iter()
+            .filter(move |(_from, _inp, to)| *to == state)
+            .map(|(from, inp, _to)| (*from, inp))
+    }
+
+    /// Check whether the current state has no outgoing transitions
+    ///
+    /// Timed transitions are not counted; see `analysis::terminal_states`
+    /// to list all such states.
+    pub fn is_terminal(&self) -> bool {
+        analysis::is_terminal(self.transitions, self.state)
+    }
+
+    /// Get current state's name, if names were attached and cover it
+    pub fn state_name(&self) -> Option<&'t str> {
+        self.names.get(self.state as usize).copied()
+    }
+
+    /// Step through all `inputs`, discarding outputs, and report acceptance
+    ///
+    /// # Errors
+    /// Returns the index of the failing input and its error; the machine
+    /// stays in the state reached before that input.
+    pub fn run(&mut self, inputs: &[I]) -> Result<RunOutcome, (usize, StepError)> {
+        for (index, input) in inputs.iter().enumerate() {
+            self.step_ref(input).map_err(|e| (index, e))?;
+        }
+        Ok(RunOutcome {
+            state: self.state,
+            accepted: self.is_accepting(),
+        })
+    }
+
     /// Reset to specific state
     pub fn reset(&mut self, state: u8) {
         self.state = state;
+        self.ticks = 0;
+        self.label = None;
+    }
+
+    /// Reset to specific state, refusing states the transition table never
+    /// mentions
+    ///
+    /// # Errors
+    /// * `StepError::InvalidState` - Unknown state, machine unchanged
+    pub fn reset_checked(&mut self, state: u8) -> Result<(), StepError> {
+        let known = self
+            .transitions
+            .iter()
+            .any(|(from, _inp, to)| *from == state || *to == state);
+        if !known {
+            return Err(StepError::InvalidState);
+        }
+        self.reset(state);
+        Ok(())
+    }
+}
+
+impl<'t, I: Copy + Eq, O: Copy, C> Mealy<'t, I, O, C> {
+    /// Iterate inputs `step` accepts in the current state, without repeats
+    ///
+    /// Guarded rows are skipped, as `step` never takes them; see
+    /// `valid_inputs_with` to evaluate guards.
+    pub fn valid_inputs(&self) -> impl Iterator<Item = I> + '_ {
+        let unguarded = |row: usize| self.guards.get(row).copied().flatten().is_none();
+        inputs_from(self.transitions, self.state, unguarded)
+    }
+
+    /// Iterate inputs `step_with(_, ctx)` accepts in the current state,
+    /// without repeats
+    pub fn valid_inputs_with<'a>(&'a self, ctx: &'a C) -> impl Iterator<Item = I> + 'a {
+        let passes = move |row: usize| match self.guards.get(row).copied().flatten() {
+            Some(guard) => guard(ctx),
+            None => true,
+        };
+        inputs_from(self.transitions, self.state, passes)
+    }
+}
+
+impl<I: Eq, O: Copy, C> Machine for Mealy<'_, I, O, C> {
+    type Input = I;
+    type Output = O;
+    type Error = StepError;
+
+    #[inline]
+    fn step(&mut self, input: I) -> Result<O, StepError> {
+        Mealy::step(self, input)
+    }
+
+    fn current_state(&self) -> u8 {
+        Mealy::current_state(self)
+    }
+
+    fn reset(&mut self, state: u8) {
+        Mealy::reset(self, state)
+    }
+}
+
+impl<I, O, C> Clone for Mealy<'_, I, O, C> {
+    fn clone(&self) -> Self {
+        *self
     }
 }
 
+impl<I, O, C> Copy for Mealy<'_, I, O, C> {}
+
+impl<I, O, C> fmt::Debug for Mealy<'_, I, O, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut s = f.debug_struct("Mealy");
+        s.field("state", &self.state);
+        if let Some(name) = self.names.get(self.state as usize) {
+            s.field("state_name", name);
+        }
+        s.field("ticks", &self.ticks)
+            .field("transitions", &self.transitions.len());
+        match &self.outputs {
+            Outputs::Table(outputs) => s.field("outputs", &outputs.len()),
+            Outputs::Fn(..) => s.field("outputs", &"fn"),
+        };
+        s.finish_non_exhaustive()
+    }
+}
+
+/// Prints the current state's name, or its number if it has none
+impl<I, O, C> fmt::Display for Mealy<'_, I, O, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.names.get(self.state as usize) {
+            Some(name) => f.write_str(name),
+            None => write!(f, "{}", self.state),
+        }
+    }
+}
+
+/// Equal when state, tick count, timeout input and error handling match
+/// and both machines use the very same tables and hooks (compared by
+/// address, not contents)
+impl<I: Eq, O, C> PartialEq for Mealy<'_, I, O, C> {
+    fn eq(&self, other: &Self) -> bool {
+        let same_outputs = match (&self.outputs, &other.outputs) {
+            (Outputs::Table(a), Outputs::Table(b)) => ptr::eq(*a, *b),
+            (Outputs::Fn(a, _), Outputs::Fn(b, _)) => *a as usize == *b as usize,
+            _ => false,
+        };
+        self.state == other.state
+            && self.ticks == other.ticks
+            && self.timeout_input == other.timeout_input
+            && self.error_state == other.error_state
+            && self.error_policy == other.error_policy
+            && self.on_error.map(|f| f as usize) == other.on_error.map(|f| f as usize)
+            && self.before_hook.map(|f| f as usize) == other.before_hook.map(|f| f as usize)
+            && self.after_hook.map(|f| f as usize) == other.after_hook.map(|f| f as usize)
+            && same_outputs
+            && ptr::eq(self.transitions, other.transitions)
+            && ptr::eq(self.index, other.index)
+            && ptr::eq(self.guards, other.guards)
+            && ptr::eq(self.actions, other.actions)
+            && ptr::eq(self.timed, other.timed)
+            && ptr::eq(self.timeouts, other.timeouts)
+            && ptr::eq(self.accepting, other.accepting)
+            && ptr::eq(self.names, other.names)
+            && ptr::eq(self.labels, other.labels)
+    }
+}
+
+/// Which state's output a Moore machine's `step` reports
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum OutputOn {
+    /// Output of the state being entered (default)
+    #[default]
+    Entry,
+    /// Output of the state the input was consumed in, i.e. the output
+    /// that was in effect while the transition was taken
+    Transition,
+}
+
 /// Moore machine: output depends only on current_state
-pub struct Moore<I: 'static, O: 'static> {
+///
+/// Tables are borrowed for `'t`, see `Mealy`.
+pub struct Moore<'t, I, O, C = ()> {
     state: u8,
+    initial: u8,
+    output_on: OutputOn,
     // Table: (from_state, input, to_state)
-    transitions: &'static [(u8, I, u8)],
+    transitions: &'t [(u8, I, u8)],
+    // Per-state (offset, len) into `transitions`; empty to scan all rows
+    index: &'t [(u16, u16)],
+    // Guards parallel to `transitions`: guards[row] gates that row
+    guards: &'t [Option<Guard<C>>],
+    // Table: (from_state, input, action)
+    actions: &'t [(u8, I, Action<C>)],
     // Array: outputs[state] = output
-    outputs: &'static [O],
+    outputs: &'t [O],
+    // Table: (state, ticks, to_state)
+    timed: &'t [(u8, u32, u8)],
+    // Table: (state, ticks) after which `timeout_input` is injected
+    timeouts: &'t [(u8, u32)],
+    timeout_input: Option<&'t I>,
+    // Table: (state, period) for re-emitting the state's output on ticks
+    periodic: &'t [(u8, u32)],
+    // Ticks elapsed since entering current state
+    ticks: u32,
+    // Accepting (final) states
+    accepting: &'t [u8],
+    // Array: names[state] = name
+    names: &'t [&'t str],
+    // Labels parallel to `transitions`: labels[row] names that row
+    labels: &'t [Option<&'t str>],
+    // Label of the last transition taken
+    label: Option<&'t str>,
+    // State entered on any step error, refusing input until `reset`
+    error_state: Option<u8>,
+    error_policy: ErrorPolicy,
+    // Called with (state, input) whenever a step fails
+    on_error: Option<ErrorHook<I>>,
+    before_hook: Option<BeforeHook<I>>,
+    after_hook: Option<AfterHook<I>>,
 }
 
-impl<I: Copy + Eq + 'static, O: Copy + 'static> Moore<I, O> {
+impl<'t, I: Eq, O: Copy> Moore<'t, I, O> {
     /// Create new Moore machine
     ///
     /// # Arguments
     /// * `initial_state` - Starting state (0-255)
     /// * `transitions` - Transition table: (from_state, input, to_state)
     /// * `outputs` - Output array: index=state, value=output
-    pub fn new(
-        initial_state: u8,
-        transitions: &'static [(u8, I, u8)],
-        outputs: &'static [O],
-    ) -> Self {
+    pub fn new(initial_state: u8, transitions: &'t [(u8, I, u8)], outputs: &'t [O]) -> Self {
         Moore {
             state: initial_state,
+            initial: initial_state,
+            output_on: OutputOn::Entry,
             transitions,
+            index: &[],
+            guards: &[],
+            actions: &[],
             outputs,
+            timed: &[],
+            timeouts: &[],
+            timeout_input: None,
+            periodic: &[],
+            ticks: 0,
+            accepting: &[],
+            names: &[],
+            labels: &[],
+            label: None,
+            error_state: None,
+            error_policy: ErrorPolicy::Reject,
+            on_error: None,
+            before_hook: None,
+            after_hook: None,
+        }
+    }
+
+    /// Create new Moore machine, verifying that every state has an output
+    ///
+    /// # Errors
+    /// * `TableError::StateOutOfRange` - Initial state or a transition's
+    ///   source or target has no entry in `outputs`
+    pub fn new_checked(
+        initial_state: u8,
+        transitions: &'t [(u8, I, u8)],
+        outputs: &'t [O],
+    ) -> Result<Self, TableError> {
+        let in_range = |state: u8| usize::from(state) < outputs.len();
+        if !in_range(initial_state) {
+            return Err(TableError::StateOutOfRange {
+                row: None,
+                state: initial_state,
+            });
+        }
+        for (row, (from, _input, to)) in transitions.iter().enumerate() {
+            if let Some(state) = [*from, *to].into_iter().find(|s| !in_range(*s)) {
+                return Err(TableError::StateOutOfRange {
+                    row: Some(row),
+                    state,
+                });
+            }
+        }
+        Ok(Self::new(initial_state, transitions, outputs))
+    }
+
+    /// Select the context type `C` seen by guards and actions
+    ///
+    /// Call before `with_guards` and `with_actions`; the context type is
+    /// usually inferred from the tables attached next.
+    pub fn with_context<C>(self) -> Moore<'t, I, O, C> {
+        Moore {
+            state: self.state,
+            initial: self.initial,
+            output_on: self.output_on,
+            transitions: self.transitions,
+            index: self.index,
+            guards: &[],
+            actions: &[],
+            outputs: self.outputs,
+            timed: self.timed,
+            timeouts: self.timeouts,
+            timeout_input: self.timeout_input,
+            periodic: self.periodic,
+            ticks: self.ticks,
+            accepting: self.accepting,
+            names: self.names,
+            labels: self.labels,
+            label: self.label,
+            error_state: self.error_state,
+            error_policy: self.error_policy,
+            on_error: self.on_error,
+            before_hook: self.before_hook,
+            after_hook: self.after_hook,
+        }
+    }
+}
+
+impl<'t, I: Eq, O: Copy, C> Moore<'t, I, O, C> {
+    /// Select which state's output `step` reports, see `OutputOn`
+    pub fn with_output_on(mut self, output_on: OutputOn) -> Self {
+        self.output_on = output_on;
+        self
+    }
+
+    /// Attach a per-state index into the transition table
+    ///
+    /// `index[state]` is the (offset, len) of `state`'s rows, which must be
+    /// contiguous, so a step scans only those instead of the whole table;
+    /// build it with `analysis::state_index`. States past the end of
+    /// `index` have no rows.
+    pub fn with_state_index(mut self, index: &'t [(u16, u16)]) -> Self {
+        self.index = index;
+        self
+    }
+
+    /// Attach guards consulted by `step_with`
+    ///
+    /// `guards[row]` gates `transitions[row]`; rows past the end of `guards`
+    /// or with `None` are unguarded. Several rows may share a (state, input)
+    /// pair, the first row whose guard passes wins. Plain `step` never takes
+    /// a guarded row.
+    pub fn with_guards(mut self, guards: &'t [Option<Guard<C>>]) -> Self {
+        self.guards = guards;
+        self
+    }
+
+    /// Attach actions run on the context when a transition commits
+    ///
+    /// Actions only run from `step_with_mut`, which has mutable access to
+    /// the context.
+    ///
+    /// # Arguments
+    /// * `actions` - Action table: (from_state, input, action)
+    pub fn with_actions(mut self, actions: &'t [(u8, I, Action<C>)]) -> Self {
+        self.actions = actions;
+        self
+    }
+
+    /// Mark states as accepting (final), see `is_accepting()`
+    pub fn with_accepting(mut self, accepting: &'t [u8]) -> Self {
+        self.accepting = accepting;
+        self
+    }
+
+    /// Attach transition labels, see `last_label()`
+    ///
+    /// `labels[row]` labels `transitions[row]`; rows past the end of `labels`
+    /// or with `None` are unlabeled.
+    pub fn with_labels(mut self, labels: &'t [Option<&'t str>]) -> Self {
+        self.labels = labels;
+        self
+    }
+
+    /// Attach state names, see `state_name()`
+    ///
+    /// # Arguments
+    /// * `names` - Name array: index=state, value=name
+    pub fn with_state_names(mut self, names: &'t [&'t str]) -> Self {
+        self.names = names;
+        self
+    }
+
+    /// Attach timed transitions, fired by `tick()`
+    ///
+    /// # Arguments
+    /// * `timed` - Timed table: (state, ticks, to_state)
+    pub fn with_timed(mut self, timed: &'t [(u8, u32, u8)]) -> Self {
+        self.timed = timed;
+        self
+    }
+
+    /// Attach per-state timeouts, injecting `input` from `tick()` on expiry
+    ///
+    /// The injected input goes through the normal transition table.
+    ///
+    /// # Arguments
+    /// * `timeouts` - Timeout table: (state, ticks)
+    /// * `input` - Input injected when the state's timeout expires
+    pub fn with_timeouts(mut self, timeouts: &'t [(u8, u32)], input: &'t I) -> Self {
+        self.timeouts = timeouts;
+        self.timeout_input = Some(input);
+        self
+    }
+
+    /// Re-emit a state's output from `tick()` every `period` ticks spent in
+    /// it, without transitioning
+    ///
+    /// Turns the machine into a pattern generator, e.g. blink codes or a
+    /// heartbeat LED.
+    ///
+    /// # Arguments
+    /// * `periodic` - Period table: (state, period), a period of 0 never fires
+    pub fn with_periodic(mut self, periodic: &'t [(u8, u32)]) -> Self {
+        self.periodic = periodic;
+        self
+    }
+
+    /// Make errors sticky: any failed step moves to `state`, where every
+    /// input is refused with `StepError::NoTransition` until `reset`
+    ///
+    /// Without an error state, a failed step leaves the machine where it was
+    /// and ready for the next input.
+    pub fn with_error_state(mut self, state: u8) -> Self {
+        self.error_state = Some(state);
+        self
+    }
+
+    /// Select what happens on a missing transition, see `ErrorPolicy`
+    ///
+    /// An error state set with `with_error_state` takes precedence.
+    pub fn with_error_policy(mut self, policy: ErrorPolicy) -> Self {
+        self.error_policy = policy;
+        self
+    }
+
+    /// Call `hook` with the state and input of every failed step, e.g. to
+    /// count or log rejected inputs in one place
+    pub fn with_on_error(mut self, hook: ErrorHook<I>) -> Self {
+        self.on_error = Some(hook);
+        self
+    }
+
+    /// Call `hook` with (state, input) before every step; returning `false`
+    /// vetoes the step as if no row matched
+    ///
+    /// A lightweight global guard, consulted before the transition table.
+    pub fn with_before(mut self, hook: BeforeHook<I>) -> Self {
+        self.before_hook = Some(hook);
+        self
+    }
+
+    /// Call `hook` with (from_state, input, to_state) after every committed
+    /// transition
+    pub fn with_after(mut self, hook: AfterHook<I>) -> Self {
+        self.after_hook = Some(hook);
+        self
+    }
+
+    /// Process input, transition to next state, return new state's output
+    ///
+    /// With `OutputOn::Transition`, returns the output of the state the
+    /// input was consumed in instead.
+    ///
+    /// # Errors
+    /// * `StepError::NoTransition` - No rule for (state, input)
+    /// * `StepError::InvalidState` - Next state index out of bounds
+    #[inline(always)]
+    pub fn step(&mut self, input: I) -> Result<O, StepError> {
+        self.step_guarded(&input, None).map(|r| r.output)
+    }
+
+    /// Process input by reference, like `step`
+    ///
+    /// For inputs that are not `Copy`, e.g. events carrying a payload.
+    ///
+    /// # Errors
+    /// As for `step`.
+    #[inline]
+    pub fn step_ref(&mut self, input: &I) -> Result<O, StepError> {
+        self.step_guarded(input, None).map(|r| r.output)
+    }
+
+    /// Process input like `step`, reporting failures with state and input
+    ///
+    /// # Errors
+    /// `StepFailure` whose `kind` is as for `step`.
+    pub fn step_reported(&mut self, input: I) -> Result<O, StepFailure<I>> {
+        let state = self.state;
+        self.step_ref(&input)
+            .map_err(|e| e.at(state).with_input(input))
+    }
+
+    /// Process input like `step`, also reporting the transition taken
+    ///
+    /// # Errors
+    /// As for `step`.
+    #[inline]
+    pub fn step_detailed(&mut self, input: I) -> Result<StepResult<O>, StepError> {
+        self.step_guarded(&input, None)
+    }
+
+    /// Process input, choosing among guarded rows using `ctx`
+    ///
+    /// # Errors
+    /// * `StepError::NoTransition` - No rule for (state, input) whose guard passes
+    /// * `StepError::InvalidState` - Next state index out of bounds
+    #[inline]
+    pub fn step_with(&mut self, input: I, ctx: &C) -> Result<O, StepError> {
+        self.step_guarded(&input, Some(ctx)).map(|r| r.output)
+    }
+
+    /// Process input like `step_with`, then run the (state, input) action
+    ///
+    /// The action runs only if the transition commits.
+    ///
+    /// # Errors
+    /// * `StepError::NoTransition` - No rule for (state, input) whose guard passes
+    /// * `StepError::InvalidState` - Next state index out of bounds
+    #[inline]
+    pub fn step_with_mut(&mut self, input: I, ctx: &mut C) -> Result<O, StepError> {
+        let from = self.state;
+        let output = self.step_guarded(&input, Some(ctx))?.output;
+        if let Some(action) = action_for(self.actions, from, &input) {
+            action(ctx);
+        }
+        Ok(output)
+    }
+
+    #[inline(always)]
+    fn step_guarded(&mut self, input: &I, ctx: Option<&C>) -> Result<StepResult<O>, StepError> {
+        let from = self.state;
+        let result = if self.error_state == Some(from) {
+            Err(StepError::NoTransition)
+        } else {
+            let result = self.transition(input, ctx);
+            if let (Err(_), Some(error_state)) = (&result, self.error_state) {
+                self.state = error_state;
+                self.ticks = 0;
+                self.label = None;
+            }
+            result
+        };
+        if let (Err(_), Some(on_error)) = (&result, self.on_error) {
+            on_error(from, input);
+        }
+        result
+    }
+
+    #[inline]
+    fn transition(&mut self, input: &I, ctx: Option<&C>) -> Result<StepResult<O>, StepError> {
+        let from = self.state;
+
+        // Find next state in transition table, unless vetoed
+        let vetoed = self.before_hook.is_some_and(|hook| !hook(from, input));
+        let found = if vetoed {
+            None
+        } else {
+            next_state(self.transitions, self.index, self.guards, from, input, ctx)
+        };
+        let Some((row, next)) = found else {
+            match self.error_policy {
+                ErrorPolicy::Reject => {}
+                ErrorPolicy::StayAndReport => self.ticks = 0,
+                ErrorPolicy::GotoState(state) => self.reset(state),
+            }
+            return Err(StepError::NoTransition);
+        };
+
+        // Output in effect while the input was consumed
+        let before = match self.output_on {
+            OutputOn::Entry => None,
+            OutputOn::Transition => Some(self.current_output()?),
+        };
+
+        // Commit state transition
+        self.state = next;
+        self.ticks = 0;
+        self.label = self.labels.get(row).copied().flatten();
+        if let Some(hook) = self.after_hook {
+            hook(from, input, next);
+        }
+
+        // Get output for new state
+        let output = match before {
+            Some(output) => output,
+            None => self.current_output()?,
+        };
+        Ok(StepResult {
+            from,
+            to: next,
+            output,
+            row_index: row,
+        })
+    }
+
+    /// Advance time by one tick, firing a timed transition, timeout or
+    /// periodic output if due
+    ///
+    /// After a timed transition, read the new output with `current_output()`.
+    ///
+    /// Timed transitions take precedence over timeouts, and timeouts over
+    /// periodic outputs. An expired timeout re-arms even if the injected
+    /// input is rejected.
+    #[inline]
+    pub fn tick(&mut self) -> Tick<O> {
+        self.ticks = self.ticks.saturating_add(1);
+
+        if let Some(next) = timed_next(self.timed, self.state, self.ticks) {
+            self.state = next;
+            self.ticks = 0;
+            self.label = None;
+            return Tick::Timed(next);
+        }
+
+        match self.timeout_input {
+            Some(input) if timeout_due(self.timeouts, self.state, self.ticks) => {
+                self.ticks = 0;
+                Tick::Timeout(self.step_ref(input))
+            }
+            _ => match self.current_output() {
+                Ok(output) if self.periodic_due() => Tick::Periodic(output),
+                _ => Tick::Idle,
+            },
+        }
+    }
+
+    // Whether the current state's period divides the ticks spent in it
+    fn periodic_due(&self) -> bool {
+        self.periodic
+            .iter()
+            .find(|(s, _period)| *s == self.state)
+            .and_then(|(_s, period)| self.ticks.checked_rem(*period))
+            == Some(0)
+    }
+
+    /// Get current state
+    pub fn current_state(&self) -> u8 {
+        self.state
+    }
+
+    /// Ticks counted by `tick()` since the current state was entered
+    ///
+    /// Restarts at 0 on every committed transition and on `reset`.
+    pub fn ticks_in_state(&self) -> u32 {
+        self.ticks
+    }
+
+    /// Check whether the current state is accepting
+    pub fn is_accepting(&self) -> bool {
+        self.accepting.contains(&self.state)
+    }
+
+    /// Get label of the transition taken by the last successful `step`
+    ///
+    /// `None` after timed transitions, `reset`, or an unlabeled row.
+    pub fn last_label(&self) -> Option<&'t str> {
+        self.label
+    }
+
+    /// Iterate outgoing transitions of `state` as (input, to_state), in
+    /// table order
+    ///
+    /// Guarded rows are included.
+    pub fn transitions_from(&self, state: u8) -> impl Iterator<Item = (&'t I, u8)> + 't {
+        self.transitions
+            .iter()
+            .filter(move |(from, _inp, _to)| *from == state)
+            .map(|(_from, inp, to)| (inp, *to))
+    }
+
+    /// Iterate incoming transitions of `state` as (from_state, input), in
+    /// table order
+    ///
+    /// Answers "how could we have gotten here?"; guarded rows are included.
+    /// See `analysis::can_reach` for states reaching it over several steps.
+    pub fn transitions_to(&self, state: u8) -> impl Iterator<Item = (u8, &'t I)> + 't {
+        self.transitions
+            .iter()
+            .filter(move |(_from, _inp, to)| *to == state)
+            .map(|(from, inp, _to)| (*from, inp))
+    }
+
+    /// Check whether the current state has no outgoing transitions
+    ///
+    /// Timed transitions are not counted; see `analysis::terminal_states`
+    /// to list all such states.
+    pub fn is_terminal(&self) -> bool {
+        analysis::is_terminal(self.transitions, self.state)
+    }
+
+    /// Get current state's name, if names were attached and cover it
+    pub fn state_name(&self) -> Option<&'t str> {
+        self.names.get(self.state as usize).copied()
+    }
+
+    /// Step through all `inputs`, discarding outputs, and report acceptance
+    ///
+    /// # Errors
+    /// Returns the index of the failing input and its error; the machine
+    /// stays in the state reached before that input.
+    pub fn run(&mut self, inputs: &[I]) -> Result<RunOutcome, (usize, StepError)> {
+        for (index, input) in inputs.iter().enumerate() {
+            self.step_ref(input).map_err(|e| (index, e))?;
+        }
+        Ok(RunOutcome {
+            state: self.state,
+            accepted: self.is_accepting(),
+        })
+    }
+
+    /// Return to the initial state and yield its output
+    ///
+    /// Gives the power-on output before any input has been processed.
+    ///
+    /// # Errors
+    /// * `StepError::InvalidState` - Initial state index out of bounds
+    pub fn start(&mut self) -> Result<O, StepError> {
+        self.reset(self.initial);
+        self.current_output()
+    }
+
+    /// Get current output (without transitioning)
+    ///
+    /// # Errors
+    /// * `StepError::InvalidState` - Current state index out of bounds
+    pub fn current_output(&self) -> Result<O, StepError> {
+        self.outputs
+            .get(self.state as usize)
+            .copied()
+            .ok_or(StepError::InvalidState)
+    }
+
+    /// Reset to specific state
+    pub fn reset(&mut self, state: u8) {
+        self.state = state;
+        self.ticks = 0;
+        self.label = None;
+    }
+
+    /// Reset to specific state, refusing states without an output
+    ///
+    /// # Errors
+    /// * `StepError::InvalidState` - State index out of bounds, machine unchanged
+    pub fn reset_checked(&mut self, state: u8) -> Result<(), StepError> {
+        if usize::from(state) >= self.outputs.len() {
+            return Err(StepError::InvalidState);
         }
+        self.reset(state);
+        Ok(())
+    }
+}
+
+impl<'t, I: Copy + Eq, O: Copy, C> Moore<'t, I, O, C> {
+    /// Iterate inputs `step` accepts in the current state, without repeats
+    ///
+    /// Guarded rows are skipped, as `step` never takes them; see
+    /// `valid_inputs_with` to evaluate guards.
+    pub fn valid_inputs(&self) -> impl Iterator<Item = I> + '_ {
+        let unguarded = |row: usize| self.guards.get(row).copied().flatten().is_none();
+        inputs_from(self.transitions, self.state, unguarded)
+    }
+
+    /// Iterate inputs `step_with(_, ctx)` accepts in the current state,
+    /// without repeats
+    pub fn valid_inputs_with<'a>(&'a self, ctx: &'a C) -> impl Iterator<Item = I> + 'a {
+        let passes = move |row: usize| match self.guards.get(row).copied().flatten() {
+            Some(guard) => guard(ctx),
+            None => true,
+        };
+        inputs_from(self.transitions, self.state, passes)
+    }
+}
+
+impl<I: Eq, O: Copy, C> Machine for Moore<'_, I, O, C> {
+    type Input = I;
+    type Output = O;
+    type Error = StepError;
+
+    #[inline]
+    fn step(&mut self, input: I) -> Result<O, StepError> {
+        Moore::step(self, input)
+    }
+
+    fn current_state(&self) -> u8 {
+        Moore::current_state(self)
+    }
+
+    fn reset(&mut self, state: u8) {
+        Moore::reset(self, state)
     }
+}
 
-    /// Process input, transition to next state, return new state's output
-    ///
-    /// # Errors
-    /// * `StepError::NoTransition` - No rule for (state, input)
-    /// * `StepError::NoOutput` - Next state index out of bounds
-    pub fn step(&mut self, input: I) -> Result<O, StepError> {
-        // Find next state in transition table
-        let next = self
-            .transitions
-            .iter()
-            .find(|(from, inp, _to)| *from == self.state && *inp == input)
-            .map(|(_from, _inp, to)| *to)
-            .ok_or(StepError::NoTransition)?;
+impl<I, O, C> Clone for Moore<'_, I, O, C> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
 
-        // Commit state transition
-        self.state = next;
+impl<I, O, C> Copy for Moore<'_, I, O, C> {}
 
-        // Get output for new state
-        self.outputs
-            .get(self.state as usize)
-            .copied()
-            .ok_or(StepError::NoOutput)
+impl<I, O, C> fmt::Debug for Moore<'_, I, O, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut s = f.debug_struct("Moore");
+        s.field("state", &self.state);
+        if let Some(name) = self.names.get(self.state as usize) {
+            s.field("state_name", name);
+        }
+        s.field("ticks", &self.ticks)
+            .field("transitions", &self.transitions.len())
+            .field("outputs", &self.outputs.len())
+            .finish_non_exhaustive()
     }
+}
 
-    /// Get current state
-    pub fn current_state(&self) -> u8 {
-        self.state
+/// Prints the current state's name, or its number if it has none
+impl<I, O, C> fmt::Display for Moore<'_, I, O, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.names.get(self.state as usize) {
+            Some(name) => f.write_str(name),
+            None => write!(f, "{}", self.state),
+        }
     }
+}
 
-    /// Get current output (without transitioning)
-    pub fn current_output(&self) -> Result<O, StepError> {
-        self.outputs
-            .get(self.state as usize)
-            .copied()
-            .ok_or(StepError::NoOutput)
+/// Equal when state, tick count and settings match and both machines use
+/// the very same tables and hooks (compared by address, not contents)
+impl<I: Eq, O, C> PartialEq for Moore<'_, I, O, C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.state == other.state
+            && self.initial == other.initial
+            && self.output_on == other.output_on
+            && self.ticks == other.ticks
+            && self.timeout_input == other.timeout_input
+            && self.error_state == other.error_state
+            && self.error_policy == other.error_policy
+            && self.on_error.map(|f| f as usize) == other.on_error.map(|f| f as usize)
+            && self.before_hook.map(|f| f as usize) == other.before_hook.map(|f| f as usize)
+            && self.after_hook.map(|f| f as usize) == other.after_hook.map(|f| f as usize)
+            && ptr::eq(self.transitions, other.transitions)
+            && ptr::eq(self.index, other.index)
+            && ptr::eq(self.guards, other.guards)
+            && ptr::eq(self.actions, other.actions)
+            && ptr::eq(self.outputs, other.outputs)
+            && ptr::eq(self.timed, other.timed)
+            && ptr::eq(self.timeouts, other.timeouts)
+            && ptr::eq(self.periodic, other.periodic)
+            && ptr::eq(self.accepting, other.accepting)
+            && ptr::eq(self.names, other.names)
+            && ptr::eq(self.labels, other.labels)
     }
+}
 
-    /// Reset to specific state
-    pub fn reset(&mut self, state: u8) {
-        self.state = state;
-    }
+// Find the first row for (state, input) whose guard passes, as (row, to);
+// guarded rows never match without a context
+fn next_state<I: Eq, C>(
+    transitions: &[(u8, I, u8)],
+    index: &[(u16, u16)],
+    guards: &[Option<Guard<C>>],
+    state: u8,
+    input: &I,
+    ctx: Option<&C>,
+) -> Option<(usize, u8)> {
+    // Rows to scan, with the row number of the first
+    let (start, rows) = if index.is_empty() {
+        (0, transitions)
+    } else {
+        let (offset, len) = index.get(usize::from(state)).copied().unwrap_or_default();
+        let (offset, len) = (usize::from(offset), usize::from(len));
+        (
+            offset,
+            transitions.get(offset..offset + len).unwrap_or_default(),
+        )
+    };
+    rows.iter()
+        .enumerate()
+        .map(|(k, entry)| (start + k, entry))
+        .find(|(row, (from, inp, _to))| {
+            *from == state
+                && inp == input
+                && match (guards.get(*row).copied().flatten(), ctx) {
+                    (None, _) => true,
+                    (Some(guard), Some(ctx)) => guard(ctx),
+                    (Some(_), None) => false,
+                }
+        })
+        .map(|(row, (_from, _inp, to))| (row, *to))
+}
+
+// Inputs of `state`'s rows passing `allowed(row)`, each yielded once
+fn inputs_from<'a, I: Copy + Eq>(
+    transitions: &'a [(u8, I, u8)],
+    state: u8,
+    allowed: impl Fn(usize) -> bool + 'a,
+) -> impl Iterator<Item = I> + 'a {
+    let takes = move |row: usize, (from, _inp, _to): &(u8, I, u8)| *from == state && allowed(row);
+    transitions
+        .iter()
+        .enumerate()
+        .filter(move |(row, entry)| {
+            // Skip inputs already yielded by an earlier row
+            takes(*row, entry)
+                && !transitions
+                    .iter()
+                    .take(*row)
+                    .enumerate()
+                    .any(|(r, earlier)| earlier.1 == entry.1 && takes(r, earlier))
+        })
+        .map(|(_row, (_from, inp, _to))| *inp)
+}
+
+// Find the action registered for (state, input)
+fn action_for<I: Eq, C>(actions: &[(u8, I, Action<C>)], state: u8, input: &I) -> Option<Action<C>> {
+    actions
+        .iter()
+        .find(|(s, i, _a)| *s == state && i == input)
+        .map(|(_s, _i, a)| *a)
+}
+
+// Find the first timed transition due for `state` after `ticks` ticks
+fn timed_next(timed: &[(u8, u32, u8)], state: u8, ticks: u32) -> Option<u8> {
+    timed
+        .iter()
+        .find(|(s, after, _to)| *s == state && ticks >= *after)
+        .map(|(_s, _after, to)| *to)
+}
+
+// Check whether the timeout for `state` has expired after `ticks` ticks
+fn timeout_due(timeouts: &[(u8, u32)], state: u8, ticks: u32) -> bool {
+    timeouts
+        .iter()
+        .any(|(s, after)| *s == state && ticks >= *after)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[derive(Copy, Clone, Eq, PartialEq)]
+    #[derive(Copy, Clone, Eq, PartialEq, Debug)]
     enum TestInput {
         A,
         B,
@@ -221,6 +2018,17 @@ mod tests {
         assert_eq!(fsm.current_state(), 0); // State unchanged on error
     }
 
+    #[test]
+    fn mealy_output_fn() {
+        fn output(state: u8, input: TestInput) -> u16 {
+            u16::from(state) * 10 + input as u16
+        }
+        let mut fsm = Mealy::from_output_fn(0, &MEALY_TRANS, output);
+        assert_eq!(fsm.step(TestInput::A), Ok(0));
+        assert_eq!(fsm.step(TestInput::B), Ok(11));
+        assert_eq!(fsm.step(TestInput::B), Err(StepError::NoTransition));
+    }
+
     #[test]
     fn mealy_reset() {
         let mut fsm = Mealy::new(0, &MEALY_TRANS, &MEALY_OUTS);
@@ -230,6 +2038,443 @@ mod tests {
         assert_eq!(fsm.current_state(), 0);
     }
 
+    #[test]
+    fn mealy_fork_and_compare() {
+        let mut fsm = Mealy::new(0, &MEALY_TRANS, &MEALY_OUTS);
+        let mut fork = fsm;
+        assert_eq!(fork.step(TestInput::A), Ok(TestOutput::X));
+        assert_ne!(fork, fsm);
+        fsm.step(TestInput::A).unwrap();
+        assert_eq!(fork, fsm);
+
+        // Same contents at another address is a different machine
+        let copy = MEALY_TRANS;
+        assert_ne!(
+            Mealy::new(0, &copy, &MEALY_OUTS),
+            Mealy::new(0, &MEALY_TRANS, &MEALY_OUTS)
+        );
+    }
+
+    #[test]
+    fn machines_debug_print_sizes() {
+        extern crate std;
+        let mealy = Mealy::new(1, &MEALY_TRANS, &MEALY_OUTS);
+        assert_eq!(
+            std::format!("{mealy:?}"),
+            "Mealy { state: 1, ticks: 0, transitions: 2, outputs: 2, .. }"
+        );
+        let moore = Moore::new(0, &MOORE_TRANS, &MOORE_OUTS);
+        assert_eq!(
+            std::format!("{moore:?}"),
+            "Moore { state: 0, ticks: 0, transitions: 2, outputs: 2, .. }"
+        );
+    }
+
+    #[test]
+    fn state_names_in_display_and_debug() {
+        extern crate std;
+        static NAMES: [&str; 1] = ["idle"];
+        let mut fsm = Mealy::new(0, &MEALY_TRANS, &MEALY_OUTS).with_state_names(&NAMES);
+        assert_eq!(fsm.state_name(), Some("idle"));
+        assert_eq!(std::format!("{fsm}"), "idle");
+        assert!(std::format!("{fsm:?}").contains("state_name: \"idle\""));
+
+        // State 1 has no name
+        fsm.step(TestInput::A).unwrap();
+        assert_eq!(fsm.state_name(), None);
+        assert_eq!(std::format!("{fsm}"), "1");
+    }
+
+    #[test]
+    fn transition_labels_follow_taken_row() {
+        static LABELS: [Option<&str>; 1] = [Some("ack received")];
+        let mut fsm = Moore::new(0, &MOORE_TRANS, &MOORE_OUTS).with_labels(&LABELS);
+        assert_eq!(fsm.last_label(), None);
+        fsm.step(TestInput::A).unwrap();
+        assert_eq!(fsm.last_label(), Some("ack received"));
+
+        // Failed step keeps the label, unlabeled row clears it
+        assert_eq!(fsm.step(TestInput::A), Err(StepError::NoTransition));
+        assert_eq!(fsm.last_label(), Some("ack received"));
+        fsm.step(TestInput::B).unwrap();
+        assert_eq!(fsm.last_label(), None);
+    }
+
+    #[test]
+    fn step_reported_carries_context() {
+        extern crate std;
+        let mut fsm = Mealy::new(0, &MEALY_TRANS, &MEALY_OUTS);
+        let failure = fsm.step_reported(TestInput::B).unwrap_err();
+        assert_eq!(
+            failure,
+            StepFailure {
+                kind: StepError::NoTransition,
+                state: 0,
+                input: Some(TestInput::B),
+            }
+        );
+        assert_eq!(
+            std::format!("{failure}"),
+            "no transition in state 0 on input B"
+        );
+        assert_eq!(StepError::from(failure), StepError::NoTransition);
+        assert_eq!(fsm.step_reported(TestInput::A), Ok(TestOutput::X));
+    }
+
+    #[test]
+    fn non_copy_inputs_step_by_reference() {
+        #[derive(PartialEq, Eq, Debug)]
+        struct Frame([u8; 3]);
+
+        let transitions = [(0, Frame(*b"SYN"), 1), (1, Frame(*b"FIN"), 0)];
+        let outputs = [(0, Frame(*b"SYN"), 'o'), (1, Frame(*b"FIN"), 'c')];
+        let mut mealy = Mealy::new(0, &transitions, &outputs);
+        assert_eq!(mealy.step_ref(&Frame(*b"SYN")), Ok('o'));
+        assert_eq!(
+            mealy.step_ref(&Frame(*b"SYN")),
+            Err(StepError::NoTransition)
+        );
+        let failure = mealy.step_reported(Frame(*b"ACK")).unwrap_err();
+        assert_eq!(failure.input, Some(Frame(*b"ACK")));
+        assert_eq!(mealy.run(&[Frame(*b"FIN")]).map(|r| r.state), Ok(0));
+
+        // Machines over non-Copy inputs still fork
+        let mut fork = mealy;
+        assert_eq!(fork.step_ref(&Frame(*b"SYN")), Ok('o'));
+        assert_eq!(mealy.current_state(), 0);
+
+        let mut moore = Moore::new(0, &transitions, &['o', 'c']);
+        assert_eq!(moore.step(Frame(*b"SYN")), Ok('c'));
+        assert_eq!(moore.step_ref(&Frame(*b"FIN")), Ok('o'));
+    }
+
+    #[test]
+    fn reset_checked_rejects_invalid_state() {
+        let mut moore = Moore::new(0, &MOORE_TRANS, &MOORE_OUTS);
+        assert_eq!(moore.reset_checked(1), Ok(()));
+        assert_eq!(moore.reset_checked(2), Err(StepError::InvalidState));
+        assert_eq!(moore.current_state(), 1);
+
+        // Unchecked reset past the outputs is reported as InvalidState
+        moore.reset(5);
+        assert_eq!(moore.current_output(), Err(StepError::InvalidState));
+
+        let mut mealy = Mealy::new(0, &MEALY_TRANS, &MEALY_OUTS);
+        assert_eq!(mealy.reset_checked(1), Ok(()));
+        assert_eq!(mealy.reset_checked(9), Err(StepError::InvalidState));
+        assert_eq!(mealy.current_state(), 1);
+    }
+
+    #[test]
+    fn new_checked_cross_validates_tables() {
+        assert!(Mealy::new_checked(0, &MEALY_TRANS, &MEALY_OUTS).is_ok());
+        assert_eq!(
+            Mealy::new_checked(0, &MEALY_TRANS, &MEALY_OUTS[..1]).unwrap_err(),
+            TableError::MissingOutput { row: 1 }
+        );
+        assert_eq!(
+            Mealy::new_checked(0, &MEALY_TRANS[..1], &MEALY_OUTS).unwrap_err(),
+            TableError::UnusedOutput { row: 1 }
+        );
+
+        assert!(Moore::new_checked(0, &MOORE_TRANS, &MOORE_OUTS).is_ok());
+        assert_eq!(
+            Moore::new_checked(0, &MOORE_TRANS, &MOORE_OUTS[..1]).unwrap_err(),
+            TableError::StateOutOfRange {
+                row: Some(0),
+                state: 1,
+            }
+        );
+        assert_eq!(
+            Moore::new_checked(2, &MOORE_TRANS, &MOORE_OUTS).unwrap_err(),
+            TableError::StateOutOfRange {
+                row: None,
+                state: 2
+            }
+        );
+    }
+
+    #[test]
+    fn step_detailed_reports_transition() {
+        let mut mealy = Mealy::new(0, &MEALY_TRANS, &MEALY_OUTS);
+        mealy.step(TestInput::A).unwrap();
+        assert_eq!(
+            mealy.step_detailed(TestInput::B),
+            Ok(StepResult {
+                from: 1,
+                to: 0,
+                output: TestOutput::Y,
+                row_index: 1,
+            })
+        );
+
+        let mut moore = Moore::new(0, &MOORE_TRANS, &MOORE_OUTS);
+        let result = moore.step_detailed(TestInput::A).unwrap();
+        assert_eq!(
+            (result.from, result.to, result.output),
+            (0, 1, TestOutput::Y)
+        );
+        assert_eq!(
+            moore.step_detailed(TestInput::A),
+            Err(StepError::NoTransition)
+        );
+    }
+
+    #[test]
+    fn transitions_from_lists_outgoing_rows() {
+        static TRANS: [(u8, TestInput, u8); 3] = [
+            (0, TestInput::A, 1),
+            (1, TestInput::B, 0),
+            (0, TestInput::B, 2),
+        ];
+        let fsm = Moore::new(0, &TRANS, &MOORE_OUTS);
+        let mut from_zero = fsm.transitions_from(0);
+        assert_eq!(from_zero.next(), Some((&TestInput::A, 1)));
+        assert_eq!(from_zero.next(), Some((&TestInput::B, 2)));
+        assert_eq!(from_zero.next(), None);
+
+        let fsm = Mealy::new(0, &MEALY_TRANS, &MEALY_OUTS);
+        assert_eq!(fsm.transitions_from(1).count(), 1);
+        assert_eq!(fsm.transitions_from(7).count(), 0);
+    }
+
+    #[test]
+    fn error_state_is_sticky_until_reset() {
+        let mut fsm = Mealy::new(0, &MEALY_TRANS, &MEALY_OUTS).with_error_state(9);
+        assert_eq!(fsm.step(TestInput::B), Err(StepError::NoTransition));
+        assert_eq!(fsm.current_state(), 9);
+        assert_eq!(fsm.step(TestInput::A), Err(StepError::NoTransition));
+        fsm.reset(0);
+        assert_eq!(fsm.step(TestInput::A), Ok(TestOutput::X));
+
+        // Error state refuses input even if the table has rows for it
+        static TRANS: [(u8, TestInput, u8); 2] = [(0, TestInput::A, 5), (1, TestInput::A, 0)];
+        let mut fsm = Moore::new(0, &TRANS, &MOORE_OUTS).with_error_state(1);
+        assert_eq!(fsm.step(TestInput::A), Err(StepError::InvalidState));
+        assert_eq!(fsm.current_state(), 1);
+        assert_eq!(fsm.step(TestInput::A), Err(StepError::NoTransition));
+    }
+
+    #[test]
+    fn error_policy_decides_where_machine_ends_up() {
+        static TIMEOUTS: [(u8, u32); 1] = [(1, 2)];
+        let fsm = Mealy::new(0, &MEALY_TRANS, &MEALY_OUTS).with_timeouts(&TIMEOUTS, &TestInput::B);
+
+        let mut reject = fsm;
+        reject.step(TestInput::A).unwrap();
+        reject.tick();
+        assert_eq!(reject.step(TestInput::A), Err(StepError::NoTransition));
+        assert!(matches!(reject.tick(), Tick::Timeout(Ok(TestOutput::Y))));
+
+        let mut stay = fsm.with_error_policy(ErrorPolicy::StayAndReport);
+        stay.step(TestInput::A).unwrap();
+        stay.tick();
+        assert_eq!(stay.step(TestInput::A), Err(StepError::NoTransition));
+        assert_eq!(stay.tick(), Tick::Idle);
+        assert_eq!(stay.current_state(), 1);
+
+        let mut goto =
+            Moore::new(0, &MEALY_TRANS, &MOORE_OUTS).with_error_policy(ErrorPolicy::GotoState(0));
+        goto.step(TestInput::A).unwrap();
+        assert_eq!(goto.step(TestInput::A), Err(StepError::NoTransition));
+        assert_eq!(goto.current_state(), 0);
+    }
+
+    #[test]
+    fn on_error_sees_every_rejected_input() {
+        use core::sync::atomic::{AtomicU32, Ordering};
+        static REJECTED: AtomicU32 = AtomicU32::new(0);
+        fn count(state: u8, input: &TestInput) {
+            assert_eq!((state, *input), (0, TestInput::B));
+            REJECTED.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let mut fsm = Mealy::new(0, &MEALY_TRANS, &MEALY_OUTS).with_on_error(count);
+        assert!(fsm.step(TestInput::B).is_err());
+        assert!(fsm.step(TestInput::A).is_ok());
+        fsm.reset(0);
+        assert!(fsm.step_detailed(TestInput::B).is_err());
+        assert_eq!(REJECTED.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn before_vetoes_and_after_observes() {
+        use core::sync::atomic::{AtomicU8, Ordering};
+        static LAST_TO: AtomicU8 = AtomicU8::new(0);
+        fn only_a(_state: u8, input: &TestInput) -> bool {
+            *input == TestInput::A
+        }
+        fn record(_from: u8, _input: &TestInput, to: u8) {
+            LAST_TO.store(to, Ordering::Relaxed);
+        }
+
+        let mut fsm = Moore::new(0, &MEALY_TRANS, &MOORE_OUTS)
+            .with_before(only_a)
+            .with_after(record);
+        assert_eq!(fsm.step(TestInput::A), Ok(TestOutput::Y));
+        assert_eq!(LAST_TO.load(Ordering::Relaxed), 1);
+        assert_eq!(fsm.step(TestInput::B), Err(StepError::NoTransition));
+        assert_eq!(fsm.current_state(), 1);
+    }
+
+    #[test]
+    fn step_optional_allows_silent_transitions() {
+        static TRANS: [(u8, TestInput, u8); 2] = [(0, TestInput::A, 1), (1, TestInput::A, 0)];
+        static OUTS: [(u8, TestInput, TestOutput); 1] = [(1, TestInput::A, TestOutput::Y)];
+        let mut fsm = Mealy::new(0, &TRANS, &OUTS);
+        assert_eq!(fsm.step(TestInput::A), Err(StepError::NoOutput));
+        assert_eq!(fsm.step_optional(TestInput::A), Ok(None));
+        assert_eq!(fsm.step_optional(TestInput::A), Ok(Some(TestOutput::Y)));
+        assert_eq!(
+            fsm.step_optional(TestInput::B),
+            Err(StepError::NoTransition)
+        );
+    }
+
+    #[test]
+    fn step_into_checks_room_first() {
+        static OUTS: [(u8, TestInput, TestOutput); 3] = [
+            (0, TestInput::A, TestOutput::X),
+            (0, TestInput::A, TestOutput::Y),
+            (1, TestInput::B, TestOutput::Y),
+        ];
+        let mut fsm = Mealy::new(0, &MEALY_TRANS, &OUTS);
+        let mut buf = [TestOutput::X; 2];
+        let mut out = &mut buf[..1];
+        assert_eq!(
+            fsm.step_into(TestInput::A, &mut out),
+            Err(StepError::BufferFull)
+        );
+        assert_eq!(fsm.current_state(), 0);
+
+        let mut out = &mut buf[..];
+        assert_eq!(fsm.step_into(TestInput::A, &mut out), Ok(2));
+        assert_eq!(buf, [TestOutput::X, TestOutput::Y]);
+        assert_eq!(fsm.current_state(), 1);
+    }
+
+    #[cfg(feature = "heapless")]
+    #[test]
+    fn step_into_fills_heapless_vec() {
+        static OUTS: [(u8, TestInput, TestOutput); 2] = [
+            (0, TestInput::A, TestOutput::X),
+            (0, TestInput::A, TestOutput::Y),
+        ];
+        let mut fsm = Mealy::new(0, &MEALY_TRANS, &OUTS);
+        let mut out: heapless::Vec<TestOutput, 3> = heapless::Vec::new();
+        out.push(TestOutput::Y).unwrap();
+        assert_eq!(fsm.step_into(TestInput::A, &mut out), Ok(2));
+        assert_eq!(out, [TestOutput::Y, TestOutput::X, TestOutput::Y]);
+
+        fsm.reset(0);
+        assert_eq!(
+            fsm.step_into(TestInput::A, &mut out),
+            Err(StepError::BufferFull)
+        );
+    }
+
+    #[test]
+    fn ticks_in_state_restarts_on_transition() {
+        let mut fsm = Moore::new(0, &MEALY_TRANS, &MOORE_OUTS);
+        fsm.tick();
+        fsm.tick();
+        assert_eq!(fsm.ticks_in_state(), 2);
+        assert_eq!(fsm.step(TestInput::B), Err(StepError::NoTransition));
+        assert_eq!(fsm.ticks_in_state(), 2);
+        fsm.step(TestInput::A).unwrap();
+        assert_eq!(fsm.ticks_in_state(), 0);
+        fsm.tick();
+        fsm.reset(0);
+        assert_eq!(fsm.ticks_in_state(), 0);
+    }
+
+    #[test]
+    fn moore_periodic_outputs_on_tick() {
+        static PERIODIC: [(u8, u32); 2] = [(0, 2), (1, 0)];
+        let mut fsm = Moore::new(0, &MEALY_TRANS, &MOORE_OUTS).with_periodic(&PERIODIC);
+        assert_eq!(fsm.tick(), Tick::Idle);
+        assert_eq!(fsm.tick(), Tick::Periodic(TestOutput::X));
+        assert_eq!(fsm.tick(), Tick::Idle);
+        assert_eq!(fsm.tick(), Tick::Periodic(TestOutput::X));
+        fsm.step(TestInput::A).unwrap();
+        assert_eq!(fsm.tick(), Tick::Idle); // Period 0 never fires
+        assert_eq!(fsm.tick(), Tick::Idle);
+    }
+
+    #[test]
+    fn transitions_to_lists_incoming_rows() {
+        static TRANS: [(u8, TestInput, u8); 3] = [
+            (0, TestInput::A, 1),
+            (1, TestInput::B, 1),
+            (2, TestInput::A, 0),
+        ];
+        let fsm = Moore::new(0, &TRANS, &MOORE_OUTS);
+        assert!(fsm
+            .transitions_to(1)
+            .eq([(0, &TestInput::A), (1, &TestInput::B)]));
+        assert_eq!(fsm.transitions_to(2).count(), 0);
+
+        let fsm = Mealy::new(0, &MEALY_TRANS, &MEALY_OUTS);
+        assert_eq!(fsm.transitions_to(0).count(), 1);
+    }
+
+    #[test]
+    fn valid_inputs_respect_guards() {
+        extern crate std;
+        use std::vec::Vec;
+
+        static TRANS: [(u8, TestInput, u8); 3] = [
+            (0, TestInput::A, 1),
+            (0, TestInput::B, 1),
+            (0, TestInput::B, 0),
+        ];
+        static GUARDS: [Option<Guard<bool>>; 2] = [None, Some(|open| *open)];
+        let fsm = Mealy::new(0, &TRANS, &MEALY_OUTS)
+            .with_context::<bool>()
+            .with_guards(&GUARDS);
+
+        // Unguarded fallback row for B counts once
+        assert_eq!(
+            fsm.valid_inputs().collect::<Vec<_>>(),
+            [TestInput::A, TestInput::B]
+        );
+        assert_eq!(
+            fsm.valid_inputs_with(&false).collect::<Vec<_>>(),
+            [TestInput::A, TestInput::B]
+        );
+
+        static ONLY_GUARDED: [Option<Guard<bool>>; 3] =
+            [None, Some(|open| *open), Some(|open| *open)];
+        let fsm = fsm.with_guards(&ONLY_GUARDED);
+        assert_eq!(fsm.valid_inputs().collect::<Vec<_>>(), [TestInput::A]);
+        assert_eq!(fsm.valid_inputs_with(&true).count(), 2);
+    }
+
+    #[test]
+    fn is_terminal_after_final_transition() {
+        static TRANS: [(u8, TestInput, u8); 1] = [(0, TestInput::A, 1)];
+        let mut fsm = Moore::new(0, &TRANS, &MOORE_OUTS);
+        assert!(!fsm.is_terminal());
+        fsm.step(TestInput::A).unwrap();
+        assert!(fsm.is_terminal());
+    }
+
+    #[test]
+    fn mealy_borrows_runtime_tables() {
+        let transitions: [(u8, u8, u8); 4] = core::array::from_fn(|s| (s as u8, b'+', s as u8 + 1));
+        let outputs: [(u8, u8, u8); 4] = core::array::from_fn(|s| (s as u8, b'+', s as u8 * 2));
+        let mut fsm = Mealy::new(0, &transitions, &outputs);
+        assert_eq!(
+            fsm.run(b"+++"),
+            Ok(RunOutcome {
+                state: 3,
+                accepted: false
+            })
+        );
+        assert_eq!(fsm.step(b'+'), Ok(6));
+        assert_eq!(fsm.step(b'+'), Err(StepError::NoTransition));
+    }
+
     // Moore tests
     static MOORE_TRANS: [(u8, TestInput, u8); 2] = [(0, TestInput::A, 1), (1, TestInput::B, 0)];
 
@@ -254,4 +2499,281 @@ mod tests {
         let mut fsm = Moore::new(0, &MOORE_TRANS, &MOORE_OUTS);
         assert_eq!(fsm.step(TestInput::B), Err(StepError::NoTransition));
     }
+
+    // Timed transition tests
+    static TIMED: [(u8, u32, u8); 1] = [(1, 3, 0)];
+
+    #[test]
+    fn mealy_timed_transition() {
+        let mut fsm = Mealy::new(0, &MEALY_TRANS, &MEALY_OUTS).with_timed(&TIMED);
+        assert_eq!(fsm.tick(), Tick::Idle); // No timed rule for state 0
+        fsm.step(TestInput::A).unwrap();
+        assert_eq!(fsm.tick(), Tick::Idle);
+        assert_eq!(fsm.tick(), Tick::Idle);
+        assert_eq!(fsm.tick(), Tick::Timed(0));
+        assert_eq!(fsm.current_state(), 0);
+    }
+
+    #[test]
+    fn moore_timed_transition_restarts_on_step() {
+        let mut fsm = Moore::new(1, &MOORE_TRANS, &MOORE_OUTS).with_timed(&TIMED);
+        fsm.tick();
+        fsm.tick();
+        fsm.step(TestInput::B).unwrap();
+        fsm.step(TestInput::A).unwrap(); // Re-enter state 1, timer restarts
+        assert_eq!(fsm.tick(), Tick::Idle);
+        assert_eq!(fsm.tick(), Tick::Idle);
+        assert_eq!(fsm.tick(), Tick::Timed(0));
+        assert_eq!(fsm.current_output(), Ok(TestOutput::X));
+    }
+
+    // Timeout tests
+    static TIMEOUTS: [(u8, u32); 2] = [(0, 2), (1, 1)];
+
+    #[test]
+    fn mealy_timeout_injects_input() {
+        let mut fsm =
+            Mealy::new(1, &MEALY_TRANS, &MEALY_OUTS).with_timeouts(&TIMEOUTS, &TestInput::B);
+        assert_eq!(fsm.tick(), Tick::Timeout(Ok(TestOutput::Y)));
+        assert_eq!(fsm.current_state(), 0);
+    }
+
+    #[test]
+    fn moore_timeout_rejected_rearms() {
+        let mut fsm =
+            Moore::new(0, &MOORE_TRANS, &MOORE_OUTS).with_timeouts(&TIMEOUTS, &TestInput::B);
+        assert_eq!(fsm.tick(), Tick::Idle);
+        assert_eq!(fsm.tick(), Tick::Timeout(Err(StepError::NoTransition)));
+        assert_eq!(fsm.tick(), Tick::Idle);
+        assert_eq!(fsm.tick(), Tick::Timeout(Err(StepError::NoTransition)));
+    }
+
+    // Guard tests
+    struct Battery {
+        millivolts: u16,
+    }
+
+    fn charged(b: &Battery) -> bool {
+        b.millivolts >= 4100
+    }
+
+    fn not_charged(b: &Battery) -> bool {
+        !charged(b)
+    }
+
+    static GUARDED_TRANS: [(u8, TestInput, u8); 3] = [
+        (0, TestInput::A, 1),
+        (0, TestInput::A, 2),
+        (0, TestInput::B, 0),
+    ];
+
+    static GUARDS: [Option<Guard<Battery>>; 2] = [Some(not_charged), Some(charged)];
+
+    static GUARDED_OUTS: [TestOutput; 3] = [TestOutput::X, TestOutput::Y, TestOutput::X];
+
+    #[test]
+    fn moore_guard_selects_row() {
+        let mut fsm = Moore::new(0, &GUARDED_TRANS, &GUARDED_OUTS)
+            .with_context()
+            .with_guards(&GUARDS);
+        let low = Battery { millivolts: 3600 };
+        let full = Battery { millivolts: 4200 };
+        assert_eq!(fsm.step_with(TestInput::A, &low), Ok(TestOutput::Y));
+        fsm.reset(0);
+        assert_eq!(fsm.step_with(TestInput::A, &full), Ok(TestOutput::X));
+        assert_eq!(fsm.current_state(), 2);
+    }
+
+    #[test]
+    fn mealy_step_skips_guarded_rows() {
+        let mut fsm = Mealy::new(0, &GUARDED_TRANS, &MEALY_OUTS)
+            .with_context()
+            .with_guards(&GUARDS);
+        assert_eq!(fsm.step(TestInput::A), Err(StepError::NoTransition));
+        assert_eq!(fsm.current_state(), 0);
+        let low = Battery { millivolts: 3600 };
+        assert_eq!(fsm.step_with(TestInput::A, &low), Ok(TestOutput::X));
+    }
+
+    // Output semantics tests
+    #[test]
+    fn moore_start_yields_initial_output() {
+        let mut fsm = Moore::new(0, &MOORE_TRANS, &MOORE_OUTS);
+        assert_eq!(fsm.start(), Ok(TestOutput::X));
+        fsm.step(TestInput::A).unwrap();
+        assert_eq!(fsm.start(), Ok(TestOutput::X));
+        assert_eq!(fsm.current_state(), 0);
+    }
+
+    #[test]
+    fn moore_output_on_transition() {
+        let mut fsm = Moore::new(0, &MOORE_TRANS, &MOORE_OUTS).with_output_on(OutputOn::Transition);
+        assert_eq!(fsm.step(TestInput::A), Ok(TestOutput::X)); // Output of state 0
+        assert_eq!(fsm.step(TestInput::B), Ok(TestOutput::Y)); // Output of state 1
+        assert_eq!(fsm.current_state(), 0);
+    }
+
+    // Action tests
+    fn count(n: &mut u32) {
+        *n += 1;
+    }
+
+    static ACTIONS: [(u8, TestInput, Action<u32>); 1] = [(0, TestInput::A, count)];
+
+    #[test]
+    fn mealy_actions_run_on_commit() {
+        let mut fsm = Mealy::new(0, &MEALY_TRANS, &MEALY_OUTS)
+            .with_context()
+            .with_actions(&ACTIONS);
+        let mut n = 0;
+        assert_eq!(fsm.step_with_mut(TestInput::A, &mut n), Ok(TestOutput::X));
+        assert_eq!(
+            fsm.step_with_mut(TestInput::A, &mut n),
+            Err(StepError::NoTransition)
+        );
+        assert_eq!(n, 1);
+    }
+
+    #[test]
+    fn moore_actions_run_on_commit() {
+        let mut fsm = Moore::new(0, &MOORE_TRANS, &MOORE_OUTS)
+            .with_context()
+            .with_actions(&ACTIONS);
+        let mut n = 0;
+        fsm.step(TestInput::A).unwrap(); // No context, no action
+        fsm.step_with_mut(TestInput::B, &mut n).unwrap();
+        fsm.step_with_mut(TestInput::A, &mut n).unwrap();
+        assert_eq!(n, 1);
+    }
+
+    // Accepting state tests
+    static ACCEPTING: [u8; 1] = [1];
+
+    #[test]
+    fn mealy_is_accepting() {
+        let mut fsm = Mealy::new(0, &MEALY_TRANS, &MEALY_OUTS).with_accepting(&ACCEPTING);
+        assert!(!fsm.is_accepting());
+        fsm.step(TestInput::A).unwrap();
+        assert!(fsm.is_accepting());
+    }
+
+    #[test]
+    fn moore_is_accepting() {
+        let mut fsm = Moore::new(0, &MOORE_TRANS, &MOORE_OUTS);
+        fsm.step(TestInput::A).unwrap();
+        assert!(!fsm.is_accepting()); // No accepting set
+        let fsm = Moore::new(1, &MOORE_TRANS, &MOORE_OUTS).with_accepting(&ACCEPTING);
+        assert!(fsm.is_accepting());
+    }
+
+    // Run tests
+    #[test]
+    fn mealy_run_reports_acceptance() {
+        let mut fsm = Mealy::new(0, &MEALY_TRANS, &MEALY_OUTS).with_accepting(&ACCEPTING);
+        let outcome = fsm.run(&[TestInput::A, TestInput::B, TestInput::A]);
+        assert_eq!(
+            outcome,
+            Ok(RunOutcome {
+                state: 1,
+                accepted: true
+            })
+        );
+    }
+
+    #[test]
+    fn moore_run_reports_failure_index() {
+        let mut fsm = Moore::new(0, &MOORE_TRANS, &MOORE_OUTS).with_accepting(&ACCEPTING);
+        let outcome = fsm.run(&[TestInput::A, TestInput::A]);
+        assert_eq!(outcome, Err((1, StepError::NoTransition)));
+        assert_eq!(fsm.current_state(), 1);
+    }
+}
+
+// Link-time proof that stepping cannot panic: `Unreachable`'s destructor
+// calls a symbol that does not exist, and it only runs while unwinding. The
+// test binary therefore links only if the optimizer removed every panic
+// path from the checked functions. Run with `cargo test --release`.
+#[cfg(all(test, not(debug_assertions)))]
+mod no_panic {
+    use super::*;
+    use core::hint::black_box;
+
+    struct Unreachable;
+
+    impl Drop for Unreachable {
+        fn drop(&mut self) {
+            extern "C" {
+                fn fsmall_step_may_panic() -> !;
+            }
+            unsafe { fsmall_step_may_panic() }
+        }
+    }
+
+    fn never_panics<R>(f: impl FnOnce() -> R) -> R {
+        let guard = Unreachable;
+        let result = f();
+        core::mem::forget(guard);
+        result
+    }
+
+    #[derive(Copy, Clone, Eq, PartialEq, Debug)]
+    enum TestInput {
+        A,
+        B,
+    }
+
+    static TRANS: [(u8, TestInput, u8); 2] = [(0, TestInput::A, 1), (1, TestInput::B, 0)];
+    static INDEX: [(u16, u16); 2] = analysis::state_index::<TestInput, 2>(&TRANS);
+    static MEALY_OUTS: [(u8, TestInput, u8); 2] = [(0, TestInput::A, 1), (1, TestInput::B, 0)];
+    static MOORE_OUTS: [u8; 2] = [10, 20];
+    static ROWS: [(u8, TestInput, u8, u8); 2] = [(0, TestInput::A, 1, 1), (1, TestInput::B, 0, 0)];
+    static SLOTS: [(u8, u8); 4] = [(2, 0), (1, 1), (0, 0), (2, 0)];
+
+    #[test]
+    fn step_never_panics() {
+        // Machines are built inside the checked closure so the optimizer sees
+        // that no user function (output fn, guard, action) is attached;
+        // those may panic and are outside the crate's control.
+        let inputs = black_box([TestInput::A, TestInput::B, TestInput::B]);
+        never_panics(|| {
+            let mut mealy = Mealy::new(black_box(0), &TRANS, &MEALY_OUTS);
+            for input in inputs {
+                let _ = black_box(mealy.step(input));
+            }
+            black_box(mealy.tick());
+        });
+        never_panics(|| {
+            let mut mealy = Mealy::new(black_box(0), &TRANS, &MEALY_OUTS).with_state_index(&INDEX);
+            for input in inputs {
+                let _ = black_box(mealy.step(input));
+            }
+        });
+        never_panics(|| {
+            let mut moore = Moore::new(black_box(0), &TRANS, &MOORE_OUTS);
+            for input in inputs {
+                let _ = black_box(moore.step(input));
+            }
+            black_box(moore.tick());
+            let _ = black_box(moore.current_output());
+        });
+        never_panics(|| {
+            let mut rows: rows::CachedRows<_, _, 2> = rows::CachedRows::new(black_box(0), &ROWS);
+            for input in inputs {
+                let _ = black_box(rows.step(input));
+            }
+        });
+        never_panics(|| {
+            let mut dense: dense::BranchlessMealy<bool, u8> =
+                dense::BranchlessMealy::new(black_box(0), 2, &SLOTS);
+            for input in black_box([true, false, true]) {
+                black_box(dense.step(input));
+            }
+        });
+        never_panics(|| {
+            let mut moore = Moore::new(black_box(0), &TRANS, &MOORE_OUTS).with_state_index(&INDEX);
+            for input in inputs {
+                let _ = black_box(moore.step(input));
+            }
+        });
+    }
 }
@@ -0,0 +1,203 @@
+//! Load machine descriptions into owned tables at runtime
+//!
+//! Requires the `std` feature. Simulators and test benches can read the
+//! description file the firmware's tables are generated from (see
+//! `codegen`) and iterate on the design without recompiling. Inputs and
+//! outputs are spelled as Rust expressions in the file, so the caller
+//! supplies functions turning that text into values.
+//!
+//! # Example
+//! ```
+//! use fsmall::description::Description;
+//! use fsmall::{load, Mealy};
+//!
+//! let description = Description::from_json(r#"{
+//!     "input": "char", "output": "bool",
+//!     "states": ["off", "on"],
+//!     "transitions": [["off", "'t'", "on", "true"], ["on", "'t'", "off", "false"]]
+//! }"#).unwrap();
+//! let tables = load::mealy_tables(
+//!     &description,
+//!     |input| input.trim_matches('\'').chars().next(),
+//!     |output| output.parse().ok(),
+//! ).unwrap();
+//!
+//! let mut fsm = Mealy::new(description.initial, &tables.transitions, &tables.outputs);
+//! assert_eq!(fsm.step('t'), Ok(true));
+//! ```
+
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::string::String;
+use std::vec::Vec;
+
+use crate::convert::{MealyTables, MooreTables};
+use crate::description::{Description, DescriptionError, Kind};
+
+/// Error returned when a description cannot be loaded
+#[derive(Debug)]
+pub enum LoadError {
+    /// File could not be read
+    Io(io::Error),
+    /// File is not a valid description
+    Description(DescriptionError),
+    /// Description is for the other machine kind
+    Kind,
+    /// Input text the input function rejected
+    Input(String),
+    /// Output text the output function rejected
+    Output(String),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io(e) => e.fmt(f),
+            LoadError::Description(e) => e.fmt(f),
+            LoadError::Kind => f.write_str("wrong machine kind"),
+            LoadError::Input(text) => write!(f, "bad input `{text}`"),
+            LoadError::Output(text) => write!(f, "bad output `{text}`"),
+        }
+    }
+}
+
+impl core::error::Error for LoadError {}
+
+impl From<io::Error> for LoadError {
+    fn from(e: io::Error) -> Self {
+        LoadError::Io(e)
+    }
+}
+
+impl From<DescriptionError> for LoadError {
+    fn from(e: DescriptionError) -> Self {
+        LoadError::Description(e)
+    }
+}
+
+/// Read a description file, choosing the format by extension
+///
+/// `.json` and `.ron` files are read as JSON and RON, anything else as TOML.
+///
+/// # Errors
+/// `LoadError::Io` or `LoadError::Description`
+pub fn read(path: impl AsRef<Path>) -> Result<Description, LoadError> {
+    let path = path.as_ref();
+    let src = std::fs::read_to_string(path)?;
+    let description = match path.extension().and_then(|e| e.to_str()) {
+        Some("json") => Description::from_json(&src),
+        Some("ron") => Description::from_ron(&src),
+        _ => Description::from_toml(&src),
+    };
+    Ok(description?)
+}
+
+/// Build Mealy tables from a Mealy description
+///
+/// # Arguments
+/// * `description` - Mealy description
+/// * `input` - Parse an input expression, `None` if invalid
+/// * `output` - Parse an output expression, `None` if invalid
+///
+/// # Errors
+/// `LoadError::Kind` for a Moore description, `LoadError::Input` and
+/// `LoadError::Output` for text the functions reject
+pub fn mealy_tables<I: Copy, O>(
+    description: &Description,
+    mut input: impl FnMut(&str) -> Option<I>,
+    mut output: impl FnMut(&str) -> Option<O>,
+) -> Result<MealyTables<I, O>, LoadError> {
+    if description.kind != Kind::Mealy {
+        return Err(LoadError::Kind);
+    }
+    let mut tables = MealyTables {
+        transitions: Vec::new(),
+        outputs: Vec::new(),
+    };
+    for row in &description.transitions {
+        let parsed = input(&row.input).ok_or_else(|| LoadError::Input(row.input.clone()))?;
+        let text = row.output.as_deref().unwrap_or_default();
+        let out = output(text).ok_or_else(|| LoadError::Output(text.into()))?;
+        tables.transitions.push((row.from, parsed, row.to));
+        tables.outputs.push((row.from, parsed, out));
+    }
+    Ok(tables)
+}
+
+/// Build Moore tables from a Moore description
+///
+/// # Arguments
+/// * `description` - Moore description
+/// * `input` - Parse an input expression, `None` if invalid
+/// * `output` - Parse an output expression, `None` if invalid
+///
+/// # Errors
+/// `LoadError::Kind` for a Mealy description, `LoadError::Input` and
+/// `LoadError::Output` for text the functions reject
+pub fn moore_tables<I, O>(
+    description: &Description,
+    mut input: impl FnMut(&str) -> Option<I>,
+    mut output: impl FnMut(&str) -> Option<O>,
+) -> Result<MooreTables<I, O>, LoadError> {
+    if description.kind != Kind::Moore {
+        return Err(LoadError::Kind);
+    }
+    let transitions = description
+        .transitions
+        .iter()
+        .map(|row| {
+            let parsed = input(&row.input).ok_or_else(|| LoadError::Input(row.input.clone()))?;
+            Ok((row.from, parsed, row.to))
+        })
+        .collect::<Result<_, LoadError>>()?;
+    let outputs = description
+        .outputs
+        .iter()
+        .map(|text| output(text).ok_or_else(|| LoadError::Output(text.clone())))
+        .collect::<Result<_, _>>()?;
+    Ok(MooreTables {
+        transitions,
+        outputs,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Moore;
+
+    #[test]
+    fn load_reads_file_by_extension() {
+        let path = std::env::temp_dir().join("fsmall_load_test.ron");
+        std::fs::write(
+            &path,
+            "Machine(kind: \"moore\", input: \"u8\", output: \"u8\",\n\
+             states: [\"low\", \"high\"], initial: \"high\",\n\
+             transitions: [[\"low\", \"1\", \"high\"], [\"high\", \"0\", \"low\"]],\n\
+             outputs: [\"10\", \"20\"])",
+        )
+        .unwrap();
+        let description = read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let tables = moore_tables(
+            &description,
+            |s| s.parse::<u8>().ok(),
+            |s| s.parse::<u8>().ok(),
+        )
+        .unwrap();
+        let mut fsm = Moore::new(description.initial, &tables.transitions, &tables.outputs);
+        assert_eq!(fsm.current_output(), Ok(20));
+        assert_eq!(fsm.step(0), Ok(10));
+
+        assert!(matches!(
+            mealy_tables(&description, |_| Some(()), |_| Some(())),
+            Err(LoadError::Kind)
+        ));
+        assert!(matches!(
+            moore_tables(&description, |s| s.parse::<bool>().ok(), |_| Some(())),
+            Err(LoadError::Input(text)) if text == "1"
+        ));
+    }
+}
@@ -0,0 +1,169 @@
+//! Table-building macros
+
+/// Define matching Mealy transition and output tables from one rule list
+///
+/// Each rule is written once as `(state, input) -> (next, output)`, so the
+/// two tables `Mealy::new` expects cannot drift apart. With a single table
+/// name, the rules become one combined table for `MealyRows`.
+///
+/// # Example
+/// ```
+/// use fsmall::{mealy_tables, Mealy};
+///
+/// #[derive(Copy, Clone, Eq, PartialEq)]
+/// enum Input { Toggle }
+///
+/// mealy_tables! {
+///     static TRANSITIONS, OUTPUTS: Input => bool = [
+///         (0, Input::Toggle) -> (1, true),
+///         (1, Input::Toggle) -> (0, false),
+///     ];
+/// }
+///
+/// let mut fsm = Mealy::new(0, &TRANSITIONS, &OUTPUTS);
+/// assert_eq!(fsm.step(Input::Toggle), Ok(true));
+/// assert_eq!(fsm.step(Input::Toggle), Ok(false));
+/// ```
+#[macro_export]
+macro_rules! mealy_tables {
+    (
+        $vis:vis static $transitions:ident, $outputs:ident : $input:ty => $output:ty = [
+            $( ($state:expr, $in:expr) -> ($next:expr, $out:expr) ),* $(,)?
+        ];
+    ) => {
+        $vis static $transitions: [(u8, $input, u8); $crate::__count!($($state)*)] =
+            [$(($state, $in, $next)),*];
+        $vis static $outputs: [(u8, $input, $output); $crate::__count!($($state)*)] =
+            [$(($state, $in, $out)),*];
+    };
+    (
+        $vis:vis static $rows:ident : $input:ty => $output:ty = [
+            $( ($state:expr, $in:expr) -> ($next:expr, $out:expr) ),* $(,)?
+        ];
+    ) => {
+        $vis static $rows: [(u8, $input, u8, $output); $crate::__count!($($state)*)] =
+            [$(($state, $in, $next, $out)),*];
+    };
+}
+
+/// Define a fieldless enum implementing `InputIndex`
+///
+/// Variants are indexed in declaration order; explicit discriminants are
+/// not accepted, so the indices are always `0..COUNT`.
+///
+/// # Example
+/// ```
+/// use fsmall::{input_index, InputIndex};
+///
+/// input_index! {
+///     #[derive(Copy, Clone, Eq, PartialEq, Debug)]
+///     pub enum Button { Up, Down, Select }
+/// }
+///
+/// assert_eq!(Button::COUNT, 3);
+/// assert_eq!(Button::Select.index(), 2);
+/// assert_eq!(Button::from_index(1), Some(Button::Down));
+/// ```
+#[macro_export]
+macro_rules! input_index {
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident {
+            $( $(#[$variant_meta:meta])* $variant:ident ),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis enum $name {
+            $( $(#[$variant_meta])* $variant ),*
+        }
+
+        impl $crate::InputIndex for $name {
+            const COUNT: usize = $crate::__count!($($variant)*);
+
+            fn index(&self) -> usize {
+                // Mirror whose discriminants are the indices, so `self`
+                // need not be `Copy` to be cast
+                #[allow(dead_code)]
+                enum Index {
+                    $($variant),*
+                }
+                match self {
+                    $($name::$variant => Index::$variant as usize),*
+                }
+            }
+
+            fn from_index(index: usize) -> Option<Self> {
+                [$($name::$variant),*].into_iter().nth(index)
+            }
+        }
+    };
+}
+
+// Number of expressions passed, usable as an array length
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __count {
+    ($($x:expr)*) => {
+        <[()]>::len(&[$($crate::__count!(@unit $x)),*])
+    };
+    (@unit $x:expr) => {
+        ()
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Mealy, StepError};
+
+    #[derive(Copy, Clone, Eq, PartialEq, Debug)]
+    enum TestInput {
+        A,
+        B,
+    }
+
+    const IDLE: u8 = 0;
+    const BUSY: u8 = 1;
+
+    mealy_tables! {
+        static TRANS, OUTS: TestInput => char = [
+            (IDLE, TestInput::A) -> (BUSY, 'a'),
+            (BUSY, TestInput::B) -> (IDLE, 'b'),
+            (BUSY, TestInput::A) -> (BUSY, 'c'),
+        ];
+    }
+
+    #[test]
+    fn macro_tables_stay_in_sync() {
+        assert_eq!(TRANS.len(), 3);
+        assert_eq!(OUTS[2], (BUSY, TestInput::A, 'c'));
+        assert!(Mealy::new_checked(IDLE, &TRANS, &OUTS).is_ok());
+
+        let mut fsm = Mealy::new(IDLE, &TRANS, &OUTS);
+        assert_eq!(fsm.step(TestInput::A), Ok('a'));
+        assert_eq!(fsm.step(TestInput::A), Ok('c'));
+        assert_eq!(fsm.step(TestInput::B), Ok('b'));
+        assert_eq!(fsm.step(TestInput::B), Err(StepError::NoTransition));
+    }
+
+    input_index! {
+        // Deliberately not Copy
+        #[derive(Debug, PartialEq)]
+        enum Event {
+            /// First
+            Start,
+            Stop,
+            Reset,
+        }
+    }
+
+    #[test]
+    fn input_index_numbers_variants() {
+        use crate::InputIndex;
+
+        assert_eq!(Event::COUNT, 3);
+        assert_eq!(Event::Reset.index(), 2);
+        assert_eq!(Event::from_index(0), Some(Event::Start));
+        assert_eq!(Event::from_index(3), None);
+        assert!((0..Event::COUNT).all(|k| Event::from_index(k).map(|e| e.index()) == Some(k)));
+    }
+}
@@ -0,0 +1,246 @@
+//! Build streaming multi-pattern matchers (Aho–Corasick style)
+//!
+//! Requires the `alloc` feature. The generated machine is complete: every
+//! state has a row for every byte class, so it can be fed an endless
+//! stream without ever returning `NoTransition`. Its Moore output is
+//! `Some(pattern_index)` in states where a pattern has just ended.
+//!
+//! # Example
+//! ```
+//! let matcher = fsmall::matcher::build(&[b"AT", b"ATZ", b"+++"]).unwrap();
+//! assert_eq!(matcher.scan(b"xxATZ+++"), [(4, 0), (5, 1), (8, 2)]);
+//! let source = matcher.to_rust("CMD");
+//! assert!(source.contains("pub static CMD_OUTPUTS: [Option<u8>;"));
+//! ```
+
+use alloc::collections::VecDeque;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+/// Error returned when a matcher cannot be built
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MatcherError {
+    /// Pattern at the given index is empty
+    EmptyPattern(usize),
+    /// More than 256 patterns
+    TooManyPatterns,
+    /// Machine needs more than 256 states
+    TooManyStates,
+}
+
+/// Streaming matcher tables over byte classes, start state 0
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Matcher {
+    /// Class table: `classes[byte]` = class index
+    pub classes: [u8; 256],
+    /// Transition table: (from_state, class, to_state), complete and sorted
+    pub transitions: Vec<(u8, u8, u8)>,
+    /// Moore outputs: `outputs[state]` = pattern ending in this state
+    pub outputs: Vec<Option<u8>>,
+}
+
+impl Matcher {
+    /// Number of states
+    pub fn states(&self) -> usize {
+        self.outputs.len()
+    }
+
+    /// Feed `input` from the start state, returning (end offset, pattern)
+    /// for every match
+    pub fn scan(&self, input: &[u8]) -> Vec<(usize, u8)> {
+        let mut state = 0;
+        let mut found = Vec::new();
+        for (offset, byte) in input.iter().enumerate() {
+            let class = self.classes[*byte as usize];
+            state = self
+                .transitions
+                .iter()
+                .find(|(from, c, _to)| *from == state && *c == class)
+                .map_or(0, |(_from, _c, to)| *to);
+            if let Some(pattern) = self.outputs[state as usize] {
+                found.push((offset + 1, pattern));
+            }
+        }
+        found
+    }
+
+    /// Emit the tables as Rust source
+    ///
+    /// Produces `{name}_CLASSES` for `ByteClasses`, `{name}_TRANSITIONS`
+    /// and `{name}_OUTPUTS` for `Moore::new`.
+    pub fn to_rust(&self, name: &str) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "pub static {name}_CLASSES: [u8; 256] = [");
+        for chunk in self.classes.chunks(16) {
+            let row: Vec<String> = chunk.iter().map(|c| format!("{c}")).collect();
+            let _ = writeln!(out, "    {},", row.join(", "));
+        }
+        out.push_str("];\n\n");
+
+        let _ = writeln!(
+            out,
+            "pub static {name}_TRANSITIONS: [(u8, u8, u8); {}] = [",
+            self.transitions.len()
+        );
+        for (from, class, to) in &self.transitions {
+            let _ = writeln!(out, "    ({from}, {class}, {to}),");
+        }
+        out.push_str("];\n\n");
+
+        let outputs: Vec<String> = self
+            .outputs
+            .iter()
+            .map(|o| match o {
+                Some(p) => format!("Some({p})"),
+                None => String::from("None"),
+            })
+            .collect();
+        let _ = writeln!(
+            out,
+            "pub static {name}_OUTPUTS: [Option<u8>; {}] = [{}];",
+            self.outputs.len(),
+            outputs.join(", ")
+        );
+        out
+    }
+}
+
+/// Build a matcher detecting every pattern in a byte stream
+///
+/// Pattern indices follow the order of `patterns`. When several patterns
+/// end on the same byte, the longest one is reported.
+///
+/// # Errors
+/// * `MatcherError::EmptyPattern` - A pattern has no bytes
+/// * `MatcherError::TooManyPatterns` - More than 256 patterns
+/// * `MatcherError::TooManyStates` - Patterns need more than 256 states
+pub fn build(patterns: &[&[u8]]) -> Result<Matcher, MatcherError> {
+    if patterns.len() > 256 {
+        return Err(MatcherError::TooManyPatterns);
+    }
+
+    // Class 0: bytes in no pattern; one class per distinct pattern byte
+    let mut classes = [0u8; 256];
+    let mut class_count = 1usize;
+    for (index, pattern) in patterns.iter().enumerate() {
+        if pattern.is_empty() {
+            return Err(MatcherError::EmptyPattern(index));
+        }
+        for byte in pattern.iter() {
+            if classes[*byte as usize] == 0 {
+                classes[*byte as usize] = class_count as u8;
+                class_count += 1;
+            }
+        }
+    }
+
+    // Trie over classes; goto[state][class] = child
+    let mut goto: Vec<Vec<Option<usize>>> = vec![vec![None; class_count]];
+    let mut own: Vec<Option<(usize, u8)>> = vec![None]; // (length, pattern)
+    for (index, pattern) in patterns.iter().enumerate() {
+        let mut state = 0;
+        for byte in pattern.iter() {
+            let class = classes[*byte as usize] as usize;
+            state = match goto[state][class] {
+                Some(next) => next,
+                None => {
+                    goto.push(vec![None; class_count]);
+                    own.push(None);
+                    let next = goto.len() - 1;
+                    goto[state][class] = Some(next);
+                    next
+                }
+            };
+        }
+        // Duplicate patterns keep the first index
+        if own[state].is_none() {
+            own[state] = Some((pattern.len(), index as u8));
+        }
+    }
+    if goto.len() > 256 {
+        return Err(MatcherError::TooManyStates);
+    }
+
+    // Breadth-first: complete goto with failure edges, inherit outputs
+    let mut delta = vec![vec![0usize; class_count]; goto.len()];
+    let mut output: Vec<Option<(usize, u8)>> = own.clone();
+    let mut fail = vec![0usize; goto.len()];
+    let mut queue = VecDeque::new();
+    for class in 0..class_count {
+        if let Some(child) = goto[0][class] {
+            delta[0][class] = child;
+            queue.push_back(child);
+        }
+    }
+    while let Some(state) = queue.pop_front() {
+        if output[state].is_none() {
+            output[state] = output[fail[state]];
+        }
+        for class in 0..class_count {
+            match goto[state][class] {
+                Some(child) => {
+                    fail[child] = delta[fail[state]][class];
+                    delta[state][class] = child;
+                    queue.push_back(child);
+                }
+                None => delta[state][class] = delta[fail[state]][class],
+            }
+        }
+    }
+
+    let transitions = delta
+        .iter()
+        .enumerate()
+        .flat_map(|(state, row)| {
+            row.iter()
+                .enumerate()
+                .map(move |(class, to)| (state as u8, class as u8, *to as u8))
+        })
+        .collect();
+
+    Ok(Matcher {
+        classes,
+        transitions,
+        outputs: output.iter().map(|o| o.map(|(_len, p)| p)).collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ByteClasses, Moore};
+    use alloc::boxed::Box;
+
+    #[test]
+    fn matcher_finds_overlapping_patterns() {
+        let matcher = build(&[b"he", b"she", b"his", b"hers"]).unwrap();
+        assert_eq!(matcher.scan(b"ushers"), [(4, 1), (6, 3)]);
+        assert_eq!(matcher.scan(b"hhis"), [(4, 2)]);
+    }
+
+    #[test]
+    fn matcher_rejects_bad_patterns() {
+        assert_eq!(build(&[b"ok", b""]), Err(MatcherError::EmptyPattern(1)));
+    }
+
+    #[test]
+    fn matcher_tables_drive_moore() {
+        let matcher = build(&[b"OK\r\n", b"ERROR"]).unwrap();
+        let classes: &'static [u8; 256] = Box::leak(Box::new(matcher.classes));
+        let transitions: &'static [(u8, u8, u8)] =
+            Box::leak(matcher.transitions.into_boxed_slice());
+        let outputs: &'static [Option<u8>] = Box::leak(matcher.outputs.into_boxed_slice());
+
+        let mut fsm = ByteClasses::new(Moore::new(0, transitions, outputs), classes);
+        let mut found = Vec::new();
+        for byte in b"xxERRORyOK\r\nOK" {
+            if let Some(pattern) = fsm.step(*byte).unwrap() {
+                found.push(pattern);
+            }
+        }
+        assert_eq!(found, [1, 0]);
+    }
+}
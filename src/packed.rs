@@ -0,0 +1,391 @@
+//! Packed tables executed from a byte blob
+//!
+//! A data-only encoding of a Mealy machine over `u16` input and output
+//! codes, suited to a dedicated flash section or an over-the-air update.
+//! The blob is validated once by `PackedTable::new`; stepping then reads
+//! it in place. `encode` (requires `alloc`) builds blobs from tables.
+//!
+//! Layout, multi-byte fields little-endian, varints LEB128:
+//!
+//! | Field | Size |
+//! |-------|------|
+//! | Magic `FSMP` | 4 |
+//! | Version, 1 | 1 |
+//! | State count - 1 | 1 |
+//! | Initial state | 1 |
+//! | Per state: offset of its row group from the end of this list | varint |
+//! | Per row group: row count | varint |
+//! | Per row: input `u16`, next state `u8`, output `u16` | 5 |
+//!
+//! Rows of a state keep their table order, so the first row for
+//! (state, input) wins as in `Mealy::step`.
+//!
+//! # Example
+//! ```
+//! use fsmall::packed::{PackedMachine, PackedTable};
+//!
+//! // Two states toggling on input 7, outputs 100 and 200
+//! static BLOB: [u8; 21] = [
+//!     b'F', b'S', b'M', b'P', 1, 1, 0,
+//!     0, 6,                // Row groups of states 0 and 1
+//!     1, 7, 0, 1, 100, 0,  // State 0: 7 -> 1 / 100
+//!     1, 7, 0, 0, 200, 0,  // State 1: 7 -> 0 / 200
+//! ];
+//!
+//! let mut fsm = PackedMachine::new(PackedTable::new(&BLOB).unwrap());
+//! assert_eq!(fsm.step(7), Ok(100));
+//! assert_eq!(fsm.step(7), Ok(200));
+//! assert!(fsm.step(8).is_err());
+//! ```
+
+use core::fmt;
+
+use crate::{Machine, StepError};
+
+const MAGIC: [u8; 4] = *b"FSMP";
+const VERSION: u8 = 1;
+const HEADER: usize = 7;
+const ROW: usize = 5;
+
+/// Error returned for a malformed blob or unencodable tables
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PackedError {
+    /// Blob does not start with `FSMP`
+    BadMagic,
+    /// Format version not supported
+    Version(u8),
+    /// Blob ends early, or an offset or count points past its end
+    Truncated,
+    /// Initial or next state at or above the state count
+    StateOutOfRange,
+    /// Transition row has no output row, see `encode`
+    MissingOutput {
+        /// Index into the transition table
+        row: usize,
+    },
+}
+
+impl fmt::Display for PackedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PackedError::BadMagic => f.write_str("not a packed table"),
+            PackedError::Version(v) => write!(f, "unsupported packed table version {v}"),
+            PackedError::Truncated => f.write_str("packed table truncated"),
+            PackedError::StateOutOfRange => f.write_str("state out of range"),
+            PackedError::MissingOutput { row } => {
+                write!(f, "transition row {row} has no output row")
+            }
+        }
+    }
+}
+
+impl core::error::Error for PackedError {}
+
+// LEB128 varint at `pos`: (value, next position)
+fn varint(blob: &[u8], mut pos: usize) -> Option<(usize, usize)> {
+    let mut value = 0usize;
+    for shift in (0..32).step_by(7) {
+        let byte = *blob.get(pos)?;
+        pos += 1;
+        value |= usize::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Some((value, pos));
+        }
+    }
+    None
+}
+
+/// Validated packed table borrowing its blob
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PackedTable<'a> {
+    blob: &'a [u8],
+    states: usize,
+    // Start of the row groups, offsets are relative to it
+    groups: usize,
+}
+
+impl<'a> PackedTable<'a> {
+    /// Validate `blob`
+    ///
+    /// Every offset, count and state is checked, so stepping never reads
+    /// outside the blob nor reaches an invalid state.
+    ///
+    /// # Errors
+    /// See `PackedError`
+    pub fn new(blob: &'a [u8]) -> Result<Self, PackedError> {
+        let header = blob.get(..HEADER).ok_or(PackedError::Truncated)?;
+        if header.get(..4) != Some(&MAGIC[..]) {
+            return Err(PackedError::BadMagic);
+        }
+        let (version, states, initial) = match *header {
+            [_, _, _, _, version, states, initial] => (version, usize::from(states) + 1, initial),
+            _ => return Err(PackedError::Truncated),
+        };
+        if version != VERSION {
+            return Err(PackedError::Version(version));
+        }
+        if usize::from(initial) >= states {
+            return Err(PackedError::StateOutOfRange);
+        }
+
+        let mut groups = HEADER;
+        for _ in 0..states {
+            groups = varint(blob, groups).ok_or(PackedError::Truncated)?.1;
+        }
+        let table = PackedTable {
+            blob,
+            states,
+            groups,
+        };
+        for state in 0..states {
+            let rows = table.rows(state).ok_or(PackedError::Truncated)?;
+            if rows
+                .chunks_exact(ROW)
+                .any(|row| row.get(2).is_none_or(|&next| usize::from(next) >= states))
+            {
+                return Err(PackedError::StateOutOfRange);
+            }
+        }
+        Ok(table)
+    }
+
+    // Row bytes of `state`, None if out of bounds
+    fn rows(&self, state: usize) -> Option<&'a [u8]> {
+        if state >= self.states {
+            return None;
+        }
+        let mut pos = HEADER;
+        let mut offset = 0;
+        for _ in 0..=state {
+            (offset, pos) = varint(self.blob, pos)?;
+        }
+        let (count, start) = varint(self.blob, self.groups.checked_add(offset)?)?;
+        self.blob
+            .get(start..start.checked_add(count.checked_mul(ROW)?)?)
+    }
+
+    /// Number of states
+    pub fn states(&self) -> usize {
+        self.states
+    }
+
+    /// Initial state
+    pub fn initial(&self) -> u8 {
+        self.blob.get(HEADER - 1).copied().unwrap_or(0)
+    }
+
+    /// Look up (state, input): (next_state, output)
+    pub fn lookup(&self, state: u8, input: u16) -> Option<(u8, u16)> {
+        self.rows(usize::from(state))?
+            .chunks_exact(ROW)
+            .find_map(|row| match *row {
+                [lo, hi, next, out_lo, out_hi] if u16::from_le_bytes([lo, hi]) == input => {
+                    Some((next, u16::from_le_bytes([out_lo, out_hi])))
+                }
+                _ => None,
+            })
+    }
+}
+
+/// Mealy machine stepping through a `PackedTable`
+pub struct PackedMachine<'a> {
+    table: PackedTable<'a>,
+    state: u8,
+}
+
+impl<'a> PackedMachine<'a> {
+    /// Create machine in the table's initial state
+    pub fn new(table: PackedTable<'a>) -> Self {
+        PackedMachine {
+            state: table.initial(),
+            table,
+        }
+    }
+
+    /// Process input code, returning the output code
+    ///
+    /// # Errors
+    /// * `StepError::NoTransition` - No row for (state, input)
+    /// * `StepError::InvalidState` - Current state not in the table, see `reset`
+    pub fn step(&mut self, input: u16) -> Result<u16, StepError> {
+        if usize::from(self.state) >= self.table.states {
+            return Err(StepError::InvalidState);
+        }
+        let (next, output) = self
+            .table
+            .lookup(self.state, input)
+            .ok_or(StepError::NoTransition)?;
+        self.state = next;
+        Ok(output)
+    }
+
+    /// Get current state
+    pub fn current_state(&self) -> u8 {
+        self.state
+    }
+
+    /// Get table
+    pub fn table(&self) -> &PackedTable<'a> {
+        &self.table
+    }
+
+    /// Jump to `state`
+    pub fn reset(&mut self, state: u8) {
+        self.state = state;
+    }
+}
+
+impl Machine for PackedMachine<'_> {
+    type Input = u16;
+    type Output = u16;
+    type Error = StepError;
+
+    fn step(&mut self, input: u16) -> Result<u16, StepError> {
+        PackedMachine::step(self, input)
+    }
+
+    fn current_state(&self) -> u8 {
+        PackedMachine::current_state(self)
+    }
+
+    fn reset(&mut self, state: u8) {
+        PackedMachine::reset(self, state)
+    }
+}
+
+/// Encode Mealy tables as a packed blob
+///
+/// Requires the `alloc` feature. The state count is one past the highest
+/// state the tables or `initial` mention.
+///
+/// # Arguments
+/// * `initial` - Initial state
+/// * `transitions` - Transition table: (from_state, input, to_state)
+/// * `outputs` - Output table: (state, input, output)
+/// * `input` - Code of an input
+/// * `output` - Code of an output
+///
+/// # Errors
+/// `PackedError::MissingOutput` if a transition row has no output row
+#[cfg(feature = "alloc")]
+pub fn encode<I: Copy + Eq, O: Copy>(
+    initial: u8,
+    transitions: &[(u8, I, u8)],
+    outputs: &[(u8, I, O)],
+    input: impl Fn(I) -> u16,
+    output: impl Fn(O) -> u16,
+) -> Result<alloc::vec::Vec<u8>, PackedError> {
+    use alloc::vec::Vec;
+
+    fn push_varint(out: &mut Vec<u8>, mut value: usize) {
+        while value >= 0x80 {
+            out.push((value & 0x7f) as u8 | 0x80);
+            value >>= 7;
+        }
+        out.push(value as u8);
+    }
+
+    let last = transitions
+        .iter()
+        .flat_map(|(from, _i, to)| [*from, *to])
+        .fold(initial, u8::max);
+    let mut offsets = Vec::new();
+    let mut groups = Vec::new();
+    for state in 0..=last {
+        offsets.push(groups.len());
+        let rows: Vec<_> = transitions
+            .iter()
+            .enumerate()
+            .filter(|(_row, (from, _i, _to))| *from == state)
+            .collect();
+        push_varint(&mut groups, rows.len());
+        for (row, (from, i, to)) in rows {
+            let out = outputs
+                .iter()
+                .find(|(s, oi, _o)| s == from && oi == i)
+                .ok_or(PackedError::MissingOutput { row })?
+                .2;
+            groups.extend_from_slice(&input(*i).to_le_bytes());
+            groups.push(*to);
+            groups.extend_from_slice(&output(out).to_le_bytes());
+        }
+    }
+
+    let mut blob = Vec::from(MAGIC);
+    blob.extend_from_slice(&[VERSION, last, initial]);
+    for offset in offsets {
+        push_varint(&mut blob, offset);
+    }
+    blob.extend_from_slice(&groups);
+    Ok(blob)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn packed_encoding_steps_like_mealy() {
+        use crate::Mealy;
+
+        // Enough rows in state 0 for multi-byte offsets
+        let mut transitions = alloc::vec::Vec::new();
+        let mut outputs = alloc::vec::Vec::new();
+        for input in 0..40u16 {
+            transitions.push((0, input, 1));
+            outputs.push((0, input, input * 3));
+        }
+        transitions.extend([(1, 5, 2), (2, 5, 0), (1, 5, 0)]);
+        outputs.extend([(1, 5, 1000), (2, 5, 2000)]);
+
+        let blob = encode(0, &transitions, &outputs, |i| i, |o| o).unwrap();
+        let mut packed = PackedMachine::new(PackedTable::new(&blob).unwrap());
+        let mut mealy = Mealy::new(0, &transitions, &outputs);
+        for input in [39, 5, 5, 7, 5, 6, 0] {
+            assert_eq!(packed.step(input), mealy.step(input));
+            assert_eq!(packed.current_state(), mealy.current_state());
+        }
+        assert_eq!(packed.table().states(), 3);
+
+        assert_eq!(
+            encode(0, &[(0, 1u16, 0)], &[(0, 2u16, 0u16)], |i| i, |o| o),
+            Err(PackedError::MissingOutput { row: 0 })
+        );
+    }
+
+    #[test]
+    fn packed_table_rejects_bad_blobs() {
+        let good = [b'F', b'S', b'M', b'P', 1, 0, 0, 0, 1, 9, 0, 0, 1, 0];
+        assert!(PackedTable::new(&good).is_ok());
+        assert_eq!(PackedTable::new(&good[..12]), Err(PackedError::Truncated));
+        assert_eq!(
+            PackedTable::new(b"FSMQ\x01\x00\x00"),
+            Err(PackedError::BadMagic)
+        );
+        assert_eq!(
+            PackedTable::new(b"FSMP\x02\x00\x00"),
+            Err(PackedError::Version(2))
+        );
+
+        let mut bad_next = good;
+        bad_next[11] = 1;
+        assert_eq!(
+            PackedTable::new(&bad_next),
+            Err(PackedError::StateOutOfRange)
+        );
+    }
+
+    #[test]
+    fn packed_machine_rejects_unknown_state() {
+        // Two states, state 1 steps to 0 on input 7 with output 100
+        let blob = [b'F', b'S', b'M', b'P', 1, 1, 0, 0, 1, 0, 1, 7, 0, 0, 100, 0];
+        let mut packed = PackedMachine::new(PackedTable::new(&blob).unwrap());
+        packed.reset(1);
+        assert_eq!(packed.step(7), Ok(100));
+        packed.reset(4);
+        assert_eq!(packed.table().lookup(4, 7), None);
+        assert_eq!(packed.step(7), Err(StepError::InvalidState));
+        assert_eq!(packed.current_state(), 4);
+    }
+}
@@ -0,0 +1,352 @@
+//! Drive machines from sampled digital input levels
+//!
+//! Any `FnMut() -> bool` is a pin. With the `embedded-hal` feature, an
+//! `InputPin` wrapped in `HalPin` is one too; with `embedded-hal-async`,
+//! `PinInputs::wait` sleeps until a pin's `digital::Wait` reports an edge
+//! instead of being polled.
+
+use crate::Machine;
+
+/// Source of a digital level
+pub trait SamplePin {
+    /// Sample the pin, `Some(true)` when high, `None` if it cannot be read
+    fn sample(&mut self) -> Option<bool>;
+}
+
+impl<F: FnMut() -> bool> SamplePin for F {
+    fn sample(&mut self) -> Option<bool> {
+        Some(self())
+    }
+}
+
+/// `embedded-hal` input pin usable by `PinInputs`
+///
+/// Requires the `embedded-hal` feature. A failed read keeps the pin's last
+/// level, so it never shows up as an edge.
+#[cfg(feature = "embedded-hal")]
+#[derive(Debug)]
+pub struct HalPin<P>(pub P);
+
+#[cfg(feature = "embedded-hal")]
+impl<P: embedded_hal::digital::InputPin> SamplePin for HalPin<P> {
+    fn sample(&mut self) -> Option<bool> {
+        self.0.is_high().ok()
+    }
+}
+
+/// Maps (pin index, new level) to a machine input, `None` to ignore
+pub type LevelMap<I> = fn(usize, bool) -> Option<I>;
+
+/// Adapter turning level changes on `N` pins into machine inputs
+///
+/// Levels are sampled once at construction so the first `poll` only
+/// reports real changes; a pin unreadable then starts out low. Pins of
+/// different types can be mixed as `&mut dyn FnMut() -> bool`.
+///
+/// # Example
+/// ```
+/// use core::cell::Cell;
+/// use fsmall::{pins::PinInputs, Mealy};
+///
+/// #[derive(Copy, Clone, Eq, PartialEq)]
+/// enum Input { Press, Release }
+///
+/// static TRANSITIONS: [(u8, Input, u8); 2] = [(0, Input::Press, 1), (1, Input::Release, 0)];
+/// static OUTPUTS: [(u8, Input, &str); 2] = [(0, Input::Press, "on"), (1, Input::Release, "off")];
+///
+/// fn map(_pin: usize, high: bool) -> Option<Input> {
+///     Some(if high { Input::Press } else { Input::Release })
+/// }
+///
+/// let button = Cell::new(false);
+/// let mut inputs = PinInputs::new(Mealy::new(0, &TRANSITIONS, &OUTPUTS), [|| button.get()], map);
+///
+/// button.set(true);
+/// let mut seen = None;
+/// assert_eq!(inputs.poll(|r| seen = r.ok()), 1);
+/// assert_eq!(seen, Some("on"));
+/// assert_eq!(inputs.poll(|_| {}), 0); // No change
+/// ```
+pub struct PinInputs<M: Machine, P, const N: usize> {
+    machine: M,
+    pins: [P; N],
+    // Last sampled level of each pin
+    levels: [bool; N],
+    map: LevelMap<M::Input>,
+}
+
+impl<M: Machine, P: SamplePin, const N: usize> PinInputs<M, P, N> {
+    /// Create adapter, sampling the initial level of every pin
+    ///
+    /// # Arguments
+    /// * `machine` - Machine receiving the mapped inputs
+    /// * `pins` - Pins to sample
+    /// * `map` - Mapping from (pin index, new level) to input
+    pub fn new(machine: M, mut pins: [P; N], map: LevelMap<M::Input>) -> Self {
+        let levels = pins.each_mut().map(|pin| pin.sample().unwrap_or(false));
+        PinInputs {
+            machine,
+            pins,
+            levels,
+            map,
+        }
+    }
+
+    /// Sample all pins, stepping the machine for every level change
+    ///
+    /// Pins are handled in index order. Returns the number of inputs stepped.
+    pub fn poll(&mut self, mut on_result: impl FnMut(Result<M::Output, M::Error>)) -> usize {
+        let mut count = 0;
+        let pins = self.pins.iter_mut().zip(self.levels.iter_mut());
+        for (index, (pin, last)) in pins.enumerate() {
+            let Some(level) = pin.sample() else {
+                continue;
+            };
+            if level == *last {
+                continue;
+            }
+            *last = level;
+            if let Some(input) = (self.map)(index, level) {
+                on_result(self.machine.step(input));
+                count += 1;
+            }
+        }
+        count
+    }
+
+    /// Last sampled level of each pin
+    pub fn levels(&self) -> &[bool; N] {
+        &self.levels
+    }
+
+    /// Get wrapped machine
+    pub fn machine(&self) -> &M {
+        &self.machine
+    }
+
+    /// Get wrapped machine mutably
+    pub fn machine_mut(&mut self) -> &mut M {
+        &mut self.machine
+    }
+
+    /// Consume adapter, returning the machine and pins
+    pub fn into_parts(self) -> (M, [P; N]) {
+        (self.machine, self.pins)
+    }
+}
+
+#[cfg(feature = "embedded-hal-async")]
+impl<M, P, const N: usize> PinInputs<M, HalPin<P>, N>
+where
+    M: Machine,
+    P: embedded_hal::digital::InputPin + embedded_hal_async::digital::Wait,
+{
+    /// Wait for level changes, then step the machine as `poll` does
+    ///
+    /// Requires the `embedded-hal-async` feature. Returns once at least one
+    /// change mapped to an input, with the number of inputs stepped. A wait
+    /// error wakes the adapter like an edge; the pins are sampled either way.
+    pub async fn wait(&mut self, mut on_result: impl FnMut(Result<M::Output, M::Error>)) -> usize {
+        use core::future::Future;
+        use core::pin::Pin;
+        use core::task::Poll;
+
+        loop {
+            let count = self.poll(&mut on_result);
+            if count > 0 {
+                return count;
+            }
+
+            let mut edges = self.pins.each_mut().map(|pin| pin.0.wait_for_any_edge());
+            core::future::poll_fn(|cx| {
+                // Poll every pin, so each registers its waker
+                let mut ready = false;
+                for edge in edges.iter_mut() {
+                    // SAFETY: `edges` stays in this frame and is not moved
+                    // until dropped after the `poll_fn` completes.
+                    let edge = unsafe { Pin::new_unchecked(edge) };
+                    ready |= edge.poll(cx).is_ready();
+                }
+                if ready {
+                    Poll::Ready(())
+                } else {
+                    Poll::Pending
+                }
+            })
+            .await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Moore, StepError};
+    use core::cell::Cell;
+
+    #[derive(Copy, Clone, Eq, PartialEq, Debug)]
+    enum TestInput {
+        Up,
+        Down,
+    }
+
+    static TRANS: [(u8, TestInput, u8); 2] = [(0, TestInput::Up, 1), (1, TestInput::Down, 0)];
+
+    static OUTS: [u8; 2] = [10, 20];
+
+    // Pin 0 rising → Up, pin 1 rising → Down, falling edges ignored
+    fn map(pin: usize, high: bool) -> Option<TestInput> {
+        match (pin, high) {
+            (0, true) => Some(TestInput::Up),
+            (1, true) => Some(TestInput::Down),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn pins_step_on_changes_only() {
+        let a = Cell::new(true); // High at start: no event
+        let b = Cell::new(false);
+        let pins: [&dyn Fn() -> bool; 2] = [&|| a.get(), &|| b.get()];
+        let mut inputs = PinInputs::new(Moore::new(0, &TRANS, &OUTS), pins, map);
+
+        assert_eq!(inputs.poll(|_| {}), 0);
+        a.set(false);
+        assert_eq!(inputs.poll(|_| {}), 0); // Falling edge ignored
+        a.set(true);
+        let mut last = None;
+        assert_eq!(inputs.poll(|r| last = Some(r)), 1);
+        assert_eq!(last, Some(Ok(20)));
+        assert_eq!(inputs.levels(), &[true, false]);
+    }
+
+    #[test]
+    fn pins_report_step_errors() {
+        let b = Cell::new(false);
+        let pins: [&dyn Fn() -> bool; 2] = [&|| false, &|| b.get()];
+        let mut inputs = PinInputs::new(Moore::new(0, &TRANS, &OUTS), pins, map);
+        b.set(true);
+        let mut last = None;
+        inputs.poll(|r| last = Some(r));
+        assert_eq!(last, Some(Err(StepError::NoTransition)));
+        assert_eq!(inputs.machine().current_state(), 0);
+    }
+
+    #[cfg(feature = "embedded-hal")]
+    mod hal {
+        use super::*;
+        #[cfg(feature = "embedded-hal-async")]
+        use core::convert::Infallible;
+        use embedded_hal::digital::{ErrorType, InputPin};
+
+        // Pin whose level and read failure are set by the test
+        pub struct FakePin<'a>(pub &'a Cell<Option<bool>>);
+
+        #[derive(Debug)]
+        pub struct ReadError;
+
+        impl embedded_hal::digital::Error for ReadError {
+            fn kind(&self) -> embedded_hal::digital::ErrorKind {
+                embedded_hal::digital::ErrorKind::Other
+            }
+        }
+
+        impl ErrorType for FakePin<'_> {
+            type Error = ReadError;
+        }
+
+        impl InputPin for FakePin<'_> {
+            fn is_high(&mut self) -> Result<bool, ReadError> {
+                self.0.get().ok_or(ReadError)
+            }
+
+            fn is_low(&mut self) -> Result<bool, ReadError> {
+                self.is_high().map(|high| !high)
+            }
+        }
+
+        // Pin toggling its level on every wait, as if an edge just occurred
+        #[cfg(feature = "embedded-hal-async")]
+        pub struct TogglingPin(pub bool);
+
+        #[cfg(feature = "embedded-hal-async")]
+        impl ErrorType for TogglingPin {
+            type Error = Infallible;
+        }
+
+        #[cfg(feature = "embedded-hal-async")]
+        impl InputPin for TogglingPin {
+            fn is_high(&mut self) -> Result<bool, Infallible> {
+                Ok(self.0)
+            }
+
+            fn is_low(&mut self) -> Result<bool, Infallible> {
+                Ok(!self.0)
+            }
+        }
+
+        #[cfg(feature = "embedded-hal-async")]
+        impl embedded_hal_async::digital::Wait for TogglingPin {
+            async fn wait_for_high(&mut self) -> Result<(), Infallible> {
+                self.0 = true;
+                Ok(())
+            }
+
+            async fn wait_for_low(&mut self) -> Result<(), Infallible> {
+                self.0 = false;
+                Ok(())
+            }
+
+            async fn wait_for_rising_edge(&mut self) -> Result<(), Infallible> {
+                self.0 = true;
+                Ok(())
+            }
+
+            async fn wait_for_falling_edge(&mut self) -> Result<(), Infallible> {
+                self.0 = false;
+                Ok(())
+            }
+
+            async fn wait_for_any_edge(&mut self) -> Result<(), Infallible> {
+                self.0 = !self.0;
+                Ok(())
+            }
+        }
+
+        #[test]
+        fn pins_sample_hal_pins() {
+            let level = Cell::new(Some(false));
+            let pins = [HalPin(FakePin(&level))];
+            let mut inputs = PinInputs::new(Moore::new(0, &TRANS, &OUTS), pins, map);
+            level.set(None);
+            assert_eq!(inputs.poll(|_| {}), 0); // Read error, level kept
+            level.set(Some(true));
+            let mut last = None;
+            assert_eq!(inputs.poll(|r| last = Some(r)), 1);
+            assert_eq!(last, Some(Ok(20)));
+        }
+
+        #[cfg(feature = "embedded-hal-async")]
+        #[test]
+        fn pins_wait_for_edges() {
+            use core::future::Future;
+            use core::pin::pin;
+            use core::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+            const NOOP: RawWakerVTable = RawWakerVTable::new(|_| RAW, |_| {}, |_| {}, |_| {});
+            const RAW: RawWaker = RawWaker::new(core::ptr::null(), &NOOP);
+            // SAFETY: every vtable function ignores the null data pointer
+            let waker = unsafe { Waker::from_raw(RAW) };
+
+            let pins = [HalPin(TogglingPin(false)), HalPin(TogglingPin(false))];
+            let mut inputs = PinInputs::new(Moore::new(0, &TRANS, &OUTS), pins, map);
+            let mut last = None;
+            let count = pin!(inputs.wait(|r| last = Some(r)))
+                .as_mut()
+                .poll(&mut Context::from_waker(&waker));
+            // Both pins rose: Up, then Down
+            assert_eq!(count, Poll::Ready(2));
+            assert_eq!(last, Some(Ok(10)));
+        }
+    }
+}
@@ -0,0 +1,21 @@
+//! Ready-made machines and table builders for common jobs
+//!
+//! Each preset lives in its own module and builds on the regular `Mealy`
+//! and `Moore` machines, so everything else in the crate (names, traces,
+//! adapters, analysis) works with them unchanged.
+
+pub mod ansi;
+pub mod charger;
+pub mod combo;
+pub mod elevator;
+pub mod json;
+pub mod modbus_rtu;
+pub mod motor;
+pub mod mqtt;
+pub mod nmea;
+pub mod sequencer;
+pub mod stuffing;
+pub mod tcp;
+pub mod utf8;
+pub mod vending;
+pub mod wizard;
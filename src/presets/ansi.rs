@@ -0,0 +1,411 @@
+//! VT/ANSI escape sequence parser
+//!
+//! Follows the DEC-compatible parser state chart by Paul Williams for the
+//! 7-bit sequences: plain text, C0 controls, `ESC` sequences, CSI
+//! sequences with parameters and OSC strings (DCS is not covered). Byte
+//! ranges are matched through byte classes, and the per-state table is
+//! generated at compile time, so "anywhere" rules such as `ESC` and
+//! `CAN`/`SUB` appear once in the generator rather than in every state.
+//!
+//! The table yields a small action per byte; `AnsiParser` applies it,
+//! collecting parameters and reporting whole sequences.
+
+use core::convert::Infallible;
+
+use crate::{ByteClasses, Machine, Mealy};
+
+/// Printing text
+pub const GROUND: u8 = 0;
+/// After `ESC`
+pub const ESCAPE: u8 = 1;
+/// `ESC` followed by intermediate bytes
+pub const ESCAPE_INTERMEDIATE: u8 = 2;
+/// After `ESC [`
+pub const CSI_ENTRY: u8 = 3;
+/// Reading CSI parameters
+pub const CSI_PARAM: u8 = 4;
+/// CSI intermediate bytes
+pub const CSI_INTERMEDIATE: u8 = 5;
+/// Malformed CSI, skipped up to its final byte
+pub const CSI_IGNORE: u8 = 6;
+/// Inside an OSC string, after `ESC ]`
+pub const OSC_STRING: u8 = 7;
+
+/// Number of states
+pub const STATE_COUNT: usize = 8;
+
+/// Most CSI parameters kept, further ones are dropped
+pub const MAX_PARAMS: usize = 16;
+
+// Byte classes
+const C_CONTROL: u8 = 0;
+const C_ABORT: u8 = 1;
+const C_ESC: u8 = 2;
+const C_INTERMEDIATE: u8 = 3;
+const C_DIGIT: u8 = 4;
+const C_SEMICOLON: u8 = 5;
+// `:` and the private markers `<=>?`
+const C_PRIVATE: u8 = 6;
+const C_FINAL: u8 = 7;
+const C_LBRACKET: u8 = 8;
+const C_RBRACKET: u8 = 9;
+const C_DEL: u8 = 10;
+const C_BEL: u8 = 11;
+const C_HIGH: u8 = 12;
+const C_BACKSLASH: u8 = 13;
+
+/// Number of byte classes
+pub const CLASS_COUNT: usize = 14;
+
+/// Byte class table: `CLASSES[byte]` = class
+pub static CLASSES: [u8; 256] = {
+    let mut classes = [C_HIGH; 256];
+    let mut b = 0;
+    while b < 0x80 {
+        classes[b] = match b as u8 {
+            0x07 => C_BEL,
+            0x18 | 0x1A => C_ABORT,
+            0x1B => C_ESC,
+            0x00..=0x1F => C_CONTROL,
+            0x20..=0x2F => C_INTERMEDIATE,
+            0x30..=0x39 => C_DIGIT,
+            b';' => C_SEMICOLON,
+            b':' | 0x3C..=0x3F => C_PRIVATE,
+            b'[' => C_LBRACKET,
+            b']' => C_RBRACKET,
+            b'\\' => C_BACKSLASH,
+            0x7F => C_DEL,
+            _ => C_FINAL,
+        };
+        b += 1;
+    }
+    classes
+};
+
+// Action applied by `AnsiParser` to the byte that caused a transition
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Op {
+    Ignore,
+    Print,
+    Execute,
+    Collect,
+    Param,
+    EscDispatch,
+    CsiDispatch,
+    OscPut,
+}
+
+const fn is_final(class: u8) -> bool {
+    matches!(class, C_FINAL | C_LBRACKET | C_RBRACKET | C_BACKSLASH)
+}
+
+const fn is_control(class: u8) -> bool {
+    matches!(class, C_CONTROL | C_BEL)
+}
+
+// (next state, action) for a byte of `class` in `state`
+const fn rule(state: u8, class: u8) -> (u8, Op) {
+    // Anywhere
+    if class == C_ESC {
+        return (ESCAPE, Op::Ignore);
+    }
+    if class == C_ABORT {
+        return (GROUND, Op::Execute);
+    }
+    if class == C_DEL {
+        return (state, Op::Ignore);
+    }
+    match state {
+        GROUND if is_control(class) => (GROUND, Op::Execute),
+        GROUND => (GROUND, Op::Print),
+        OSC_STRING if class == C_BEL => (GROUND, Op::Ignore),
+        OSC_STRING if class == C_CONTROL => (OSC_STRING, Op::Ignore),
+        OSC_STRING => (OSC_STRING, Op::OscPut),
+        _ if is_control(class) => (state, Op::Execute),
+        ESCAPE => match class {
+            C_INTERMEDIATE => (ESCAPE_INTERMEDIATE, Op::Collect),
+            C_LBRACKET => (CSI_ENTRY, Op::Ignore),
+            C_RBRACKET => (OSC_STRING, Op::Ignore),
+            // String terminator `ESC \`, its OSC already ended at `ESC`
+            C_BACKSLASH | C_HIGH => (GROUND, Op::Ignore),
+            _ => (GROUND, Op::EscDispatch),
+        },
+        ESCAPE_INTERMEDIATE => match class {
+            C_INTERMEDIATE => (ESCAPE_INTERMEDIATE, Op::Collect),
+            C_HIGH => (GROUND, Op::Ignore),
+            _ => (GROUND, Op::EscDispatch),
+        },
+        _ if is_final(class) && state == CSI_IGNORE => (GROUND, Op::Ignore),
+        _ if is_final(class) => (GROUND, Op::CsiDispatch),
+        CSI_ENTRY => match class {
+            C_INTERMEDIATE => (CSI_INTERMEDIATE, Op::Collect),
+            C_PRIVATE => (CSI_PARAM, Op::Collect),
+            C_DIGIT | C_SEMICOLON => (CSI_PARAM, Op::Param),
+            _ => (CSI_IGNORE, Op::Ignore),
+        },
+        CSI_PARAM => match class {
+            C_INTERMEDIATE => (CSI_INTERMEDIATE, Op::Collect),
+            C_DIGIT | C_SEMICOLON => (CSI_PARAM, Op::Param),
+            _ => (CSI_IGNORE, Op::Ignore),
+        },
+        CSI_INTERMEDIATE => match class {
+            C_INTERMEDIATE => (CSI_INTERMEDIATE, Op::Collect),
+            _ => (CSI_IGNORE, Op::Ignore),
+        },
+        _ => (CSI_IGNORE, Op::Ignore),
+    }
+}
+
+/// Transition table over classes: (from_state, class, to_state), complete
+pub static TRANSITIONS: [(u8, u8, u8); STATE_COUNT * CLASS_COUNT] = {
+    let mut rows = [(0, 0, 0); STATE_COUNT * CLASS_COUNT];
+    let mut i = 0;
+    while i < rows.len() {
+        let (state, class) = ((i / CLASS_COUNT) as u8, (i % CLASS_COUNT) as u8);
+        rows[i] = (state, class, rule(state, class).0);
+        i += 1;
+    }
+    rows
+};
+
+fn op(state: u8, class: u8) -> Op {
+    rule(state, class).1
+}
+
+/// CSI sequence: `ESC [ private? params intermediate? final`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Csi {
+    params: [u16; MAX_PARAMS],
+    len: u8,
+    /// Private marker such as `?`
+    pub private: Option<u8>,
+    /// Last intermediate byte
+    pub intermediate: Option<u8>,
+    /// Final byte selecting the function, e.g. `m` for SGR
+    pub final_byte: u8,
+}
+
+impl Csi {
+    /// Get parameters; empty ones are 0
+    pub fn params(&self) -> &[u16] {
+        self.params.get(..self.len as usize).unwrap_or(&[])
+    }
+}
+
+/// Parser output
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AnsiEvent {
+    /// Printable byte, including bytes of UTF-8 text
+    Print(u8),
+    /// C0 control such as `\n` or BEL
+    Execute(u8),
+    /// `ESC intermediate? final`
+    Esc {
+        /// Last intermediate byte
+        intermediate: Option<u8>,
+        /// Final byte
+        final_byte: u8,
+    },
+    /// Complete CSI sequence
+    Csi(Csi),
+    /// OSC string started
+    OscStart,
+    /// Byte of the OSC string
+    OscByte(u8),
+    /// OSC string ended by BEL, `ESC` or an abort
+    OscEnd,
+}
+
+/// Escape sequence parser
+///
+/// # Example
+/// ```
+/// use fsmall::presets::ansi::{AnsiEvent, AnsiParser};
+///
+/// let mut parser = AnsiParser::new();
+/// let mut csi = None;
+/// for byte in b"\x1b[1;31m" {
+///     if let Some(AnsiEvent::Csi(seq)) = parser.step(*byte) {
+///         csi = Some(seq);
+///     }
+/// }
+/// let csi = csi.unwrap();
+/// assert_eq!(csi.final_byte, b'm');
+/// assert_eq!(csi.params(), [1, 31]);
+/// ```
+pub struct AnsiParser {
+    machine: ByteClasses<Mealy<'static, u8, Op>>,
+    csi: Csi,
+}
+
+impl Default for AnsiParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AnsiParser {
+    /// Create parser in `GROUND`
+    pub fn new() -> Self {
+        AnsiParser {
+            machine: ByteClasses::new(Mealy::from_output_fn(GROUND, &TRANSITIONS, op), &CLASSES),
+            csi: Csi {
+                params: [0; MAX_PARAMS],
+                len: 0,
+                private: None,
+                intermediate: None,
+                final_byte: 0,
+            },
+        }
+    }
+
+    /// Feed one byte, return event if any
+    pub fn step(&mut self, byte: u8) -> Option<AnsiEvent> {
+        let from = self.machine.current_state();
+        let op = self.machine.step(byte).unwrap_or(Op::Ignore);
+        let to = self.machine.current_state();
+
+        // Entry and exit actions
+        if matches!(to, ESCAPE | CSI_ENTRY) && to != from {
+            self.clear();
+        }
+        match (from == OSC_STRING, to == OSC_STRING) {
+            (true, false) => return Some(AnsiEvent::OscEnd),
+            (false, true) => return Some(AnsiEvent::OscStart),
+            _ => {}
+        }
+
+        match op {
+            Op::Ignore => None,
+            Op::Print => Some(AnsiEvent::Print(byte)),
+            Op::Execute => Some(AnsiEvent::Execute(byte)),
+            Op::OscPut => Some(AnsiEvent::OscByte(byte)),
+            Op::Collect => {
+                match byte {
+                    0x20..=0x2F => self.csi.intermediate = Some(byte),
+                    _ => self.csi.private = Some(byte),
+                }
+                None
+            }
+            Op::Param => {
+                self.param(byte);
+                None
+            }
+            Op::EscDispatch => Some(AnsiEvent::Esc {
+                intermediate: self.csi.intermediate,
+                final_byte: byte,
+            }),
+            Op::CsiDispatch => {
+                self.csi.final_byte = byte;
+                Some(AnsiEvent::Csi(self.csi))
+            }
+        }
+    }
+
+    // Add a digit to the last parameter, or start the next one on `;`
+    fn param(&mut self, byte: u8) {
+        if self.csi.len == 0 {
+            self.csi.len = 1;
+        }
+        if byte == b';' {
+            if (self.csi.len as usize) < MAX_PARAMS {
+                self.csi.len += 1;
+            }
+            return;
+        }
+        if let Some(value) = self.csi.params.get_mut(self.csi.len as usize - 1) {
+            *value = value
+                .saturating_mul(10)
+                .saturating_add(u16::from(byte - b'0'));
+        }
+    }
+
+    fn clear(&mut self) {
+        self.csi.params = [0; MAX_PARAMS];
+        self.csi.len = 0;
+        self.csi.private = None;
+        self.csi.intermediate = None;
+    }
+
+    /// Get current state
+    pub fn current_state(&self) -> u8 {
+        self.machine.current_state()
+    }
+
+    /// Return to `GROUND`, dropping any partial sequence
+    pub fn reset(&mut self) {
+        self.machine.reset(GROUND);
+        self.clear();
+    }
+}
+
+impl Machine for AnsiParser {
+    type Input = u8;
+    type Output = Option<AnsiEvent>;
+    type Error = Infallible;
+
+    fn step(&mut self, byte: u8) -> Result<Option<AnsiEvent>, Infallible> {
+        Ok(AnsiParser::step(self, byte))
+    }
+
+    fn current_state(&self) -> u8 {
+        AnsiParser::current_state(self)
+    }
+
+    /// Always returns to `GROUND`, partial sequences cannot be resumed
+    fn reset(&mut self, _state: u8) {
+        AnsiParser::reset(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn last_event(parser: &mut AnsiParser, bytes: &[u8]) -> Option<AnsiEvent> {
+        bytes.iter().filter_map(|b| parser.step(*b)).last()
+    }
+
+    #[test]
+    fn ansi_csi_private_and_empty_params() {
+        let mut parser = AnsiParser::new();
+        let Some(AnsiEvent::Csi(csi)) = last_event(&mut parser, b"\x1b[?25l") else {
+            panic!("no CSI");
+        };
+        assert_eq!(
+            (csi.private, csi.params(), csi.final_byte),
+            (Some(b'?'), &[25][..], b'l')
+        );
+        let Some(AnsiEvent::Csi(csi)) = last_event(&mut parser, b"\x1b[;5H") else {
+            panic!("no CSI");
+        };
+        assert_eq!(csi.params(), [0, 5]);
+        assert_eq!(parser.step(b'x'), Some(AnsiEvent::Print(b'x')));
+    }
+
+    #[test]
+    fn ansi_osc_and_aborts() {
+        let mut parser = AnsiParser::new();
+        assert_eq!(last_event(&mut parser, b"\x1b]"), Some(AnsiEvent::OscStart));
+        assert_eq!(parser.step(b'0'), Some(AnsiEvent::OscByte(b'0')));
+        assert_eq!(parser.step(0x07), Some(AnsiEvent::OscEnd));
+        assert_eq!(
+            last_event(&mut parser, b"\x1b]2;t\x1b\\"),
+            Some(AnsiEvent::OscEnd)
+        );
+        assert_eq!(parser.current_state(), GROUND);
+        // CAN aborts a CSI sequence
+        assert_eq!(
+            last_event(&mut parser, b"\x1b[12\x18"),
+            Some(AnsiEvent::Execute(0x18))
+        );
+        assert_eq!(parser.step(b'm'), Some(AnsiEvent::Print(b'm')));
+        assert_eq!(
+            last_event(&mut parser, b"\x1b(B"),
+            Some(AnsiEvent::Esc {
+                intermediate: Some(b'('),
+                final_byte: b'B'
+            })
+        );
+    }
+}
@@ -0,0 +1,273 @@
+//! Battery charging controller
+//!
+//! Stages are pre-charge, constant current (CC) and constant voltage (CV).
+//! Voltage and current thresholds are guards over the latest reading, and
+//! each stage has a time limit whose timeout moves to `FAULT`.
+//!
+//! A reading that crosses no threshold matches no row. The default
+//! `ErrorPolicy::Reject` leaves such a step without effect, so the stage
+//! timer keeps running; a self-transition would restart it.
+
+use crate::{Guard, Moore, Tick};
+
+/// Not charging
+pub const IDLE: u8 = 0;
+/// Trickle charging a deeply discharged battery
+pub const PRECHARGE: u8 = 1;
+/// Charging at constant current
+pub const CONSTANT_CURRENT: u8 = 2;
+/// Charging at constant voltage while the current tapers off
+pub const CONSTANT_VOLTAGE: u8 = 3;
+/// Charged
+pub const DONE: u8 = 4;
+/// Over-voltage or stage time limit exceeded, charging stopped
+pub const FAULT: u8 = 5;
+
+/// State names, indexed by state
+pub static STATE_NAMES: [&str; 6] = ["idle", "precharge", "cc", "cv", "done", "fault"];
+
+/// Charging mode to drive the power stage with, one per state
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ChargeMode {
+    /// Output off
+    Off,
+    /// Small pre-charge current
+    Precharge,
+    /// Regulate current
+    ConstantCurrent,
+    /// Regulate voltage
+    ConstantVoltage,
+    /// Output off, battery full
+    Done,
+    /// Output off until the fault is cleared
+    Fault,
+}
+
+/// Charging thresholds
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Limits {
+    /// Leave pre-charge above this voltage
+    pub precharge_mv: u16,
+    /// Switch from CC to CV at this voltage
+    pub cv_mv: u16,
+    /// Done once the CV current drops below this
+    pub taper_ma: u16,
+    /// Fault above this voltage
+    pub max_mv: u16,
+}
+
+/// Guard context: thresholds and the latest reading
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Battery {
+    limits: Limits,
+    voltage_mv: u16,
+    current_ma: u16,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Input {
+    Start,
+    Stop,
+    Sample,
+    Timeout,
+    Clear,
+}
+
+fn over_voltage(b: &Battery) -> bool {
+    b.voltage_mv > b.limits.max_mv
+}
+
+fn precharged(b: &Battery) -> bool {
+    b.voltage_mv >= b.limits.precharge_mv
+}
+
+fn at_cv(b: &Battery) -> bool {
+    b.voltage_mv >= b.limits.cv_mv
+}
+
+fn tapered(b: &Battery) -> bool {
+    b.current_ma < b.limits.taper_ma
+}
+
+static TRANSITIONS: [(u8, Input, u8); 16] = [
+    (IDLE, Input::Start, PRECHARGE),
+    (DONE, Input::Start, PRECHARGE),
+    (PRECHARGE, Input::Sample, FAULT),
+    (PRECHARGE, Input::Sample, CONSTANT_CURRENT),
+    (CONSTANT_CURRENT, Input::Sample, FAULT),
+    (CONSTANT_CURRENT, Input::Sample, CONSTANT_VOLTAGE),
+    (CONSTANT_VOLTAGE, Input::Sample, FAULT),
+    (CONSTANT_VOLTAGE, Input::Sample, DONE),
+    (PRECHARGE, Input::Timeout, FAULT),
+    (CONSTANT_CURRENT, Input::Timeout, FAULT),
+    (CONSTANT_VOLTAGE, Input::Timeout, FAULT),
+    (PRECHARGE, Input::Stop, IDLE),
+    (CONSTANT_CURRENT, Input::Stop, IDLE),
+    (CONSTANT_VOLTAGE, Input::Stop, IDLE),
+    (FAULT, Input::Clear, IDLE),
+    (DONE, Input::Stop, IDLE),
+];
+
+// Over-voltage rows come first so they win over progress
+static GUARDS: [Option<Guard<Battery>>; 8] = [
+    None,
+    None,
+    Some(over_voltage),
+    Some(precharged),
+    Some(over_voltage),
+    Some(at_cv),
+    Some(over_voltage),
+    Some(tapered),
+];
+
+static OUTPUTS: [ChargeMode; 6] = [
+    ChargeMode::Off,
+    ChargeMode::Precharge,
+    ChargeMode::ConstantCurrent,
+    ChargeMode::ConstantVoltage,
+    ChargeMode::Done,
+    ChargeMode::Fault,
+];
+
+/// Build stage time limit table for `Charger::new`
+///
+/// # Arguments
+/// * `precharge` - Ticks allowed in pre-charge
+/// * `cc` - Ticks allowed in constant current
+/// * `cv` - Ticks allowed in constant voltage
+pub const fn timeouts(precharge: u32, cc: u32, cv: u32) -> [(u8, u32); 3] {
+    [
+        (PRECHARGE, precharge),
+        (CONSTANT_CURRENT, cc),
+        (CONSTANT_VOLTAGE, cv),
+    ]
+}
+
+/// Charging controller owning its latest reading
+///
+/// Every method returns the new mode if the state changed.
+///
+/// # Example
+/// ```
+/// use fsmall::presets::charger::{self, ChargeMode, Charger, Limits};
+///
+/// static TIMEOUTS: [(u8, u32); 3] = charger::timeouts(600, 7200, 3600);
+///
+/// let limits = Limits { precharge_mv: 3000, cv_mv: 4200, taper_ma: 50, max_mv: 4250 };
+/// let mut charger = Charger::new(limits, &TIMEOUTS);
+/// assert_eq!(charger.start(), Some(ChargeMode::Precharge));
+/// assert_eq!(charger.sample(3100, 100), Some(ChargeMode::ConstantCurrent));
+/// assert_eq!(charger.sample(3900, 1000), None);
+/// assert_eq!(charger.sample(4200, 800), Some(ChargeMode::ConstantVoltage));
+/// assert_eq!(charger.sample(4200, 40), Some(ChargeMode::Done));
+/// ```
+pub struct Charger<'t> {
+    machine: Moore<'t, Input, ChargeMode, Battery>,
+    battery: Battery,
+}
+
+impl<'t> Charger<'t> {
+    /// Create idle charger
+    ///
+    /// # Arguments
+    /// * `limits` - Charging thresholds
+    /// * `timeouts` - Stage time limits, usually from `timeouts()`
+    pub fn new(limits: Limits, timeouts: &'t [(u8, u32)]) -> Self {
+        Charger {
+            machine: Moore::new(IDLE, &TRANSITIONS, &OUTPUTS)
+                .with_context()
+                .with_guards(&GUARDS)
+                .with_state_names(&STATE_NAMES)
+                .with_timeouts(timeouts, &Input::Timeout),
+            battery: Battery {
+                limits,
+                voltage_mv: 0,
+                current_ma: 0,
+            },
+        }
+    }
+
+    fn input(&mut self, input: Input) -> Option<ChargeMode> {
+        self.machine.step_with(input, &self.battery).ok()
+    }
+
+    /// Start charging from `IDLE` or `DONE`
+    pub fn start(&mut self) -> Option<ChargeMode> {
+        self.input(Input::Start)
+    }
+
+    /// Stop charging, back to `IDLE`
+    pub fn stop(&mut self) -> Option<ChargeMode> {
+        self.input(Input::Stop)
+    }
+
+    /// Leave `FAULT` for `IDLE`
+    pub fn clear_fault(&mut self) -> Option<ChargeMode> {
+        self.input(Input::Clear)
+    }
+
+    /// Feed a battery reading, checking the thresholds of the current stage
+    pub fn sample(&mut self, voltage_mv: u16, current_ma: u16) -> Option<ChargeMode> {
+        self.battery.voltage_mv = voltage_mv;
+        self.battery.current_ma = current_ma;
+        self.input(Input::Sample)
+    }
+
+    /// Advance time by one tick, faulting once a stage overruns its limit
+    pub fn tick(&mut self) -> Option<ChargeMode> {
+        match self.machine.tick() {
+            Tick::Timeout(Ok(mode)) => Some(mode),
+            _ => None,
+        }
+    }
+
+    /// Get current mode
+    pub fn mode(&self) -> ChargeMode {
+        self.machine.current_output().unwrap_or(ChargeMode::Fault)
+    }
+
+    /// Get current state
+    pub fn current_state(&self) -> u8 {
+        self.machine.current_state()
+    }
+
+    /// Get current state's name
+    pub fn state_name(&self) -> Option<&'t str> {
+        self.machine.state_name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static TIMEOUTS: [(u8, u32); 3] = timeouts(3, 10, 10);
+
+    const LIMITS: Limits = Limits {
+        precharge_mv: 3000,
+        cv_mv: 4200,
+        taper_ma: 50,
+        max_mv: 4250,
+    };
+
+    #[test]
+    fn charger_stage_timeout_survives_samples() {
+        let mut charger = Charger::new(LIMITS, &TIMEOUTS);
+        charger.start();
+        assert_eq!(charger.tick(), None);
+        assert_eq!(charger.sample(2500, 100), None);
+        assert_eq!(charger.tick(), None);
+        assert_eq!(charger.tick(), Some(ChargeMode::Fault));
+        assert_eq!(charger.start(), None);
+        assert_eq!(charger.clear_fault(), Some(ChargeMode::Off));
+    }
+
+    #[test]
+    fn charger_over_voltage_wins() {
+        let mut charger = Charger::new(LIMITS, &TIMEOUTS);
+        charger.start();
+        charger.sample(3500, 1000);
+        assert_eq!(charger.sample(4300, 1000), Some(ChargeMode::Fault));
+        assert_eq!(charger.state_name(), Some("fault"));
+    }
+}
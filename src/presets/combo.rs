@@ -0,0 +1,137 @@
+//! Input sequence (combo) detectors
+//!
+//! The table is the KMP string-matching automaton of the target sequence:
+//! state `k` means the last `k` inputs match the start of the sequence.
+//! Only edges into states other than 0 are stored, at most `2 * N` rows;
+//! every other input falls back to state 0 through `ErrorPolicy::GotoState`.
+
+use crate::{ErrorPolicy, Mealy};
+
+/// Detector tables for a sequence of `N` inputs, with room for `R` rows
+///
+/// `R = 2 * N` always suffices.
+///
+/// # Example
+/// ```
+/// use fsmall::presets::combo::Combo;
+///
+/// #[derive(Copy, Clone, Eq, PartialEq)]
+/// enum Key { Up, Down, Left, Right, B, A }
+/// use Key::*;
+///
+/// let konami: Combo<Key, 10, 20> = Combo::new([Up, Up, Down, Down, Left, Right, Left, Right, B, A]);
+/// let mut fsm = konami.machine();
+/// let mut hits = 0;
+/// for key in [Up, Up, Up, Down, Down, Left, Right, Left, Right, B, A] {
+///     let _ = fsm.step(key); // Off-sequence keys restart the match
+///     hits += fsm.is_accepting() as u32;
+/// }
+/// assert_eq!(hits, 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Combo<I, const N: usize, const R: usize> {
+    // Table: (from_state, input, to_state), [0..len) occupied
+    transitions: [(u8, I, u8); R],
+    len: usize,
+    // State reached when the whole sequence was seen
+    accepting: [u8; 1],
+}
+
+impl<I: Copy + Eq, const N: usize, const R: usize> Combo<I, N, R> {
+    /// Build the detector table for `sequence`
+    ///
+    /// # Panics
+    /// If `N` is 0 or above 254, or `R` is below `2 * N`.
+    pub fn new(sequence: [I; N]) -> Self {
+        const { assert!(N > 0 && N < 255, "a combo has 1 to 254 inputs") };
+        const { assert!(R >= 2 * N, "a combo of N inputs needs room for 2 * N rows") };
+
+        // fail[k] = length of the longest proper border of sequence[..k]
+        let mut fail = [0; N];
+        for k in 1..N {
+            let mut border = fail[k - 1];
+            while border > 0 && sequence[k] != sequence[border] {
+                border = fail[border - 1];
+            }
+            fail[k] = border + usize::from(sequence[k] == sequence[border]);
+        }
+        // Fallback from state `k`: the border of the k inputs seen
+        let fallback = |k: usize| if k == 0 { 0 } else { fail[k - 1] };
+
+        let mut combo = Combo {
+            transitions: [(0, sequence[0], 0); R],
+            len: 0,
+            accepting: [N as u8],
+        };
+        for state in 0..=N {
+            for (k, input) in sequence.iter().enumerate() {
+                // Each distinct input once
+                if sequence[..k].contains(input) {
+                    continue;
+                }
+                let mut at = state;
+                let to = loop {
+                    if at < N && sequence[at] == *input {
+                        break at + 1;
+                    }
+                    if at == 0 {
+                        break 0;
+                    }
+                    at = fallback(at);
+                };
+                if to != 0 {
+                    combo.transitions[combo.len] = (state as u8, *input, to as u8);
+                    combo.len += 1;
+                }
+            }
+        }
+        combo
+    }
+
+    /// Create a detector starting with nothing matched
+    ///
+    /// After each `step`, `is_accepting()` is true if the inputs so far end
+    /// with the full sequence. Overlapping occurrences are all detected.
+    pub fn machine(&self) -> Mealy<'_, I, ()> {
+        Mealy::from_output_fn(0, &self.transitions[..self.len], |_state, _input| ())
+            .with_accepting(&self.accepting)
+            .with_error_policy(ErrorPolicy::GotoState(0))
+    }
+
+    /// Number of rows in the generated table
+    pub fn rows(&self) -> usize {
+        self.len
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches<const N: usize, const R: usize>(combo: &Combo<u8, N, R>, input: &[u8]) -> usize {
+        let mut fsm = combo.machine();
+        input
+            .iter()
+            .filter(|byte| {
+                let _ = fsm.step(**byte);
+                fsm.is_accepting()
+            })
+            .count()
+    }
+
+    #[test]
+    fn combo_falls_back_like_kmp() {
+        let combo: Combo<u8, 5, 10> = Combo::new(*b"abcab");
+        assert_eq!(matches(&combo, b"abcabcab"), 2); // Overlapping
+        assert_eq!(matches(&combo, b"ababcabx"), 1);
+        assert_eq!(matches(&combo, b"abcxab"), 0);
+        assert!(combo.rows() <= 10);
+    }
+
+    #[test]
+    fn combo_repeated_input() {
+        let combo: Combo<u8, 3, 6> = Combo::new(*b"aaa");
+        assert_eq!(matches(&combo, b"aaaaa"), 3);
+        assert_eq!(matches(&combo, b"aabaaa"), 1);
+    }
+}
@@ -0,0 +1,293 @@
+//! Elevator controller built from a door machine and a motion machine
+//!
+//! The door (`DOOR_CLOSED`, `DOOR_OPEN`) closes by itself after a dwell
+//! time, the motion machine (`STOPPED`, `MOVING_UP`, `MOVING_DOWN`) reports
+//! each floor passed with a timeout. `Elevator` owns both, plus one pending
+//! request flag per floor, and decides between them on every tick: the car
+//! only moves with the door closed, and keeps its direction while requests
+//! remain ahead of it.
+
+use crate::{Moore, Tick};
+
+/// Door closed
+pub const DOOR_CLOSED: u8 = 0;
+/// Door open
+pub const DOOR_OPEN: u8 = 1;
+
+/// Car standing at a floor
+pub const STOPPED: u8 = 0;
+/// Car moving up
+pub const MOVING_UP: u8 = 1;
+/// Car moving down
+pub const MOVING_DOWN: u8 = 2;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum DoorInput {
+    Open,
+}
+
+// Opening an open door restarts the dwell time
+static DOOR_TRANSITIONS: [(u8, DoorInput, u8); 2] = [
+    (DOOR_CLOSED, DoorInput::Open, DOOR_OPEN),
+    (DOOR_OPEN, DoorInput::Open, DOOR_OPEN),
+];
+
+static DOOR_OUTPUTS: [bool; 2] = [false, true];
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum MotionInput {
+    Up,
+    Down,
+    Floor,
+    Stop,
+}
+
+static MOTION_TRANSITIONS: [(u8, MotionInput, u8); 6] = [
+    (STOPPED, MotionInput::Up, MOVING_UP),
+    (STOPPED, MotionInput::Down, MOVING_DOWN),
+    (MOVING_UP, MotionInput::Floor, MOVING_UP),
+    (MOVING_DOWN, MotionInput::Floor, MOVING_DOWN),
+    (MOVING_UP, MotionInput::Stop, STOPPED),
+    (MOVING_DOWN, MotionInput::Stop, STOPPED),
+];
+
+/// Car motion, output of the motion machine
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Motion {
+    /// Standing at a floor
+    Stopped,
+    /// Moving up
+    Up,
+    /// Moving down
+    Down,
+}
+
+static MOTION_OUTPUTS: [Motion; 3] = [Motion::Stopped, Motion::Up, Motion::Down];
+
+/// Something the elevator did on a tick
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Event {
+    /// Door opened at a floor where the car was standing
+    DoorOpened(u8),
+    /// Door closed at the given floor
+    DoorClosed(u8),
+    /// Car started moving
+    Departed(Motion),
+    /// Car passed the given floor without stopping
+    Passed(u8),
+    /// Car stopped at a requested floor and opened the door
+    Arrived(u8),
+}
+
+/// Door and travel timing, borrowed by `Elevator`
+///
+/// Usable in a `static`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Timing {
+    // Table: (door_state, dwell, next_state)
+    dwell: [(u8, u32, u8); 1],
+    // Table: (motion_state, ticks per floor)
+    travel: [(u8, u32); 2],
+}
+
+impl Timing {
+    /// Create timing
+    ///
+    /// # Arguments
+    /// * `dwell` - Ticks the door stays open
+    /// * `travel` - Ticks to move one floor
+    pub const fn new(dwell: u32, travel: u32) -> Self {
+        Timing {
+            dwell: [(DOOR_OPEN, dwell, DOOR_CLOSED)],
+            travel: [(MOVING_UP, travel), (MOVING_DOWN, travel)],
+        }
+    }
+}
+
+/// Elevator serving `F` floors, numbered from 0
+///
+/// # Example
+/// ```
+/// use fsmall::presets::elevator::{Elevator, Event, Motion, Timing};
+///
+/// static TIMING: Timing = Timing::new(3, 2);
+///
+/// let mut car: Elevator<'_, 4> = Elevator::new(&TIMING);
+/// assert!(car.request(2));
+/// assert_eq!(car.tick(), Some(Event::Departed(Motion::Up)));
+/// let arrived = (0..10).find_map(|_| match car.tick() {
+///     Some(Event::Arrived(floor)) => Some(floor),
+///     _ => None,
+/// });
+/// assert_eq!(arrived, Some(2));
+/// assert!(car.door_open());
+/// ```
+pub struct Elevator<'t, const F: usize> {
+    door: Moore<'t, DoorInput, bool>,
+    motion: Moore<'t, MotionInput, Motion>,
+    floor: u8,
+    // Direction kept while requests remain ahead
+    heading: Motion,
+    requests: [bool; F],
+}
+
+impl<'t, const F: usize> Elevator<'t, F> {
+    /// Create elevator standing at floor 0 with the door closed
+    pub fn new(timing: &'t Timing) -> Self {
+        const { assert!(F > 0 && F <= 256, "an elevator serves 1 to 256 floors") };
+        Elevator {
+            door: Moore::new(DOOR_CLOSED, &DOOR_TRANSITIONS, &DOOR_OUTPUTS)
+                .with_timed(&timing.dwell),
+            motion: Moore::new(STOPPED, &MOTION_TRANSITIONS, &MOTION_OUTPUTS)
+                .with_timeouts(&timing.travel, &MotionInput::Floor),
+            floor: 0,
+            heading: Motion::Up,
+            requests: [false; F],
+        }
+    }
+
+    /// Request a stop at `floor`, return `false` if there is no such floor
+    pub fn request(&mut self, floor: u8) -> bool {
+        match self.requests.get_mut(floor as usize) {
+            Some(pending) => {
+                *pending = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Advance time by one tick, moving the car and door
+    pub fn tick(&mut self) -> Option<Event> {
+        if let Tick::Timed(DOOR_CLOSED) = self.door.tick() {
+            return Some(Event::DoorClosed(self.floor));
+        }
+        if self.door_open() {
+            // A request for this floor holds the door open
+            return self.serve().then_some(Event::DoorOpened(self.floor));
+        }
+
+        if let Tick::Timeout(Ok(motion)) = self.motion.tick() {
+            self.floor = match motion {
+                Motion::Up => self.floor.saturating_add(1),
+                _ => self.floor.saturating_sub(1),
+            };
+            if !self.serve() {
+                return Some(Event::Passed(self.floor));
+            }
+            let _ = self.motion.step(MotionInput::Stop);
+            return Some(Event::Arrived(self.floor));
+        }
+
+        if self.motion() != Motion::Stopped {
+            return None;
+        }
+        if self.serve() {
+            return Some(Event::DoorOpened(self.floor));
+        }
+        let (above, below) = (self.pending_above(), self.pending_below());
+        self.heading = match self.heading {
+            Motion::Up if above => Motion::Up,
+            _ if below => Motion::Down,
+            _ if above => Motion::Up,
+            _ => return None,
+        };
+        let input = match self.heading {
+            Motion::Up => MotionInput::Up,
+            _ => MotionInput::Down,
+        };
+        self.motion.step(input).ok().map(Event::Departed)
+    }
+
+    // Clear the current floor's request and open the door if it was pending
+    fn serve(&mut self) -> bool {
+        match self.requests.get_mut(self.floor as usize) {
+            Some(pending) if *pending => {
+                *pending = false;
+                let _ = self.door.step(DoorInput::Open);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn pending_above(&self) -> bool {
+        let above = self.requests.get(self.floor as usize + 1..).unwrap_or(&[]);
+        above.contains(&true)
+    }
+
+    fn pending_below(&self) -> bool {
+        let below = self.requests.get(..self.floor as usize).unwrap_or(&[]);
+        below.contains(&true)
+    }
+
+    /// Get floor the car is at or last passed
+    pub fn floor(&self) -> u8 {
+        self.floor
+    }
+
+    /// Check whether a stop at `floor` is pending
+    pub fn is_requested(&self, floor: u8) -> bool {
+        self.requests.get(floor as usize).copied().unwrap_or(false)
+    }
+
+    /// Check whether the door is open
+    pub fn door_open(&self) -> bool {
+        self.door.current_state() == DOOR_OPEN
+    }
+
+    /// Get current motion
+    pub fn motion(&self) -> Motion {
+        self.motion.current_output().unwrap_or(Motion::Stopped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static TIMING: Timing = Timing::new(2, 1);
+
+    fn run<const F: usize>(car: &mut Elevator<'_, F>, ticks: usize, events: &mut [Option<Event>]) {
+        let mut n = 0;
+        for _ in 0..ticks {
+            if let (Some(event), Some(slot)) = (car.tick(), events.get_mut(n)) {
+                *slot = Some(event);
+                n += 1;
+            }
+        }
+    }
+
+    #[test]
+    fn elevator_keeps_heading() {
+        let mut car: Elevator<'_, 4> = Elevator::new(&TIMING);
+        car.request(2);
+        car.tick();
+        car.tick();
+        assert_eq!(car.floor(), 1);
+        car.request(0);
+        let mut events = [None; 6];
+        run(&mut car, 12, &mut events);
+        assert_eq!(
+            events,
+            [
+                Some(Event::Arrived(2)),
+                Some(Event::DoorClosed(2)),
+                Some(Event::Departed(Motion::Down)),
+                Some(Event::Passed(1)),
+                Some(Event::Arrived(0)),
+                Some(Event::DoorClosed(0)),
+            ]
+        );
+        assert!(!car.is_requested(0));
+    }
+
+    #[test]
+    fn elevator_rejects_unknown_floor() {
+        let mut car: Elevator<'_, 2> = Elevator::new(&TIMING);
+        assert!(!car.request(2));
+        assert!(car.request(0));
+        assert_eq!(car.tick(), Some(Event::DoorOpened(0)));
+        assert_eq!(car.motion(), Motion::Stopped);
+    }
+}
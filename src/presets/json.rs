@@ -0,0 +1,344 @@
+//! JSON tokenizer tables over byte classes
+//!
+//! Recognizes the tokens of RFC 8259: structural characters, strings with
+//! escapes, numbers, the three literals and whitespace. Grammar (matching
+//! brackets, commas between values) is left to the parser on top. String
+//! contents are not checked for valid UTF-8.
+//!
+//! Bytes are first mapped to one of 30 classes (`CLASSES`), and the
+//! 36-state Moore machine is written over classes; the transition table is
+//! generated at compile time.
+
+use crate::lexer::Lexer;
+use crate::{ByteClasses, Moore};
+
+/// Token kind, output of accepting states
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum JsonToken {
+    /// `{`
+    BeginObject,
+    /// `}`
+    EndObject,
+    /// `[`
+    BeginArray,
+    /// `]`
+    EndArray,
+    /// `:`
+    Colon,
+    /// `,`
+    Comma,
+    /// String, quotes included
+    String,
+    /// Number
+    Number,
+    /// `true`
+    True,
+    /// `false`
+    False,
+    /// `null`
+    Null,
+    /// Run of whitespace
+    Whitespace,
+}
+
+// Byte classes
+const C_OTHER: u8 = 0;
+const C_SPACE: u8 = 1;
+const C_LBRACE: u8 = 2;
+const C_RBRACE: u8 = 3;
+const C_LBRACKET: u8 = 4;
+const C_RBRACKET: u8 = 5;
+const C_COLON: u8 = 6;
+const C_COMMA: u8 = 7;
+const C_QUOTE: u8 = 8;
+const C_BACKSLASH: u8 = 9;
+const C_MINUS: u8 = 10;
+const C_PLUS: u8 = 11;
+const C_ZERO: u8 = 12;
+const C_DIGIT: u8 = 13;
+const C_DOT: u8 = 14;
+const C_LOWER_E: u8 = 15;
+const C_UPPER_E: u8 = 16;
+const C_T: u8 = 17;
+const C_R: u8 = 18;
+const C_U: u8 = 19;
+const C_F: u8 = 20;
+const C_A: u8 = 21;
+const C_L: u8 = 22;
+const C_S: u8 = 23;
+const C_N: u8 = 24;
+// Hex digits without a class of their own: c, d, A-D, F
+const C_HEX: u8 = 25;
+const C_CTRL: u8 = 26;
+// Tab, line feed, carriage return: whitespace, but not allowed in strings
+const C_WS: u8 = 27;
+const C_SLASH: u8 = 28;
+const C_B: u8 = 29;
+
+/// Number of byte classes
+pub const CLASS_COUNT: usize = 30;
+
+/// Byte class table: `CLASSES[byte]` = class
+pub static CLASSES: [u8; 256] = {
+    let mut classes = [C_OTHER; 256];
+    let mut b = 0;
+    while b < 0x20 {
+        classes[b] = C_CTRL;
+        b += 1;
+    }
+    let mut b = b'1';
+    while b <= b'9' {
+        classes[b as usize] = C_DIGIT;
+        b += 1;
+    }
+    let single = [
+        (b' ', C_SPACE),
+        (b'\t', C_WS),
+        (b'\n', C_WS),
+        (b'\r', C_WS),
+        (b'{', C_LBRACE),
+        (b'}', C_RBRACE),
+        (b'[', C_LBRACKET),
+        (b']', C_RBRACKET),
+        (b':', C_COLON),
+        (b',', C_COMMA),
+        (b'"', C_QUOTE),
+        (b'\\', C_BACKSLASH),
+        (b'/', C_SLASH),
+        (b'-', C_MINUS),
+        (b'+', C_PLUS),
+        (b'0', C_ZERO),
+        (b'.', C_DOT),
+        (b'e', C_LOWER_E),
+        (b'E', C_UPPER_E),
+        (b't', C_T),
+        (b'r', C_R),
+        (b'u', C_U),
+        (b'f', C_F),
+        (b'a', C_A),
+        (b'l', C_L),
+        (b's', C_S),
+        (b'n', C_N),
+        (b'b', C_B),
+        (b'c', C_HEX),
+        (b'd', C_HEX),
+        (b'A', C_HEX),
+        (b'B', C_HEX),
+        (b'C', C_HEX),
+        (b'D', C_HEX),
+        (b'F', C_HEX),
+    ];
+    let mut i = 0;
+    while i < single.len() {
+        classes[single[i].0 as usize] = single[i].1;
+        i += 1;
+    }
+    classes
+};
+
+// States
+const START: u8 = 0;
+const WS: u8 = 1;
+const BEGIN_OBJECT: u8 = 2;
+const END_OBJECT: u8 = 3;
+const BEGIN_ARRAY: u8 = 4;
+const END_ARRAY: u8 = 5;
+const COLON: u8 = 6;
+const COMMA: u8 = 7;
+const STR: u8 = 8;
+const STR_ESC: u8 = 9;
+// Inside `\u`, hex digits still expected: 4, 3, 2, 1
+const HEX_4: u8 = 10;
+const HEX_1: u8 = 13;
+const STR_END: u8 = 14;
+const MINUS: u8 = 15;
+const ZERO: u8 = 16;
+const INT: u8 = 17;
+const DOT: u8 = 18;
+const FRAC: u8 = 19;
+const EXP: u8 = 20;
+const EXP_SIGN: u8 = 21;
+const EXP_DIGITS: u8 = 22;
+// Literal prefixes, each word spelled one state per letter
+const TRUE_1: u8 = 23;
+const FALSE_1: u8 = 27;
+const NULL_1: u8 = 32;
+
+/// Number of states
+pub const STATE_COUNT: usize = 36;
+
+const HEX_DIGITS: [u8; 8] = [C_ZERO, C_DIGIT, C_A, C_B, C_HEX, C_LOWER_E, C_UPPER_E, C_F];
+const DIGITS: [u8; 2] = [C_ZERO, C_DIGIT];
+const EXPONENT: [u8; 2] = [C_LOWER_E, C_UPPER_E];
+
+// Append (from, each class, to) rows, return the new row count
+const fn push(rows: &mut [(u8, u8, u8)], n: usize, from: u8, classes: &[u8], to: u8) -> usize {
+    let mut i = 0;
+    while i < classes.len() {
+        if n + i < rows.len() {
+            rows[n + i] = (from, classes[i], to);
+        }
+        i += 1;
+    }
+    n + classes.len()
+}
+
+// Write every transition into `rows` as far as it fits, return the count
+const fn transitions(rows: &mut [(u8, u8, u8)]) -> usize {
+    let mut n = 0;
+    n = push(rows, n, START, &[C_SPACE, C_WS], WS);
+    n = push(rows, n, WS, &[C_SPACE, C_WS], WS);
+    n = push(rows, n, START, &[C_LBRACE], BEGIN_OBJECT);
+    n = push(rows, n, START, &[C_RBRACE], END_OBJECT);
+    n = push(rows, n, START, &[C_LBRACKET], BEGIN_ARRAY);
+    n = push(rows, n, START, &[C_RBRACKET], END_ARRAY);
+    n = push(rows, n, START, &[C_COLON], COLON);
+    n = push(rows, n, START, &[C_COMMA], COMMA);
+
+    // Strings: raw control characters are not allowed
+    n = push(rows, n, START, &[C_QUOTE], STR);
+    let mut class = 0;
+    while class < CLASS_COUNT as u8 {
+        n = match class {
+            C_QUOTE => push(rows, n, STR, &[class], STR_END),
+            C_BACKSLASH => push(rows, n, STR, &[class], STR_ESC),
+            C_CTRL | C_WS => n,
+            _ => push(rows, n, STR, &[class], STR),
+        };
+        class += 1;
+    }
+    let escapes = [C_QUOTE, C_BACKSLASH, C_SLASH, C_B, C_F, C_N, C_R, C_T];
+    n = push(rows, n, STR_ESC, &escapes, STR);
+    n = push(rows, n, STR_ESC, &[C_U], HEX_4);
+    let mut state = HEX_4;
+    while state < HEX_1 {
+        n = push(rows, n, state, &HEX_DIGITS, state + 1);
+        state += 1;
+    }
+    n = push(rows, n, HEX_1, &HEX_DIGITS, STR);
+
+    // Numbers: -?(0|[1-9][0-9]*)(\.[0-9]+)?([eE][+-]?[0-9]+)?
+    n = push(rows, n, START, &[C_MINUS], MINUS);
+    n = push(rows, n, START, &[C_ZERO], ZERO);
+    n = push(rows, n, START, &[C_DIGIT], INT);
+    n = push(rows, n, MINUS, &[C_ZERO], ZERO);
+    n = push(rows, n, MINUS, &[C_DIGIT], INT);
+    n = push(rows, n, INT, &DIGITS, INT);
+    n = push(rows, n, ZERO, &[C_DOT], DOT);
+    n = push(rows, n, INT, &[C_DOT], DOT);
+    n = push(rows, n, DOT, &DIGITS, FRAC);
+    n = push(rows, n, FRAC, &DIGITS, FRAC);
+    n = push(rows, n, ZERO, &EXPONENT, EXP);
+    n = push(rows, n, INT, &EXPONENT, EXP);
+    n = push(rows, n, FRAC, &EXPONENT, EXP);
+    n = push(rows, n, EXP, &[C_PLUS, C_MINUS], EXP_SIGN);
+    n = push(rows, n, EXP, &DIGITS, EXP_DIGITS);
+    n = push(rows, n, EXP_SIGN, &DIGITS, EXP_DIGITS);
+    n = push(rows, n, EXP_DIGITS, &DIGITS, EXP_DIGITS);
+
+    // Literals
+    n = word(rows, n, C_T, &[C_R, C_U, C_LOWER_E], TRUE_1);
+    n = word(rows, n, C_F, &[C_A, C_L, C_S, C_LOWER_E], FALSE_1);
+    word(rows, n, C_N, &[C_U, C_L, C_L], NULL_1)
+}
+
+// Spell a literal from START through consecutive states starting at `first`
+const fn word(rows: &mut [(u8, u8, u8)], n: usize, head: u8, tail: &[u8], first: u8) -> usize {
+    let mut n = push(rows, n, START, &[head], first);
+    let mut i = 0;
+    while i < tail.len() {
+        n = push(rows, n, first + i as u8, &[tail[i]], first + i as u8 + 1);
+        i += 1;
+    }
+    n
+}
+
+/// Number of transition rows
+pub const ROW_COUNT: usize = transitions(&mut []);
+
+/// Transition table over classes: (from_state, class, to_state)
+pub static TRANSITIONS: [(u8, u8, u8); ROW_COUNT] = {
+    let mut rows = [(0, 0, 0); ROW_COUNT];
+    transitions(&mut rows);
+    rows
+};
+
+/// Output array: index=state, value=token accepted in that state
+pub static OUTPUTS: [Option<JsonToken>; STATE_COUNT] = {
+    let mut outputs = [None; STATE_COUNT];
+    outputs[WS as usize] = Some(JsonToken::Whitespace);
+    outputs[BEGIN_OBJECT as usize] = Some(JsonToken::BeginObject);
+    outputs[END_OBJECT as usize] = Some(JsonToken::EndObject);
+    outputs[BEGIN_ARRAY as usize] = Some(JsonToken::BeginArray);
+    outputs[END_ARRAY as usize] = Some(JsonToken::EndArray);
+    outputs[COLON as usize] = Some(JsonToken::Colon);
+    outputs[COMMA as usize] = Some(JsonToken::Comma);
+    outputs[STR_END as usize] = Some(JsonToken::String);
+    outputs[ZERO as usize] = Some(JsonToken::Number);
+    outputs[INT as usize] = Some(JsonToken::Number);
+    outputs[FRAC as usize] = Some(JsonToken::Number);
+    outputs[EXP_DIGITS as usize] = Some(JsonToken::Number);
+    outputs[TRUE_1 as usize + 3] = Some(JsonToken::True);
+    outputs[FALSE_1 as usize + 4] = Some(JsonToken::False);
+    outputs[NULL_1 as usize + 3] = Some(JsonToken::Null);
+    outputs
+};
+
+/// JSON token machine: byte classes in front of the Moore machine
+pub type JsonMachine = ByteClasses<Moore<'static, u8, Option<JsonToken>>>;
+
+/// Create token machine in its start state
+pub fn machine() -> JsonMachine {
+    ByteClasses::new(Moore::new(START, &TRANSITIONS, &OUTPUTS), &CLASSES)
+}
+
+/// Tokenize `input`
+///
+/// # Example
+/// ```
+/// use fsmall::presets::json::{self, JsonToken};
+///
+/// let mut tokens = json::lexer(br#"{"id": -1.5e3}"#).map(|t| t.map(|(kind, _range)| kind));
+/// assert_eq!(tokens.next(), Some(Ok(JsonToken::BeginObject)));
+/// assert_eq!(tokens.next(), Some(Ok(JsonToken::String)));
+/// assert_eq!(tokens.next(), Some(Ok(JsonToken::Colon)));
+/// assert_eq!(tokens.next(), Some(Ok(JsonToken::Whitespace)));
+/// assert_eq!(tokens.next(), Some(Ok(JsonToken::Number)));
+/// assert_eq!(tokens.next(), Some(Ok(JsonToken::EndObject)));
+/// assert_eq!(tokens.next(), None);
+/// ```
+pub fn lexer(input: &[u8]) -> Lexer<'_, JsonMachine> {
+    Lexer::new(machine(), input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis;
+    use crate::lexer::LexError;
+
+    #[test]
+    fn json_tokens_with_ranges() {
+        let input = b"[true,null,\"a\xC3\xA9\\n\",0.5]";
+        let mut tokens = lexer(input);
+        assert_eq!(tokens.next(), Some(Ok((JsonToken::BeginArray, 0..1))));
+        assert_eq!(tokens.next(), Some(Ok((JsonToken::True, 1..5))));
+        tokens.next();
+        assert_eq!(tokens.next(), Some(Ok((JsonToken::Null, 6..10))));
+        tokens.next();
+        assert_eq!(tokens.next(), Some(Ok((JsonToken::String, 11..18))));
+        tokens.next();
+        assert_eq!(tokens.next(), Some(Ok((JsonToken::Number, 19..22))));
+        assert_eq!(tokens.next(), Some(Ok((JsonToken::EndArray, 22..23))));
+    }
+
+    #[test]
+    fn json_rejects_bad_tokens() {
+        let mut tokens = lexer(br#""\x""#);
+        assert_eq!(tokens.next(), Some(Err(LexError { position: 0 })));
+        let mut tokens = lexer(b"1.");
+        assert_eq!(tokens.next(), Some(Ok((JsonToken::Number, 0..1))));
+        assert_eq!(tokens.next(), Some(Err(LexError { position: 1 })));
+        assert_eq!(analysis::states(&TRANSITIONS).len(), STATE_COUNT);
+    }
+}
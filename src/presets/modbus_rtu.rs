@@ -0,0 +1,291 @@
+//! Modbus RTU frame receiver
+//!
+//! RTU frames are `ADDRESS FUNCTION DATA[..] CRC_LO CRC_HI` and end with a
+//! silence of at least 3.5 character times, reported as
+//! `RtuInput::Silence` by a timer or counted with `tick()`. Since the CRC
+//! is only known once the frame ends, data bytes are reported two bytes
+//! late, so nothing needs buffering.
+
+use core::convert::Infallible;
+
+use crate::Machine;
+
+/// Waiting for the address byte
+pub const IDLE: u8 = 0;
+/// Waiting for the function code
+pub const FUNCTION: u8 = 1;
+/// Receiving data and CRC bytes
+pub const DATA: u8 = 2;
+/// Ignoring bytes until the next silence
+pub const DISCARD: u8 = 3;
+
+/// Largest RTU frame, including address and CRC
+pub const MAX_FRAME: usize = 256;
+
+/// Input of the receiver
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RtuInput {
+    /// Byte received from the line
+    Byte(u8),
+    /// Line idle for at least 3.5 character times
+    Silence,
+}
+
+/// Event reported by `ModbusRtu::step`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RtuEvent {
+    /// Frame begins, addressed to the given unit (0 is broadcast)
+    Address(u8),
+    /// Function code of the frame
+    Function(u8),
+    /// Data byte
+    Data(u8),
+    /// Frame ended and its CRC matched
+    FrameOk,
+    /// Frame too short, too long or with a bad CRC; drop its data
+    FrameError,
+}
+
+/// Compute the Modbus CRC-16 of `bytes`, sent low byte first
+pub fn crc16(bytes: &[u8]) -> u16 {
+    bytes.iter().fold(0xFFFF, |crc, b| crc16_update(crc, *b))
+}
+
+fn crc16_update(crc: u16, byte: u8) -> u16 {
+    let mut crc = crc ^ u16::from(byte);
+    for _ in 0..8 {
+        crc = if crc & 1 != 0 {
+            (crc >> 1) ^ 0xA001
+        } else {
+            crc >> 1
+        };
+    }
+    crc
+}
+
+/// Modbus RTU receiver state machine
+///
+/// States are `IDLE`, `FUNCTION`, `DATA` and `DISCARD`. The line is assumed
+/// quiet at construction.
+///
+/// # Example
+/// ```
+/// use fsmall::presets::modbus_rtu::{ModbusRtu, RtuEvent, RtuInput};
+///
+/// let mut rtu = ModbusRtu::new().with_address(1);
+/// // Read holding registers 0..10 from unit 1
+/// let frame = [0x01, 0x03, 0x00, 0x00, 0x00, 0x0A, 0xC5, 0xCD];
+/// let mut data = 0;
+/// for byte in frame {
+///     if let Some(RtuEvent::Data(_)) = rtu.step(RtuInput::Byte(byte)) {
+///         data += 1;
+///     }
+/// }
+/// assert_eq!(rtu.step(RtuInput::Silence), Some(RtuEvent::FrameOk));
+/// assert_eq!(data, 4);
+/// ```
+pub struct ModbusRtu {
+    state: u8,
+    // Unit address to accept besides broadcast, `None` accepts all
+    address: Option<u8>,
+    crc: u16,
+    // Last two bytes, not yet known to be data rather than CRC
+    window: [u8; 2],
+    held: u8,
+    received: usize,
+    // Ticks since the last byte and ticks that count as silence
+    ticks: u32,
+    silence_ticks: u32,
+}
+
+impl Default for ModbusRtu {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ModbusRtu {
+    /// Create receiver in `IDLE`, accepting frames for every unit
+    pub fn new() -> Self {
+        ModbusRtu {
+            state: IDLE,
+            address: None,
+            crc: 0xFFFF,
+            window: [0; 2],
+            held: 0,
+            received: 0,
+            ticks: 0,
+            silence_ticks: 0,
+        }
+    }
+
+    /// Only report frames for unit `address` and broadcasts
+    pub fn with_address(mut self, address: u8) -> Self {
+        self.address = Some(address);
+        self
+    }
+
+    /// Let `tick()` detect silence after `ticks` ticks without a byte
+    pub fn with_silence_ticks(mut self, ticks: u32) -> Self {
+        self.silence_ticks = ticks;
+        self
+    }
+
+    /// Feed one byte or silence, return event if any
+    pub fn step(&mut self, input: RtuInput) -> Option<RtuEvent> {
+        let byte = match input {
+            RtuInput::Byte(byte) => byte,
+            RtuInput::Silence => return self.end(),
+        };
+        self.ticks = 0;
+        self.received += 1;
+        if self.received > MAX_FRAME && self.state != DISCARD {
+            self.state = DISCARD;
+            return Some(RtuEvent::FrameError);
+        }
+
+        match self.state {
+            IDLE => {
+                if self.address.is_some_and(|a| byte != a && byte != 0) {
+                    self.state = DISCARD;
+                    return None;
+                }
+                self.crc = crc16_update(0xFFFF, byte);
+                self.state = FUNCTION;
+                Some(RtuEvent::Address(byte))
+            }
+            FUNCTION => {
+                self.crc = crc16_update(self.crc, byte);
+                self.held = 0;
+                self.state = DATA;
+                Some(RtuEvent::Function(byte))
+            }
+            DATA => {
+                let [oldest, newest] = self.window;
+                self.window = [newest, byte];
+                if self.held < 2 {
+                    self.held += 1;
+                    return None;
+                }
+                self.crc = crc16_update(self.crc, oldest);
+                Some(RtuEvent::Data(oldest))
+            }
+            _ => None,
+        }
+    }
+
+    /// Advance time by one tick, ending the frame after the configured
+    /// silence
+    pub fn tick(&mut self) -> Option<RtuEvent> {
+        if self.silence_ticks == 0 {
+            return None;
+        }
+        self.ticks = self.ticks.saturating_add(1);
+        if self.ticks == self.silence_ticks {
+            return self.end();
+        }
+        None
+    }
+
+    fn end(&mut self) -> Option<RtuEvent> {
+        let state = self.state;
+        self.state = IDLE;
+        self.received = 0;
+        match state {
+            FUNCTION => Some(RtuEvent::FrameError),
+            DATA if self.held < 2 => Some(RtuEvent::FrameError),
+            DATA if u16::from_le_bytes(self.window) == self.crc => Some(RtuEvent::FrameOk),
+            DATA => Some(RtuEvent::FrameError),
+            _ => None,
+        }
+    }
+
+    /// Get current state
+    pub fn current_state(&self) -> u8 {
+        self.state
+    }
+
+    /// Reset to `IDLE`, dropping any partial frame
+    pub fn reset(&mut self) {
+        self.state = IDLE;
+        self.received = 0;
+        self.ticks = 0;
+    }
+}
+
+impl Machine for ModbusRtu {
+    type Input = RtuInput;
+    type Output = Option<RtuEvent>;
+    type Error = Infallible;
+
+    fn step(&mut self, input: RtuInput) -> Result<Option<RtuEvent>, Infallible> {
+        Ok(ModbusRtu::step(self, input))
+    }
+
+    fn current_state(&self) -> u8 {
+        self.state
+    }
+
+    /// Any state other than `IDLE` waits for the next silence
+    fn reset(&mut self, state: u8) {
+        ModbusRtu::reset(self);
+        if state != IDLE {
+            self.state = DISCARD;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(payload: &[u8], out: &mut [u8; 16]) -> usize {
+        let crc = crc16(payload).to_le_bytes();
+        out[..payload.len()].copy_from_slice(payload);
+        out[payload.len()..payload.len() + 2].copy_from_slice(&crc);
+        payload.len() + 2
+    }
+
+    #[test]
+    fn rtu_reports_frame_fields() {
+        let mut bytes = [0; 16];
+        let len = frame(&[0x11, 0x06, 0xAB, 0xCD], &mut bytes);
+        let mut rtu = ModbusRtu::new().with_silence_ticks(2);
+        let mut events = [None; 6];
+        for (event, byte) in events.iter_mut().zip(&bytes[..len]) {
+            *event = rtu.step(RtuInput::Byte(*byte));
+        }
+        assert_eq!(
+            events,
+            [
+                Some(RtuEvent::Address(0x11)),
+                Some(RtuEvent::Function(0x06)),
+                None,
+                None,
+                Some(RtuEvent::Data(0xAB)),
+                Some(RtuEvent::Data(0xCD)),
+            ]
+        );
+        assert_eq!(rtu.tick(), None);
+        assert_eq!(rtu.tick(), Some(RtuEvent::FrameOk));
+        assert_eq!(rtu.tick(), None);
+        assert_eq!(rtu.current_state(), IDLE);
+    }
+
+    #[test]
+    fn rtu_rejects_bad_frames() {
+        let mut rtu = ModbusRtu::new().with_address(5);
+        // Corrupted CRC
+        for byte in [0x05, 0x03, 0x00, 0x00, 0x00] {
+            rtu.step(RtuInput::Byte(byte));
+        }
+        assert_eq!(rtu.step(RtuInput::Silence), Some(RtuEvent::FrameError));
+        // Other unit, ignored up to the silence
+        assert_eq!(rtu.step(RtuInput::Byte(0x07)), None);
+        assert_eq!(rtu.step(RtuInput::Byte(0x03)), None);
+        assert_eq!(rtu.step(RtuInput::Silence), None);
+        // Too short
+        rtu.step(RtuInput::Byte(0x00));
+        assert_eq!(rtu.step(RtuInput::Silence), Some(RtuEvent::FrameError));
+    }
+}
@@ -0,0 +1,156 @@
+//! Stepper motor motion phases
+//!
+//! A move ramps up, cruises until stopped and ramps down, with step pulses
+//! issued from `tick()` as periodic outputs at a per-phase cadence. The
+//! emergency stop is accepted in every state: the crate has no wildcard
+//! rows, so the table spells out one `EmergencyStop` row per state.
+
+use crate::Moore;
+
+/// Not moving, output off
+pub const IDLE: u8 = 0;
+/// Ramping up
+pub const ACCELERATING: u8 = 1;
+/// Moving at full speed
+pub const CRUISE: u8 = 2;
+/// Ramping down
+pub const DECELERATING: u8 = 3;
+/// Stopped hard, waiting for release
+pub const EMERGENCY_STOP: u8 = 4;
+
+/// State names, indexed by state
+pub static STATE_NAMES: [&str; 5] = ["idle", "accelerating", "cruise", "decelerating", "e-stop"];
+
+/// Motion command
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MotorInput {
+    /// Begin a move
+    Start,
+    /// Ramp down and stop
+    Stop,
+    /// Stop at once, from any state
+    EmergencyStop,
+    /// Leave emergency stop
+    Release,
+}
+
+/// Driver output of a state, re-emitted as `Tick::Periodic` at the cadence
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Drive {
+    /// Driver disabled
+    Off,
+    /// Issue one step pulse
+    Step,
+    /// Driver holding with brake engaged
+    Brake,
+}
+
+static TRANSITIONS: [(u8, MotorInput, u8); 10] = [
+    (IDLE, MotorInput::Start, ACCELERATING),
+    (ACCELERATING, MotorInput::Stop, DECELERATING),
+    (CRUISE, MotorInput::Stop, DECELERATING),
+    (DECELERATING, MotorInput::Start, ACCELERATING),
+    (EMERGENCY_STOP, MotorInput::Release, IDLE),
+    // Global stop, one row per state
+    (IDLE, MotorInput::EmergencyStop, EMERGENCY_STOP),
+    (ACCELERATING, MotorInput::EmergencyStop, EMERGENCY_STOP),
+    (CRUISE, MotorInput::EmergencyStop, EMERGENCY_STOP),
+    (DECELERATING, MotorInput::EmergencyStop, EMERGENCY_STOP),
+    (EMERGENCY_STOP, MotorInput::EmergencyStop, EMERGENCY_STOP),
+];
+
+static OUTPUTS: [Drive; 5] = [
+    Drive::Off,
+    Drive::Step,
+    Drive::Step,
+    Drive::Step,
+    Drive::Brake,
+];
+
+/// Motion profile: ramp durations and step cadence per phase
+///
+/// Usable in a `static`; `machine()` borrows the profile's tables.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Profile {
+    // Table: (state, duration, next_state)
+    timed: [(u8, u32, u8); 2],
+    // Table: (state, ticks per step pulse)
+    periodic: [(u8, u32); 3],
+}
+
+impl Profile {
+    /// Create profile
+    ///
+    /// # Arguments
+    /// * `ramp_up` - Ticks spent accelerating before cruising
+    /// * `ramp_down` - Ticks spent decelerating before stopping
+    /// * `cadence` - Ticks per step pulse while accelerating, cruising and
+    ///   decelerating
+    pub const fn new(ramp_up: u32, ramp_down: u32, cadence: [u32; 3]) -> Self {
+        Profile {
+            timed: [
+                (ACCELERATING, ramp_up, CRUISE),
+                (DECELERATING, ramp_down, IDLE),
+            ],
+            periodic: [
+                (ACCELERATING, cadence[0]),
+                (CRUISE, cadence[1]),
+                (DECELERATING, cadence[2]),
+            ],
+        }
+    }
+
+    /// Create a machine in `IDLE`, with state names attached
+    ///
+    /// # Example
+    /// ```
+    /// use fsmall::presets::motor::{Drive, MotorInput, Profile, CRUISE};
+    /// use fsmall::Tick;
+    ///
+    /// static PROFILE: Profile = Profile::new(4, 4, [2, 1, 2]);
+    ///
+    /// let mut motor = PROFILE.machine();
+    /// assert_eq!(motor.step(MotorInput::Start), Ok(Drive::Step));
+    /// assert_eq!(motor.tick(), Tick::Idle);
+    /// assert_eq!(motor.tick(), Tick::Periodic(Drive::Step));
+    /// motor.tick();
+    /// assert_eq!(motor.tick(), Tick::Timed(CRUISE));
+    /// assert_eq!(motor.step(MotorInput::EmergencyStop), Ok(Drive::Brake));
+    /// ```
+    pub fn machine(&self) -> Moore<'_, MotorInput, Drive> {
+        Moore::new(IDLE, &TRANSITIONS, &OUTPUTS)
+            .with_state_names(&STATE_NAMES)
+            .with_timed(&self.timed)
+            .with_periodic(&self.periodic)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Tick;
+
+    #[test]
+    fn motor_ramps_down_to_idle() {
+        let profile = Profile::new(2, 2, [1, 1, 1]);
+        let mut motor = profile.machine();
+        motor.step(MotorInput::Start).unwrap();
+        assert_eq!(motor.tick(), Tick::Periodic(Drive::Step));
+        assert_eq!(motor.tick(), Tick::Timed(CRUISE));
+        assert_eq!(motor.step(MotorInput::Stop), Ok(Drive::Step));
+        assert_eq!(motor.tick(), Tick::Periodic(Drive::Step));
+        assert_eq!(motor.tick(), Tick::Timed(IDLE));
+        assert_eq!(motor.tick(), Tick::Idle);
+    }
+
+    #[test]
+    fn motor_stops_from_every_state() {
+        let profile = Profile::new(2, 2, [1, 1, 1]);
+        for state in IDLE..=EMERGENCY_STOP {
+            let mut motor = profile.machine();
+            motor.reset(state);
+            assert_eq!(motor.step(MotorInput::EmergencyStop), Ok(Drive::Brake));
+            assert_eq!(motor.current_state(), EMERGENCY_STOP);
+        }
+    }
+}
@@ -0,0 +1,188 @@
+//! MQTT client connection lifecycle
+//!
+//! Covers connecting, keepalive pings and reconnecting after a lost or
+//! silent broker. All waiting is done with timeouts on `MqttInput::Timeout`,
+//! so the caller only ticks the machine and reports packets; encoding and
+//! sending the packets stays with the MQTT layer.
+
+use crate::Mealy;
+
+/// Not connected, not trying to
+pub const DISCONNECTED: u8 = 0;
+/// CONNECT sent, waiting for CONNACK
+pub const CONNECT_SENT: u8 = 1;
+/// Connected, keepalive running
+pub const CONNECTED: u8 = 2;
+/// PINGREQ sent, waiting for PINGRESP
+pub const PING_PENDING: u8 = 3;
+/// Connection lost, waiting before the next attempt
+pub const RECONNECTING: u8 = 4;
+
+/// State names, indexed by state
+pub static STATE_NAMES: [&str; 5] = [
+    "disconnected",
+    "connect-sent",
+    "connected",
+    "ping-pending",
+    "reconnecting",
+];
+
+/// User request, broker packet or timer
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MqttInput {
+    /// User asks to connect
+    Connect,
+    /// User asks to disconnect
+    Disconnect,
+    /// CONNACK accepting the connection
+    ConnAck,
+    /// CONNACK refusing the connection
+    ConnRefused,
+    /// PINGRESP received
+    PingResp,
+    /// Any other packet sent to the broker, restarting the keepalive
+    Activity,
+    /// Socket closed or failed
+    ConnectionLost,
+    /// Timeout of the current state expired
+    Timeout,
+}
+
+/// What the MQTT layer should do
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MqttAction {
+    /// Nothing to do
+    Nothing,
+    /// Open the socket and send CONNECT
+    SendConnect,
+    /// Send PINGREQ
+    SendPing,
+    /// Send DISCONNECT and close the socket
+    SendDisconnect,
+    /// Close the socket, a reconnect follows
+    CloseSocket,
+    /// Report the refused connection to the user
+    Refused,
+}
+
+/// Transition table: (from_state, input, to_state)
+pub static TRANSITIONS: [(u8, MqttInput, u8); 17] = [
+    (DISCONNECTED, MqttInput::Connect, CONNECT_SENT),
+    (CONNECT_SENT, MqttInput::ConnAck, CONNECTED),
+    (CONNECT_SENT, MqttInput::ConnRefused, DISCONNECTED),
+    (CONNECT_SENT, MqttInput::Timeout, RECONNECTING),
+    (CONNECT_SENT, MqttInput::ConnectionLost, RECONNECTING),
+    (CONNECT_SENT, MqttInput::Disconnect, DISCONNECTED),
+    (CONNECTED, MqttInput::Activity, CONNECTED),
+    (CONNECTED, MqttInput::Timeout, PING_PENDING),
+    (CONNECTED, MqttInput::ConnectionLost, RECONNECTING),
+    (CONNECTED, MqttInput::Disconnect, DISCONNECTED),
+    (PING_PENDING, MqttInput::PingResp, CONNECTED),
+    (PING_PENDING, MqttInput::Timeout, RECONNECTING),
+    (PING_PENDING, MqttInput::ConnectionLost, RECONNECTING),
+    (PING_PENDING, MqttInput::Disconnect, DISCONNECTED),
+    (PING_PENDING, MqttInput::Activity, PING_PENDING),
+    (RECONNECTING, MqttInput::Timeout, CONNECT_SENT),
+    (RECONNECTING, MqttInput::Disconnect, DISCONNECTED),
+];
+
+/// Output table: (state, input, action), aligned with `TRANSITIONS`
+pub static OUTPUTS: [(u8, MqttInput, MqttAction); 17] = [
+    (DISCONNECTED, MqttInput::Connect, MqttAction::SendConnect),
+    (CONNECT_SENT, MqttInput::ConnAck, MqttAction::Nothing),
+    (CONNECT_SENT, MqttInput::ConnRefused, MqttAction::Refused),
+    (CONNECT_SENT, MqttInput::Timeout, MqttAction::CloseSocket),
+    (CONNECT_SENT, MqttInput::ConnectionLost, MqttAction::Nothing),
+    (CONNECT_SENT, MqttInput::Disconnect, MqttAction::CloseSocket),
+    (CONNECTED, MqttInput::Activity, MqttAction::Nothing),
+    (CONNECTED, MqttInput::Timeout, MqttAction::SendPing),
+    (CONNECTED, MqttInput::ConnectionLost, MqttAction::Nothing),
+    (CONNECTED, MqttInput::Disconnect, MqttAction::SendDisconnect),
+    (PING_PENDING, MqttInput::PingResp, MqttAction::Nothing),
+    (PING_PENDING, MqttInput::Timeout, MqttAction::CloseSocket),
+    (PING_PENDING, MqttInput::ConnectionLost, MqttAction::Nothing),
+    (
+        PING_PENDING,
+        MqttInput::Disconnect,
+        MqttAction::SendDisconnect,
+    ),
+    // Sending does not replace the outstanding PINGRESP
+    (PING_PENDING, MqttInput::Activity, MqttAction::Nothing),
+    (RECONNECTING, MqttInput::Timeout, MqttAction::SendConnect),
+    (RECONNECTING, MqttInput::Disconnect, MqttAction::Nothing),
+];
+
+/// Build timeout table for `machine`
+///
+/// # Arguments
+/// * `keepalive` - Ticks without sending before a PINGREQ
+/// * `response` - Ticks to wait for CONNACK or PINGRESP
+/// * `backoff` - Ticks to wait before reconnecting
+pub const fn timeouts(keepalive: u32, response: u32, backoff: u32) -> [(u8, u32); 4] {
+    [
+        (CONNECT_SENT, response),
+        (CONNECTED, keepalive),
+        (PING_PENDING, response),
+        (RECONNECTING, backoff),
+    ]
+}
+
+/// Create MQTT client machine in `DISCONNECTED`, with state names attached
+///
+/// # Arguments
+/// * `timeouts` - Timeout table, usually from `timeouts()`
+///
+/// # Example
+/// ```
+/// use fsmall::presets::mqtt::{self, MqttAction, MqttInput};
+/// use fsmall::Tick;
+///
+/// static TIMEOUTS: [(u8, u32); 4] = mqtt::timeouts(60, 5, 10);
+///
+/// let mut client = mqtt::machine(&TIMEOUTS);
+/// assert_eq!(client.step(MqttInput::Connect), Ok(MqttAction::SendConnect));
+/// client.step(MqttInput::ConnAck).unwrap();
+/// for _ in 0..59 {
+///     assert_eq!(client.tick(), Tick::Idle);
+/// }
+/// assert_eq!(client.tick(), Tick::Timeout(Ok(MqttAction::SendPing)));
+/// ```
+pub fn machine(timeouts: &[(u8, u32)]) -> Mealy<'_, MqttInput, MqttAction> {
+    Mealy::new(DISCONNECTED, &TRANSITIONS, &OUTPUTS)
+        .with_state_names(&STATE_NAMES)
+        .with_timeouts(timeouts, &MqttInput::Timeout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Tick;
+
+    static TIMEOUTS: [(u8, u32); 4] = timeouts(3, 2, 1);
+
+    #[test]
+    fn mqtt_activity_postpones_ping() {
+        let mut client = machine(&TIMEOUTS);
+        client.reset(CONNECTED);
+        client.tick();
+        client.tick();
+        assert_eq!(client.step(MqttInput::Activity), Ok(MqttAction::Nothing));
+        client.tick();
+        client.tick();
+        assert_eq!(client.tick(), Tick::Timeout(Ok(MqttAction::SendPing)));
+        assert_eq!(client.step(MqttInput::PingResp), Ok(MqttAction::Nothing));
+        assert_eq!(client.current_state(), CONNECTED);
+    }
+
+    #[test]
+    fn mqtt_missing_pingresp_reconnects() {
+        let mut client = machine(&TIMEOUTS);
+        client.reset(PING_PENDING);
+        client.tick();
+        assert_eq!(client.tick(), Tick::Timeout(Ok(MqttAction::CloseSocket)));
+        assert_eq!(client.state_name(), Some("reconnecting"));
+        assert_eq!(client.tick(), Tick::Timeout(Ok(MqttAction::SendConnect)));
+        assert_eq!(client.current_state(), CONNECT_SENT);
+        assert!(Mealy::new_checked(DISCONNECTED, &TRANSITIONS, &OUTPUTS).is_ok());
+    }
+}
@@ -0,0 +1,349 @@
+//! NMEA 0183 sentence parser
+//!
+//! Sentences look like `$GPGGA,123519,4807.038,N*47\r\n`: fields separated
+//! by commas, then `*`, two hex digits of XOR checksum over everything
+//! between `$` and `*`, and CR LF. The checksum is required.
+//!
+//! A `StatefulMealy` over byte classes does the framing; its context
+//! accumulates the checksum and the current field through actions. Fields
+//! are reported as soon as they end, before the checksum is known, so
+//! drop a sentence's fields on `ChecksumError` or `Malformed`.
+
+use core::convert::Infallible;
+
+use crate::{Action, Machine, StatefulMealy};
+
+/// Waiting for `$`
+pub const IDLE: u8 = 0;
+/// Reading fields
+pub const FIELD: u8 = 1;
+/// Waiting for the checksum's high nibble
+pub const CHECKSUM_HI: u8 = 2;
+/// Waiting for the checksum's low nibble
+pub const CHECKSUM_LO: u8 = 3;
+/// Waiting for CR
+pub const END_CR: u8 = 4;
+/// Waiting for LF
+pub const END_LF: u8 = 5;
+
+/// Longest field kept; longer ones make the sentence `Malformed`
+pub const MAX_FIELD: usize = 32;
+
+// Byte classes
+const C_OTHER: u8 = 0;
+const C_DOLLAR: u8 = 1;
+const C_COMMA: u8 = 2;
+const C_STAR: u8 = 3;
+const C_HEX: u8 = 4;
+const C_CR: u8 = 5;
+const C_LF: u8 = 6;
+const C_INVALID: u8 = 7;
+
+static CLASSES: [u8; 256] = {
+    let mut classes = [C_INVALID; 256];
+    let mut b = 0x20;
+    while b < 0x7F {
+        classes[b] = match b as u8 {
+            b'$' => C_DOLLAR,
+            b',' => C_COMMA,
+            b'*' => C_STAR,
+            b'0'..=b'9' | b'A'..=b'F' | b'a'..=b'f' => C_HEX,
+            _ => C_OTHER,
+        };
+        b += 1;
+    }
+    classes[b'\r' as usize] = C_CR;
+    classes[b'\n' as usize] = C_LF;
+    classes
+};
+
+/// Complete field of a sentence
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Field {
+    /// Position in the sentence, 0 is the address such as `GPGGA`
+    pub index: u8,
+    bytes: [u8; MAX_FIELD],
+    len: u8,
+}
+
+impl Field {
+    /// Get field contents
+    pub fn as_bytes(&self) -> &[u8] {
+        self.bytes.get(..self.len as usize).unwrap_or(&[])
+    }
+}
+
+/// Parser output
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum NmeaEvent {
+    /// Field ended
+    Field(Field),
+    /// Sentence ended and its checksum matched
+    SentenceOk,
+    /// Sentence ended with a wrong checksum
+    ChecksumError,
+    /// Sentence broken off by a bad byte, a new `$` or an overlong field
+    Malformed,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Out {
+    Nothing,
+    FieldEnd,
+    Verdict,
+    Restart,
+}
+
+// Context: the byte being stepped, set before each step, and the sentence
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Sentence {
+    byte: u8,
+    sum: u8,
+    expected: u8,
+    current: Field,
+    done: Field,
+    overflow: bool,
+}
+
+const EMPTY: Field = Field {
+    index: 0,
+    bytes: [0; MAX_FIELD],
+    len: 0,
+};
+
+fn start(s: &mut Sentence) {
+    s.sum = 0;
+    s.current = EMPTY;
+    s.overflow = false;
+}
+
+fn push(s: &mut Sentence) {
+    s.sum ^= s.byte;
+    match s.current.bytes.get_mut(s.current.len as usize) {
+        Some(slot) => {
+            *slot = s.byte;
+            s.current.len += 1;
+        }
+        None => s.overflow = true,
+    }
+}
+
+fn end_field(s: &mut Sentence) {
+    s.done = s.current;
+    s.current = Field {
+        index: s.current.index.saturating_add(1),
+        ..EMPTY
+    };
+}
+
+fn separate(s: &mut Sentence) {
+    s.sum ^= s.byte;
+    end_field(s);
+}
+
+fn nibble(byte: u8) -> u8 {
+    match byte {
+        b'0'..=b'9' => byte - b'0',
+        _ => (byte | 0x20) - b'a' + 10,
+    }
+}
+
+fn checksum_hi(s: &mut Sentence) {
+    s.expected = nibble(s.byte) << 4;
+}
+
+fn checksum_lo(s: &mut Sentence) {
+    s.expected |= nibble(s.byte);
+}
+
+static TRANSITIONS: [(u8, u8, u8); 14] = [
+    (IDLE, C_DOLLAR, FIELD),
+    (FIELD, C_OTHER, FIELD),
+    (FIELD, C_HEX, FIELD),
+    (FIELD, C_COMMA, FIELD),
+    (FIELD, C_STAR, CHECKSUM_HI),
+    (CHECKSUM_HI, C_HEX, CHECKSUM_LO),
+    (CHECKSUM_LO, C_HEX, END_CR),
+    (END_CR, C_CR, END_LF),
+    (END_LF, C_LF, IDLE),
+    // `$` always starts over
+    (FIELD, C_DOLLAR, FIELD),
+    (CHECKSUM_HI, C_DOLLAR, FIELD),
+    (CHECKSUM_LO, C_DOLLAR, FIELD),
+    (END_CR, C_DOLLAR, FIELD),
+    (END_LF, C_DOLLAR, FIELD),
+];
+
+static OUTPUTS: [(u8, u8, Out); 14] = [
+    (IDLE, C_DOLLAR, Out::Nothing),
+    (FIELD, C_OTHER, Out::Nothing),
+    (FIELD, C_HEX, Out::Nothing),
+    (FIELD, C_COMMA, Out::FieldEnd),
+    (FIELD, C_STAR, Out::FieldEnd),
+    (CHECKSUM_HI, C_HEX, Out::Nothing),
+    (CHECKSUM_LO, C_HEX, Out::Nothing),
+    (END_CR, C_CR, Out::Nothing),
+    (END_LF, C_LF, Out::Verdict),
+    (FIELD, C_DOLLAR, Out::Restart),
+    (CHECKSUM_HI, C_DOLLAR, Out::Restart),
+    (CHECKSUM_LO, C_DOLLAR, Out::Restart),
+    (END_CR, C_DOLLAR, Out::Restart),
+    (END_LF, C_DOLLAR, Out::Restart),
+];
+
+static ACTIONS: [(u8, u8, Action<Sentence>); 12] = [
+    (IDLE, C_DOLLAR, start),
+    (FIELD, C_OTHER, push),
+    (FIELD, C_HEX, push),
+    (FIELD, C_COMMA, separate),
+    (FIELD, C_STAR, end_field),
+    (CHECKSUM_HI, C_HEX, checksum_hi),
+    (CHECKSUM_LO, C_HEX, checksum_lo),
+    (FIELD, C_DOLLAR, start),
+    (CHECKSUM_HI, C_DOLLAR, start),
+    (CHECKSUM_LO, C_DOLLAR, start),
+    (END_CR, C_DOLLAR, start),
+    (END_LF, C_DOLLAR, start),
+];
+
+/// NMEA 0183 sentence parser
+///
+/// # Example
+/// ```
+/// use fsmall::presets::nmea::{NmeaEvent, NmeaParser};
+///
+/// let mut parser = NmeaParser::new();
+/// let mut fields = 0;
+/// let mut verdict = None;
+/// for byte in b"$GPGLL,4916.45,N,12311.12,W*71\r\n" {
+///     match parser.step(*byte) {
+///         Some(NmeaEvent::Field(field)) if field.index == 0 => {
+///             assert_eq!(field.as_bytes(), b"GPGLL");
+///             fields += 1;
+///         }
+///         Some(NmeaEvent::Field(_)) => fields += 1,
+///         Some(event) => verdict = Some(event),
+///         None => {}
+///     }
+/// }
+/// assert_eq!(fields, 5);
+/// assert_eq!(verdict, Some(NmeaEvent::SentenceOk));
+/// ```
+pub struct NmeaParser {
+    machine: StatefulMealy<'static, u8, Out, Sentence>,
+}
+
+impl Default for NmeaParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NmeaParser {
+    /// Create parser waiting for `$`
+    pub fn new() -> Self {
+        let sentence = Sentence {
+            byte: 0,
+            sum: 0,
+            expected: 0,
+            current: EMPTY,
+            done: EMPTY,
+            overflow: false,
+        };
+        NmeaParser {
+            machine: StatefulMealy::new(IDLE, &TRANSITIONS, &OUTPUTS, sentence)
+                .with_actions(&ACTIONS),
+        }
+    }
+
+    /// Feed one byte, return event if any
+    ///
+    /// Bytes outside sentences are skipped.
+    pub fn step(&mut self, byte: u8) -> Option<NmeaEvent> {
+        let from = self.machine.current_state();
+        self.machine.context_mut().byte = byte;
+        let Ok(out) = self.machine.step(CLASSES[byte as usize]) else {
+            self.machine.reset(IDLE);
+            return (from != IDLE).then_some(NmeaEvent::Malformed);
+        };
+
+        let sentence = self.machine.context();
+        match out {
+            Out::Nothing => None,
+            Out::FieldEnd => Some(NmeaEvent::Field(sentence.done)),
+            Out::Restart => Some(NmeaEvent::Malformed),
+            Out::Verdict if sentence.overflow => Some(NmeaEvent::Malformed),
+            Out::Verdict if sentence.sum == sentence.expected => Some(NmeaEvent::SentenceOk),
+            Out::Verdict => Some(NmeaEvent::ChecksumError),
+        }
+    }
+
+    /// Get current state
+    pub fn current_state(&self) -> u8 {
+        self.machine.current_state()
+    }
+
+    /// Drop any partial sentence and wait for `$`
+    pub fn reset(&mut self) {
+        self.machine.reset(IDLE);
+    }
+}
+
+impl Machine for NmeaParser {
+    type Input = u8;
+    type Output = Option<NmeaEvent>;
+    type Error = Infallible;
+
+    fn step(&mut self, byte: u8) -> Result<Option<NmeaEvent>, Infallible> {
+        Ok(NmeaParser::step(self, byte))
+    }
+
+    fn current_state(&self) -> u8 {
+        NmeaParser::current_state(self)
+    }
+
+    /// Always waits for the next `$`, sentences cannot be resumed
+    fn reset(&mut self, _state: u8) {
+        NmeaParser::reset(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn verdict(parser: &mut NmeaParser, bytes: &[u8]) -> Option<NmeaEvent> {
+        bytes
+            .iter()
+            .filter_map(|b| parser.step(*b))
+            .filter(|e| !matches!(e, NmeaEvent::Field(_)))
+            .last()
+    }
+
+    #[test]
+    fn nmea_checksum_verdicts() {
+        let mut parser = NmeaParser::new();
+        let ok = b"noise$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47\r\n";
+        assert_eq!(verdict(&mut parser, ok), Some(NmeaEvent::SentenceOk));
+        let bad = b"$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*48\r\n";
+        assert_eq!(verdict(&mut parser, bad), Some(NmeaEvent::ChecksumError));
+        assert_eq!(parser.current_state(), IDLE);
+    }
+
+    #[test]
+    fn nmea_malformed_sentences() {
+        let mut parser = NmeaParser::new();
+        assert_eq!(
+            verdict(&mut parser, b"$GPGLL,49\n"),
+            Some(NmeaEvent::Malformed)
+        );
+        assert_eq!(
+            verdict(&mut parser, b"$GP$GPGLL,4916.45,N,12311.12,W*71\r\n"),
+            Some(NmeaEvent::SentenceOk)
+        );
+        let long = [b'A'; MAX_FIELD + 1];
+        parser.step(b'$');
+        assert_eq!(verdict(&mut parser, &long), None);
+        assert_eq!(verdict(&mut parser, b"*00\r\n"), Some(NmeaEvent::Malformed));
+    }
+}
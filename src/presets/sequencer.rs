@@ -0,0 +1,84 @@
+//! Cyclic time-based sequencers, e.g. traffic lights
+//!
+//! A sequence of `(output, duration_ticks)` phases becomes a Moore machine
+//! with one state per phase, advanced by timed transitions from `tick()`.
+//! The unit input `()` skips to the next phase early.
+
+use crate::Moore;
+
+/// Tables of a cyclic sequencer with `N` phases, see `sequencer`
+#[derive(Debug, Clone)]
+pub struct Sequence<O, const N: usize> {
+    // Array: outputs[phase] = output
+    outputs: [O; N],
+    // Table: (phase, duration, next_phase)
+    timed: [(u8, u32, u8); N],
+    // Table: (phase, (), next_phase)
+    skip: [(u8, (), u8); N],
+}
+
+/// Build sequencer tables from `(output, duration_ticks)` phases
+///
+/// Phase `k` is state `k`; after its duration it moves on to phase `k + 1`,
+/// and the last phase wraps around to the first. Usable in a `static`.
+///
+/// # Example
+/// ```
+/// use fsmall::presets::sequencer::{sequencer, Sequence};
+/// use fsmall::Tick;
+///
+/// #[derive(Copy, Clone, Debug, PartialEq)]
+/// enum Light { Red, Green, Yellow }
+///
+/// static LIGHTS: Sequence<Light, 3> = sequencer([(Light::Red, 3), (Light::Green, 3), (Light::Yellow, 1)]);
+///
+/// let mut fsm = LIGHTS.machine();
+/// assert_eq!(fsm.current_output(), Ok(Light::Red));
+/// fsm.tick();
+/// fsm.tick();
+/// assert_eq!(fsm.tick(), Tick::Timed(1));
+/// assert_eq!(fsm.current_output(), Ok(Light::Green));
+/// assert_eq!(fsm.step(()), Ok(Light::Yellow)); // Skip ahead
+/// ```
+pub const fn sequencer<O: Copy, const N: usize>(phases: [(O, u32); N]) -> Sequence<O, N> {
+    const { assert!(N > 0 && N <= 256, "a sequence has 1 to 256 phases") };
+    let mut outputs = [phases[0].0; N];
+    let mut timed = [(0, 0, 0); N];
+    let mut skip = [(0, (), 0); N];
+    let mut phase = 0;
+    while phase < N {
+        let next = if phase + 1 == N { 0 } else { phase + 1 };
+        outputs[phase] = phases[phase].0;
+        timed[phase] = (phase as u8, phases[phase].1, next as u8);
+        skip[phase] = (phase as u8, (), next as u8);
+        phase += 1;
+    }
+    Sequence {
+        outputs,
+        timed,
+        skip,
+    }
+}
+
+impl<O: Copy, const N: usize> Sequence<O, N> {
+    /// Create a machine starting in the first phase
+    pub fn machine(&self) -> Moore<'_, (), O> {
+        Moore::new(0, &self.skip, &self.outputs).with_timed(&self.timed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Tick;
+
+    #[test]
+    fn sequencer_cycles_through_phases() {
+        let blink = sequencer([(true, 1), (false, 2)]);
+        let mut fsm = blink.machine();
+        assert_eq!(fsm.tick(), Tick::Timed(1));
+        assert_eq!(fsm.tick(), Tick::Idle);
+        assert_eq!(fsm.tick(), Tick::Timed(0));
+        assert_eq!(fsm.current_output(), Ok(true));
+    }
+}
@@ -0,0 +1,254 @@
+//! SLIP and COBS frame decoders over `u8`
+//!
+//! Both decoders undo byte stuffing and report each payload byte as it
+//! arrives, so frames of any length are received without a buffer.
+
+use core::convert::Infallible;
+
+use crate::{Machine, Mealy};
+
+/// Decoder output
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Decoded {
+    /// Payload byte
+    Data(u8),
+    /// Frame ended, its payload is complete
+    FrameEnd,
+    /// Frame broken by a bad escape or delimiter; drop its payload
+    FrameError,
+}
+
+/// SLIP frame delimiter
+pub const END: u8 = 0xC0;
+/// SLIP escape byte
+pub const ESC: u8 = 0xDB;
+/// Escaped `END`
+pub const ESC_END: u8 = 0xDC;
+/// Escaped `ESC`
+pub const ESC_ESC: u8 = 0xDD;
+
+/// SLIP: receiving payload bytes
+pub const SLIP_DATA: u8 = 0;
+/// SLIP: after `ESC`
+pub const SLIP_ESCAPED: u8 = 1;
+/// SLIP: ignoring bytes up to the next `END` after an error
+pub const SLIP_DISCARD: u8 = 2;
+
+/// SLIP transition table, one row per (state, byte)
+pub static SLIP_TRANSITIONS: [(u8, u8, u8); 768] = slip_transitions();
+
+const fn slip_transitions() -> [(u8, u8, u8); 768] {
+    let mut rows = [(0, 0, 0); 768];
+    let mut b = 0;
+    while b < 256 {
+        let byte = b as u8;
+        rows[b] = match byte {
+            ESC => (SLIP_DATA, byte, SLIP_ESCAPED),
+            _ => (SLIP_DATA, byte, SLIP_DATA),
+        };
+        rows[256 + b] = match byte {
+            ESC_END | ESC_ESC | END => (SLIP_ESCAPED, byte, SLIP_DATA),
+            _ => (SLIP_ESCAPED, byte, SLIP_DISCARD),
+        };
+        rows[512 + b] = match byte {
+            END => (SLIP_DISCARD, byte, SLIP_DATA),
+            _ => (SLIP_DISCARD, byte, SLIP_DISCARD),
+        };
+        b += 1;
+    }
+    rows
+}
+
+fn slip_output(state: u8, byte: u8) -> Option<Decoded> {
+    match (state, byte) {
+        (SLIP_DATA, END) => Some(Decoded::FrameEnd),
+        (SLIP_DATA, ESC) => None,
+        (SLIP_DATA, _) => Some(Decoded::Data(byte)),
+        (SLIP_ESCAPED, ESC_END) => Some(Decoded::Data(END)),
+        (SLIP_ESCAPED, ESC_ESC) => Some(Decoded::Data(ESC)),
+        (SLIP_ESCAPED, _) => Some(Decoded::FrameError),
+        _ => None,
+    }
+}
+
+/// Create SLIP (RFC 1055) decoder
+///
+/// An `END` right after `ESC` aborts the frame; any other bad escape also
+/// drops the rest of the frame up to the next `END`. Senders that lead
+/// each frame with `END` produce empty frames, reported as a `FrameEnd`
+/// without data. Every step scans the 768-row table linearly.
+///
+/// # Example
+/// ```
+/// use fsmall::presets::stuffing::{slip, Decoded, END, ESC, ESC_END};
+///
+/// let mut fsm = slip();
+/// assert_eq!(fsm.step(0x42), Ok(Some(Decoded::Data(0x42))));
+/// assert_eq!(fsm.step(ESC), Ok(None));
+/// assert_eq!(fsm.step(ESC_END), Ok(Some(Decoded::Data(END))));
+/// assert_eq!(fsm.step(END), Ok(Some(Decoded::FrameEnd)));
+/// ```
+pub fn slip() -> Mealy<'static, u8, Option<Decoded>> {
+    Mealy::from_output_fn(SLIP_DATA, &SLIP_TRANSITIONS, slip_output)
+}
+
+/// COBS: waiting for a code byte
+pub const COBS_CODE: u8 = 0;
+/// COBS: receiving the bytes of a block
+pub const COBS_BLOCK: u8 = 1;
+
+/// COBS decoder, frames delimited by `0x00`
+///
+/// A block spans up to 254 bytes, and whether a zero follows it depends on
+/// its code byte; that is more than a `u8` state can count, so the block
+/// counter lives beside the two states `COBS_CODE` and `COBS_BLOCK`. A
+/// delimiter inside a block reports `FrameError` and starts a new frame.
+///
+/// # Example
+/// ```
+/// use fsmall::presets::stuffing::{Cobs, Decoded};
+///
+/// let mut cobs = Cobs::new();
+/// let mut payload = [0; 4];
+/// let mut len = 0;
+/// // Encodes [0x11, 0x00, 0x22]
+/// for byte in [0x02, 0x11, 0x02, 0x22, 0x00] {
+///     match cobs.step(byte) {
+///         Some(Decoded::Data(b)) => {
+///             payload[len] = b;
+///             len += 1;
+///         }
+///         Some(Decoded::FrameEnd) => assert_eq!(payload[..len], [0x11, 0x00, 0x22]),
+///         _ => {}
+///     }
+/// }
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Cobs {
+    state: u8,
+    // Bytes left in the current block
+    remaining: u8,
+    // Current block ends with an implicit zero
+    zero_after: bool,
+    // A code byte was seen since the last delimiter
+    started: bool,
+}
+
+impl Default for Cobs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Cobs {
+    /// Create decoder waiting for the first code byte
+    pub const fn new() -> Self {
+        Cobs {
+            state: COBS_CODE,
+            remaining: 0,
+            zero_after: false,
+            started: false,
+        }
+    }
+
+    /// Feed one byte, return decoded output if any
+    pub fn step(&mut self, byte: u8) -> Option<Decoded> {
+        match (self.state, byte) {
+            (COBS_CODE, 0) => {
+                // Trailing implicit zero is not part of the payload
+                let started = self.started;
+                self.reset();
+                started.then_some(Decoded::FrameEnd)
+            }
+            (COBS_CODE, code) => {
+                let zero = self.zero_after.then_some(Decoded::Data(0));
+                self.started = true;
+                self.remaining = code - 1;
+                self.zero_after = code < 0xFF;
+                if self.remaining > 0 {
+                    self.state = COBS_BLOCK;
+                }
+                zero
+            }
+            (_, 0) => {
+                self.reset();
+                Some(Decoded::FrameError)
+            }
+            (_, data) => {
+                self.remaining -= 1;
+                if self.remaining == 0 {
+                    self.state = COBS_CODE;
+                }
+                Some(Decoded::Data(data))
+            }
+        }
+    }
+
+    /// Get current state
+    pub fn current_state(&self) -> u8 {
+        self.state
+    }
+
+    /// Drop any partial frame and wait for a code byte
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+}
+
+impl Machine for Cobs {
+    type Input = u8;
+    type Output = Option<Decoded>;
+    type Error = Infallible;
+
+    fn step(&mut self, byte: u8) -> Result<Option<Decoded>, Infallible> {
+        Ok(Cobs::step(self, byte))
+    }
+
+    fn current_state(&self) -> u8 {
+        self.state
+    }
+
+    /// Frames restart at `COBS_CODE` whatever `state` is given
+    fn reset(&mut self, _state: u8) {
+        Cobs::reset(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slip_bad_escape_drops_frame() {
+        let mut fsm = slip();
+        assert_eq!(fsm.step(0x01), Ok(Some(Decoded::Data(0x01))));
+        fsm.step(ESC).unwrap();
+        assert_eq!(fsm.step(0x02), Ok(Some(Decoded::FrameError)));
+        assert_eq!(fsm.step(0x03), Ok(None));
+        assert_eq!(fsm.step(END), Ok(None));
+        assert_eq!(fsm.step(ESC), Ok(None));
+        assert_eq!(fsm.step(ESC_ESC), Ok(Some(Decoded::Data(ESC))));
+        assert_eq!(fsm.step(END), Ok(Some(Decoded::FrameEnd)));
+    }
+
+    #[test]
+    fn cobs_long_block_has_no_zero() {
+        let mut cobs = Cobs::new();
+        let mut data = 0;
+        assert_eq!(cobs.step(0xFF), None);
+        for byte in 1..=254 {
+            if let Some(Decoded::Data(b)) = cobs.step(byte) {
+                assert_eq!(b, byte);
+                data += 1;
+            }
+        }
+        assert_eq!(cobs.step(0x01), None);
+        assert_eq!(cobs.step(0x00), Some(Decoded::FrameEnd));
+        assert_eq!(data, 254);
+        // Delimiter inside a block
+        cobs.step(0x03);
+        cobs.step(0x01);
+        assert_eq!(cobs.step(0x00), Some(Decoded::FrameError));
+        assert_eq!(cobs.step(0x00), None);
+    }
+}
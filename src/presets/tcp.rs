@@ -0,0 +1,206 @@
+//! TCP connection state machine from RFC 793
+//!
+//! Tables follow the connection state diagram (RFC 793, figure 6):
+//! inputs are user calls, received segments and the 2MSL timeout, outputs
+//! the segment to send or bookkeeping to do. Sequence numbers, windows and
+//! retransmission are out of scope.
+//!
+//! `TIME_WAIT` leaves on `TcpInput::Timeout`; arm it with
+//! `with_timeouts(&[(TIME_WAIT, ticks)], &TcpInput::Timeout)`.
+
+use crate::Mealy;
+
+/// No connection
+pub const CLOSED: u8 = 0;
+/// Waiting for a connection request
+pub const LISTEN: u8 = 1;
+/// Sent SYN, waiting for a matching SYN
+pub const SYN_SENT: u8 = 2;
+/// Sent and received SYN, waiting for the ACK
+pub const SYN_RECEIVED: u8 = 3;
+/// Open connection, data flows both ways
+pub const ESTABLISHED: u8 = 4;
+/// Sent FIN, waiting for its ACK or the peer's FIN
+pub const FIN_WAIT_1: u8 = 5;
+/// FIN acknowledged, waiting for the peer's FIN
+pub const FIN_WAIT_2: u8 = 6;
+/// Peer sent FIN, waiting for the local close
+pub const CLOSE_WAIT: u8 = 7;
+/// Both sides sent FIN, waiting for the ACK of ours
+pub const CLOSING: u8 = 8;
+/// Sent FIN after the peer's, waiting for its ACK
+pub const LAST_ACK: u8 = 9;
+/// Waiting 2MSL for stray segments to expire
+pub const TIME_WAIT: u8 = 10;
+
+/// State names, indexed by state
+pub static STATE_NAMES: [&str; 11] = [
+    "CLOSED",
+    "LISTEN",
+    "SYN-SENT",
+    "SYN-RECEIVED",
+    "ESTABLISHED",
+    "FIN-WAIT-1",
+    "FIN-WAIT-2",
+    "CLOSE-WAIT",
+    "CLOSING",
+    "LAST-ACK",
+    "TIME-WAIT",
+];
+
+/// User call, received segment or timer
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TcpInput {
+    /// User OPEN, passive
+    PassiveOpen,
+    /// User OPEN, active
+    ActiveOpen,
+    /// User SEND on a listening connection
+    Send,
+    /// User CLOSE
+    Close,
+    /// Received SYN
+    Syn,
+    /// Received SYN with ACK
+    SynAck,
+    /// Received ACK of our SYN or FIN
+    Ack,
+    /// Received FIN
+    Fin,
+    /// Received FIN that also acknowledges ours
+    FinAck,
+    /// Received RST
+    Rst,
+    /// 2MSL timer expired
+    Timeout,
+}
+
+/// What to do on a transition
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TcpAction {
+    /// Nothing to send
+    Nothing,
+    /// Create the transmission control block
+    CreateTcb,
+    /// Send SYN
+    SendSyn,
+    /// Send SYN with ACK
+    SendSynAck,
+    /// Send ACK
+    SendAck,
+    /// Send FIN
+    SendFin,
+    /// Delete the transmission control block
+    DeleteTcb,
+}
+
+/// Transition table: (from_state, input, to_state)
+pub static TRANSITIONS: [(u8, TcpInput, u8); 30] = [
+    (CLOSED, TcpInput::PassiveOpen, LISTEN),
+    (CLOSED, TcpInput::ActiveOpen, SYN_SENT),
+    (LISTEN, TcpInput::Syn, SYN_RECEIVED),
+    (LISTEN, TcpInput::Send, SYN_SENT),
+    (LISTEN, TcpInput::Close, CLOSED),
+    (SYN_SENT, TcpInput::Syn, SYN_RECEIVED),
+    (SYN_SENT, TcpInput::SynAck, ESTABLISHED),
+    (SYN_SENT, TcpInput::Close, CLOSED),
+    (SYN_SENT, TcpInput::Rst, CLOSED),
+    (SYN_RECEIVED, TcpInput::Ack, ESTABLISHED),
+    (SYN_RECEIVED, TcpInput::Close, FIN_WAIT_1),
+    (SYN_RECEIVED, TcpInput::Rst, LISTEN),
+    (ESTABLISHED, TcpInput::Close, FIN_WAIT_1),
+    (ESTABLISHED, TcpInput::Fin, CLOSE_WAIT),
+    (ESTABLISHED, TcpInput::Rst, CLOSED),
+    (FIN_WAIT_1, TcpInput::Ack, FIN_WAIT_2),
+    (FIN_WAIT_1, TcpInput::Fin, CLOSING),
+    (FIN_WAIT_1, TcpInput::FinAck, TIME_WAIT),
+    (FIN_WAIT_1, TcpInput::Rst, CLOSED),
+    (FIN_WAIT_2, TcpInput::Fin, TIME_WAIT),
+    (FIN_WAIT_2, TcpInput::Rst, CLOSED),
+    (CLOSE_WAIT, TcpInput::Close, LAST_ACK),
+    (CLOSE_WAIT, TcpInput::Rst, CLOSED),
+    (CLOSING, TcpInput::Ack, TIME_WAIT),
+    (CLOSING, TcpInput::Rst, CLOSED),
+    (LAST_ACK, TcpInput::Ack, CLOSED),
+    (LAST_ACK, TcpInput::Rst, CLOSED),
+    (TIME_WAIT, TcpInput::Timeout, CLOSED),
+    (TIME_WAIT, TcpInput::Rst, CLOSED),
+    // Retransmitted FIN while waiting, acknowledge again
+    (TIME_WAIT, TcpInput::Fin, TIME_WAIT),
+];
+
+/// Output table: (state, input, action), aligned with `TRANSITIONS`
+pub static OUTPUTS: [(u8, TcpInput, TcpAction); 30] = [
+    (CLOSED, TcpInput::PassiveOpen, TcpAction::CreateTcb),
+    (CLOSED, TcpInput::ActiveOpen, TcpAction::SendSyn),
+    (LISTEN, TcpInput::Syn, TcpAction::SendSynAck),
+    (LISTEN, TcpInput::Send, TcpAction::SendSyn),
+    (LISTEN, TcpInput::Close, TcpAction::DeleteTcb),
+    // Simultaneous open
+    (SYN_SENT, TcpInput::Syn, TcpAction::SendSynAck),
+    (SYN_SENT, TcpInput::SynAck, TcpAction::SendAck),
+    (SYN_SENT, TcpInput::Close, TcpAction::DeleteTcb),
+    (SYN_SENT, TcpInput::Rst, TcpAction::DeleteTcb),
+    (SYN_RECEIVED, TcpInput::Ack, TcpAction::Nothing),
+    (SYN_RECEIVED, TcpInput::Close, TcpAction::SendFin),
+    (SYN_RECEIVED, TcpInput::Rst, TcpAction::Nothing),
+    (ESTABLISHED, TcpInput::Close, TcpAction::SendFin),
+    (ESTABLISHED, TcpInput::Fin, TcpAction::SendAck),
+    (ESTABLISHED, TcpInput::Rst, TcpAction::DeleteTcb),
+    (FIN_WAIT_1, TcpInput::Ack, TcpAction::Nothing),
+    (FIN_WAIT_1, TcpInput::Fin, TcpAction::SendAck),
+    (FIN_WAIT_1, TcpInput::FinAck, TcpAction::SendAck),
+    (FIN_WAIT_1, TcpInput::Rst, TcpAction::DeleteTcb),
+    (FIN_WAIT_2, TcpInput::Fin, TcpAction::SendAck),
+    (FIN_WAIT_2, TcpInput::Rst, TcpAction::DeleteTcb),
+    (CLOSE_WAIT, TcpInput::Close, TcpAction::SendFin),
+    (CLOSE_WAIT, TcpInput::Rst, TcpAction::DeleteTcb),
+    (CLOSING, TcpInput::Ack, TcpAction::Nothing),
+    (CLOSING, TcpInput::Rst, TcpAction::DeleteTcb),
+    (LAST_ACK, TcpInput::Ack, TcpAction::DeleteTcb),
+    (LAST_ACK, TcpInput::Rst, TcpAction::DeleteTcb),
+    (TIME_WAIT, TcpInput::Timeout, TcpAction::DeleteTcb),
+    (TIME_WAIT, TcpInput::Rst, TcpAction::DeleteTcb),
+    (TIME_WAIT, TcpInput::Fin, TcpAction::SendAck),
+];
+
+/// Create TCP connection machine in `CLOSED`, with state names attached
+///
+/// # Example
+/// ```
+/// use fsmall::presets::tcp::{self, TcpAction, TcpInput};
+///
+/// let mut conn = tcp::machine();
+/// assert_eq!(conn.step(TcpInput::ActiveOpen), Ok(TcpAction::SendSyn));
+/// assert_eq!(conn.step(TcpInput::SynAck), Ok(TcpAction::SendAck));
+/// assert_eq!(conn.state_name(), Some("ESTABLISHED"));
+/// ```
+pub fn machine() -> Mealy<'static, TcpInput, TcpAction> {
+    Mealy::new(CLOSED, &TRANSITIONS, &OUTPUTS).with_state_names(&STATE_NAMES)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{analysis, Tick};
+
+    #[test]
+    fn tcp_tables_are_consistent() {
+        assert!(Mealy::new_checked(CLOSED, &TRANSITIONS, &OUTPUTS).is_ok());
+        assert!(analysis::unreachable_states(&TRANSITIONS, CLOSED).is_empty());
+    }
+
+    #[test]
+    fn tcp_active_close_waits_2msl() {
+        let mut conn = machine().with_timeouts(&[(TIME_WAIT, 3)], &TcpInput::Timeout);
+        conn.reset(ESTABLISHED);
+        assert_eq!(conn.step(TcpInput::Close), Ok(TcpAction::SendFin));
+        assert_eq!(conn.step(TcpInput::Ack), Ok(TcpAction::Nothing));
+        assert_eq!(conn.step(TcpInput::Fin), Ok(TcpAction::SendAck));
+        assert_eq!(conn.current_state(), TIME_WAIT);
+        assert_eq!(conn.tick(), Tick::Idle);
+        assert_eq!(conn.tick(), Tick::Idle);
+        assert_eq!(conn.tick(), Tick::Timeout(Ok(TcpAction::DeleteTcb)));
+        assert_eq!(conn.current_state(), CLOSED);
+    }
+}
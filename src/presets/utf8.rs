@@ -0,0 +1,174 @@
+//! UTF-8 validation DFA
+//!
+//! The classic 9-state acceptance automaton (after Björn Höhrmann's
+//! decoder): bytes are mapped to 12 classes, and each state's output says
+//! whether the input so far is complete valid UTF-8, a valid prefix
+//! waiting for continuation bytes, or invalid. Overlong encodings,
+//! surrogates and code points above U+10FFFF are rejected.
+
+use crate::{ByteClasses, Moore};
+
+/// Validation status after a byte
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Utf8Status {
+    /// Input so far is valid and ends on a character boundary
+    Accept,
+    /// Input so far is invalid; stays so until `reset`
+    Reject,
+    /// Inside a multi-byte sequence
+    Continue,
+}
+
+/// Valid, on a character boundary
+pub const ACCEPT: u8 = 0;
+/// Invalid input seen
+pub const REJECT: u8 = 1;
+// One, two or three continuation bytes (0x80..=0xBF) expected
+const TAIL_1: u8 = 2;
+const TAIL_2: u8 = 3;
+const TAIL_3: u8 = 4;
+// After a lead byte restricting the next continuation byte's range
+const AFTER_E0: u8 = 5;
+const AFTER_ED: u8 = 6;
+const AFTER_F0: u8 = 7;
+const AFTER_F4: u8 = 8;
+
+/// Number of states
+pub const STATE_COUNT: usize = 9;
+/// Number of byte classes
+pub const CLASS_COUNT: usize = 12;
+
+/// Byte class table: `CLASSES[byte]` = class
+///
+/// 0: ASCII, 1: 80..8F, 9: 90..9F, 7: A0..BF, 8: C0, C1, F5..FF,
+/// 2: C2..DF, 10: E0, 3: E1..EC, EE, EF, 4: ED, 11: F0, 6: F1..F3, 5: F4
+pub static CLASSES: [u8; 256] = {
+    let mut classes = [0; 256];
+    let mut b = 0x80;
+    while b < 256 {
+        classes[b] = match b {
+            0x80..=0x8F => 1,
+            0x90..=0x9F => 9,
+            0xA0..=0xBF => 7,
+            0xC2..=0xDF => 2,
+            0xE0 => 10,
+            0xED => 4,
+            0xE1..=0xEF => 3,
+            0xF0 => 11,
+            0xF1..=0xF3 => 6,
+            0xF4 => 5,
+            _ => 8,
+        };
+        b += 1;
+    }
+    classes
+};
+
+const fn next(state: u8, class: u8) -> u8 {
+    match (state, class) {
+        (ACCEPT, 0) => ACCEPT,
+        (ACCEPT, 2) => TAIL_1,
+        (ACCEPT, 3) => TAIL_2,
+        (ACCEPT, 6) => TAIL_3,
+        (ACCEPT, 10) => AFTER_E0,
+        (ACCEPT, 4) => AFTER_ED,
+        (ACCEPT, 11) => AFTER_F0,
+        (ACCEPT, 5) => AFTER_F4,
+        (TAIL_1, 1 | 7 | 9) => ACCEPT,
+        (TAIL_2, 1 | 7 | 9) => TAIL_1,
+        (TAIL_3, 1 | 7 | 9) => TAIL_2,
+        // No overlong three-byte forms
+        (AFTER_E0, 7) => TAIL_1,
+        // No surrogates
+        (AFTER_ED, 1 | 9) => TAIL_1,
+        // No overlong four-byte forms
+        (AFTER_F0, 7 | 9) => TAIL_2,
+        // Nothing above U+10FFFF
+        (AFTER_F4, 1) => TAIL_2,
+        _ => REJECT,
+    }
+}
+
+/// Transition table over classes: (from_state, class, to_state), complete
+pub static TRANSITIONS: [(u8, u8, u8); STATE_COUNT * CLASS_COUNT] = {
+    let mut rows = [(0, 0, 0); STATE_COUNT * CLASS_COUNT];
+    let mut i = 0;
+    while i < rows.len() {
+        let (state, class) = ((i / CLASS_COUNT) as u8, (i % CLASS_COUNT) as u8);
+        rows[i] = (state, class, next(state, class));
+        i += 1;
+    }
+    rows
+};
+
+/// Output array: index=state, value=status
+pub static OUTPUTS: [Utf8Status; STATE_COUNT] = {
+    let mut outputs = [Utf8Status::Continue; STATE_COUNT];
+    outputs[ACCEPT as usize] = Utf8Status::Accept;
+    outputs[REJECT as usize] = Utf8Status::Reject;
+    outputs
+};
+
+/// UTF-8 validation machine: byte classes in front of the Moore machine
+pub type Utf8Machine = ByteClasses<Moore<'static, u8, Utf8Status>>;
+
+/// Create validator in `ACCEPT`
+///
+/// # Example
+/// ```
+/// use fsmall::presets::utf8::{self, Utf8Status};
+///
+/// let mut fsm = utf8::machine();
+/// assert_eq!(fsm.step(0xC3), Ok(Utf8Status::Continue));
+/// assert_eq!(fsm.step(0xA9), Ok(Utf8Status::Accept)); // é
+/// assert_eq!(fsm.step(0xFF), Ok(Utf8Status::Reject));
+/// assert_eq!(fsm.step(b'a'), Ok(Utf8Status::Reject));
+/// ```
+pub fn machine() -> Utf8Machine {
+    ByteClasses::new(Moore::new(ACCEPT, &TRANSITIONS, &OUTPUTS), &CLASSES)
+}
+
+/// Check whether `bytes` are complete, valid UTF-8
+pub fn is_valid(bytes: &[u8]) -> bool {
+    let mut fsm = machine();
+    for byte in bytes {
+        if fsm.step(*byte) == Ok(Utf8Status::Reject) {
+            return false;
+        }
+    }
+    fsm.current_state() == ACCEPT
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn utf8_agrees_with_core() {
+        let samples: [&[u8]; 10] = [
+            b"plain",
+            "h\u{e9}llo \u{20ac} \u{1f600}".as_bytes(),
+            b"\xC0\x80",
+            b"\xE0\x80\x80",
+            b"\xED\xA0\x80",
+            b"\xF4\x90\x80\x80",
+            b"\xF0\x9F\x98",
+            b"\x80",
+            "\u{10ffff}".as_bytes(),
+            b"",
+        ];
+        for sample in samples {
+            assert_eq!(is_valid(sample), core::str::from_utf8(sample).is_ok());
+        }
+    }
+
+    #[test]
+    fn utf8_every_byte_and_pair() {
+        for a in 0..=255u8 {
+            assert_eq!(is_valid(&[a]), core::str::from_utf8(&[a]).is_ok());
+            for b in 0..=255u8 {
+                assert_eq!(is_valid(&[a, b]), core::str::from_utf8(&[a, b]).is_ok());
+            }
+        }
+    }
+}
@@ -0,0 +1,247 @@
+//! Vending machine accumulating coin credit
+//!
+//! The classic teaching machine, with the credit kept as extended state in
+//! the context of a `StatefulMealy` rather than one state per amount:
+//! coin actions add to it, a guard checks "credit ≥ price" on selection,
+//! and dispensing or cancelling returns the change.
+
+use crate::{Action, Guard, Machine, StatefulMealy, StepError};
+
+/// No credit
+pub const IDLE: u8 = 0;
+/// Credit inserted, waiting for selection or cancel
+pub const COLLECTING: u8 = 1;
+
+/// Customer action
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum VendInput {
+    /// 5 cents inserted
+    Nickel,
+    /// 10 cents inserted
+    Dime,
+    /// 25 cents inserted
+    Quarter,
+    /// Product selected
+    Select,
+    /// Cancel and refund
+    Cancel,
+}
+
+/// Result of a customer action, amounts in cents
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum VendEvent {
+    /// Coin accepted, total credit now
+    Credit(u16),
+    /// Dispense the product and return the change
+    Dispense {
+        /// Change to return
+        change: u16,
+    },
+    /// Selection refused, this much is still missing
+    NeedMore(u16),
+    /// Return the whole credit
+    Refund(u16),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Outcome {
+    Credit,
+    Dispense,
+    Refund,
+}
+
+/// Context: credit and the amount returned by the last transition
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Credit {
+    cents: u16,
+    price: u16,
+    returned: u16,
+}
+
+fn add_5(c: &mut Credit) {
+    c.cents = c.cents.saturating_add(5);
+}
+
+fn add_10(c: &mut Credit) {
+    c.cents = c.cents.saturating_add(10);
+}
+
+fn add_25(c: &mut Credit) {
+    c.cents = c.cents.saturating_add(25);
+}
+
+fn vend(c: &mut Credit) {
+    c.returned = c.cents.saturating_sub(c.price);
+    c.cents = 0;
+}
+
+fn refund(c: &mut Credit) {
+    c.returned = c.cents;
+    c.cents = 0;
+}
+
+fn enough(c: &Credit) -> bool {
+    c.cents >= c.price
+}
+
+static TRANSITIONS: [(u8, VendInput, u8); 8] = [
+    (COLLECTING, VendInput::Select, IDLE),
+    (IDLE, VendInput::Nickel, COLLECTING),
+    (IDLE, VendInput::Dime, COLLECTING),
+    (IDLE, VendInput::Quarter, COLLECTING),
+    (COLLECTING, VendInput::Nickel, COLLECTING),
+    (COLLECTING, VendInput::Dime, COLLECTING),
+    (COLLECTING, VendInput::Quarter, COLLECTING),
+    (COLLECTING, VendInput::Cancel, IDLE),
+];
+
+// Too little credit leaves `Select` without a row, so `vend` cannot run
+static GUARDS: [Option<Guard<Credit>>; 1] = [Some(enough)];
+
+static OUTPUTS: [(u8, VendInput, Outcome); 8] = [
+    (COLLECTING, VendInput::Select, Outcome::Dispense),
+    (IDLE, VendInput::Nickel, Outcome::Credit),
+    (IDLE, VendInput::Dime, Outcome::Credit),
+    (IDLE, VendInput::Quarter, Outcome::Credit),
+    (COLLECTING, VendInput::Nickel, Outcome::Credit),
+    (COLLECTING, VendInput::Dime, Outcome::Credit),
+    (COLLECTING, VendInput::Quarter, Outcome::Credit),
+    (COLLECTING, VendInput::Cancel, Outcome::Refund),
+];
+
+static ACTIONS: [(u8, VendInput, Action<Credit>); 8] = [
+    (IDLE, VendInput::Nickel, add_5),
+    (IDLE, VendInput::Dime, add_10),
+    (IDLE, VendInput::Quarter, add_25),
+    (COLLECTING, VendInput::Nickel, add_5),
+    (COLLECTING, VendInput::Dime, add_10),
+    (COLLECTING, VendInput::Quarter, add_25),
+    (COLLECTING, VendInput::Cancel, refund),
+    (COLLECTING, VendInput::Select, vend),
+];
+
+/// Vending machine selling one product at a fixed price
+///
+/// # Example
+/// ```
+/// use fsmall::presets::vending::{VendEvent, VendInput, Vending};
+///
+/// let mut vm = Vending::new(35);
+/// assert_eq!(vm.step(VendInput::Quarter), Ok(VendEvent::Credit(25)));
+/// assert_eq!(vm.step(VendInput::Select), Ok(VendEvent::NeedMore(10)));
+/// assert_eq!(vm.step(VendInput::Quarter), Ok(VendEvent::Credit(50)));
+/// assert_eq!(vm.step(VendInput::Select), Ok(VendEvent::Dispense { change: 15 }));
+/// assert_eq!(vm.credit(), 0);
+/// ```
+pub struct Vending {
+    machine: StatefulMealy<'static, VendInput, Outcome, Credit>,
+}
+
+impl Vending {
+    /// Create vending machine in `IDLE`
+    ///
+    /// # Arguments
+    /// * `price` - Product price in cents
+    pub fn new(price: u16) -> Self {
+        let credit = Credit {
+            cents: 0,
+            price,
+            returned: 0,
+        };
+        Vending {
+            machine: StatefulMealy::new(IDLE, &TRANSITIONS, &OUTPUTS, credit)
+                .with_guards(&GUARDS)
+                .with_actions(&ACTIONS),
+        }
+    }
+
+    /// Process customer action
+    ///
+    /// A selection refused by the credit guard is reported as `NeedMore`.
+    ///
+    /// # Errors
+    /// * `StepError::NoTransition` - `Cancel` without credit
+    pub fn step(&mut self, input: VendInput) -> Result<VendEvent, StepError> {
+        let outcome = match self.machine.step(input) {
+            Err(StepError::NoTransition) if input == VendInput::Select => {
+                let credit = self.machine.context();
+                return Ok(VendEvent::NeedMore(
+                    credit.price.saturating_sub(credit.cents),
+                ));
+            }
+            result => result?,
+        };
+        let credit = self.machine.context();
+        Ok(match outcome {
+            Outcome::Dispense => VendEvent::Dispense {
+                change: credit.returned,
+            },
+            Outcome::Credit => VendEvent::Credit(credit.cents),
+            Outcome::Refund => VendEvent::Refund(credit.returned),
+        })
+    }
+
+    /// Get credit inserted so far, in cents
+    pub fn credit(&self) -> u16 {
+        self.machine.context().cents
+    }
+
+    /// Get current state
+    pub fn current_state(&self) -> u8 {
+        self.machine.current_state()
+    }
+
+    /// Return to `IDLE`, dropping any credit without refund
+    pub fn reset(&mut self) {
+        self.machine.reset(IDLE);
+        self.machine.context_mut().cents = 0;
+    }
+}
+
+impl Machine for Vending {
+    type Input = VendInput;
+    type Output = VendEvent;
+    type Error = StepError;
+
+    fn step(&mut self, input: VendInput) -> Result<VendEvent, StepError> {
+        Vending::step(self, input)
+    }
+
+    fn current_state(&self) -> u8 {
+        Vending::current_state(self)
+    }
+
+    /// Always returns to `IDLE`, the credit defines the rest of the state
+    fn reset(&mut self, _state: u8) {
+        Vending::reset(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vending_refunds_credit() {
+        let mut vm = Vending::new(50);
+        assert_eq!(vm.step(VendInput::Cancel), Err(StepError::NoTransition));
+        assert_eq!(vm.step(VendInput::Select), Ok(VendEvent::NeedMore(50)));
+        vm.step(VendInput::Dime).unwrap();
+        assert_eq!(vm.step(VendInput::Nickel), Ok(VendEvent::Credit(15)));
+        assert_eq!(vm.step(VendInput::Select), Ok(VendEvent::NeedMore(35)));
+        assert_eq!(vm.step(VendInput::Cancel), Ok(VendEvent::Refund(15)));
+        assert_eq!(vm.current_state(), IDLE);
+        assert_eq!(vm.credit(), 0);
+    }
+
+    #[test]
+    fn vending_exact_price_has_no_change() {
+        let mut vm = Vending::new(50);
+        vm.step(VendInput::Quarter).unwrap();
+        vm.step(VendInput::Quarter).unwrap();
+        assert_eq!(
+            vm.step(VendInput::Select),
+            Ok(VendEvent::Dispense { change: 0 })
+        );
+    }
+}
@@ -0,0 +1,164 @@
+//! Menu and wizard navigation with a universal back input
+//!
+//! Forward moves come from a transition table over screen indices; `Back`
+//! returns to the previous screen from a bounded history, so back behavior
+//! needs no table rows.
+
+use crate::{Machine, Mealy, StepError};
+
+/// Input of a `Wizard`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Nav<I> {
+    /// Follow a forward transition
+    Go(I),
+    /// Return to the previous screen
+    Back,
+}
+
+/// Screen navigation machine remembering up to `D` previous screens
+///
+/// Once the history is full, the oldest entry is forgotten.
+///
+/// # Example
+/// ```
+/// use fsmall::presets::wizard::{Nav, Wizard};
+///
+/// #[derive(Copy, Clone, Eq, PartialEq)]
+/// enum Item { Network, Wifi, Display }
+///
+/// static SCREENS: [&str; 4] = ["settings", "network", "wifi", "display"];
+/// static FORWARD: [(u8, Item, u8); 3] = [(0, Item::Network, 1), (1, Item::Wifi, 2), (0, Item::Display, 3)];
+///
+/// let mut menu: Wizard<_, 8> = Wizard::new(&SCREENS, &FORWARD);
+/// menu.step(Nav::Go(Item::Network)).unwrap();
+/// menu.step(Nav::Go(Item::Wifi)).unwrap();
+/// assert_eq!(menu.screen_name(), Some("wifi"));
+/// assert_eq!(menu.step(Nav::Back), Ok(1));
+/// assert_eq!(menu.step(Nav::Back), Ok(0));
+/// assert!(menu.step(Nav::Back).is_err()); // Nothing left
+/// ```
+pub struct Wizard<'t, I, const D: usize> {
+    machine: Mealy<'t, I, ()>,
+    // Previous screens, most recent last, [0..depth) occupied
+    history: [u8; D],
+    depth: usize,
+}
+
+impl<'t, I: Copy + Eq, const D: usize> Wizard<'t, I, D> {
+    /// Create navigation starting on screen 0
+    ///
+    /// # Arguments
+    /// * `screens` - Name array: index=screen, value=name
+    /// * `forward` - Forward table: (from_screen, input, to_screen)
+    pub fn new(screens: &'t [&'t str], forward: &'t [(u8, I, u8)]) -> Self {
+        Wizard {
+            machine: Mealy::from_output_fn(0, forward, |_screen, _input| ())
+                .with_state_names(screens),
+            history: [0; D],
+            depth: 0,
+        }
+    }
+
+    /// Navigate, returning the screen now shown
+    ///
+    /// # Errors
+    /// * `StepError::NoTransition` - No forward rule for (screen, input), or
+    ///   `Back` with an empty history
+    pub fn step(&mut self, nav: Nav<I>) -> Result<u8, StepError> {
+        match nav {
+            Nav::Go(input) => {
+                let from = self.machine.current_state();
+                self.machine.step(input)?;
+                if self.depth == D {
+                    self.history.rotate_left(1);
+                    self.depth -= 1;
+                }
+                if let Some(slot) = self.history.get_mut(self.depth) {
+                    *slot = from;
+                    self.depth += 1;
+                }
+            }
+            Nav::Back => {
+                let previous = self
+                    .depth
+                    .checked_sub(1)
+                    .and_then(|top| self.history.get(top).copied())
+                    .ok_or(StepError::NoTransition)?;
+                self.depth -= 1;
+                self.machine.reset(previous);
+            }
+        }
+        Ok(self.machine.current_state())
+    }
+
+    /// Get current screen
+    pub fn current_state(&self) -> u8 {
+        self.machine.current_state()
+    }
+
+    /// Get current screen's name
+    pub fn screen_name(&self) -> Option<&'t str> {
+        self.machine.state_name()
+    }
+
+    /// Number of screens `Back` can return through
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Jump to `screen`, clearing the history
+    pub fn reset(&mut self, screen: u8) {
+        self.machine.reset(screen);
+        self.depth = 0;
+    }
+}
+
+impl<I: Copy + Eq, const D: usize> Machine for Wizard<'_, I, D> {
+    type Input = Nav<I>;
+    type Output = u8;
+    type Error = StepError;
+
+    fn step(&mut self, nav: Nav<I>) -> Result<u8, StepError> {
+        Wizard::step(self, nav)
+    }
+
+    fn current_state(&self) -> u8 {
+        Wizard::current_state(self)
+    }
+
+    fn reset(&mut self, screen: u8) {
+        Wizard::reset(self, screen)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static SCREENS: [&str; 3] = ["a", "b", "c"];
+    static FORWARD: [(u8, char, u8); 3] = [(0, 'n', 1), (1, 'n', 2), (2, 'n', 0)];
+
+    #[test]
+    fn wizard_history_is_bounded() {
+        let mut nav: Wizard<_, 2> = Wizard::new(&SCREENS, &FORWARD);
+        for _ in 0..3 {
+            nav.step(Nav::Go('n')).unwrap();
+        }
+        // Came through 0, 1, 2; only the last two are remembered
+        assert_eq!(nav.depth(), 2);
+        assert_eq!(nav.step(Nav::Back), Ok(2));
+        assert_eq!(nav.step(Nav::Back), Ok(1));
+        assert_eq!(nav.step(Nav::Back), Err(StepError::NoTransition));
+        assert_eq!(nav.screen_name(), Some("b"));
+    }
+
+    #[test]
+    fn wizard_rejects_unknown_forward_moves() {
+        let mut nav: Wizard<_, 4> = Wizard::new(&SCREENS, &FORWARD);
+        assert_eq!(nav.step(Nav::Go('x')), Err(StepError::NoTransition));
+        assert_eq!(nav.depth(), 0);
+        nav.step(Nav::Go('n')).unwrap();
+        nav.reset(0);
+        assert_eq!(nav.depth(), 0);
+    }
+}
@@ -0,0 +1,711 @@
+//! Compile a restricted regex syntax into static DFA tables
+//!
+//! Requires the `alloc` feature. Patterns are byte-oriented and anchored
+//! at both ends: a pattern matches an input only if the whole input is
+//! consumed and the machine ends in an accepting state.
+//!
+//! Supported syntax:
+//! - Literals, `.` (any byte)
+//! - Classes `[abc]`, `[a-z]`, `[^...]`
+//! - Escapes `\d`, `\w`, `\s`, `\n`, `\r`, `\t`, `\xHH` and escaped
+//!   metacharacters such as `\.`
+//! - Grouping `(...)`, alternation `|`, quantifiers `*`, `+`, `?`
+//!
+//! Byte-level tables get large quickly; `Dfa::compress` merges equivalent
+//! bytes into classes for use with `ByteClasses`.
+//!
+//! # Example
+//! ```
+//! let dfa = fsmall::regex::compile("[0-9]+(\\.[0-9]+)?").unwrap();
+//! assert!(dfa.matches(b"3.14"));
+//! assert!(!dfa.matches(b"3."));
+//! let source = dfa.to_rust("NUMBER");
+//! assert!(source.contains("pub static NUMBER_TRANSITIONS"));
+//! ```
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+/// Error returned when a pattern cannot be compiled
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RegexError {
+    /// Unexpected character at byte offset
+    Unexpected(usize),
+    /// Pattern ended inside a group, class or escape
+    UnexpectedEnd,
+    /// DFA needs more than 256 states
+    TooManyStates,
+}
+
+/// Deterministic automaton over bytes, start state 0
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dfa {
+    /// Transition table: (from_state, byte, to_state), sorted
+    pub transitions: Vec<(u8, u8, u8)>,
+    /// Accepting states, sorted
+    pub accepting: Vec<u8>,
+    /// Number of states
+    pub states: usize,
+}
+
+impl Dfa {
+    /// Check whether the whole `input` is accepted
+    pub fn matches(&self, input: &[u8]) -> bool {
+        let mut state = 0;
+        for byte in input {
+            match self
+                .transitions
+                .iter()
+                .find(|(from, b, _to)| *from == state && b == byte)
+            {
+                Some((_from, _b, to)) => state = *to,
+                None => return false,
+            }
+        }
+        self.accepting.contains(&state)
+    }
+
+    /// Emit the tables as Rust source
+    ///
+    /// Produces `{name}_TRANSITIONS` for the machine's transition table,
+    /// `{name}_ACCEPTING` for `with_accepting`, and `{name}_OUTPUTS`, a
+    /// per-state accepting flag usable directly as Moore outputs.
+    pub fn to_rust(&self, name: &str) -> String {
+        self.tables_to_rust(name, byte_literal)
+    }
+
+    /// Merge bytes that every state treats alike into classes
+    ///
+    /// The returned DFA's transitions are over class indices; drive it
+    /// through `ByteClasses` with the returned class table.
+    pub fn compress(&self) -> ClassDfa {
+        // Signature of a byte: its target in every state
+        let signature = |byte: u8| -> Vec<Option<u8>> {
+            (0..self.states)
+                .map(|state| {
+                    self.transitions
+                        .iter()
+                        .find(|(from, b, _to)| usize::from(*from) == state && *b == byte)
+                        .map(|(_from, _b, to)| *to)
+                })
+                .collect()
+        };
+
+        let mut classes = [0u8; 256];
+        let mut seen: Vec<Vec<Option<u8>>> = Vec::new();
+        for byte in 0..=255u8 {
+            let sig = signature(byte);
+            classes[byte as usize] = match seen.iter().position(|s| *s == sig) {
+                Some(class) => class as u8,
+                None => {
+                    seen.push(sig);
+                    (seen.len() - 1) as u8
+                }
+            };
+        }
+
+        let mut transitions = Vec::new();
+        for (class, sig) in seen.iter().enumerate() {
+            for (state, to) in sig.iter().enumerate() {
+                if let Some(to) = to {
+                    transitions.push((state as u8, class as u8, *to));
+                }
+            }
+        }
+        transitions.sort_unstable();
+
+        ClassDfa {
+            classes,
+            dfa: Dfa {
+                transitions,
+                accepting: self.accepting.clone(),
+                states: self.states,
+            },
+        }
+    }
+
+    fn tables_to_rust(&self, name: &str, input: fn(u8) -> String) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "pub static {name}_TRANSITIONS: [(u8, u8, u8); {}] = [",
+            self.transitions.len()
+        );
+        for (from, byte, to) in &self.transitions {
+            let _ = writeln!(out, "    ({from}, {}, {to}),", input(*byte));
+        }
+        out.push_str("];\n\n");
+
+        let accepting: Vec<String> = self.accepting.iter().map(|s| format!("{s}")).collect();
+        let _ = writeln!(
+            out,
+            "pub static {name}_ACCEPTING: [u8; {}] = [{}];\n",
+            accepting.len(),
+            accepting.join(", ")
+        );
+
+        let outputs: Vec<&str> = (0..self.states)
+            .map(|s| {
+                if self.accepting.contains(&(s as u8)) {
+                    "true"
+                } else {
+                    "false"
+                }
+            })
+            .collect();
+        let _ = writeln!(
+            out,
+            "pub static {name}_OUTPUTS: [bool; {}] = [{}];",
+            self.states,
+            outputs.join(", ")
+        );
+        out
+    }
+}
+
+/// DFA over byte classes, produced by `Dfa::compress`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassDfa {
+    /// Class table: `classes[byte]` = class index
+    pub classes: [u8; 256],
+    /// DFA whose inputs are class indices
+    pub dfa: Dfa,
+}
+
+impl ClassDfa {
+    /// Number of distinct byte classes
+    pub fn class_count(&self) -> usize {
+        self.classes
+            .iter()
+            .map(|c| usize::from(*c) + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Check whether the whole `input` is accepted
+    pub fn matches(&self, input: &[u8]) -> bool {
+        let mut state = 0;
+        for byte in input {
+            let class = self.classes[*byte as usize];
+            match self
+                .dfa
+                .transitions
+                .iter()
+                .find(|(from, c, _to)| *from == state && *c == class)
+            {
+                Some((_from, _c, to)) => state = *to,
+                None => return false,
+            }
+        }
+        self.dfa.accepting.contains(&state)
+    }
+
+    /// Emit the tables as Rust source
+    ///
+    /// Same as `Dfa::to_rust`, with inputs as class indices, plus
+    /// `{name}_CLASSES` for `ByteClasses`.
+    pub fn to_rust(&self, name: &str) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "pub static {name}_CLASSES: [u8; 256] = [");
+        for chunk in self.classes.chunks(16) {
+            let row: Vec<String> = chunk.iter().map(|c| format!("{c}")).collect();
+            let _ = writeln!(out, "    {},", row.join(", "));
+        }
+        out.push_str("];\n\n");
+        out.push_str(&self.dfa.tables_to_rust(name, |class| format!("{class}")));
+        out
+    }
+}
+
+/// Compile `pattern` into a minimal DFA
+///
+/// # Errors
+/// * `RegexError::Unexpected` - Syntax error at the given offset
+/// * `RegexError::UnexpectedEnd` - Unterminated group, class or escape
+/// * `RegexError::TooManyStates` - Result does not fit in 256 states
+pub fn compile(pattern: &str) -> Result<Dfa, RegexError> {
+    let mut parser = Parser {
+        src: pattern.as_bytes(),
+        pos: 0,
+    };
+    let ast = parser.alternation()?;
+    if parser.pos < parser.src.len() {
+        return Err(RegexError::Unexpected(parser.pos));
+    }
+
+    let mut nfa = Nfa::default();
+    let (start, end) = nfa.build(&ast);
+    let (table, accepting) = nfa.determinize(start, end);
+    minimize(&table, &accepting)
+}
+
+// Render a byte as a readable Rust u8 literal
+fn byte_literal(byte: u8) -> String {
+    if byte.is_ascii_graphic() && byte != b'\'' && byte != b'\\' {
+        format!("b'{}'", byte as char)
+    } else {
+        format!("0x{byte:02x}")
+    }
+}
+
+type ByteSet = [bool; 256];
+
+// Dense DFA row: row[byte] = next state, None = dead
+type DenseRow = [Option<usize>; 256];
+
+enum Node {
+    Empty,
+    Set(Box<ByteSet>),
+    Concat(Vec<Node>),
+    Alt(Vec<Node>),
+    Star(Box<Node>),
+    Plus(Box<Node>),
+    Opt(Box<Node>),
+}
+
+struct Parser<'a> {
+    src: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.src.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Result<u8, RegexError> {
+        let byte = self.peek().ok_or(RegexError::UnexpectedEnd)?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    // alternation := concat ('|' concat)*
+    fn alternation(&mut self) -> Result<Node, RegexError> {
+        let mut branches = vec![self.concat()?];
+        while self.peek() == Some(b'|') {
+            self.pos += 1;
+            branches.push(self.concat()?);
+        }
+        Ok(if branches.len() == 1 {
+            branches.pop().unwrap_or(Node::Empty)
+        } else {
+            Node::Alt(branches)
+        })
+    }
+
+    // concat := repeat*
+    fn concat(&mut self) -> Result<Node, RegexError> {
+        let mut items = Vec::new();
+        while let Some(byte) = self.peek() {
+            if byte == b'|' || byte == b')' {
+                break;
+            }
+            items.push(self.repeat()?);
+        }
+        Ok(match items.len() {
+            0 => Node::Empty,
+            1 => items.pop().unwrap_or(Node::Empty),
+            _ => Node::Concat(items),
+        })
+    }
+
+    // repeat := atom ('*' | '+' | '?')*
+    fn repeat(&mut self) -> Result<Node, RegexError> {
+        let mut node = self.atom()?;
+        loop {
+            node = match self.peek() {
+                Some(b'*') => Node::Star(Box::new(node)),
+                Some(b'+') => Node::Plus(Box::new(node)),
+                Some(b'?') => Node::Opt(Box::new(node)),
+                _ => return Ok(node),
+            };
+            self.pos += 1;
+        }
+    }
+
+    fn atom(&mut self) -> Result<Node, RegexError> {
+        let at = self.pos;
+        match self.next()? {
+            b'(' => {
+                let inner = self.alternation()?;
+                if self.next()? != b')' {
+                    return Err(RegexError::Unexpected(self.pos - 1));
+                }
+                Ok(inner)
+            }
+            b'[' => self.class(),
+            b'.' => Ok(Node::Set(Box::new([true; 256]))),
+            b'\\' => {
+                let mut set = [false; 256];
+                if let Some(literal) = self.escape(&mut set)? {
+                    set[literal as usize] = true;
+                }
+                Ok(Node::Set(Box::new(set)))
+            }
+            b'*' | b'+' | b'?' | b')' | b']' => Err(RegexError::Unexpected(at)),
+            byte => Ok(Node::Set(Box::new(single(byte)))),
+        }
+    }
+
+    // Parse after '[' up to and including ']'
+    fn class(&mut self) -> Result<Node, RegexError> {
+        let negate = self.peek() == Some(b'^');
+        if negate {
+            self.pos += 1;
+        }
+
+        let mut set = [false; 256];
+        let mut first = true;
+        loop {
+            let at = self.pos;
+            let byte = self.next()?;
+            let low = match byte {
+                b']' if !first => break,
+                b'\\' => self.escape(&mut set)?,
+                other => Some(other),
+            };
+            first = false;
+
+            // Range a-z, unless '-' is last in the class
+            if self.peek() == Some(b'-') && self.src.get(self.pos + 1) != Some(&b']') {
+                // Class escapes such as `\d` cannot bound a range
+                let low = low.ok_or(RegexError::Unexpected(at))?;
+                self.pos += 1;
+                let at = self.pos;
+                let high = match self.next()? {
+                    b'\\' => self
+                        .escape(&mut [false; 256])?
+                        .ok_or(RegexError::Unexpected(at))?,
+                    other => other,
+                };
+                if high < low {
+                    return Err(RegexError::Unexpected(self.pos - 1));
+                }
+                for b in low..=high {
+                    set[b as usize] = true;
+                }
+            } else if let Some(low) = low {
+                set[low as usize] = true;
+            }
+        }
+
+        if negate {
+            for b in set.iter_mut() {
+                *b = !*b;
+            }
+        }
+        Ok(Node::Set(Box::new(set)))
+    }
+
+    // Parse after '\'. Classes such as `\d` are added to `set`,
+    // single escaped bytes are returned.
+    fn escape(&mut self, set: &mut ByteSet) -> Result<Option<u8>, RegexError> {
+        let byte = self.next()?;
+        let class: Option<fn(u8) -> bool> = match byte {
+            b'd' => Some(|b: u8| b.is_ascii_digit()),
+            b'w' => Some(|b: u8| b.is_ascii_alphanumeric() || b == b'_'),
+            b's' => Some(|b: u8| b.is_ascii_whitespace()),
+            _ => None,
+        };
+        if let Some(pred) = class {
+            for b in 0..=255u8 {
+                if pred(b) {
+                    set[b as usize] = true;
+                }
+            }
+            return Ok(None);
+        }
+
+        let literal = match byte {
+            b'n' => b'\n',
+            b'r' => b'\r',
+            b't' => b'\t',
+            b'x' => {
+                let hi = hex(self.next()?).ok_or(RegexError::Unexpected(self.pos - 1))?;
+                let lo = hex(self.next()?).ok_or(RegexError::Unexpected(self.pos - 1))?;
+                hi << 4 | lo
+            }
+            other if other.is_ascii_alphanumeric() => {
+                return Err(RegexError::Unexpected(self.pos - 1))
+            }
+            other => other,
+        };
+        Ok(Some(literal))
+    }
+}
+
+fn hex(byte: u8) -> Option<u8> {
+    (byte as char).to_digit(16).map(|d| d as u8)
+}
+
+fn single(byte: u8) -> ByteSet {
+    let mut set = [false; 256];
+    set[byte as usize] = true;
+    set
+}
+
+// Thompson NFA: each state has epsilon edges and at most one byte-set edge
+#[derive(Default)]
+struct Nfa {
+    eps: Vec<Vec<usize>>,
+    edge: Vec<Option<(ByteSet, usize)>>,
+}
+
+impl Nfa {
+    fn add(&mut self) -> usize {
+        self.eps.push(Vec::new());
+        self.edge.push(None);
+        self.eps.len() - 1
+    }
+
+    // Build fragment for `node`, returning (start, end)
+    fn build(&mut self, node: &Node) -> (usize, usize) {
+        match node {
+            Node::Empty => {
+                let s = self.add();
+                (s, s)
+            }
+            Node::Set(set) => {
+                let s = self.add();
+                let e = self.add();
+                self.edge[s] = Some((**set, e));
+                (s, e)
+            }
+            Node::Concat(items) => {
+                let s = self.add();
+                let mut end = s;
+                for item in items {
+                    let (is, ie) = self.build(item);
+                    self.eps[end].push(is);
+                    end = ie;
+                }
+                (s, end)
+            }
+            Node::Alt(branches) => {
+                let s = self.add();
+                let e = self.add();
+                for branch in branches {
+                    let (bs, be) = self.build(branch);
+                    self.eps[s].push(bs);
+                    self.eps[be].push(e);
+                }
+                (s, e)
+            }
+            Node::Star(inner) | Node::Plus(inner) | Node::Opt(inner) => {
+                let s = self.add();
+                let e = self.add();
+                let (is, ie) = self.build(inner);
+                self.eps[s].push(is);
+                self.eps[ie].push(e);
+                if !matches!(node, Node::Plus(_)) {
+                    self.eps[s].push(e);
+                }
+                if !matches!(node, Node::Opt(_)) {
+                    self.eps[ie].push(is);
+                }
+                (s, e)
+            }
+        }
+    }
+
+    fn closure(&self, states: &mut Vec<usize>) {
+        let mut stack = states.clone();
+        while let Some(s) = stack.pop() {
+            for &t in &self.eps[s] {
+                if !states.contains(&t) {
+                    states.push(t);
+                    stack.push(t);
+                }
+            }
+        }
+        states.sort_unstable();
+    }
+
+    // Subset construction; returns dense table and accepting flags
+    fn determinize(&self, start: usize, end: usize) -> (Vec<DenseRow>, Vec<bool>) {
+        let mut first = vec![start];
+        self.closure(&mut first);
+
+        let mut ids: BTreeMap<Vec<usize>, usize> = BTreeMap::new();
+        let mut sets = vec![first.clone()];
+        ids.insert(first, 0);
+        let mut table = Vec::new();
+
+        let mut current = 0;
+        while current < sets.len() {
+            let mut row = [None; 256];
+            for (byte, slot) in row.iter_mut().enumerate() {
+                let mut next: Vec<usize> = sets[current]
+                    .iter()
+                    .filter_map(|&s| match &self.edge[s] {
+                        Some((set, to)) if set[byte] => Some(*to),
+                        _ => None,
+                    })
+                    .collect();
+                if next.is_empty() {
+                    continue;
+                }
+                self.closure(&mut next);
+                let id = match ids.get(&next) {
+                    Some(id) => *id,
+                    None => {
+                        sets.push(next.clone());
+                        ids.insert(next, sets.len() - 1);
+                        sets.len() - 1
+                    }
+                };
+                *slot = Some(id);
+            }
+            table.push(row);
+            current += 1;
+        }
+
+        let accepting = sets.iter().map(|set| set.contains(&end)).collect();
+        (table, accepting)
+    }
+}
+
+// Partition refinement; keeps the start state as state 0
+fn minimize(table: &[DenseRow], accepting: &[bool]) -> Result<Dfa, RegexError> {
+    let mut class: Vec<usize> = accepting.iter().map(|&a| usize::from(a)).collect();
+    loop {
+        let mut signatures: BTreeMap<(usize, Vec<Option<usize>>), usize> = BTreeMap::new();
+        let mut refined = Vec::with_capacity(class.len());
+        for (state, row) in table.iter().enumerate() {
+            let targets = row.iter().map(|t| t.map(|t| class[t])).collect();
+            let count = signatures.len();
+            refined.push(*signatures.entry((class[state], targets)).or_insert(count));
+        }
+        let stable = signatures.len() == count_classes(&class);
+        class = refined;
+        if stable {
+            break;
+        }
+    }
+
+    // Renumber classes in discovery order so the start state is 0
+    let mut order: Vec<Option<usize>> = vec![None; table.len()];
+    let mut next_id = 0;
+    for &c in &class {
+        if order[c].is_none() {
+            order[c] = Some(next_id);
+            next_id += 1;
+        }
+    }
+    if next_id > 256 {
+        return Err(RegexError::TooManyStates);
+    }
+    let id = |state: usize| order[class[state]].unwrap_or(0) as u8;
+
+    let mut transitions = Vec::new();
+    let mut accepting_states = Vec::new();
+    let mut seen = vec![false; next_id];
+    for (state, row) in table.iter().enumerate() {
+        let from = id(state);
+        if seen[from as usize] {
+            continue;
+        }
+        seen[from as usize] = true;
+        if accepting[state] {
+            accepting_states.push(from);
+        }
+        for (byte, to) in row.iter().enumerate() {
+            if let Some(to) = to {
+                transitions.push((from, byte as u8, id(*to)));
+            }
+        }
+    }
+    transitions.sort_unstable();
+    accepting_states.sort_unstable();
+
+    Ok(Dfa {
+        transitions,
+        accepting: accepting_states,
+        states: next_id,
+    })
+}
+
+fn count_classes(class: &[usize]) -> usize {
+    let mut seen: Vec<usize> = class.to_vec();
+    seen.sort_unstable();
+    seen.dedup();
+    seen.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Moore;
+
+    #[test]
+    fn regex_matches_whole_input() {
+        let dfa = compile("a(b|c)*d").unwrap();
+        assert!(dfa.matches(b"ad"));
+        assert!(dfa.matches(b"abcbd"));
+        assert!(!dfa.matches(b"abc"));
+        assert!(!dfa.matches(b"xad"));
+    }
+
+    #[test]
+    fn regex_minimizes_states() {
+        // (a|b)*abb needs exactly 4 states
+        let dfa = compile("(a|b)*abb").unwrap();
+        assert_eq!(dfa.states, 4);
+        assert!(dfa.matches(b"babb"));
+    }
+
+    #[test]
+    fn regex_classes_and_escapes() {
+        let dfa = compile("[^0-9]\\d+\\.[a-c-]?[\\n\\x41]").unwrap();
+        assert!(dfa.matches(b"x42.\n"));
+        assert!(dfa.matches(b"_1.-A"));
+        assert!(!dfa.matches(b"142.A"));
+        assert!(!dfa.matches(b"x4.n"));
+
+        let dfa = compile("[\\x00-\\x1f][a-\\x7a]").unwrap();
+        assert!(dfa.matches(b"\x1bz"));
+        assert!(!dfa.matches(b"Az"));
+        assert!(!dfa.matches(b"\x1b{"));
+    }
+
+    #[test]
+    fn regex_compress_merges_byte_classes() {
+        let dfa = compile("[a-z]+[0-9]").unwrap();
+        let compressed = dfa.compress();
+        // Letters, digits, everything else
+        assert_eq!(compressed.class_count(), 3);
+        assert!(compressed.dfa.transitions.len() < dfa.transitions.len() / 10);
+        assert!(compressed.matches(b"abc7"));
+        assert!(!compressed.matches(b"abc"));
+        assert!(compressed
+            .to_rust("ID")
+            .contains("pub static ID_CLASSES: [u8; 256]"));
+    }
+
+    #[test]
+    fn regex_syntax_errors() {
+        assert_eq!(compile("a)").unwrap_err(), RegexError::Unexpected(1));
+        assert_eq!(compile("(ab").unwrap_err(), RegexError::UnexpectedEnd);
+        assert_eq!(compile("*a").unwrap_err(), RegexError::Unexpected(0));
+        assert_eq!(compile("[a-\\d]").unwrap_err(), RegexError::Unexpected(3));
+        assert_eq!(compile("[\\d-z]").unwrap_err(), RegexError::Unexpected(1));
+    }
+
+    #[test]
+    fn regex_tables_drive_moore() {
+        let dfa = compile("ok|okay").unwrap();
+        let transitions: &'static [(u8, u8, u8)] = Box::leak(dfa.transitions.into_boxed_slice());
+        let accepting: &'static [u8] = Box::leak(dfa.accepting.into_boxed_slice());
+        let outputs: &'static [bool] = Box::leak(vec![false; dfa.states].into_boxed_slice());
+
+        let mut fsm = Moore::new(0, transitions, outputs).with_accepting(accepting);
+        assert!(fsm.run(b"okay").unwrap().accepted);
+        fsm.reset(0);
+        assert!(!fsm.run(b"oka").unwrap().accepted);
+    }
+}
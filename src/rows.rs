@@ -0,0 +1,272 @@
+//! Mealy machines over a single combined table
+
+use crate::{Machine, StepError};
+
+/// Mealy machine whose rows carry both the next state and the output
+///
+/// A step is one scan of one table, where `Mealy::step` scans the
+/// transition table and then the output table; this halves worst-case
+/// step time on cores without caches. Rows cannot lack an output, so
+/// `step` never returns `StepError::NoOutput`. `mealy_tables!` with a
+/// single table name builds the rows.
+///
+/// # Example
+/// ```
+/// use fsmall::{mealy_tables, MealyRows};
+///
+/// #[derive(Copy, Clone, Eq, PartialEq)]
+/// enum Input { Toggle }
+///
+/// mealy_tables! {
+///     static ROWS: Input => bool = [
+///         (0, Input::Toggle) -> (1, true),
+///         (1, Input::Toggle) -> (0, false),
+///     ];
+/// }
+///
+/// let mut fsm = MealyRows::new(0, &ROWS);
+/// assert_eq!(fsm.step(Input::Toggle), Ok(true));
+/// assert_eq!(fsm.step(Input::Toggle), Ok(false));
+/// ```
+#[derive(Debug)]
+pub struct MealyRows<'t, I, O> {
+    state: u8,
+    // Table: (from_state, input, to_state, output)
+    rows: &'t [(u8, I, u8, O)],
+}
+
+impl<'t, I: Copy + Eq, O: Copy> MealyRows<'t, I, O> {
+    /// Create new Mealy machine over combined rows
+    ///
+    /// # Arguments
+    /// * `initial_state` - Starting state (0-255)
+    /// * `rows` - Table: (from_state, input, to_state, output), first
+    ///   match wins
+    pub const fn new(initial_state: u8, rows: &'t [(u8, I, u8, O)]) -> Self {
+        MealyRows {
+            state: initial_state,
+            rows,
+        }
+    }
+
+    /// Process input, transition to next state, return output
+    ///
+    /// # Errors
+    /// * `StepError::NoTransition` - No row for (state, input)
+    #[inline]
+    pub fn step(&mut self, input: I) -> Result<O, StepError> {
+        let (next, output) = self
+            .rows
+            .iter()
+            .find(|(from, i, _to, _o)| *from == self.state && *i == input)
+            .map(|(_from, _i, to, o)| (*to, *o))
+            .ok_or(StepError::NoTransition)?;
+        self.state = next;
+        Ok(output)
+    }
+
+    /// Get current state
+    pub fn current_state(&self) -> u8 {
+        self.state
+    }
+
+    /// Get rows
+    pub fn rows(&self) -> &'t [(u8, I, u8, O)] {
+        self.rows
+    }
+
+    /// Reset to specific state
+    pub fn reset(&mut self, state: u8) {
+        self.state = state;
+    }
+}
+
+impl<I, O> Clone for MealyRows<'_, I, O> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<I, O> Copy for MealyRows<'_, I, O> {}
+
+impl<I: Copy + Eq, O: Copy> Machine for MealyRows<'_, I, O> {
+    type Input = I;
+    type Output = O;
+    type Error = StepError;
+
+    fn step(&mut self, input: I) -> Result<O, StepError> {
+        MealyRows::step(self, input)
+    }
+
+    fn current_state(&self) -> u8 {
+        MealyRows::current_state(self)
+    }
+
+    fn reset(&mut self, state: u8) {
+        MealyRows::reset(self, state)
+    }
+}
+
+/// `MealyRows` remembering its `K` most recently matched rows
+///
+/// Steps check the remembered rows first, most recent first, and scan the
+/// whole table only on a miss. When traffic keeps hitting a few hot rows,
+/// most steps then cost at most `K` compares. A remembered row was the
+/// first match of a full scan and rows never change, so results are those
+/// of `MealyRows::step`.
+///
+/// # Example
+/// ```
+/// use fsmall::rows::CachedRows;
+///
+/// static ROWS: [(u8, u8, u8, char); 3] = [(0, 1, 0, 'x'), (0, 2, 0, 'y'), (0, 3, 0, 'z')];
+///
+/// let mut fsm: CachedRows<u8, char, 2> = CachedRows::new(0, &ROWS);
+/// assert_eq!(fsm.step(3), Ok('z')); // Scans, then remembers row 2
+/// assert_eq!(fsm.step(3), Ok('z')); // One compare
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct CachedRows<'t, I, O, const K: usize> {
+    machine: MealyRows<'t, I, O>,
+    // Indices of recently matched rows, most recent first; out of range
+    // for empty slots
+    recent: [usize; K],
+}
+
+impl<'t, I: Copy + Eq, O: Copy, const K: usize> CachedRows<'t, I, O, K> {
+    /// Create new machine over combined rows with an empty cache
+    ///
+    /// # Arguments
+    /// * `initial_state` - Starting state (0-255)
+    /// * `rows` - Table: (from_state, input, to_state, output), first
+    ///   match wins
+    pub const fn new(initial_state: u8, rows: &'t [(u8, I, u8, O)]) -> Self {
+        CachedRows {
+            machine: MealyRows::new(initial_state, rows),
+            recent: [usize::MAX; K],
+        }
+    }
+
+    /// Process input, transition to next state, return output
+    ///
+    /// # Errors
+    /// * `StepError::NoTransition` - No row for (state, input)
+    #[inline]
+    pub fn step(&mut self, input: I) -> Result<O, StepError> {
+        let state = self.machine.state;
+        let rows = self.machine.rows;
+        let matches = |row: &usize| {
+            rows.get(*row)
+                .is_some_and(|(from, i, _to, _o)| *from == state && *i == input)
+        };
+        let row = match self.recent.iter().position(matches) {
+            Some(slot) => {
+                // Move to front
+                if let Some(recent) = self.recent.get_mut(..=slot) {
+                    recent.rotate_right(1);
+                }
+                self.recent.first().copied()
+            }
+            None => {
+                let row = rows
+                    .iter()
+                    .position(|(from, i, _to, _o)| *from == state && *i == input);
+                if let Some(row) = row {
+                    // Make room at the front, dropping the least recent
+                    self.recent.rotate_right(usize::from(K > 0));
+                    if let Some(first) = self.recent.first_mut() {
+                        *first = row;
+                    }
+                }
+                row
+            }
+        };
+        let (_from, _i, to, output) = row
+            .and_then(|row| rows.get(row))
+            .copied()
+            .ok_or(StepError::NoTransition)?;
+        self.machine.state = to;
+        Ok(output)
+    }
+
+    /// Get current state
+    pub fn current_state(&self) -> u8 {
+        self.machine.current_state()
+    }
+
+    /// Reset to specific state, keeping the cache
+    pub fn reset(&mut self, state: u8) {
+        self.machine.reset(state);
+    }
+
+    /// Forget the remembered rows
+    pub fn clear_cache(&mut self) {
+        self.recent = [usize::MAX; K];
+    }
+}
+
+impl<I: Copy + Eq, O: Copy, const K: usize> Machine for CachedRows<'_, I, O, K> {
+    type Input = I;
+    type Output = O;
+    type Error = StepError;
+
+    fn step(&mut self, input: I) -> Result<O, StepError> {
+        CachedRows::step(self, input)
+    }
+
+    fn current_state(&self) -> u8 {
+        CachedRows::current_state(self)
+    }
+
+    fn reset(&mut self, state: u8) {
+        CachedRows::reset(self, state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Mealy;
+
+    #[test]
+    fn rows_step_like_split_tables() {
+        static ROWS: [(u8, char, u8, u8); 4] = [
+            (0, 'a', 1, 10),
+            (1, 'b', 0, 20),
+            (1, 'a', 1, 30),
+            (1, 'b', 1, 99),
+        ];
+        static TRANS: [(u8, char, u8); 4] = [(0, 'a', 1), (1, 'b', 0), (1, 'a', 1), (1, 'b', 1)];
+        static OUTS: [(u8, char, u8); 4] = [(0, 'a', 10), (1, 'b', 20), (1, 'a', 30), (1, 'b', 99)];
+
+        let mut rows = MealyRows::new(0, &ROWS);
+        let mut mealy = Mealy::new(0, &TRANS, &OUTS);
+        for input in "aabbaxab".chars() {
+            assert_eq!(rows.step(input), mealy.step(input));
+            assert_eq!(rows.current_state(), mealy.current_state());
+        }
+    }
+
+    #[test]
+    fn cached_rows_keep_first_match_semantics() {
+        // Row 4 is shadowed by row 1 and must never be taken
+        static ROWS: [(u8, u8, u8, u8); 5] = [
+            (0, 1, 1, 10),
+            (1, 2, 0, 20),
+            (1, 1, 1, 30),
+            (0, 3, 0, 40),
+            (1, 2, 1, 99),
+        ];
+
+        let mut cached: CachedRows<u8, u8, 2> = CachedRows::new(0, &ROWS);
+        let mut plain = MealyRows::new(0, &ROWS);
+        for input in [1, 1, 2, 3, 3, 1, 2, 2, 1, 1, 5] {
+            assert_eq!(cached.step(input), plain.step(input));
+            assert_eq!(cached.current_state(), plain.current_state());
+        }
+        assert_eq!(cached.recent, [2, 0]);
+
+        let mut uncached: CachedRows<u8, u8, 0> = CachedRows::new(0, &ROWS);
+        assert_eq!(uncached.step(1), Ok(10));
+    }
+}
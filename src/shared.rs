@@ -0,0 +1,278 @@
+//! Machines shared between the main loop and interrupt handlers
+//!
+//! Every access runs inside a critical section, so a `SharedFsm` can live
+//! in a `static` and be stepped from any context. With the
+//! `critical-section` feature, the default `Global` locks through
+//! `critical_section::with`, so no glue is needed:
+//!
+//! ```
+//! # #[cfg(feature = "critical-section")] {
+//! use fsmall::shared::SharedFsm;
+//! use fsmall::Mealy;
+//!
+//! static TRANSITIONS: [(u8, u8, u8); 1] = [(0, b'x', 1)];
+//! static OUTPUTS: [(u8, u8, bool); 1] = [(0, b'x', true)];
+//!
+//! let fsm: SharedFsm<_> = SharedFsm::new(Mealy::new(0, &TRANSITIONS, &OUTPUTS));
+//! assert_eq!(fsm.step(b'x'), Ok(true));
+//! # }
+//! ```
+//!
+//! Without it, implement `CriticalSection` for the target's own primitive.
+
+use core::cell::{Cell, UnsafeCell};
+use core::marker::PhantomData;
+
+use crate::Machine;
+
+/// Runs code with all other contexts excluded
+///
+/// # Safety
+/// `with` must guarantee that no other call to `with` on any core or in
+/// any interrupt handler runs concurrently with `f`, e.g. by disabling
+/// interrupts on a single-core target.
+pub unsafe trait CriticalSection {
+    /// Run `f` inside the critical section
+    fn with<R>(f: impl FnOnce() -> R) -> R;
+}
+
+/// Critical section of the `critical-section` crate
+///
+/// Implements `CriticalSection` with the `critical-section` feature; the
+/// target's HAL or runtime crate provides the actual implementation.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Global;
+
+// SAFETY: `critical_section::with` excludes every other context, on every
+// core, while its closure runs.
+#[cfg(feature = "critical-section")]
+unsafe impl CriticalSection for Global {
+    #[inline]
+    fn with<R>(f: impl FnOnce() -> R) -> R {
+        critical_section::with(|_| f())
+    }
+}
+
+/// Machine wrapper whose every access runs inside critical section `CS`
+///
+/// # Example
+/// ```
+/// use fsmall::shared::{CriticalSection, SharedFsm};
+/// use fsmall::Mealy;
+///
+/// // Stand-in for `critical_section::with` in this single-threaded example
+/// struct Cs;
+/// unsafe impl CriticalSection for Cs {
+///     fn with<R>(f: impl FnOnce() -> R) -> R {
+///         f()
+///     }
+/// }
+///
+/// #[derive(Copy, Clone, Eq, PartialEq)]
+/// enum Input { Tick }
+///
+/// static TRANSITIONS: [(u8, Input, u8); 2] = [(0, Input::Tick, 1), (1, Input::Tick, 0)];
+/// static OUTPUTS: [(u8, Input, bool); 2] = [(0, Input::Tick, true), (1, Input::Tick, false)];
+///
+/// let fsm: SharedFsm<_, Cs> = SharedFsm::new(Mealy::new(0, &TRANSITIONS, &OUTPUTS));
+/// assert_eq!(fsm.step(Input::Tick), Ok(true));
+/// assert_eq!(fsm.current_state(), 1);
+/// ```
+pub struct SharedFsm<M, CS = Global> {
+    machine: UnsafeCell<M>,
+    // Set while `with` holds the machine, guards against reentrant use
+    busy: Cell<bool>,
+    _cs: PhantomData<CS>,
+}
+
+// SAFETY: the machine and `busy` are only touched inside `CS::with`, which
+// the `CriticalSection` contract makes mutually exclusive across contexts.
+unsafe impl<M: Send, CS> Sync for SharedFsm<M, CS> {}
+
+impl<M, CS> SharedFsm<M, CS> {
+    /// Wrap `machine`, usable in a `static`
+    pub const fn new(machine: M) -> Self {
+        SharedFsm {
+            machine: UnsafeCell::new(machine),
+            busy: Cell::new(false),
+            _cs: PhantomData,
+        }
+    }
+
+    /// Consume wrapper, returning the machine
+    pub fn into_inner(self) -> M {
+        self.machine.into_inner()
+    }
+}
+
+impl<M: Machine, CS: CriticalSection> SharedFsm<M, CS> {
+    /// Run `f` with exclusive access to the machine
+    ///
+    /// # Panics
+    /// If called again from inside `f` (e.g. from a guard or action), see
+    /// `try_with` for a panic-free variant.
+    pub fn with<R>(&self, f: impl FnOnce(&mut M) -> R) -> R {
+        match self.try_with(f) {
+            Some(result) => result,
+            None => panic!("SharedFsm accessed reentrantly"),
+        }
+    }
+
+    /// Run `f` with exclusive access to the machine
+    ///
+    /// Returns `None` without running `f` if called again from inside `f`.
+    #[inline]
+    pub fn try_with<R>(&self, f: impl FnOnce(&mut M) -> R) -> Option<R> {
+        CS::with(|| {
+            if self.busy.replace(true) {
+                return None;
+            }
+            // Clears `busy` even if `f` unwinds
+            let _busy = Busy(&self.busy);
+            // SAFETY: inside the critical section and not reentrant, so this
+            // is the only live reference to the machine.
+            Some(f(unsafe { &mut *self.machine.get() }))
+        })
+    }
+
+    /// Process input inside the critical section, never panicking
+    ///
+    /// Returns `None` if called reentrantly.
+    #[inline]
+    pub fn try_step(&self, input: M::Input) -> Option<Result<M::Output, M::Error>> {
+        self.try_with(|m| m.step(input))
+    }
+
+    /// Process input inside the critical section
+    ///
+    /// # Errors
+    /// Whatever the wrapped machine's `step` returns.
+    ///
+    /// # Panics
+    /// If called reentrantly, see `try_step` for a panic-free variant.
+    pub fn step(&self, input: M::Input) -> Result<M::Output, M::Error> {
+        self.with(|m| m.step(input))
+    }
+
+    /// Get current state
+    ///
+    /// # Panics
+    /// If called reentrantly, see `try_with`.
+    pub fn current_state(&self) -> u8 {
+        self.with(|m| m.current_state())
+    }
+
+    /// Reset to specific state inside the critical section
+    ///
+    /// # Panics
+    /// If called reentrantly, see `try_with`.
+    pub fn reset(&self, state: u8) {
+        self.with(|m| m.reset(state))
+    }
+}
+
+// Clears the busy flag when dropped
+struct Busy<'a>(&'a Cell<bool>);
+
+impl Drop for Busy<'_> {
+    fn drop(&mut self) {
+        self.0.set(false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::Moore;
+    use std::sync::Mutex;
+
+    // Global lock standing in for disabling interrupts
+    static LOCK: Mutex<()> = Mutex::new(());
+
+    struct TestCs;
+
+    unsafe impl CriticalSection for TestCs {
+        fn with<R>(f: impl FnOnce() -> R) -> R {
+            let _guard = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+            f()
+        }
+    }
+
+    #[derive(Copy, Clone, Eq, PartialEq, Debug)]
+    enum TestInput {
+        Inc,
+    }
+
+    static TRANS: [(u8, TestInput, u8); 3] = [
+        (0, TestInput::Inc, 1),
+        (1, TestInput::Inc, 2),
+        (2, TestInput::Inc, 0),
+    ];
+
+    static OUTS: [u8; 3] = [0, 1, 2];
+
+    #[test]
+    fn shared_steps_from_many_threads() {
+        let fsm: SharedFsm<_, TestCs> = SharedFsm::new(Moore::new(0, &TRANS, &OUTS));
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for _ in 0..30 {
+                        fsm.step(TestInput::Inc).unwrap();
+                    }
+                });
+            }
+        });
+        assert_eq!(fsm.current_state(), 0); // 120 steps, cycle of 3
+    }
+
+    #[test]
+    fn shared_with_gives_exclusive_access() {
+        let fsm: SharedFsm<_, TestCs> = SharedFsm::new(Moore::new(0, &TRANS, &OUTS));
+        let out = fsm.with(|m| {
+            m.reset(2);
+            m.current_output()
+        });
+        assert_eq!(out, Ok(2));
+        assert_eq!(fsm.step(TestInput::Inc), Ok(0));
+        assert_eq!(fsm.into_inner().current_output(), Ok(0));
+    }
+
+    // Nests like `critical_section::with` does
+    struct ReentrantCs;
+
+    unsafe impl CriticalSection for ReentrantCs {
+        fn with<R>(f: impl FnOnce() -> R) -> R {
+            f()
+        }
+    }
+
+    #[test]
+    fn shared_try_step_rejects_reentrancy() {
+        let fsm: SharedFsm<_, ReentrantCs> = SharedFsm::new(Moore::new(0, &TRANS, &OUTS));
+        let nested = fsm.with(|_| fsm.try_step(TestInput::Inc));
+        assert_eq!(nested, None);
+        assert_eq!(fsm.try_step(TestInput::Inc), Some(Ok(1)));
+
+        // A panic inside `with` must not leave the machine locked
+        let unwound = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            fsm.with(|_| panic!("action failed"))
+        }));
+        assert!(unwound.is_err());
+        assert_eq!(fsm.try_step(TestInput::Inc), Some(Ok(2)));
+    }
+
+    #[cfg(feature = "critical-section")]
+    #[test]
+    fn shared_locks_through_critical_section_crate() {
+        let fsm: SharedFsm<_> = SharedFsm::new(Moore::new(0, &TRANS, &OUTS));
+        std::thread::scope(|s| {
+            for _ in 0..3 {
+                s.spawn(|| fsm.step(TestInput::Inc).unwrap());
+            }
+        });
+        assert_eq!(fsm.current_state(), 0);
+    }
+}
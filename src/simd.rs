@@ -0,0 +1,244 @@
+//! Vectorized table scan for simulators
+//!
+//! Requires the `simd` feature. A `SimdTable` stores each row's
+//! (state, input) as one `u32` key in a contiguous array, which is scanned
+//! four keys per instruction with SSE2 on x86_64 and in chunks the compiler
+//! vectorizes elsewhere. One table serves any number of `SimdMealy`s.
+//!
+//! # Example
+//! ```
+//! use fsmall::simd::{SimdMealy, SimdTable};
+//!
+//! static TRANSITIONS: [(u8, u8, u8); 2] = [(0, b'a', 1), (1, b'b', 0)];
+//! static OUTPUTS: [(u8, u8, char); 2] = [(0, b'a', 'x'), (1, b'b', 'y')];
+//!
+//! let table = SimdTable::new(&TRANSITIONS, &OUTPUTS);
+//! let mut machines = [SimdMealy::new(0, &table), SimdMealy::new(1, &table)];
+//! assert_eq!(machines[0].step(b'a'), Ok('x'));
+//! assert_eq!(machines[1].step(b'b'), Ok('y'));
+//! ```
+
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+use crate::{InputIndex, Machine, StepError};
+
+/// Table of keyed rows for vectorized lookup
+#[derive(Debug, Clone)]
+pub struct SimdTable<I, O> {
+    // keys[row] = state * I::COUNT + input index
+    keys: Vec<u32>,
+    // rows[row] = (next_state, output)
+    rows: Vec<(u8, O)>,
+    input: PhantomData<fn(I)>,
+}
+
+impl<I: InputIndex, O: Copy> SimdTable<I, O> {
+    /// Build from Mealy tables
+    ///
+    /// As in `Mealy::step`, the first rows for a (state, input) win. Rows
+    /// without an output are dropped, their step fails either way.
+    ///
+    /// # Arguments
+    /// * `transitions` - Transition table: (from_state, input, to_state)
+    /// * `outputs` - Output table: (state, input, output)
+    ///
+    /// # Panics
+    /// If `256 * I::COUNT` does not fit in a `u32`
+    pub fn new(transitions: &[(u8, I, u8)], outputs: &[(u8, I, O)]) -> Self {
+        assert!(
+            I::COUNT
+                .checked_mul(256)
+                .is_some_and(|n| u32::try_from(n).is_ok()),
+            "input alphabet too large for u32 keys"
+        );
+        let mut table = SimdTable {
+            keys: Vec::with_capacity(transitions.len()),
+            rows: Vec::with_capacity(transitions.len()),
+            input: PhantomData,
+        };
+        let mut seen = Vec::new();
+        for (from, input, to) in transitions {
+            let key = Self::key(*from, input);
+            if seen.contains(&key) {
+                continue;
+            }
+            seen.push(key);
+            let output = outputs
+                .iter()
+                .find(|(s, i, _o)| s == from && i.index() == input.index());
+            if let Some((_s, _i, output)) = output {
+                table.keys.push(key);
+                table.rows.push((*to, *output));
+            }
+        }
+        table
+    }
+
+    // Fits in u32, checked in `new`
+    fn key(state: u8, input: &I) -> u32 {
+        (usize::from(state) * I::COUNT + input.index()) as u32
+    }
+
+    /// Look up (state, input): (next_state, output)
+    #[inline]
+    pub fn lookup(&self, state: u8, input: &I) -> Option<(u8, O)> {
+        let row = position(&self.keys, Self::key(state, input))?;
+        self.rows.get(row).copied()
+    }
+
+    /// Number of rows
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Check whether the table has no rows
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+// Index of the first `key` in `keys`
+#[cfg(target_arch = "x86_64")]
+fn position(keys: &[u32], key: u32) -> Option<usize> {
+    use core::arch::x86_64::{
+        __m128i, _mm_castsi128_ps, _mm_cmpeq_epi32, _mm_loadu_si128, _mm_movemask_ps,
+        _mm_set1_epi32,
+    };
+
+    let chunks = keys.chunks_exact(4);
+    let rest = chunks.remainder();
+    // SAFETY: SSE2 is part of the x86_64 baseline, and each load reads the
+    // 16 bytes of a four-u32 `chunk`, unaligned.
+    unsafe {
+        let needle = _mm_set1_epi32(key as i32);
+        for (k, chunk) in chunks.enumerate() {
+            let lanes = _mm_loadu_si128(chunk.as_ptr().cast::<__m128i>());
+            let mask = _mm_movemask_ps(_mm_castsi128_ps(_mm_cmpeq_epi32(lanes, needle)));
+            if mask != 0 {
+                return Some(k * 4 + mask.trailing_zeros() as usize);
+            }
+        }
+    }
+    let start = keys.len() - rest.len();
+    rest.iter().position(|k| *k == key).map(|k| start + k)
+}
+
+// Index of the first `key` in `keys`, branch-free within each chunk so the
+// compare vectorizes
+#[cfg(not(target_arch = "x86_64"))]
+fn position(keys: &[u32], key: u32) -> Option<usize> {
+    const LANES: usize = 8;
+    let chunks = keys.chunks_exact(LANES);
+    let rest = chunks.remainder();
+    for (k, chunk) in chunks.enumerate() {
+        let mask = chunk.iter().enumerate().fold(0u32, |mask, (lane, x)| {
+            mask | (u32::from(*x == key) << lane)
+        });
+        if mask != 0 {
+            return Some(k * LANES + mask.trailing_zeros() as usize);
+        }
+    }
+    let start = keys.len() - rest.len();
+    rest.iter().position(|k| *k == key).map(|k| start + k)
+}
+
+/// Mealy machine stepping through a shared `SimdTable`
+#[derive(Debug)]
+pub struct SimdMealy<'t, I, O> {
+    state: u8,
+    table: &'t SimdTable<I, O>,
+}
+
+impl<I, O> Clone for SimdMealy<'_, I, O> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<I, O> Copy for SimdMealy<'_, I, O> {}
+
+impl<'t, I: InputIndex, O: Copy> SimdMealy<'t, I, O> {
+    /// Create machine over `table`
+    pub fn new(initial_state: u8, table: &'t SimdTable<I, O>) -> Self {
+        SimdMealy {
+            state: initial_state,
+            table,
+        }
+    }
+
+    /// Process input, transition to next state, return output
+    ///
+    /// # Errors
+    /// * `StepError::NoTransition` - No row for (state, input)
+    #[inline]
+    pub fn step(&mut self, input: I) -> Result<O, StepError> {
+        let (next, output) = self
+            .table
+            .lookup(self.state, &input)
+            .ok_or(StepError::NoTransition)?;
+        self.state = next;
+        Ok(output)
+    }
+
+    /// Get current state
+    pub fn current_state(&self) -> u8 {
+        self.state
+    }
+
+    /// Reset to specific state
+    pub fn reset(&mut self, state: u8) {
+        self.state = state;
+    }
+}
+
+impl<I: InputIndex, O: Copy> Machine for SimdMealy<'_, I, O> {
+    type Input = I;
+    type Output = O;
+    type Error = StepError;
+
+    fn step(&mut self, input: I) -> Result<O, StepError> {
+        SimdMealy::step(self, input)
+    }
+
+    fn current_state(&self) -> u8 {
+        SimdMealy::current_state(self)
+    }
+
+    fn reset(&mut self, state: u8) {
+        SimdMealy::reset(self, state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Mealy;
+
+    #[test]
+    fn simd_scan_steps_like_mealy() {
+        // Enough rows for several chunks plus a remainder, with a
+        // shadowed duplicate and a row without output
+        let mut transitions = Vec::new();
+        let mut outputs = Vec::new();
+        for input in 0..37u8 {
+            transitions.push((input % 3, input, (input + 1) % 3));
+            outputs.push((input % 3, input, u32::from(input) * 7));
+        }
+        transitions.push((0, 0, 2));
+        transitions.push((1, 200, 0));
+
+        let table = SimdTable::new(&transitions, &outputs);
+        assert_eq!(table.len(), 37);
+        for state in 0..4 {
+            for input in 0..=255 {
+                let mut simd = SimdMealy::new(state, &table);
+                let mut mealy = Mealy::new(state, &transitions, &outputs);
+                assert_eq!(simd.step(input).ok(), mealy.step(input).ok());
+                assert_eq!(simd.current_state(), mealy.current_state());
+            }
+        }
+        assert_eq!(position(&[1, 2, 3, 4, 5, 5], 5), Some(4));
+        assert_eq!(position(&[], 5), None);
+    }
+}
@@ -0,0 +1,505 @@
+//! Hierarchical state machines
+//!
+//! A `Chart` arranges states in a tree through a parent table. Only leaf
+//! states are ever active; an input the active leaf does not handle is
+//! tried on its ancestors, innermost first. Entering a composite state
+//! descends to its initial substate, and every transition runs the exit
+//! actions of the states it leaves and the entry actions of the states it
+//! enters. `statechart!` writes all tables from a nested state list.
+
+use core::iter;
+
+use crate::{Action, Guard, Machine, StepError};
+
+/// Tables of a hierarchical state machine, see `Statechart`
+///
+/// Transitions are external: a transition leaves and re-enters its source
+/// state even when the target is the source itself or one of its
+/// descendants or ancestors.
+pub struct Chart<'t, I, C> {
+    // Array: parents[state] = parent state, or state itself for top-level states
+    parents: &'t [u8],
+    // Table: (from_state, input, to_state), from_state may be composite
+    transitions: &'t [(u8, I, u8)],
+    // Aligned with transitions, None or missing = always allowed
+    guards: &'t [Option<Guard<C>>],
+    // Table: (composite_state, initial_substate)
+    initial: &'t [(u8, u8)],
+    // Arrays: entry[state] / exit[state] = action, None or missing = no action
+    entry: &'t [Option<Action<C>>],
+    exit: &'t [Option<Action<C>>],
+}
+
+impl<I, C> Clone for Chart<'_, I, C> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<I, C> Copy for Chart<'_, I, C> {}
+
+impl<'t, I, C> Chart<'t, I, C> {
+    /// Create chart tables, usable in a `static`
+    ///
+    /// # Arguments
+    /// * `parents` - Parent array: index=state, value=parent state, or the
+    ///   state itself for top-level states
+    /// * `transitions` - Transition table: (from_state, input, to_state)
+    pub const fn new(parents: &'t [u8], transitions: &'t [(u8, I, u8)]) -> Self {
+        Chart {
+            parents,
+            transitions,
+            guards: &[],
+            initial: &[],
+            entry: &[],
+            exit: &[],
+        }
+    }
+
+    /// Attach guards, one per transition row, evaluated against the context
+    pub const fn with_guards(mut self, guards: &'t [Option<Guard<C>>]) -> Self {
+        self.guards = guards;
+        self
+    }
+
+    /// Attach initial substates: (composite_state, initial_substate)
+    pub const fn with_initial(mut self, initial: &'t [(u8, u8)]) -> Self {
+        self.initial = initial;
+        self
+    }
+
+    /// Attach entry actions: index=state, value=action
+    pub const fn with_entry(mut self, entry: &'t [Option<Action<C>>]) -> Self {
+        self.entry = entry;
+        self
+    }
+
+    /// Attach exit actions: index=state, value=action
+    pub const fn with_exit(mut self, exit: &'t [Option<Action<C>>]) -> Self {
+        self.exit = exit;
+        self
+    }
+
+    // State and its ancestors, innermost first; bounded against parent cycles
+    fn ancestors(&self, state: u8) -> impl Iterator<Item = u8> + '_ {
+        iter::successors(Some(state), |&s| {
+            self.parents
+                .get(usize::from(s))
+                .copied()
+                .filter(|&parent| parent != s)
+        })
+        .take(usize::from(u8::MAX) + 1)
+    }
+
+    fn initial_of(&self, state: u8) -> Option<u8> {
+        self.initial
+            .iter()
+            .find(|(composite, _)| *composite == state)
+            .map(|(_, substate)| *substate)
+    }
+}
+
+fn run<C>(actions: &[Option<Action<C>>], state: u8, ctx: &mut C) {
+    if let Some(Some(action)) = actions.get(usize::from(state)) {
+        action(ctx);
+    }
+}
+
+/// Hierarchical state machine owning a context `C`
+///
+/// # Example
+/// ```
+/// use fsmall::statechart::{Chart, Statechart};
+///
+/// #[derive(Copy, Clone, Eq, PartialEq)]
+/// enum Input { Power, Up }
+///
+/// // 0: off, 1: on (composite), 2: dim, 3: bright
+/// static PARENTS: [u8; 4] = [0, 1, 1, 1];
+/// static TRANSITIONS: [(u8, Input, u8); 3] = [
+///     (0, Input::Power, 1),
+///     (1, Input::Power, 0), // Handled by `on` for both substates
+///     (2, Input::Up, 3),
+/// ];
+/// static INITIAL: [(u8, u8); 1] = [(1, 2)];
+/// static ENTRY: [Option<fsmall::Action<u32>>; 2] = [None, Some(|starts| *starts += 1)];
+///
+/// let chart = Chart::new(&PARENTS, &TRANSITIONS)
+///     .with_initial(&INITIAL)
+///     .with_entry(&ENTRY);
+/// let mut lamp = Statechart::new(chart, 0, 0);
+/// assert_eq!(lamp.step(Input::Power), Ok(2)); // Enters `on`, lands in `dim`
+/// assert_eq!(lamp.step(Input::Up), Ok(3));
+/// assert!(lamp.is_in(1));
+/// assert_eq!(lamp.step(Input::Power), Ok(0));
+/// assert_eq!(*lamp.context(), 1);
+/// ```
+pub struct Statechart<'t, I, C> {
+    chart: Chart<'t, I, C>,
+    // Active leaf state
+    state: u8,
+    ctx: C,
+}
+
+impl<'t, I: Copy + Eq, C> Statechart<'t, I, C> {
+    /// Create statechart in `initial_state`, descended to its initial leaf
+    ///
+    /// No entry actions run until `start`.
+    pub fn new(chart: Chart<'t, I, C>, initial_state: u8, ctx: C) -> Self {
+        let mut machine = Statechart {
+            chart,
+            state: initial_state,
+            ctx,
+        };
+        machine.reset(initial_state);
+        machine
+    }
+
+    /// Run the entry actions of the active leaf and its ancestors, outermost first
+    ///
+    /// Returns the active leaf.
+    pub fn start(&mut self) -> u8 {
+        let depth = self.chart.ancestors(self.state).count();
+        for level in (0..depth).rev() {
+            if let Some(state) = self.chart.ancestors(self.state).nth(level) {
+                run(self.chart.entry, state, &mut self.ctx);
+            }
+        }
+        self.state
+    }
+
+    /// Process input, returning the new active leaf
+    ///
+    /// The first row for (state, input) whose guard passes is taken, looking
+    /// at the active leaf first and then at each ancestor. Exit actions run
+    /// innermost first, then entry actions outermost first, down to the
+    /// target's initial leaf.
+    ///
+    /// # Errors
+    /// * `StepError::NoTransition` - Neither the leaf nor an ancestor handles the input
+    pub fn step(&mut self, input: I) -> Result<u8, StepError> {
+        let (source, target) = self.find(input).ok_or(StepError::NoTransition)?;
+        let chart = self.chart;
+
+        // Innermost state containing both ends without being either of them
+        let domain = chart
+            .ancestors(source)
+            .skip(1)
+            .find(|&s| s != target && chart.ancestors(target).any(|a| a == s));
+
+        for state in chart
+            .ancestors(self.state)
+            .take_while(|&s| Some(s) != domain)
+        {
+            run(chart.exit, state, &mut self.ctx);
+        }
+
+        let entered = chart
+            .ancestors(target)
+            .take_while(|&s| Some(s) != domain)
+            .count();
+        for level in (0..entered).rev() {
+            if let Some(state) = chart.ancestors(target).nth(level) {
+                run(chart.entry, state, &mut self.ctx);
+            }
+        }
+
+        self.state = target;
+        for _ in 0..=u8::MAX {
+            let Some(substate) = chart.initial_of(self.state) else {
+                break;
+            };
+            run(chart.entry, substate, &mut self.ctx);
+            self.state = substate;
+        }
+        Ok(self.state)
+    }
+
+    // (source, target) of the transition taken for `input`
+    fn find(&self, input: I) -> Option<(u8, u8)> {
+        self.chart.ancestors(self.state).find_map(|state| {
+            self.chart
+                .transitions
+                .iter()
+                .enumerate()
+                .find(|(row, (from, i, _))| {
+                    *from == state
+                        && *i == input
+                        && match self.chart.guards.get(*row) {
+                            Some(Some(guard)) => guard(&self.ctx),
+                            _ => true,
+                        }
+                })
+                .map(|(_, (_, _, to))| (state, *to))
+        })
+    }
+
+    /// Get active leaf state
+    pub fn current_state(&self) -> u8 {
+        self.state
+    }
+
+    /// Whether `state` is the active leaf or one of its ancestors
+    pub fn is_in(&self, state: u8) -> bool {
+        self.chart.ancestors(self.state).any(|s| s == state)
+    }
+
+    /// Get context
+    pub fn context(&self) -> &C {
+        &self.ctx
+    }
+
+    /// Get mutable context
+    pub fn context_mut(&mut self) -> &mut C {
+        &mut self.ctx
+    }
+
+    /// Consume machine, returning the context
+    pub fn into_context(self) -> C {
+        self.ctx
+    }
+
+    /// Jump to `state`, descended to its initial leaf, without running actions
+    pub fn reset(&mut self, state: u8) {
+        self.state = state;
+        for _ in 0..=u8::MAX {
+            match self.chart.initial_of(self.state) {
+                Some(substate) => self.state = substate,
+                None => break,
+            }
+        }
+    }
+}
+
+impl<I: Copy + Eq, C> Machine for Statechart<'_, I, C> {
+    type Input = I;
+    type Output = u8;
+    type Error = StepError;
+
+    fn step(&mut self, input: I) -> Result<u8, StepError> {
+        Statechart::step(self, input)
+    }
+
+    fn current_state(&self) -> u8 {
+        Statechart::current_state(self)
+    }
+
+    fn reset(&mut self, state: u8) {
+        Statechart::reset(self, state)
+    }
+}
+
+/// Generate a `Chart` and state constants from a nested state list
+///
+/// States are numbered explicitly with ids `0..N` and nest in braces; the
+/// first substate listed is the initial one. Entry and exit actions go in
+/// brackets after the id, and a transition takes an optional guard after
+/// `if`. Each state becomes a `u8` constant.
+///
+/// # Example
+/// ```
+/// use fsmall::statechart::Statechart;
+///
+/// #[derive(Copy, Clone, Eq, PartialEq)]
+/// enum Input { Power, Up, Down }
+///
+/// struct Lamp { level: u8, powered: bool }
+///
+/// fn power_up(lamp: &mut Lamp) { lamp.powered = true; }
+/// fn power_down(lamp: &mut Lamp) { lamp.powered = false; }
+/// fn brighter(lamp: &mut Lamp) { lamp.level += 1; }
+/// fn has_headroom(lamp: &Lamp) -> bool { lamp.level < 2 }
+///
+/// fsmall::statechart! {
+///     static LAMP: Input, Lamp {
+///         states: {
+///             OFF = 0,
+///             ON = 1 [entry: power_up, exit: power_down] {
+///                 DIM = 2,
+///                 BRIGHT = 3 [entry: brighter],
+///             },
+///         },
+///         transitions: [
+///             OFF + Input::Power => ON,
+///             ON + Input::Power => OFF,
+///             DIM + Input::Up => BRIGHT if has_headroom,
+///             BRIGHT + Input::Down => DIM,
+///         ]
+///     }
+/// }
+///
+/// let mut lamp = Statechart::new(LAMP, OFF, Lamp { level: 0, powered: false });
+/// assert_eq!(lamp.step(Input::Power), Ok(DIM));
+/// assert!(lamp.context().powered);
+/// assert_eq!(lamp.step(Input::Up), Ok(BRIGHT));
+/// assert_eq!(lamp.step(Input::Down), Ok(DIM));
+/// assert_eq!(lamp.step(Input::Up), Ok(BRIGHT));
+/// assert!(lamp.step(Input::Up).is_err());
+/// lamp.step(Input::Down).unwrap();
+/// assert!(lamp.step(Input::Up).is_err()); // Guard refuses at level 2
+/// assert_eq!(lamp.step(Input::Power), Ok(OFF));
+/// assert!(!lamp.context().powered);
+/// ```
+#[macro_export]
+macro_rules! statechart {
+    // Composite state: record it, walk its substates, then its siblings
+    (@walk $head:tt $transitions:tt [$($acc:tt)*] [$($init:tt)*] [
+        ($parent:tt ; $name:ident = $id:literal $([$($attr:tt)*])?
+            { $first:ident $($children:tt)* } $(, $($rest:tt)*)?)
+        $($stack:tt)*
+    ]) => {
+        $crate::statechart!(@walk $head $transitions
+            [$($acc)* ($name $id $parent [$($($attr)*)?])]
+            [$($init)* ($name $first)]
+            [([$name] ; $first $($children)*) ($parent ; $($($rest)*)?) $($stack)*]);
+    };
+    // Leaf state
+    (@walk $head:tt $transitions:tt [$($acc:tt)*] $init:tt [
+        ($parent:tt ; $name:ident = $id:literal $([$($attr:tt)*])? $(, $($rest:tt)*)?)
+        $($stack:tt)*
+    ]) => {
+        $crate::statechart!(@walk $head $transitions
+            [$($acc)* ($name $id $parent [$($($attr)*)?])]
+            $init
+            [($parent ; $($($rest)*)?) $($stack)*]);
+    };
+    // Level done
+    (@walk $head:tt $transitions:tt $acc:tt $init:tt [($parent:tt ;) $($stack:tt)*]) => {
+        $crate::statechart!(@walk $head $transitions $acc $init [$($stack)*]);
+    };
+    (@walk [$vis:vis $chart:ident $input:ty, $ctx:ty]
+        [$($from:ident + $in:expr => $to:ident $(if $guard:expr)?),* $(,)?]
+        [$(($name:ident $id:literal [$($parent:ident)?] [$($attr:tt)*]))*]
+        [$(($composite:ident $initial:ident))*]
+        []
+    ) => {
+        $( $vis const $name: u8 = $id; )*
+
+        $vis static $chart: $crate::statechart::Chart<'static, $input, $ctx> = {
+            const STATES: usize = $crate::__count!($($id)*);
+            const ROWS: usize = $crate::__count!($($from)*);
+            static PARENTS: [u8; STATES] = {
+                let mut parents = [0; STATES];
+                $(
+                    parents[$id] = $id;
+                    $( parents[$id] = $parent; )?
+                )*
+                parents
+            };
+            static TRANSITIONS: [(u8, $input, u8); ROWS] = [$(($from, $in, $to)),*];
+            static GUARDS: [Option<$crate::Guard<$ctx>>; ROWS] =
+                [$($crate::statechart!(@guard $ctx $(, $guard)?)),*];
+            static INITIAL: &[(u8, u8)] = &[$(($composite, $initial)),*];
+            static ENTRY: [Option<$crate::Action<$ctx>>; STATES] = {
+                let mut entry = [None; STATES];
+                $( entry[$id] = $crate::statechart!(@entry $ctx; $($attr)*); )*
+                entry
+            };
+            static EXIT: [Option<$crate::Action<$ctx>>; STATES] = {
+                let mut exit = [None; STATES];
+                $( exit[$id] = $crate::statechart!(@exit $ctx; $($attr)*); )*
+                exit
+            };
+            $crate::statechart::Chart::new(&PARENTS, &TRANSITIONS)
+                .with_guards(&GUARDS)
+                .with_initial(INITIAL)
+                .with_entry(&ENTRY)
+                .with_exit(&EXIT)
+        };
+    };
+    // Pick one kind of action out of a state's attributes
+    (@entry $ctx:ty; entry: $action:expr $(, $($rest:tt)*)?) => {
+        Some($action as $crate::Action<$ctx>)
+    };
+    (@exit $ctx:ty; exit: $action:expr $(, $($rest:tt)*)?) => {
+        Some($action as $crate::Action<$ctx>)
+    };
+    (@$kind:ident $ctx:ty; $other:ident: $action:expr $(, $($rest:tt)*)?) => {
+        $crate::statechart!(@$kind $ctx; $($($rest)*)?)
+    };
+    (@$kind:ident $ctx:ty;) => {
+        None
+    };
+    (@guard $ctx:ty) => {
+        None
+    };
+    (@guard $ctx:ty, $guard:expr) => {
+        Some($guard as $crate::Guard<$ctx>)
+    };
+    (
+        $vis:vis static $chart:ident: $input:ty, $ctx:ty {
+            states: { $($states:tt)* } $(,)?
+            transitions: [ $($transitions:tt)* ] $(,)?
+        }
+    ) => {
+        $crate::statechart!(@walk [$vis $chart $input, $ctx] [$($transitions)*] [] []
+            [([] ; $($states)*)]);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Trace of actions as digits: entry = state + 1, exit = 10 * (state + 1)
+    type Log = u32;
+
+    fn push(log: &mut Log, code: u32) {
+        *log = *log * 100 + code;
+    }
+
+    crate::statechart! {
+        static CHART: char, Log {
+            states: {
+                A = 0 [entry: |l| push(l, 1), exit: |l| push(l, 10)] {
+                    A1 = 1 [entry: |l| push(l, 2), exit: |l| push(l, 20)],
+                    A2 = 2 [entry: |l| push(l, 3), exit: |l| push(l, 30)],
+                },
+                B = 3 [entry: |l| push(l, 4)],
+            },
+            transitions: [
+                A1 + 'n' => A2,
+                A + 'r' => A,
+                A + 'b' => B,
+                B + 'a' => A2,
+            ]
+        }
+    }
+
+    #[test]
+    fn statechart_runs_exit_and_entry_along_the_path() {
+        let mut fsm = Statechart::new(CHART, A, 0);
+        assert_eq!(fsm.current_state(), A1);
+        fsm.start();
+        assert_eq!(*fsm.context(), 1_02);
+
+        // Sibling move stays inside A
+        *fsm.context_mut() = 0;
+        assert_eq!(fsm.step('n'), Ok(A2));
+        assert_eq!(*fsm.context(), 20_03);
+
+        // Self-transition on the parent leaves and re-enters it
+        *fsm.context_mut() = 0;
+        assert_eq!(fsm.step('r'), Ok(A1));
+        assert_eq!(*fsm.context(), 30_10_01_02);
+
+        *fsm.context_mut() = 0;
+        assert_eq!(fsm.step('b'), Ok(B));
+        assert_eq!(*fsm.context(), 20_10_04);
+        assert!(!fsm.is_in(A));
+
+        // Entering a deep target skips the initial substate
+        *fsm.context_mut() = 0;
+        assert_eq!(fsm.step('a'), Ok(A2));
+        assert_eq!(*fsm.context(), 1_03);
+        assert!(fsm.is_in(A));
+    }
+
+    #[test]
+    fn statechart_rejects_unhandled_input() {
+        let mut fsm = Statechart::new(CHART, B, 0);
+        assert_eq!(fsm.step('n'), Err(StepError::NoTransition));
+        assert_eq!(fsm.current_state(), B);
+        assert_eq!(fsm.into_context(), 0);
+    }
+}
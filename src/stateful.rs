@@ -0,0 +1,185 @@
+//! Extended state machines owning a context
+
+use crate::{Action, Guard, Machine, Mealy, StepError};
+
+/// Mealy machine that owns a context `C`, mutated by transition actions
+///
+/// Guards see the context, and actions registered for (state, input) run on
+/// it once a transition commits, exactly as `Mealy::step_with_mut` with the
+/// context stored alongside the machine. Use it for data that does not belong in
+/// the state space, such as retry counters or accumulated lengths.
+///
+/// # Example
+/// ```
+/// use fsmall::StatefulMealy;
+///
+/// #[derive(Copy, Clone, Eq, PartialEq)]
+/// enum Input { Fail, Ok }
+///
+/// struct Retries { count: u8 }
+///
+/// fn bump(r: &mut Retries) { r.count += 1; }
+/// fn clear(r: &mut Retries) { r.count = 0; }
+///
+/// static TRANSITIONS: [(u8, Input, u8); 2] = [(0, Input::Fail, 0), (0, Input::Ok, 1)];
+/// static OUTPUTS: [(u8, Input, bool); 2] = [(0, Input::Fail, false), (0, Input::Ok, true)];
+/// static ACTIONS: [(u8, Input, fsmall::Action<Retries>); 2] = [
+///     (0, Input::Fail, bump),
+///     (0, Input::Ok, clear),
+/// ];
+///
+/// let mut fsm = StatefulMealy::new(0, &TRANSITIONS, &OUTPUTS, Retries { count: 0 })
+///     .with_actions(&ACTIONS);
+/// fsm.step(Input::Fail).unwrap();
+/// fsm.step(Input::Fail).unwrap();
+/// assert_eq!(fsm.context().count, 2);
+/// ```
+pub struct StatefulMealy<'t, I, O, C> {
+    machine: Mealy<'t, I, O, C>,
+    ctx: C,
+}
+
+impl<'t, I: Copy + Eq, O: Copy, C> StatefulMealy<'t, I, O, C> {
+    /// Create new stateful Mealy machine
+    ///
+    /// # Arguments
+    /// * `initial_state` - Starting state (0-255)
+    /// * `transitions` - Transition table: (from_state, input, to_state)
+    /// * `outputs` - Output table: (state, input, output)
+    /// * `ctx` - Initial context
+    pub fn new(
+        initial_state: u8,
+        transitions: &'t [(u8, I, u8)],
+        outputs: &'t [(u8, I, O)],
+        ctx: C,
+    ) -> Self {
+        StatefulMealy {
+            machine: Mealy::new(initial_state, transitions, outputs).with_context(),
+            ctx,
+        }
+    }
+
+    /// Attach guards evaluated against the owned context
+    ///
+    /// See `Mealy::with_guards` for row alignment rules.
+    pub fn with_guards(mut self, guards: &'t [Option<Guard<C>>]) -> Self {
+        self.machine = self.machine.with_guards(guards);
+        self
+    }
+
+    /// Attach actions run on the context when a transition commits
+    ///
+    /// # Arguments
+    /// * `actions` - Action table: (from_state, input, action)
+    pub fn with_actions(mut self, actions: &'t [(u8, I, Action<C>)]) -> Self {
+        self.machine = self.machine.with_actions(actions);
+        self
+    }
+
+    /// Process input, transition to next state, run action, return output
+    ///
+    /// The action runs only if the transition commits.
+    ///
+    /// # Errors
+    /// * `StepError::NoTransition` - No rule for (state, input) whose guard passes
+    /// * `StepError::NoOutput` - No output for (state, input)
+    pub fn step(&mut self, input: I) -> Result<O, StepError> {
+        self.machine.step_with_mut(input, &mut self.ctx)
+    }
+
+    /// Get current state
+    pub fn current_state(&self) -> u8 {
+        self.machine.current_state()
+    }
+
+    /// Reset to specific state, leaving the context untouched
+    pub fn reset(&mut self, state: u8) {
+        self.machine.reset(state);
+    }
+
+    /// Get context
+    pub fn context(&self) -> &C {
+        &self.ctx
+    }
+
+    /// Get context mutably
+    pub fn context_mut(&mut self) -> &mut C {
+        &mut self.ctx
+    }
+
+    /// Consume machine, returning the context
+    pub fn into_context(self) -> C {
+        self.ctx
+    }
+}
+
+impl<I: Copy + Eq, O: Copy, C> Machine for StatefulMealy<'_, I, O, C> {
+    type Input = I;
+    type Output = O;
+    type Error = StepError;
+
+    fn step(&mut self, input: I) -> Result<O, StepError> {
+        StatefulMealy::step(self, input)
+    }
+
+    fn current_state(&self) -> u8 {
+        StatefulMealy::current_state(self)
+    }
+
+    fn reset(&mut self, state: u8) {
+        StatefulMealy::reset(self, state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Copy, Clone, Eq, PartialEq, Debug)]
+    enum TestInput {
+        Byte,
+        End,
+    }
+
+    struct Frame {
+        len: u8,
+        max: u8,
+    }
+
+    fn grow(f: &mut Frame) {
+        f.len += 1;
+    }
+
+    fn has_room(f: &Frame) -> bool {
+        f.len < f.max
+    }
+
+    static TRANS: [(u8, TestInput, u8); 2] = [(0, TestInput::Byte, 0), (0, TestInput::End, 1)];
+
+    static GUARDS: [Option<Guard<Frame>>; 1] = [Some(has_room)];
+
+    static OUTS: [(u8, TestInput, bool); 2] =
+        [(0, TestInput::Byte, false), (0, TestInput::End, true)];
+
+    static ACTIONS: [(u8, TestInput, Action<Frame>); 1] = [(0, TestInput::Byte, grow)];
+
+    #[test]
+    fn stateful_actions_mutate_context() {
+        let mut fsm =
+            StatefulMealy::new(0, &TRANS, &OUTS, Frame { len: 0, max: 8 }).with_actions(&ACTIONS);
+        fsm.step(TestInput::Byte).unwrap();
+        fsm.step(TestInput::Byte).unwrap();
+        assert_eq!(fsm.step(TestInput::End), Ok(true));
+        assert_eq!(fsm.context().len, 2);
+    }
+
+    #[test]
+    fn stateful_guard_sees_context() {
+        let mut fsm = StatefulMealy::new(0, &TRANS, &OUTS, Frame { len: 0, max: 1 })
+            .with_guards(&GUARDS)
+            .with_actions(&ACTIONS);
+        assert_eq!(fsm.step(TestInput::Byte), Ok(false));
+        assert_eq!(fsm.step(TestInput::Byte), Err(StepError::NoTransition));
+        assert_eq!(fsm.into_context().len, 1); // Rejected step runs no action
+    }
+}
@@ -0,0 +1,490 @@
+//! Helpers for smoke-testing machines
+//!
+//! Inputs are drawn from the machine's transition table, so every step
+//! taken is one the table allows; guards may still reject it, which shows
+//! up as an error in the trace. `transition_tour` requires the `alloc`
+//! feature.
+//!
+//! For property testing, `valid_sequence` turns arbitrary numbers into an
+//! input sequence the table accepts, paired with the expected states. With
+//! the `proptest` or `arbitrary` feature, `ValidSequence` wraps it for a
+//! `Table`, generated by `valid_sequences` or either crate's `Arbitrary`:
+//!
+//! ```
+//! # #[cfg(feature = "proptest")] {
+//! use fsmall::testing::{Table, ValidSequence};
+//! use fsmall::Mealy;
+//! use proptest::prelude::*;
+//!
+//! static TRANSITIONS: [(u8, char, u8); 3] = [(0, 'a', 1), (0, 'b', 0), (1, 'c', 0)];
+//! static OUTPUTS: [(u8, char, u8); 3] = [(0, 'a', 1), (0, 'b', 2), (1, 'c', 3)];
+//!
+//! struct Door;
+//!
+//! impl Table for Door {
+//!     type Input = char;
+//!     const TRANSITIONS: &'static [(u8, char, u8)] = &TRANSITIONS;
+//! }
+//!
+//! proptest!(|(sequence: ValidSequence<Door>)| {
+//!     let mut fsm = Mealy::new(0, &TRANSITIONS, &OUTPUTS);
+//!     for (input, expected) in sequence.inputs.iter().zip(&sequence.states) {
+//!         prop_assert!(fsm.step(*input).is_ok());
+//!         prop_assert_eq!(fsm.current_state(), *expected);
+//!     }
+//! });
+//! # }
+//! ```
+
+#[cfg(feature = "alloc")]
+use alloc::collections::VecDeque;
+#[cfg(feature = "alloc")]
+use alloc::vec;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use crate::analysis::takes_row;
+#[cfg(feature = "alloc")]
+use crate::analysis::StateSet;
+use crate::Machine;
+
+/// One step of a random walk
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct WalkStep<I, O, E> {
+    /// State before the step
+    pub from: u8,
+    /// Input fed to the machine
+    pub input: I,
+    /// What `step` returned
+    pub result: Result<O, E>,
+}
+
+/// Iterator stepping a machine with random valid inputs, see `random_walk`
+pub struct RandomWalk<'a, M: Machine, R> {
+    machine: &'a mut M,
+    // Table: (from_state, input, to_state)
+    transitions: &'a [(u8, M::Input, u8)],
+    rng: R,
+    remaining: usize,
+}
+
+/// Walk `machine` for up to `steps` random steps
+///
+/// Each step picks, using `rng`, one row of `transitions` leaving the
+/// current state and feeds its input to the machine. The walk ends early
+/// in a state with no outgoing row.
+///
+/// # Example
+/// ```
+/// use fsmall::testing::random_walk;
+/// use fsmall::Mealy;
+///
+/// static TRANSITIONS: [(u8, char, u8); 3] = [(0, 'a', 1), (0, 'b', 0), (1, 'c', 0)];
+/// static OUTPUTS: [(u8, char, u8); 3] = [(0, 'a', 1), (0, 'b', 2), (1, 'c', 3)];
+///
+/// let mut fsm = Mealy::new(0, &TRANSITIONS, &OUTPUTS);
+/// let mut seed = 1u32;
+/// let rng = move || {
+///     seed ^= seed << 13;
+///     seed ^= seed >> 17;
+///     seed ^= seed << 5;
+///     seed
+/// };
+/// for step in random_walk(&mut fsm, &TRANSITIONS, rng, 100) {
+///     assert!(step.result.is_ok(), "{step:?}");
+/// }
+/// ```
+pub fn random_walk<'a, M, R>(
+    machine: &'a mut M,
+    transitions: &'a [(u8, M::Input, u8)],
+    rng: R,
+    steps: usize,
+) -> RandomWalk<'a, M, R>
+where
+    M: Machine,
+    M::Input: Copy,
+    R: FnMut() -> u32,
+{
+    RandomWalk {
+        machine,
+        transitions,
+        rng,
+        remaining: steps,
+    }
+}
+
+impl<M, R> Iterator for RandomWalk<'_, M, R>
+where
+    M: Machine,
+    M::Input: Copy,
+    R: FnMut() -> u32,
+{
+    type Item = WalkStep<M::Input, M::Output, M::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.remaining = self.remaining.checked_sub(1)?;
+        let from = self.machine.current_state();
+        let rows = || {
+            self.transitions
+                .iter()
+                .filter(move |(f, _input, _to)| *f == from)
+        };
+        let count = rows().count();
+        if count == 0 {
+            self.remaining = 0;
+            return None;
+        }
+        let pick = (self.rng)() as usize % count;
+        let (_from, input, _to) = *rows().nth(pick)?;
+        Some(WalkStep {
+            from,
+            input,
+            result: self.machine.step(input),
+        })
+    }
+}
+
+/// Turn `choices` into inputs `transitions` accepts from `initial`, each
+/// paired with the state the table says it leads to
+///
+/// Each choice picks, modulo their count, one of the rows `step` would take
+/// from the current state. The sequence ends when choices run out or in a
+/// state with no outgoing row.
+///
+/// # Example
+/// ```
+/// use fsmall::testing::valid_sequence;
+///
+/// static TRANSITIONS: [(u8, char, u8); 3] = [(0, 'a', 1), (0, 'b', 0), (1, 'c', 0)];
+/// let trace = valid_sequence(&TRANSITIONS, 0, [1, 0, 7]);
+/// assert!(trace.eq([('b', 0), ('a', 1), ('c', 0)]));
+/// ```
+pub fn valid_sequence<'a, I: Copy + Eq>(
+    transitions: &'a [(u8, I, u8)],
+    initial: u8,
+    choices: impl IntoIterator<Item = u32> + 'a,
+) -> impl Iterator<Item = (I, u8)> + 'a {
+    let mut state = initial;
+    choices.into_iter().map_while(move |choice| {
+        let rows = || (0..transitions.len()).filter(move |row| takes_row(transitions, state, *row));
+        let count = rows().count();
+        let pick = rows().nth(choice as usize % count.max(1))?;
+        let (_from, input, to) = transitions.get(pick)?;
+        state = *to;
+        Some((*input, *to))
+    })
+}
+
+/// Machine table generated sequences follow, see `ValidSequence`
+#[cfg(any(feature = "proptest", feature = "arbitrary"))]
+pub trait Table {
+    /// Input type of the table
+    type Input: Copy + Eq + 'static;
+
+    /// Transition table: (from_state, input, to_state)
+    const TRANSITIONS: &'static [(u8, Self::Input, u8)];
+
+    /// State sequences start in
+    const INITIAL: u8 = 0;
+}
+
+/// Inputs `T` accepts from its initial state, with the states they lead to
+///
+/// Requires the `proptest` or `arbitrary` feature, which implement the
+/// respective crate's `Arbitrary` for it; see `valid_sequence`.
+#[cfg(any(feature = "proptest", feature = "arbitrary"))]
+pub struct ValidSequence<T: Table> {
+    /// Inputs to feed, in order
+    pub inputs: Vec<T::Input>,
+    /// `states[k]` is the state after `inputs[k]`
+    pub states: Vec<u8>,
+}
+
+#[cfg(any(feature = "proptest", feature = "arbitrary"))]
+impl<T: Table> ValidSequence<T> {
+    /// Build sequence from choices as `valid_sequence` does
+    pub fn from_choices(choices: impl IntoIterator<Item = u32>) -> Self {
+        let (inputs, states) = valid_sequence(T::TRANSITIONS, T::INITIAL, choices).unzip();
+        ValidSequence { inputs, states }
+    }
+}
+
+#[cfg(any(feature = "proptest", feature = "arbitrary"))]
+impl<T: Table> Clone for ValidSequence<T> {
+    fn clone(&self) -> Self {
+        ValidSequence {
+            inputs: self.inputs.clone(),
+            states: self.states.clone(),
+        }
+    }
+}
+
+#[cfg(any(feature = "proptest", feature = "arbitrary"))]
+impl<T: Table> core::fmt::Debug for ValidSequence<T>
+where
+    T::Input: core::fmt::Debug,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ValidSequence")
+            .field("inputs", &self.inputs)
+            .field("states", &self.states)
+            .finish()
+    }
+}
+
+/// Strategy generating `ValidSequence`s of `len` choices
+///
+/// Sequences end early in a state without outgoing rows, so they may be
+/// shorter than `len`. Requires the `proptest` feature.
+#[cfg(feature = "proptest")]
+pub fn valid_sequences<T: Table>(
+    len: impl Into<proptest::collection::SizeRange>,
+) -> impl proptest::strategy::Strategy<Value = ValidSequence<T>>
+where
+    T::Input: core::fmt::Debug,
+{
+    use proptest::strategy::Strategy;
+
+    proptest::collection::vec(proptest::arbitrary::any::<u32>(), len)
+        .prop_map(ValidSequence::from_choices)
+}
+
+#[cfg(feature = "proptest")]
+impl<T: Table + 'static> proptest::arbitrary::Arbitrary for ValidSequence<T>
+where
+    T::Input: core::fmt::Debug,
+{
+    type Parameters = ();
+    type Strategy = proptest::strategy::BoxedStrategy<Self>;
+
+    fn arbitrary_with((): ()) -> Self::Strategy {
+        use proptest::strategy::Strategy;
+
+        valid_sequences(0..64).boxed()
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a, T: Table> arbitrary::Arbitrary<'a> for ValidSequence<T> {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let choices = u
+            .arbitrary_iter::<u32>()?
+            .collect::<arbitrary::Result<Vec<_>>>()?;
+        Ok(Self::from_choices(choices))
+    }
+}
+
+/// Input sequence covering transitions, see `transition_tour`
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tour<I> {
+    /// Inputs to feed, in order, starting from the initial state
+    pub inputs: Vec<I>,
+    /// Rows never taken: unreachable, or shadowed by an earlier row with
+    /// the same (from_state, input)
+    pub uncovered: Vec<usize>,
+}
+
+/// Compute an input sequence that takes every reachable row of
+/// `transitions` at least once, starting in `initial`
+///
+/// Greedy: repeatedly walks the shortest route to the nearest row not yet
+/// taken. Tours are short but not necessarily minimal.
+///
+/// # Example
+/// ```
+/// use fsmall::testing::transition_tour;
+///
+/// static TRANSITIONS: [(u8, char, u8); 4] = [(0, 'a', 1), (1, 'b', 0), (1, 'c', 2), (2, 'd', 0)];
+/// let tour = transition_tour(&TRANSITIONS, 0);
+/// assert_eq!(tour.inputs, ['a', 'b', 'a', 'c', 'd']);
+/// assert!(tour.uncovered.is_empty());
+/// ```
+#[cfg(feature = "alloc")]
+pub fn transition_tour<I: Copy + Eq>(transitions: &[(u8, I, u8)], initial: u8) -> Tour<I> {
+    let live: Vec<bool> = (0..transitions.len())
+        .map(|row| takes_row(transitions, transitions[row].0, row))
+        .collect();
+    let mut covered = vec![false; transitions.len()];
+    let mut inputs = Vec::new();
+    let mut state = initial;
+
+    while let Some(route) = nearest_uncovered(transitions, state, &live, &covered) {
+        for row in route {
+            let (_from, input, to) = transitions[row];
+            covered[row] = true;
+            inputs.push(input);
+            state = to;
+        }
+    }
+
+    let uncovered = (0..transitions.len())
+        .filter(|row| !covered[*row])
+        .collect();
+    Tour { inputs, uncovered }
+}
+
+// Rows of a shortest route from `start` ending with a live row not yet covered
+#[cfg(feature = "alloc")]
+fn nearest_uncovered<I>(
+    transitions: &[(u8, I, u8)],
+    start: u8,
+    live: &[bool],
+    covered: &[bool],
+) -> Option<Vec<usize>> {
+    // parent[state] = row a shortest route enters state by
+    let mut parent = [0; 256];
+    let mut visited = StateSet::new();
+    let mut queue = VecDeque::new();
+    visited.insert(start);
+    queue.push_back(start);
+
+    while let Some(state) = queue.pop_front() {
+        let leaving =
+            || (0..transitions.len()).filter(move |row| live[*row] && transitions[*row].0 == state);
+        if let Some(row) = leaving().find(|row| !covered[*row]) {
+            let mut route = vec![row];
+            let mut at = state;
+            while at != start {
+                let row = parent[usize::from(at)];
+                route.push(row);
+                at = transitions[row].0;
+            }
+            route.reverse();
+            return Some(route);
+        }
+        for row in leaving() {
+            let to = transitions[row].2;
+            if visited.insert(to) {
+                parent[usize::from(to)] = row;
+                queue.push_back(to);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Moore, StepError};
+
+    static TRANS: [(u8, u8, u8); 4] = [(0, 1, 1), (0, 2, 2), (1, 0, 0), (2, 9, 3)];
+    static OUTS: [char; 4] = ['a', 'b', 'c', 'd'];
+
+    #[test]
+    fn walk_follows_table() {
+        let mut fsm = Moore::new(0, &TRANS, &OUTS);
+        let mut n = 0;
+        let walk = random_walk(
+            &mut fsm,
+            &TRANS,
+            || {
+                n += 1;
+                n
+            },
+            10,
+        );
+        let mut count = 0;
+        for step in walk {
+            assert!(TRANS
+                .iter()
+                .any(|(f, i, _)| *f == step.from && *i == step.input));
+            assert!(step.result.is_ok());
+            count += 1;
+        }
+        // 0 -2-> 2 -9-> 3, which has no outgoing row
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn walk_reports_errors() {
+        static SHORT: [char; 2] = ['a', 'b'];
+        let mut fsm = Moore::new(0, &TRANS, &SHORT);
+        let mut walk = random_walk(&mut fsm, &TRANS, || 1, 5);
+        let step = walk.next().unwrap();
+        assert_eq!((step.from, step.input), (0, 2));
+        assert_eq!(step.result, Err(StepError::InvalidState));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn tour_covers_reachable_rows() {
+        // Row 3 is shadowed by row 0, row 5 leaves unreachable state 4
+        static GRAPH: [(u8, u8, u8); 6] = [
+            (0, 1, 1),
+            (1, 2, 2),
+            (2, 3, 0),
+            (0, 1, 2),
+            (2, 4, 2),
+            (4, 5, 0),
+        ];
+        let tour = transition_tour(&GRAPH, 0);
+        assert_eq!(tour.uncovered, [3, 5]);
+
+        let mut fsm = Moore::new(0, &GRAPH, &OUTS);
+        let mut taken = [false; 6];
+        for input in tour.inputs {
+            let from = fsm.current_state();
+            taken[GRAPH
+                .iter()
+                .position(|(f, i, _)| *f == from && *i == input)
+                .unwrap()] = true;
+            fsm.step(input).unwrap();
+        }
+        assert_eq!(taken, [true, true, true, false, true, false]);
+    }
+
+    #[test]
+    fn valid_sequence_matches_machine() {
+        let mut fsm = Moore::new(0, &TRANS, &OUTS);
+        let mut steps = 0;
+        for (input, expected) in valid_sequence(&TRANS, 0, [0, 5, 3, 1, 0, 8]) {
+            assert!(fsm.step(input).is_ok());
+            assert_eq!(fsm.current_state(), expected);
+            steps += 1;
+        }
+        // 0 -1-> 1 -0-> 0 -2-> 2 -9-> 3, then no outgoing row
+        assert_eq!(steps, 4);
+    }
+
+    #[cfg(any(feature = "proptest", feature = "arbitrary"))]
+    struct Branches;
+
+    #[cfg(any(feature = "proptest", feature = "arbitrary"))]
+    impl Table for Branches {
+        type Input = u8;
+        const TRANSITIONS: &'static [(u8, u8, u8)] = &TRANS;
+    }
+
+    #[cfg(feature = "proptest")]
+    proptest::proptest! {
+        #[test]
+        fn proptest_sequences_follow_table(sequence in valid_sequences::<Branches>(0..16)) {
+            let mut fsm = Moore::new(0, &TRANS, &OUTS);
+            for (input, expected) in sequence.inputs.iter().zip(&sequence.states) {
+                proptest::prop_assert!(fsm.step(*input).is_ok());
+                proptest::prop_assert_eq!(fsm.current_state(), *expected);
+            }
+        }
+    }
+
+    #[cfg(feature = "arbitrary")]
+    #[test]
+    fn arbitrary_sequences_follow_table() {
+        use arbitrary::{Arbitrary, Unstructured};
+
+        for seed in 0..32u8 {
+            let bytes: Vec<u8> = (0..64)
+                .map(|k| seed.wrapping_mul(31).wrapping_add(k))
+                .collect();
+            let sequence =
+                ValidSequence::<Branches>::arbitrary(&mut Unstructured::new(&bytes)).unwrap();
+            let mut fsm = Moore::new(0, &TRANS, &OUTS);
+            for (input, expected) in sequence.inputs.iter().zip(&sequence.states) {
+                assert!(fsm.step(*input).is_ok());
+                assert_eq!(fsm.current_state(), *expected);
+            }
+        }
+    }
+}
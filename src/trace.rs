@@ -0,0 +1,194 @@
+//! Timestamped transition traces
+//!
+//! `Traced` wraps a machine and records every successful step, stamped by
+//! a `Clock`, into a fixed ring buffer. Use a cycle counter or a timer
+//! shared with a logic analyzer to line transitions up with captures.
+
+use crate::Machine;
+
+/// Source of timestamps
+pub trait Clock {
+    /// Current time, in any monotonic unit
+    fn now(&self) -> u64;
+}
+
+impl<F: Fn() -> u64> Clock for F {
+    fn now(&self) -> u64 {
+        self()
+    }
+}
+
+/// One recorded transition
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TraceEntry<I> {
+    /// Clock reading taken after the step
+    pub timestamp: u64,
+    /// State before the step
+    pub from: u8,
+    /// Input that caused the transition
+    pub input: I,
+    /// State after the step
+    pub to: u8,
+}
+
+/// Machine wrapper recording the last `N` transitions with timestamps
+///
+/// # Example
+/// ```
+/// use fsmall::trace::Traced;
+/// use fsmall::Mealy;
+///
+/// static TRANSITIONS: [(u8, char, u8); 2] = [(0, 'a', 1), (1, 'b', 0)];
+/// static OUTPUTS: [(u8, char, ()); 2] = [(0, 'a', ()), (1, 'b', ())];
+///
+/// let clock = || 42;
+/// let mut fsm: Traced<_, _, 8> = Traced::new(Mealy::new(0, &TRANSITIONS, &OUTPUTS), clock);
+/// fsm.step('a').unwrap();
+/// let entry = fsm.entries().next().unwrap();
+/// assert_eq!((entry.timestamp, entry.from, entry.input, entry.to), (42, 0, 'a', 1));
+/// ```
+pub struct Traced<M: Machine, C, const N: usize> {
+    machine: M,
+    clock: C,
+    // Ring buffer, `next` is the slot written next
+    entries: [Option<TraceEntry<M::Input>>; N],
+    next: usize,
+    len: usize,
+}
+
+impl<M: Machine, C: Clock, const N: usize> Traced<M, C, N>
+where
+    M::Input: Copy,
+{
+    /// Wrap `machine`, stamping entries with `clock`
+    pub fn new(machine: M, clock: C) -> Self {
+        const { assert!(N > 0, "Traced needs a capacity of at least 1") };
+        Traced {
+            machine,
+            clock,
+            entries: core::array::from_fn(|_| None),
+            next: 0,
+            len: 0,
+        }
+    }
+
+    /// Process input, recording the transition if the step succeeds
+    ///
+    /// # Errors
+    /// Whatever the wrapped machine's `step` returns.
+    pub fn step(&mut self, input: M::Input) -> Result<M::Output, M::Error> {
+        let from = self.machine.current_state();
+        let output = self.machine.step(input)?;
+        let entry = TraceEntry {
+            timestamp: self.clock.now(),
+            from,
+            input,
+            to: self.machine.current_state(),
+        };
+        if let Some(slot) = self.entries.get_mut(self.next) {
+            *slot = Some(entry);
+        }
+        self.next = (self.next + 1) % N;
+        self.len = (self.len + 1).min(N);
+        Ok(output)
+    }
+
+    /// Iterate recorded transitions, oldest first
+    pub fn entries(&self) -> impl Iterator<Item = &TraceEntry<M::Input>> + '_ {
+        let start = (self.next + N - self.len) % N;
+        (0..self.len).filter_map(move |k| self.entries.get((start + k) % N)?.as_ref())
+    }
+
+    /// Forget recorded transitions
+    pub fn clear(&mut self) {
+        self.entries.iter_mut().for_each(|entry| *entry = None);
+        self.next = 0;
+        self.len = 0;
+    }
+
+    /// Get wrapped machine
+    pub fn machine(&self) -> &M {
+        &self.machine
+    }
+
+    /// Consume wrapper, returning the machine
+    pub fn into_inner(self) -> M {
+        self.machine
+    }
+}
+
+impl<M: Machine, C: Clock, const N: usize> Machine for Traced<M, C, N>
+where
+    M::Input: Copy,
+{
+    type Input = M::Input;
+    type Output = M::Output;
+    type Error = M::Error;
+
+    fn step(&mut self, input: M::Input) -> Result<M::Output, M::Error> {
+        Traced::step(self, input)
+    }
+
+    fn current_state(&self) -> u8 {
+        self.machine.current_state()
+    }
+
+    fn reset(&mut self, state: u8) {
+        self.machine.reset(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Moore;
+    use core::cell::Cell;
+
+    static TRANS: [(u8, u8, u8); 3] = [(0, 1, 1), (1, 2, 2), (2, 0, 0)];
+    static OUTS: [(); 3] = [(); 3];
+
+    #[test]
+    fn trace_keeps_last_entries() {
+        let time = Cell::new(0);
+        let clock = || {
+            time.set(time.get() + 10);
+            time.get()
+        };
+        let mut fsm: Traced<_, _, 2> = Traced::new(Moore::new(0, &TRANS, &OUTS), clock);
+        for input in [1, 2, 0] {
+            fsm.step(input).unwrap();
+        }
+        assert!(fsm.step(7).is_err());
+
+        let mut entries = fsm.entries();
+        assert_eq!(
+            entries.next(),
+            Some(&TraceEntry {
+                timestamp: 20,
+                from: 1,
+                input: 2,
+                to: 2
+            })
+        );
+        assert_eq!(
+            entries.next(),
+            Some(&TraceEntry {
+                timestamp: 30,
+                from: 2,
+                input: 0,
+                to: 0
+            })
+        );
+        assert_eq!(entries.next(), None);
+    }
+
+    #[test]
+    fn trace_clear_empties_buffer() {
+        let mut fsm: Traced<_, _, 4> = Traced::new(Moore::new(0, &TRANS, &OUTS), || 0);
+        fsm.step(1).unwrap();
+        assert_eq!(fsm.entries().count(), 1);
+        fsm.clear();
+        assert_eq!(fsm.entries().count(), 0);
+        assert_eq!(fsm.into_inner().current_state(), 1);
+    }
+}
@@ -0,0 +1,155 @@
+//! Compile-time checked machines
+//!
+//! `typestate!` turns a rule list into zero-sized state types and methods
+//! that consume the machine in one state and return it in the next, so an
+//! illegal transition is a compile error instead of a runtime `Err`.
+
+/// Zero-sized state type generated by `typestate!`
+pub trait State {
+    /// Numeric state, as used by the table-driven machines
+    const ID: u8;
+}
+
+/// Generate a typestate machine from `(state, input) -> (next, output)` rules
+///
+/// Each input becomes a method on the machine in its source state.
+///
+/// # Example
+/// ```
+/// fsmall::typestate! {
+///     pub machine Door: &'static str {
+///         initial: Closed,
+///         states: { Closed = 0, Opened = 1, Locked = 2 },
+///         rules: [
+///             (Closed, open) -> (Opened, "creak"),
+///             (Opened, close) -> (Closed, "thud"),
+///             (Closed, lock) -> (Locked, "click"),
+///             (Locked, unlock) -> (Closed, "click"),
+///         ]
+///     }
+/// }
+///
+/// let door = Door::new();
+/// let (door, sound) = door.open();
+/// assert_eq!(sound, "creak");
+/// assert_eq!(door.current_state(), 1);
+/// ```
+///
+/// Taking a transition the rules do not allow does not compile:
+/// ```compile_fail
+/// fsmall::typestate! {
+///     machine Door: () {
+///         initial: Closed,
+///         states: { Closed = 0, Locked = 1 },
+///         rules: [(Closed, lock) -> (Locked, ())]
+///     }
+/// }
+///
+/// let (door, ()) = Door::new().lock();
+/// door.lock(); // Locked has no `lock` rule
+/// ```
+///
+/// Nor does taking a transition from a state already left:
+/// ```compile_fail
+/// fsmall::typestate! {
+///     machine Door: () {
+///         initial: Closed,
+///         states: { Closed = 0, Locked = 1 },
+///         rules: [(Closed, lock) -> (Locked, ())]
+///     }
+/// }
+///
+/// let door = Door::new();
+/// let (_locked, ()) = door.lock();
+/// let (_again, ()) = door.lock(); // `door` was consumed
+/// ```
+#[macro_export]
+macro_rules! typestate {
+    (
+        $vis:vis machine $name:ident: $output:ty {
+            initial: $initial:ident,
+            states: { $($state:ident = $id:expr),+ $(,)? },
+            rules: [ $( ($from:ident, $input:ident) -> ($to:ident, $out:expr) ),* $(,)? ] $(,)?
+        }
+    ) => {
+        $(
+            #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+            $vis struct $state;
+
+            impl $crate::typestate::State for $state {
+                const ID: u8 = $id;
+            }
+        )+
+
+        #[derive(Debug, PartialEq, Eq)]
+        $vis struct $name<S> {
+            _state: ::core::marker::PhantomData<S>,
+        }
+
+        impl $name<$initial> {
+            /// Create machine in its initial state
+            $vis const fn new() -> Self {
+                $name {
+                    _state: ::core::marker::PhantomData,
+                }
+            }
+        }
+
+        impl<S: $crate::typestate::State> $name<S> {
+            /// Get current state as a number
+            $vis const fn current_state(&self) -> u8 {
+                S::ID
+            }
+        }
+
+        $(
+            impl $name<$from> {
+                #[must_use]
+                $vis fn $input(self) -> ($name<$to>, $output) {
+                    (
+                        $name {
+                            _state: ::core::marker::PhantomData,
+                        },
+                        $out,
+                    )
+                }
+            }
+        )*
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::State;
+
+    crate::typestate! {
+        machine Uart: u8 {
+            initial: Off,
+            states: { Off = 0, Idle = 1, Sending = 2 },
+            rules: [
+                (Off, power) -> (Idle, 1),
+                (Idle, send) -> (Sending, 2),
+                (Sending, done) -> (Idle, 3),
+                (Idle, power) -> (Off, 4),
+            ]
+        }
+    }
+
+    #[test]
+    fn typestate_walks_rules() {
+        let uart = Uart::new();
+        assert_eq!(uart.current_state(), Off::ID);
+        let (uart, a) = uart.power();
+        let (uart, b) = uart.send();
+        let (uart, c) = uart.done();
+        let (uart, d) = uart.power();
+        assert_eq!((a, b, c, d), (1, 2, 3, 4));
+        assert_eq!(uart, Uart::<Off>::new());
+    }
+
+    #[test]
+    fn typestate_is_zero_sized() {
+        assert_eq!(core::mem::size_of::<Uart<Sending>>(), 0);
+        assert_eq!(Sending::ID, 2);
+    }
+}
@@ -0,0 +1,526 @@
+//! Table checks evaluable in `const` context
+//!
+//! `assert_fsm_valid!` runs them on a machine's tables at compile time, so
+//! a table mistake fails the build with the offending row. The functions
+//! here take inputs by index and are usable at runtime as well.
+
+use core::fmt;
+
+/// Table a `Violation` was found in
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Table {
+    /// Transition table: (from_state, input, to_state)
+    Transitions,
+    /// Output table
+    Outputs,
+}
+
+impl Table {
+    const fn name(self) -> &'static str {
+        match self {
+            Table::Transitions => "transition",
+            Table::Outputs => "output",
+        }
+    }
+}
+
+/// Broken table invariant
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Violation {
+    /// Row mentions a state at or above the state count
+    StateOutOfRange {
+        /// Table of the row
+        table: Table,
+        /// Row index
+        row: usize,
+    },
+    /// Row's input index at or above the input count
+    InputOutOfRange {
+        /// Table of the row
+        table: Table,
+        /// Row index
+        row: usize,
+    },
+    /// Row repeats the (state, input) of an earlier row, so it never applies
+    Duplicate {
+        /// Table of the row
+        table: Table,
+        /// Row index
+        row: usize,
+        /// Earlier row with the same (state, input)
+        first: usize,
+    },
+    /// Transition row without an output row, its step fails with `NoOutput`
+    MissingOutput {
+        /// Transition row index
+        row: usize,
+    },
+    /// Output row without a transition row, it is never returned
+    UnusedOutput {
+        /// Output row index
+        row: usize,
+    },
+    /// State without a transition for an input, see `complete`
+    Incomplete {
+        /// State lacking the transition
+        state: u8,
+        /// Input index lacking a transition
+        input: usize,
+    },
+    /// Moore output array length differs from the state count
+    OutputCount {
+        /// Length of the output array
+        len: usize,
+    },
+}
+
+impl Violation {
+    /// Describe the violation without allocating, usable in `const` context
+    pub const fn message(&self) -> Message {
+        let mut m = Message::new();
+        match *self {
+            Violation::StateOutOfRange { table, row } => {
+                m = m.row(table, row).str("state out of range");
+            }
+            Violation::InputOutOfRange { table, row } => {
+                m = m.row(table, row).str("input out of range");
+            }
+            Violation::Duplicate { table, row, first } => {
+                m = m.row(table, row).str("same (state, input) as row ");
+                m = m.num(first);
+            }
+            Violation::MissingOutput { row } => {
+                m = m.row(Table::Transitions, row).str("no output row");
+            }
+            Violation::UnusedOutput { row } => {
+                m = m.row(Table::Outputs, row).str("no transition row");
+            }
+            Violation::Incomplete { state, input } => {
+                m = m.str("state ").num(state as usize);
+                m = m.str(" has no transition for input ").num(input);
+            }
+            Violation::OutputCount { len } => {
+                m = m.str("output array has ").num(len);
+                m = m.str(" entries, not one per state");
+            }
+        }
+        m
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message().as_str())
+    }
+}
+
+impl core::error::Error for Violation {}
+
+/// Fixed-capacity text of a `Violation`, built in `const` context
+#[derive(Debug, Copy, Clone)]
+pub struct Message {
+    buf: [u8; 64],
+    len: usize,
+}
+
+impl Message {
+    const fn new() -> Self {
+        Message {
+            buf: [0; 64],
+            len: 0,
+        }
+    }
+
+    // Append ASCII text, truncating at capacity
+    const fn str(mut self, text: &str) -> Self {
+        let bytes = text.as_bytes();
+        let mut k = 0;
+        while k < bytes.len() && self.len < self.buf.len() {
+            self.buf[self.len] = bytes[k];
+            self.len += 1;
+            k += 1;
+        }
+        self
+    }
+
+    const fn num(mut self, mut n: usize) -> Self {
+        let mut digits = [0u8; 20];
+        let mut count = 0;
+        loop {
+            digits[count] = b'0' + (n % 10) as u8;
+            count += 1;
+            n /= 10;
+            if n == 0 {
+                break;
+            }
+        }
+        while count > 0 && self.len < self.buf.len() {
+            count -= 1;
+            self.buf[self.len] = digits[count];
+            self.len += 1;
+        }
+        self
+    }
+
+    const fn row(self, table: Table, row: usize) -> Self {
+        self.str(table.name()).str(" row ").num(row).str(": ")
+    }
+
+    /// Get text
+    pub const fn as_str(&self) -> &str {
+        match core::str::from_utf8(self.buf.split_at(self.len).0) {
+            Ok(text) => text,
+            Err(_) => "invalid table",
+        }
+    }
+}
+
+/// Check transition rows: bounds, and no (state, input) twice
+///
+/// # Arguments
+/// * `transitions` - (from_state, input_index, to_state)
+/// * `states` - State count
+/// * `inputs` - Input count
+///
+/// # Errors
+/// The first violation found, in row order
+pub const fn transitions(
+    transitions: &[(u8, usize, u8)],
+    states: usize,
+    inputs: usize,
+) -> Result<(), Violation> {
+    let table = Table::Transitions;
+    let mut row = 0;
+    while row < transitions.len() {
+        let (from, input, to) = transitions[row];
+        if from as usize >= states || to as usize >= states {
+            return Err(Violation::StateOutOfRange { table, row });
+        }
+        if input >= inputs {
+            return Err(Violation::InputOutOfRange { table, row });
+        }
+        if let Some(first) = find(transitions, from, input) {
+            if first < row {
+                return Err(Violation::Duplicate { table, row, first });
+            }
+        }
+        row += 1;
+    }
+    Ok(())
+}
+
+/// Check Mealy tables: transition rows as in `transitions`, output rows
+/// likewise, and a one-to-one match between the two
+///
+/// # Arguments
+/// * `transitions` - (from_state, input_index, to_state)
+/// * `outputs` - (state, input_index) of each output row
+/// * `states` - State count
+/// * `inputs` - Input count
+///
+/// # Errors
+/// The first violation found, transition rows first
+pub const fn mealy(
+    transitions: &[(u8, usize, u8)],
+    outputs: &[(u8, usize)],
+    states: usize,
+    inputs: usize,
+) -> Result<(), Violation> {
+    if let Err(e) = self::transitions(transitions, states, inputs) {
+        return Err(e);
+    }
+    let table = Table::Outputs;
+    let mut row = 0;
+    while row < outputs.len() {
+        let (state, input) = outputs[row];
+        if state as usize >= states {
+            return Err(Violation::StateOutOfRange { table, row });
+        }
+        if input >= inputs {
+            return Err(Violation::InputOutOfRange { table, row });
+        }
+        let mut first = 0;
+        while first < row {
+            if outputs[first].0 == state && outputs[first].1 == input {
+                return Err(Violation::Duplicate { table, row, first });
+            }
+            first += 1;
+        }
+        if find(transitions, state, input).is_none() {
+            return Err(Violation::UnusedOutput { row });
+        }
+        row += 1;
+    }
+    let mut row = 0;
+    while row < transitions.len() {
+        let (from, input, _to) = transitions[row];
+        let mut found = false;
+        let mut k = 0;
+        while k < outputs.len() {
+            found |= outputs[k].0 == from && outputs[k].1 == input;
+            k += 1;
+        }
+        if !found {
+            return Err(Violation::MissingOutput { row });
+        }
+        row += 1;
+    }
+    Ok(())
+}
+
+/// Check Moore tables: transition rows as in `transitions`, and one
+/// output per state
+///
+/// # Errors
+/// The first violation found
+pub const fn moore(
+    transitions: &[(u8, usize, u8)],
+    outputs: usize,
+    states: usize,
+    inputs: usize,
+) -> Result<(), Violation> {
+    if outputs != states {
+        return Err(Violation::OutputCount { len: outputs });
+    }
+    self::transitions(transitions, states, inputs)
+}
+
+/// Check that every (state, input) pair has a transition row
+///
+/// Catches forgotten inputs in machines meant to handle every input in
+/// every state; run `transitions` first for bounds.
+///
+/// # Errors
+/// The first missing pair, state-major
+pub const fn complete(
+    transitions: &[(u8, usize, u8)],
+    states: usize,
+    inputs: usize,
+) -> Result<(), Violation> {
+    let mut state = 0;
+    while state < states && state <= u8::MAX as usize {
+        let mut input = 0;
+        while input < inputs {
+            if find(transitions, state as u8, input).is_none() {
+                return Err(Violation::Incomplete {
+                    state: state as u8,
+                    input,
+                });
+            }
+            input += 1;
+        }
+        state += 1;
+    }
+    Ok(())
+}
+
+// First transition row for (state, input)
+const fn find(transitions: &[(u8, usize, u8)], state: u8, input: usize) -> Option<usize> {
+    let mut row = 0;
+    while row < transitions.len() {
+        if transitions[row].0 == state && transitions[row].1 == input {
+            return Some(row);
+        }
+        row += 1;
+    }
+    None
+}
+
+/// Fail the build unless a machine's tables are valid
+///
+/// Checked at compile time: every state is below `STATES` and every input
+/// index below `INPUTS`, no (state, input) appears twice in a table, and
+/// each transition row has exactly one output row and vice versa. For
+/// Moore tables, prefix `moore:` and pass the output array, whose length
+/// must be `STATES`. The error names the first bad row, e.g.
+/// `transition row 2: same (state, input) as row 0`.
+///
+/// Pairs without any row are allowed, as `step` reports them; append
+/// `complete` to also require a transition for every (state, input), see
+/// `validate::complete`.
+///
+/// Inputs are converted with `as usize`, so they must be integers, `char`,
+/// `bool` or fieldless enums such as those of `input_index!`; the tables
+/// must be `static`s or `const`s.
+///
+/// # Example
+/// ```
+/// use fsmall::{assert_fsm_valid, input_index, InputIndex};
+///
+/// input_index! {
+///     #[derive(Copy, Clone, Eq, PartialEq)]
+///     enum Input { Coin, Push }
+/// }
+///
+/// static TRANSITIONS: [(u8, Input, u8); 2] = [(0, Input::Coin, 1), (1, Input::Push, 0)];
+/// static OUTPUTS: [(u8, Input, &str); 2] = [(0, Input::Coin, "unlock"), (1, Input::Push, "lock")];
+///
+/// assert_fsm_valid!(TRANSITIONS, OUTPUTS, 2, Input::COUNT);
+/// assert_fsm_valid!(moore: TRANSITIONS, [false, true], 2, Input::COUNT);
+/// ```
+///
+/// A forgotten input fails to compile once completeness is asked for:
+/// ```compile_fail
+/// use fsmall::assert_fsm_valid;
+///
+/// static TRANSITIONS: [(u8, bool, u8); 3] = [(0, false, 0), (0, true, 1), (1, true, 0)];
+/// static OUTPUTS: [(u8, bool, u8); 3] = [(0, false, 0), (0, true, 1), (1, true, 2)];
+/// assert_fsm_valid!(TRANSITIONS, OUTPUTS, 2, 2, complete);
+/// ```
+///
+/// A duplicated row fails to compile:
+/// ```compile_fail
+/// use fsmall::assert_fsm_valid;
+///
+/// static TRANSITIONS: [(u8, u8, u8); 2] = [(0, 7, 1), (0, 7, 0)];
+/// static OUTPUTS: [(u8, u8, u8); 1] = [(0, 7, 1)];
+/// assert_fsm_valid!(TRANSITIONS, OUTPUTS, 2, 256);
+/// ```
+#[macro_export]
+macro_rules! assert_fsm_valid {
+    (moore: $transitions:expr, $outputs:expr, $states:expr, $inputs:expr, complete $(,)?) => {
+        $crate::assert_fsm_valid!(moore: $transitions, $outputs, $states, $inputs);
+        $crate::assert_fsm_valid!(@complete $transitions, $states, $inputs);
+    };
+    ($transitions:expr, $outputs:expr, $states:expr, $inputs:expr, complete $(,)?) => {
+        $crate::assert_fsm_valid!($transitions, $outputs, $states, $inputs);
+        $crate::assert_fsm_valid!(@complete $transitions, $states, $inputs);
+    };
+    (@complete $transitions:expr, $states:expr, $inputs:expr) => {
+        const _: () = {
+            let result = $crate::validate::complete(
+                &$crate::__indexed_transitions!($transitions),
+                $states,
+                $inputs,
+            );
+            if let Err(violation) = result {
+                panic!("{}", violation.message().as_str());
+            }
+        };
+    };
+    (moore: $transitions:expr, $outputs:expr, $states:expr, $inputs:expr $(,)?) => {
+        const _: () = {
+            let result = $crate::validate::moore(
+                &$crate::__indexed_transitions!($transitions),
+                $outputs.len(),
+                $states,
+                $inputs,
+            );
+            if let Err(violation) = result {
+                panic!("{}", violation.message().as_str());
+            }
+        };
+    };
+    ($transitions:expr, $outputs:expr, $states:expr, $inputs:expr $(,)?) => {
+        const _: () = {
+            const __FSMALL_U: usize = $outputs.len();
+            const __FSMALL_OUTPUTS: [(u8, usize); __FSMALL_U] = {
+                let mut rows = [(0, 0); __FSMALL_U];
+                let mut k = 0;
+                while k < __FSMALL_U {
+                    rows[k] = ($outputs[k].0, $outputs[k].1 as usize);
+                    k += 1;
+                }
+                rows
+            };
+            let result = $crate::validate::mealy(
+                &$crate::__indexed_transitions!($transitions),
+                &__FSMALL_OUTPUTS,
+                $states,
+                $inputs,
+            );
+            if let Err(violation) = result {
+                panic!("{}", violation.message().as_str());
+            }
+        };
+    };
+}
+
+// Transition table with inputs converted to indices, as a const array;
+// item names are prefixed as items are not hygienic
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __indexed_transitions {
+    ($transitions:expr) => {{
+        const __FSMALL_T: usize = $transitions.len();
+        const __FSMALL_TRANSITIONS: [(u8, usize, u8); __FSMALL_T] = {
+            let mut rows = [(0, 0, 0); __FSMALL_T];
+            let mut k = 0;
+            while k < __FSMALL_T {
+                rows[k] = (
+                    $transitions[k].0,
+                    $transitions[k].1 as usize,
+                    $transitions[k].2,
+                );
+                k += 1;
+            }
+            rows
+        };
+        __FSMALL_TRANSITIONS
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static TRANS: [(u8, char, u8); 3] = [(0, 'a', 1), (1, 'b', 0), (1, 'a', 1)];
+    static OUTS: [(u8, char, u8); 3] = [(0, 'a', 10), (1, 'b', 20), (1, 'a', 30)];
+    crate::assert_fsm_valid!(TRANS, OUTS, 2, 128);
+    crate::assert_fsm_valid!(moore: TRANS, [(); 2], 2, 128);
+    static FLIP: [(u8, bool, u8); 4] = [(0, false, 0), (0, true, 1), (1, false, 1), (1, true, 0)];
+    crate::assert_fsm_valid!(moore: FLIP, [(); 2], 2, 2, complete);
+
+    #[test]
+    fn violations_name_the_row() {
+        let trans = [(0, 1, 1), (1, 0, 0), (0, 1, 0)];
+        assert_eq!(
+            mealy(&trans, &[(0, 1), (1, 0)], 2, 2),
+            Err(Violation::Duplicate {
+                table: Table::Transitions,
+                row: 2,
+                first: 0
+            })
+        );
+        assert_eq!(
+            mealy(&trans[..2], &[(0, 1)], 2, 2),
+            Err(Violation::MissingOutput { row: 1 })
+        );
+        assert_eq!(
+            mealy(&trans[..2], &[(0, 1), (1, 0), (1, 1)], 2, 2),
+            Err(Violation::UnusedOutput { row: 2 })
+        );
+        assert_eq!(
+            transitions(&trans, 1, 2),
+            Err(Violation::StateOutOfRange {
+                table: Table::Transitions,
+                row: 0
+            })
+        );
+        assert_eq!(
+            moore(&trans[..2], 3, 2, 2),
+            Err(Violation::OutputCount { len: 3 })
+        );
+        assert_eq!(
+            complete(&trans, 2, 2),
+            Err(Violation::Incomplete { state: 0, input: 0 })
+        );
+        assert_eq!(
+            Violation::Incomplete { state: 1, input: 1 }
+                .message()
+                .as_str(),
+            "state 1 has no transition for input 1"
+        );
+
+        let message = Violation::Duplicate {
+            table: Table::Outputs,
+            row: 12,
+            first: 0,
+        }
+        .message();
+        assert_eq!(
+            message.as_str(),
+            "output row 12: same (state, input) as row 0"
+        );
+    }
+}
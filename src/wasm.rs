@@ -0,0 +1,292 @@
+//! WebAssembly bindings for browser-based simulators
+//!
+//! Requires the `wasm` feature. `WasmMachine` is a `wasm-bindgen` class
+//! constructed from a JavaScript array of rows, so a simulator runs the
+//! same tables as `ffi::FsmallMachine` on the device. Each `new` makes an
+//! independent handle. Export it from the simulator's `cdylib` crate and
+//! build that with `wasm-pack`; like any `wasm-bindgen` crate, the module
+//! needs a global allocator.
+//!
+//! ```js
+//! const machine = new WasmMachine([0, 1, 1, 10, /* from, input, to, output */ 1, 2, 0, 20], 0);
+//! machine.step(1); // 10
+//! machine.step(1); // undefined, no transition
+//! machine.currentState(); // 1
+//! machine.trace(); // Uint32Array [0, 1, 1], (from, input, to) triples oldest first
+//! ```
+
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::fmt;
+
+use wasm_bindgen::prelude::*;
+
+use crate::ffi::FsmallRow;
+use crate::{Machine, StepError};
+
+/// Most rows a `WasmMachine` holds
+pub const MAX_ROWS: usize = 256;
+/// Transitions a `WasmMachine`'s trace keeps
+pub const TRACE_LEN: usize = 64;
+
+/// Error returned by `Simulator::load`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RowsError {
+    /// More rows than the simulator holds, or a partial row
+    TooMany,
+    /// State number above 255
+    StateOutOfRange {
+        /// Row index, `None` for the initial state
+        row: Option<usize>,
+    },
+}
+
+impl fmt::Display for RowsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RowsError::TooMany => write!(f, "too many rows, or a partial row"),
+            RowsError::StateOutOfRange { row: None } => write!(f, "initial state above 255"),
+            RowsError::StateOutOfRange { row: Some(row) } => {
+                write!(f, "row {row}: state above 255")
+            }
+        }
+    }
+}
+
+impl core::error::Error for RowsError {}
+
+/// Machine over owned `FsmallRow`s recording its last `T` transitions
+///
+/// # Example
+/// ```
+/// use fsmall::wasm::Simulator;
+///
+/// let mut sim: Simulator<8, 4> = Simulator::new();
+/// sim.load(&[0, 1, 1, 10, 1, 2, 0, 20], 0).unwrap();
+/// assert_eq!(sim.step(1), Ok(10));
+/// assert_eq!(sim.step(2), Ok(20));
+/// assert_eq!(sim.trace().collect::<Vec<_>>(), [(0, 1, 1), (1, 2, 0)]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Simulator<const R: usize, const T: usize> {
+    rows: [FsmallRow; R],
+    len: usize,
+    state: u8,
+    // Ring buffer of (from, input, to), `next` is the slot written next
+    trace: [(u8, u32, u8); T],
+    next: usize,
+    traced: usize,
+}
+
+impl<const R: usize, const T: usize> Simulator<R, T> {
+    const EMPTY: FsmallRow = FsmallRow {
+        from: 0,
+        input: 0,
+        to: 0,
+        output: 0,
+    };
+
+    /// Create simulator without rows in state 0
+    pub const fn new() -> Self {
+        const { assert!(T > 0, "Simulator needs a trace capacity of at least 1") };
+        Simulator {
+            rows: [Self::EMPTY; R],
+            len: 0,
+            state: 0,
+            trace: [(0, 0, 0); T],
+            next: 0,
+            traced: 0,
+        }
+    }
+
+    /// Replace the rows and restart in `initial`, clearing the trace
+    ///
+    /// # Arguments
+    /// * `flat` - Rows as consecutive (from_state, input, to_state, output)
+    /// * `initial` - Starting state
+    ///
+    /// # Errors
+    /// See `RowsError`; the simulator is left unchanged.
+    pub fn load(&mut self, flat: &[u32], initial: u32) -> Result<(), RowsError> {
+        let state =
+            |value: u32, row| u8::try_from(value).map_err(|_| RowsError::StateOutOfRange { row });
+        let initial = state(initial, None)?;
+        if flat.len() % 4 != 0 || flat.len() / 4 > R {
+            return Err(RowsError::TooMany);
+        }
+        let mut rows = [Self::EMPTY; R];
+        for (row, (slot, chunk)) in rows.iter_mut().zip(flat.chunks_exact(4)).enumerate() {
+            if let [from, input, to, output] = *chunk {
+                *slot = FsmallRow {
+                    from: state(from, Some(row))?,
+                    input,
+                    to: state(to, Some(row))?,
+                    output,
+                };
+            }
+        }
+        self.rows = rows;
+        self.len = flat.len() / 4;
+        self.state = initial;
+        self.next = 0;
+        self.traced = 0;
+        Ok(())
+    }
+
+    /// Process input code, returning the output code
+    ///
+    /// # Errors
+    /// * `StepError::NoTransition` - No row for (state, input)
+    pub fn step(&mut self, input: u32) -> Result<u32, StepError> {
+        let row = self
+            .rows
+            .get(..self.len)
+            .unwrap_or_default()
+            .iter()
+            .find(|row| row.from == self.state && row.input == input)
+            .copied()
+            .ok_or(StepError::NoTransition)?;
+        if let Some(slot) = self.trace.get_mut(self.next) {
+            *slot = (self.state, input, row.to);
+        }
+        self.next = (self.next + 1) % T;
+        self.traced = (self.traced + 1).min(T);
+        self.state = row.to;
+        Ok(row.output)
+    }
+
+    /// Get current state
+    pub fn current_state(&self) -> u8 {
+        self.state
+    }
+
+    /// Recorded (from_state, input, to_state) transitions, oldest first
+    pub fn trace(&self) -> impl Iterator<Item = (u8, u32, u8)> + '_ {
+        let start = (self.next + T - self.traced) % T;
+        (0..self.traced).filter_map(move |k| self.trace.get((start + k) % T).copied())
+    }
+
+    /// Jump to `state`, keeping the trace
+    pub fn reset(&mut self, state: u8) {
+        self.state = state;
+    }
+}
+
+impl<const R: usize, const T: usize> Default for Simulator<R, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const R: usize, const T: usize> Machine for Simulator<R, T> {
+    type Input = u32;
+    type Output = u32;
+    type Error = StepError;
+
+    fn step(&mut self, input: u32) -> Result<u32, StepError> {
+        Simulator::step(self, input)
+    }
+
+    fn current_state(&self) -> u8 {
+        Simulator::current_state(self)
+    }
+
+    fn reset(&mut self, state: u8) {
+        Simulator::reset(self, state)
+    }
+}
+
+/// Simulator handle exported to JavaScript
+///
+/// Holds up to `MAX_ROWS` rows and the last `TRACE_LEN` transitions.
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct WasmMachine {
+    sim: Simulator<MAX_ROWS, TRACE_LEN>,
+}
+
+#[wasm_bindgen]
+impl WasmMachine {
+    /// Create machine in `initial` from rows
+    ///
+    /// # Arguments
+    /// * `rows` - Array of consecutive (from_state, input, to_state, output)
+    /// * `initial` - Starting state
+    ///
+    /// # Errors
+    /// Throws with the `RowsError` message.
+    #[wasm_bindgen(constructor)]
+    pub fn new(rows: &[u32], initial: u32) -> Result<WasmMachine, JsError> {
+        let mut sim = Simulator::new();
+        match sim.load(rows, initial) {
+            Ok(()) => Ok(WasmMachine { sim }),
+            Err(error) => Err(JsError::new(&error.to_string())),
+        }
+    }
+
+    /// Process input code, `undefined` if there is no transition
+    pub fn step(&mut self, input: u32) -> Option<u32> {
+        self.sim.step(input).ok()
+    }
+
+    /// Get current state
+    #[wasm_bindgen(js_name = currentState)]
+    pub fn current_state(&self) -> u8 {
+        self.sim.current_state()
+    }
+
+    /// Jump to `state`, keeping the trace
+    pub fn reset(&mut self, state: u8) {
+        self.sim.reset(state);
+    }
+
+    /// Recorded transitions as flat (from_state, input, to_state) triples,
+    /// oldest first
+    pub fn trace(&self) -> Vec<u32> {
+        self.sim
+            .trace()
+            .flat_map(|(from, input, to)| [from.into(), input, to.into()])
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn simulator_trace_keeps_latest() {
+        let mut sim: Simulator<2, 2> = Simulator::new();
+        assert_eq!(sim.load(&[0, 1, 1, 10, 1, 1, 0, 20], 0), Ok(()));
+        for _ in 0..3 {
+            sim.step(1).unwrap();
+        }
+        assert_eq!(sim.step(2), Err(StepError::NoTransition));
+        assert!(sim.trace().eq([(1, 1, 0), (0, 1, 1)]));
+
+        assert_eq!(
+            sim.load(&[0, 1, 256, 0], 0),
+            Err(RowsError::StateOutOfRange { row: Some(0) })
+        );
+        assert_eq!(sim.load(&[0; 12], 0), Err(RowsError::TooMany));
+        assert_eq!(sim.current_state(), 1);
+    }
+
+    #[test]
+    fn wasm_machines_are_independent() {
+        let rows = [0, 7, 1, 70, 1, 8, 0, 80];
+        let (Ok(mut first), Ok(mut second)) =
+            (WasmMachine::new(&rows, 0), WasmMachine::new(&rows, 1))
+        else {
+            panic!("valid rows rejected");
+        };
+        assert_eq!(first.step(7), Some(70));
+        assert_eq!(first.step(7), None);
+        assert_eq!(second.step(8), Some(80));
+        assert_eq!((first.current_state(), second.current_state()), (1, 0));
+        assert_eq!(first.trace(), [0, 7, 1]);
+        first.reset(0);
+        assert_eq!(first.step(7), Some(70));
+        assert_eq!(first.trace(), [0, 7, 1, 0, 7, 1]);
+    }
+}